@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use ed25519_dalek::{Signature, VerifyingKey};
+use crate::engine::{self, Progress};
+use crate::http::{self, Url};
+use crate::method::Registry;
+use crate::policy::Policy;
+use crate::settings::Settings;
+use crate::worker::Worker;
+use crate::{audit, certificate, db, hash, history, identity, mail, progress, units, webhook};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Waiting grows up to this after failed requests, so a server outage does
+/// not turn a fleet of agents into a flood.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A job pushed by the server. The wire format is one `key value` pair per
+/// line, `id` once, `method` at most once and `path` once per file.
+#[derive(Debug, Clone, PartialEq)]
+struct Job {
+    id: u64,
+    method: Option<String>,
+    /// Case or ticket number the server attached to the job.
+    tag: Option<String>,
+    /// Who asked the server for the job.
+    operator: Option<String>,
+    paths: Vec<PathBuf>,
+}
+
+impl Job {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut id = None;
+        let mut method = None;
+        let mut tag = None;
+        let mut operator = None;
+        let mut paths = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            match line.split_once(' ') {
+                Some(("id", value)) => id = Some(value.trim().parse().map_err(|_| format!("Bad job id {}", value))?),
+                Some(("method", value)) => method = Some(value.trim().to_string()),
+                Some(("tag", value)) => tag = Some(value.trim().to_string()),
+                Some(("operator", value)) => operator = Some(value.trim().to_string()),
+                Some(("path", value)) => paths.push(PathBuf::from(value.trim())),
+                _ => return Err(format!("Unexpected line in job: {}", line)),
+            }
+        }
+        Ok(Self {
+            id: id.ok_or("Job without an id")?,
+            method,
+            tag,
+            operator,
+            paths,
+        })
+    }
+}
+
+/// Reads the server's Ed25519 public key, 64 hex digits.
+pub fn parse_key(text: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hash::unhex(text.trim())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "The server key must be 64 hex digits".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Bad server key: {}", e))
+}
+
+/// Checks that `body`, a job for the agent called `name`, carries the
+/// server's signature, given as `ed25519=<hex>`. The name is signed along
+/// with the job, so a job meant for one machine cannot be handed to another.
+pub fn verify(key: &VerifyingKey, name: &str, body: &str, signature: Option<&str>) -> Result<(), String> {
+    let signature = signature.ok_or("The job is not signed")?;
+    let signature = signature
+        .strip_prefix("ed25519=")
+        .and_then(hash::unhex)
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| format!("Malformed job signature {}", signature))?;
+    key.verify_strict(format!("{}\n{}", name, body).as_bytes(), &signature)
+        .map_err(|_| "The job signature does not match the server key".to_string())
+}
+
+/// Runs as a fleet agent: registers with `server`, then keeps asking it for
+/// erase jobs, reporting progress and uploading a certificate for each.
+/// Only jobs signed with the pinned server `key` are run, and they go
+/// through the same policy checks as local ones.
+///
+/// `server` has to be an `https://` address. Every request carries
+/// `Authorization: Bearer <token>`:
+///
+/// - `POST /agents/<name>` registers, the body lists version and OS
+/// - `GET /agents/<name>/jobs` answers 204 when idle or 200 with a job:
+///   `id N`, optionally `method KEY`, `tag TEXT` and `operator NAME`, and a
+///   `path P` per file. `X-File-Eraser-Signature: ed25519=<hex>` signs the
+///   agent's name, a newline and the body with the server key
+/// - `POST /agents/<name>/jobs/<id>/progress` sends `percent N` and, on a
+///   second line, `phase` with what the job is doing (`Pass 2 of 3: writing
+///   40%`); an answer of `cancel` cancels the job
+/// - `POST /agents/<name>/jobs/<id>/certificate` uploads the JSON certificate
+/// - `POST /agents/<name>/jobs/<id>/rejected` explains a refused job
+pub fn run(server: &str, token: &str, key: &VerifyingKey, methods: &Registry, settings: &Settings, policy: &Policy) -> Result<(), String> {
+    let url = Url::parse(server)?;
+    if !url.secure() {
+        return Err(format!("{} is not an https:// address; the token and the jobs would cross the network in the clear", server));
+    }
+    let name = hostname();
+    let agent = Agent {
+        base: url.join("agents").join(&name),
+        token: format!("Bearer {}", token),
+    };
+    let registration = format!("version {}\nos {}\n", env!("CARGO_PKG_VERSION"), std::env::consts::OS);
+    let response = agent.post("", "text/plain", registration.as_bytes()).map_err(|e| e.to_string())?;
+    if !response.success() {
+        return Err(format!("{} refused the registration: {} {}", server, response.status, response.body.trim()));
+    }
+    println!("Registered with {} as {}", server, name);
+
+    // A signed job captured earlier must not run a second time.
+    let mut seen = HashSet::new();
+    let mut wait = POLL_INTERVAL;
+    loop {
+        std::thread::sleep(wait);
+        match agent.get("jobs") {
+            Ok(response) if response.status == 204 => wait = POLL_INTERVAL,
+            Ok(response) if response.success() => {
+                wait = POLL_INTERVAL;
+                let signature = response.header("X-File-Eraser-Signature");
+                match verify(key, &name, &response.body, signature).and_then(|()| Job::parse(&response.body)) {
+                    Ok(job) if !seen.insert(job.id) => agent.reject(&job, "This job was already run"),
+                    Ok(job) => agent.execute(job, methods, settings, policy),
+                    Err(e) => eprintln!("Ignoring a job from {}: {}", server, e),
+                }
+            }
+            Ok(response) => {
+                eprintln!("{} answered {} when asked for jobs", server, response.status);
+                wait = (wait * 2).min(MAX_BACKOFF);
+            }
+            Err(e) => {
+                eprintln!("Cannot reach {}: {}", server, e);
+                wait = (wait * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+struct Agent {
+    base: Url,
+    token: String,
+}
+
+impl Agent {
+    fn get(&self, path: &str) -> std::io::Result<http::Response> {
+        http::request("GET", &self.base.join(path), &[("Authorization", &self.token)], &[])
+    }
+
+    fn post(&self, path: &str, content_type: &str, body: &[u8]) -> std::io::Result<http::Response> {
+        let headers = [("Authorization", self.token.as_str()), ("Content-Type", content_type)];
+        http::request("POST", &self.base.join(path), &headers, body)
+    }
+
+    fn reject(&self, job: &Job, reason: &str) {
+        eprintln!("Refusing job {}: {}", job.id, reason);
+        if let Err(e) = self.post(&format!("jobs/{}/rejected", job.id), "text/plain", reason.as_bytes()) {
+            eprintln!("Error reporting the refusal of job {}: {}", job.id, e);
+        }
+    }
+
+    fn execute(&self, job: Job, methods: &Registry, settings: &Settings, policy: &Policy) {
+        let method = match &job.method {
+            Some(key) => match methods.find(key) {
+                Some(method) => method,
+                None => return self.reject(&job, &format!("Unknown method {}", key)),
+            },
+            None => settings.method.clone(),
+        };
+        if let Err(e) = policy.check_method(&method) {
+            return self.reject(&job, &e);
+        }
+        if let Some(path) = job.paths.iter().find(|path| policy.forbids(path)) {
+            return self.reject(&job, &format!("{} is protected by the policy on this machine", path.display()));
+        }
+        println!("Running job {} with {} file(s)", job.id, job.paths.len());
+
+        let verification = settings.verification.at_least(if method.verify() {
+            engine::Verification::LastPass
+        } else {
+            engine::Verification::None
+        });
+        let options = engine::Options {
+            passes: method.passes(),
+            verification,
+            timestamps: settings.timestamps,
+            churn_directories: settings.churn_directories,
+            simulate: settings.simulate,
+            checksums: settings.checksums,
+        };
+        let bytes: Vec<u64> = job.paths
+            .iter()
+            .map(|path| std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0))
+            .collect();
+        let (tx, rx) = progress::channel();
+        let started = Instant::now();
+        let worker = {
+            let paths = job.paths.clone();
+            Worker::spawn(move |cancel| engine::erase_all(&paths, &options, cancel, &tx))
+        };
+
+        let mut errors: Vec<Option<String>> = vec![None; job.paths.len()];
+        let mut erased = vec![false; job.paths.len()];
+        let mut strategies = vec![String::new(); job.paths.len()];
+        let (mut current, mut percent, mut verified) = (0, 0.0, 0);
+        let mut outcome = history::Outcome::Failed;
+        let mut phase = progress::Phase::default();
+        let mut reported = Instant::now();
+        for progress in rx.iter() {
+            phase.follow(&progress);
+            match progress {
+                Progress::Started(index) => current = index,
+                Progress::Updated(value) => percent = (current as f32 * 100.0 + value) / job.paths.len().max(1) as f32,
+                Progress::Failed(index, error) => errors[index] = Some(error),
+                Progress::Strategy(strategy) => strategies[current] = strategy.to_string(),
+                Progress::Unsanitized(index, mismatch) => {
+                    errors[index] = Some(mismatch.to_string());
+                    outcome = history::Outcome::Unsanitized;
+                }
+                // Sent once per file that made it all the way through.
+                Progress::Verified(bytes) => {
+                    erased[current] = true;
+                    verified += bytes;
+                }
+                Progress::Finished(success) => {
+                    if success {
+                        outcome = history::Outcome::Success;
+                    }
+                }
+                Progress::Cancelled => outcome = history::Outcome::Cancelled,
+                Progress::DriveUpdated(..) | Progress::DriveFinished(..) | Progress::CryptoErased(_)
+                | Progress::MemoryBacked(_) | Progress::MtpReplaced(_) | Progress::ShadowCopies(_) => {}
+                Progress::ScanStarted(_) | Progress::ScanUpdated(_) | Progress::PassStarted(..)
+                | Progress::WipeUpdated(_) | Progress::VerifyUpdated(_) | Progress::CleanupDone => {}
+            }
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                reported = Instant::now();
+                let body = format!("percent {:.1}\nphase {}", percent, phase);
+                match self.post(&format!("jobs/{}/progress", job.id), "text/plain", body.as_bytes()) {
+                    Ok(response) if response.body.trim() == "cancel" => worker.cancel(),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Error reporting progress of job {}: {}", job.id, e),
+                }
+            }
+        }
+        if let Err(e) = worker.join() {
+            eprintln!("Job {} crashed: {}", job.id, e);
+        }
+
+        let entries: Vec<history::FileEntry> = job.paths
+            .iter()
+            .zip(bytes)
+            .zip(errors.into_iter().zip(erased))
+            .zip(strategies)
+            .map(|(((path, bytes), (error, erased)), strategy)| history::FileEntry {
+                path: path.clone(),
+                bytes,
+                error: error.or_else(|| (!erased).then(|| "Not attempted".to_string())),
+                strategy,
+            })
+            .collect();
+        let destroyed = entries.iter().filter(|entry| entry.error.is_none());
+        let record = history::Record {
+            id: 0,
+            finished: history::unix_now(),
+            description: format!("Remote job {}, {} file(s)", job.id, job.paths.len()),
+            method: match settings.simulate {
+                Some(_) => format!("{} (simulated, nothing was written)", method),
+                None => method.to_string(),
+            },
+            preset: String::new(),
+            tag: job.tag.clone().unwrap_or_else(|| settings.tag.clone()),
+            user: identity::os_user(),
+            operator: job.operator.clone().unwrap_or_else(|| settings.operator.clone()),
+            verification: match verification {
+                engine::Verification::None => verification.to_string(),
+                _ => format!("{}: {} read back", verification, units::size(verified)),
+            },
+            files: destroyed.clone().count(),
+            bytes: destroyed.map(|entry| entry.bytes).sum(),
+            duration: started.elapsed(),
+            outcome,
+            entries,
+        };
+        // A simulated job still reports to the server, which is what it is
+        // for, but leaves nothing behind on this machine.
+        let record = match settings.simulate {
+            Some(_) => record,
+            None => keep(record, settings, policy),
+        };
+        let body = certificate::json(&record);
+        match self.post(&format!("jobs/{}/certificate", job.id), "application/json", body.as_bytes()) {
+            Ok(response) if response.success() => println!("Job {} finished: {}", job.id, record.outcome.key()),
+            Ok(response) => eprintln!("The server refused the certificate of job {}: {}", job.id, response.status),
+            Err(e) => eprintln!("Error uploading the certificate of job {}: {}", job.id, e),
+        }
+    }
+}
+
+/// Saves a finished job to the local history, certificates and audit log
+/// and passes it on to the webhook and mail receivers.
+fn keep(record: history::Record, settings: &Settings, policy: &Policy) -> history::Record {
+    let (record, key) = match db::Database::open().and_then(|mut db| db.insert(record.clone()).map(|id| (db, id))) {
+        Ok((db, id)) => (db.get(id).expect("record was just inserted").clone(), db.key().cloned()),
+        Err(e) => {
+            eprintln!("Error saving history: {}", e);
+            (record, None)
+        }
+    };
+    webhook::notify(settings, &record);
+    mail::notify(settings, &record);
+    if let Err(e) = certificate::store(&record, key.as_ref()) {
+        eprintln!("Error writing certificate: {}", e);
+    }
+    if policy.audit
+        && let Err(e) = audit::append(&record)
+    {
+        eprintln!("Error writing audit log: {}", e);
+    }
+    record
+}
+
+/// The name this machine registers under.
+fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        // SAFETY: gethostname writes at most `buffer.len()` bytes.
+        if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } == 0 {
+            let end = buffer.iter().position(|byte| *byte == 0).unwrap_or(buffer.len());
+            return String::from_utf8_lossy(&buffer[..end]).into_owned();
+        }
+    }
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string())
+}
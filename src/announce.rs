@@ -0,0 +1,72 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use crate::runtime;
+
+/// Speaks `text` through the platform speech service so screen-reader users
+/// hear job milestones. This is a side channel, not accessibility: iced
+/// exposes no accessibility tree yet, so buttons, the progress bar and the
+/// status line still have no names or roles a screen reader can read.
+pub fn speak(text: &str) {
+    let (mut command, input) = speech(text);
+    let spawned = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Cannot announce \"{}\": {}", text, e);
+            return;
+        }
+    };
+    // Dropping stdin closes it, which is where the speech service stops
+    // reading. An announcement is far smaller than a pipe's buffer.
+    if let Some(mut stdin) = child.stdin.take()
+        && let Err(e) = stdin.write_all(input.as_bytes())
+    {
+        eprintln!("Cannot announce \"{}\": {}", text, e);
+    }
+    // Reap it once it is done talking, or it stays behind as a zombie.
+    runtime::handle().spawn_blocking(move || {
+        if let Err(e) = child.wait() {
+            eprintln!("Error waiting for the speech service: {}", e);
+        }
+    });
+}
+
+/// The command that speaks what it reads from stdin, and what to write to
+/// it. Announcements name files, so the text never goes on the command
+/// line where a name could pass for an option or, on Windows, for code.
+pub fn speech(text: &str) -> (Command, String) {
+    (speech_command(), text.to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn speech_command() -> Command {
+    let mut command = Command::new("say");
+    command.args(["-f", "-"]);
+    command
+}
+
+#[cfg(windows)]
+fn speech_command() -> Command {
+    let mut command = Command::new("powershell");
+    command.args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "[Console]::InputEncoding = [Text.Encoding]::UTF8; Add-Type -AssemblyName System.Speech; \
+         (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak([Console]::In.ReadToEnd())",
+    ]);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn speech_command() -> Command {
+    // speech-dispatcher is what Orca itself talks to; pipe mode reads the
+    // text from stdin.
+    let mut command = Command::new("spd-say");
+    command.arg("--pipe-mode");
+    command
+}
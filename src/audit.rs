@@ -0,0 +1,27 @@
+use std::io::{self, Write};
+use crate::history::Record;
+use crate::identity::one_line;
+use crate::reports;
+
+/// Appends one line per job to `audit.log`. Unlike the history database this
+/// file is never rewritten, so it can be shipped to a log collector as is.
+pub fn append(record: &Record) -> io::Result<()> {
+    let dir = reports::data_dir()?;
+
+    let line = format!(
+        "{} UTC\tjob={}\tuser={}\toperator={}\tpreset={}\tmethod={}\tverification={}\toutcome={}\tfiles={}\tbytes={}\n",
+        record.date(),
+        record.id,
+        one_line(&record.user),
+        one_line(&record.operator),
+        record.preset,
+        record.method,
+        record.verification,
+        record.outcome.key(),
+        record.files,
+        record.bytes,
+    );
+    let mut file = reports::private_file().create(true).append(true).open(dir.join("audit.log"))?;
+    file.write_all(line.as_bytes())?;
+    file.sync_data()
+}
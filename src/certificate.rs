@@ -0,0 +1,256 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+use crate::history::{Outcome, Record};
+use crate::vault::{self, Key};
+use crate::{reports, units};
+
+/// Appended to the name of a certificate stored encrypted.
+pub const SEALED: &str = ".sealed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Pdf,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Pdf => "pdf",
+        }
+    }
+}
+
+/// What a certificate stored as `name` is sealed for: its job and format,
+/// so one cannot be passed off as another.
+fn context(name: &str) -> String {
+    format!("file-eraser certificate {}", name)
+}
+
+/// Writes JSON and PDF copies into `<data dir>/file-eraser/certificates`,
+/// sealed with `key` when the history is encrypted. Returns where the PDF
+/// went.
+pub fn store(record: &Record, key: Option<&Key>) -> std::io::Result<PathBuf> {
+    let dir = reports::certificates_dir()?;
+    let key = match vault::enabled() {
+        true => Some(key.ok_or_else(vault::locked_error)?),
+        false => None,
+    };
+    let mut written = PathBuf::new();
+    for format in [Format::Json, Format::Pdf] {
+        let name = format!("erasure-certificate-{}.{}", record.id, format.extension());
+        let (path, contents) = match key {
+            Some(key) => (dir.join(name.clone() + SEALED), key.seal(&render(record, format), &context(&name))),
+            None => (dir.join(name), render(record, format)),
+        };
+        reports::write(&path, &contents)?;
+        written = path;
+    }
+    Ok(written)
+}
+
+/// Seals the certificates stored before the history was encrypted and
+/// shreds the plain copies.
+pub fn seal_existing(key: &Key) -> std::io::Result<()> {
+    let dir = reports::certificates_dir()?;
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        if !name.starts_with("erasure-certificate-") || name.ends_with(SEALED) || name.ends_with(".draft") {
+            continue;
+        }
+        let sealed = key.seal(&std::fs::read(&path)?, &context(&name));
+        reports::write(&dir.join(name + SEALED), &sealed)?;
+        reports::shred(&path)?;
+    }
+    Ok(())
+}
+
+pub fn render(record: &Record, format: Format) -> Vec<u8> {
+    match format {
+        Format::Json => json(record).into_bytes(),
+        Format::Pdf => pdf(&lines(record)),
+    }
+}
+
+/// The account, with the operator's name when one was typed in.
+fn performer(record: &Record) -> String {
+    let user = if record.user.is_empty() { "Unknown account" } else { &record.user };
+    match record.operator.is_empty() {
+        true => user.to_string(),
+        false => format!("{} (operator: {})", user, record.operator),
+    }
+}
+
+fn outcome(record: &Record) -> &'static str {
+    match record.outcome {
+        Outcome::Success => "All files destroyed",
+        Outcome::Failed => "Completed with failures",
+        Outcome::Cancelled => "Cancelled",
+        Outcome::Unsanitized => "NOT RELIABLY SANITIZED: verification found mismatching data",
+    }
+}
+
+pub fn json(record: &Record) -> String {
+    let mut out = String::from("{\n");
+    let _ = writeln!(out, "  \"certificate\": \"file-eraser erasure certificate\",");
+    let _ = writeln!(out, "  \"job_id\": {},", record.id);
+    let _ = writeln!(out, "  \"finished\": \"{} UTC\",", record.date());
+    let _ = writeln!(out, "  \"finished_unix\": {},", record.finished);
+    let _ = writeln!(out, "  \"description\": {},", quote(&record.description));
+    let _ = writeln!(out, "  \"tag\": {},", quote(&record.tag));
+    let _ = writeln!(out, "  \"user\": {},", quote(&record.user));
+    let _ = writeln!(out, "  \"operator\": {},", quote(&record.operator));
+    let _ = writeln!(out, "  \"method\": {},", quote(&record.method));
+    let _ = writeln!(out, "  \"compliance_preset\": {},", quote(&record.preset));
+    let _ = writeln!(out, "  \"verification\": {},", quote(&record.verification));
+    let _ = writeln!(out, "  \"outcome\": \"{}\",", record.outcome.key());
+    let _ = writeln!(out, "  \"reliably_sanitized\": {},", record.outcome != Outcome::Unsanitized);
+    let _ = writeln!(out, "  \"files_destroyed\": {},", record.files);
+    let _ = writeln!(out, "  \"bytes_destroyed\": {},", record.bytes);
+    let _ = writeln!(out, "  \"duration_ms\": {},", record.duration.as_millis());
+    out += "  \"files\": [";
+    for (index, entry) in record.entries.iter().enumerate() {
+        out += if index == 0 { "\n" } else { ",\n" };
+        let _ = write!(
+            out,
+            "    {{ \"path\": {}, \"bytes\": {}, \"erased\": {}, \"error\": {}, \"strategy\": {} }}",
+            quote(&entry.path.to_string_lossy()),
+            entry.bytes,
+            entry.error.is_none(),
+            entry.error.as_deref().map(quote).unwrap_or_else(|| "null".to_string()),
+            quote(&entry.strategy),
+        );
+    }
+    out += if record.entries.is_empty() { "]\n}\n" } else { "\n  ]\n}\n" };
+    out
+}
+
+/// Escapes `text` as a JSON string literal.
+pub fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            '\r' => quoted += "\\r",
+            '\t' => quoted += "\\t",
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The certificate as plain text, one line per line of the PDF.
+pub fn text(record: &Record) -> String {
+    lines(record).join("\n")
+}
+
+fn lines(record: &Record) -> Vec<String> {
+    let mut lines = vec![
+        "Certificate of Data Erasure".to_string(),
+        String::new(),
+    ];
+    if record.outcome == Outcome::Unsanitized {
+        lines.extend([
+            "WARNING: THIS JOB DID NOT RELIABLY SANITIZE ALL DATA.".to_string(),
+            "The storage returned different data than was written. Treat the files".to_string(),
+            "marked below as still holding recoverable data.".to_string(),
+            String::new(),
+        ]);
+    }
+    lines.extend([
+        format!("Job number: {}", record.id),
+        format!("Completed: {} UTC", record.date()),
+        format!("Job: {}", record.description),
+        format!("Case or ticket: {}", if record.tag.is_empty() { "None" } else { &record.tag }),
+        format!("Performed by: {}", performer(record)),
+        format!("Method: {}", record.method),
+        format!("Compliance preset: {}", if record.preset.is_empty() { "None" } else { &record.preset }),
+        format!("Verification: {}", record.verification),
+        format!("Result: {}", outcome(record)),
+        format!("Files destroyed: {} ({}, {} bytes)", record.files, units::size(record.bytes), record.bytes),
+        format!("Duration: {}", units::duration(record.duration)),
+        String::new(),
+    ]);
+    if !record.entries.is_empty() {
+        lines.push("Files:".to_string());
+    }
+    for entry in &record.entries {
+        let status = match &entry.error {
+            None => "erased".to_string(),
+            Some(error) => format!("NOT erased: {}", error),
+        };
+        lines.push(format!("  {} ({} bytes) - {}", entry.path.to_string_lossy(), entry.bytes, status));
+        if !entry.strategy.is_empty() {
+            lines.push(format!("    {}", entry.strategy));
+        }
+    }
+    lines
+}
+
+const LINES_PER_PAGE: usize = 56;
+
+/// Writes a plain text PDF with the built-in Helvetica font, one page per
+/// `LINES_PER_PAGE` lines. Characters outside Latin-1 are replaced by `?`.
+fn pdf(lines: &[String]) -> Vec<u8> {
+    let pages: Vec<&[String]> = lines.chunks(LINES_PER_PAGE).collect();
+    // Objects: 1 catalog, 2 pages, 3 font, then a page and a content stream per page.
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let kids: Vec<String> = (0..pages.len()).map(|page| format!("{} 0 R", 4 + page * 2)).collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+
+    for (page, page_lines) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                5 + page * 2
+            )
+            .into_bytes(),
+        );
+        let mut stream = b"BT /F1 10 Tf 14 TL 50 800 Td\n".to_vec();
+        for line in page_lines.iter() {
+            stream.push(b'(');
+            for c in line.chars() {
+                match c {
+                    '(' | ')' | '\\' => stream.extend([b'\\', c as u8]),
+                    c if (c as u32) < 0x20 => stream.push(b' '),
+                    c if (c as u32) < 0x100 => stream.push(c as u32 as u8),
+                    _ => stream.push(b'?'),
+                }
+            }
+            stream.extend(b") Tj T*\n");
+        }
+        stream.extend(b"ET");
+        let mut object = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+        object.extend(stream);
+        object.extend(b"\nendstream");
+        objects.push(object);
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", index + 1).into_bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+    let xref = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    out.extend(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).into_bytes(),
+    );
+    out
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+/// Patterns, checksums and the read-back all work in blocks of this size,
+/// and every chunk is a whole number of them, so the data written is the
+/// same whatever size the chunks end up.
+pub const BLOCK: usize = 4096;
+/// Where every pass starts.
+pub const START: usize = 1024 * 1024;
+pub const MIN: usize = 64 * 1024;
+pub const MAX: usize = 64 * 1024 * 1024;
+/// Added after every full chunk that went faster than `TARGET`.
+pub const STEP: usize = 1024 * 1024;
+/// How long one chunk should take at most: long enough that the calls
+/// themselves cost nothing, short enough that progress and cancelling
+/// stay responsive on a slow SD card.
+pub const TARGET: Duration = Duration::from_millis(250);
+
+/// How much the engine writes or reads at a time, adjusted to the drive
+/// the way TCP adjusts its window: a step larger after every fast chunk,
+/// half the size after a slow one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunks {
+    size: usize,
+}
+
+impl Default for Chunks {
+    fn default() -> Self {
+        Self { size: START }
+    }
+}
+
+impl Chunks {
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Takes note of a chunk of `bytes` that took `took`. A short last
+    /// chunk going fast says nothing about the drive and leaves the size.
+    pub fn record(&mut self, bytes: usize, took: Duration) {
+        if took > TARGET {
+            self.size = (self.size / 2 / BLOCK * BLOCK).max(MIN);
+        } else if bytes >= self.size {
+            self.size = (self.size + STEP).min(MAX);
+        }
+    }
+}
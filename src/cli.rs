@@ -0,0 +1,1006 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+use crate::certificate::quote;
+use crate::engine::{self, CancelToken, Mismatch, Progress};
+use crate::method::{Method, Registry};
+use crate::policy::Policy;
+use crate::progress::Phase;
+use crate::region::Region;
+use crate::strategy::{Score, Strategy};
+use crate::settings::Settings;
+use crate::{container, db, diagnostics, history, identity, mail, pack, picker, progress, runtime, search, space, shadow, snapshot, staging, webhook};
+
+const USAGE: &str = "\
+Usage: file-eraser [options] [FILE...]
+
+Without arguments the graphical interface starts. Files given on the
+command line are overwritten and deleted without further questions.
+
+  --method KEY        wipe method, e.g. dod or random-3 (default: from settings)
+  --fd N              overwrite the already open file descriptor N (Unix)
+  --handle N          overwrite the inherited file handle N (Windows)
+  --fd-socket PATH    listen on a Unix socket at PATH and overwrite every file
+                      descriptor sent over it with SCM_RIGHTS; one status line
+                      (\"ok\" or \"error: ...\") is written back per descriptor
+  --pack KEY          add the files of an app cleanup pack, e.g. telegram or
+                      downloads; may be given more than once
+  --list-packs        list the cleanup packs with what they remove
+  --diagnostics       check what this machine supports (direct I/O, punching
+                      holes, TRIM, secure erase, extended attributes, long
+                      paths, elevation) and print the results
+  --wipe-reports DAYS erase the certificates written more than DAYS days ago
+                      and any report drafts left by a crash
+  --list-staged       list the files waiting in the holding area (see the
+                      staging setting) with the time they are destroyed
+  --restore N         move staged file N back to where it came from
+  --destroy-staged    erase the staged files whose time is up; run it from
+                      cron or a timer when staging from the command line
+  --summary-json      print a JSON summary of the job to stdout at the end
+  --region OFF:LEN    overwrite only LEN bytes from offset OFF of each FILE
+                      (decimal, or hexadecimal with 0x) and keep the files,
+                      for a secret inside a container or database file
+  --with-siblings     also erase the write-ahead logs and journals of SQLite
+                      databases, the lock files of documents and the folders
+                      archives were extracted to, which may hold copies of
+                      their content; without it they are only named
+  --purge-shadows     delete the Volume Shadow Copies (restore points) of the
+                      files' drives before erasing, so no earlier version
+                      survives in them; needs an elevated prompt (Windows)
+  --purge-hashes LIST erase every file below the given folders whose SHA-256
+                      is in LIST (one hash per line, sha256sum output works)
+                      and print a report of which hashes were found and
+                      destroyed
+  --tui               start the terminal interface instead of the window, with
+                      any FILEs already queued (Unix)
+  --gui FILE...       start the window with the FILEs, and everything in the
+                      folders among them, queued for the usual confirmation;
+                      what the file manager entries run
+  --agent URL         run as a fleet agent: register with the https:// server at
+                      URL, take erase jobs it signed and upload their
+                      certificates; the token is read from
+                      FILE_ERASER_AGENT_TOKEN, the server's Ed25519 public key
+                      (hex) from FILE_ERASER_AGENT_KEY
+  --config PATH       read settings from PATH instead of the user's settings
+                      file (also FILE_ERASER_CONFIG)
+  --portable          keep settings, history and reports in file-eraser-data
+                      beside the executable, for running from removable
+                      media (also FILE_ERASER_PORTABLE=1, or an empty file
+                      named portable beside the executable)
+  --set KEY=VALUE     override one setting for this run: method, verification,
+                      timestamps, units, churn_directories, checksums, advanced,
+                      announce, simulate, tag, operator, staging or
+                      onboarded (false shows the first-start walkthrough
+                      again); the same
+                      keys can be set as FILE_ERASER_METHOD and so on
+  --tag TEXT          attach a case, ticket or work order number to the job's
+                      history entry and certificate (same as --set tag=TEXT)
+  --operator NAME     name the person erasing in the audit log and certificate,
+                      next to the account (same as --set operator=NAME)
+  --simulate MBPS     pretend, for demos and development: jobs report progress
+                      as if writing at MBPS megabytes per second but nothing
+                      is written, deleted or recorded (with or without a
+                      window; same as --set simulate=MBPS)
+  --help              show this help
+
+Handed-over files are overwritten in place and not removed, the caller keeps
+the name and decides what happens to it.
+
+Exit codes:
+  0  every file was erased
+  1  the command line was wrong
+  2  some files were erased, others failed
+  3  verification read back different data; treat the storage as unreliable
+  4  nothing was erased because access was denied
+  5  nothing was erased for another reason";
+
+/// Stable exit codes, so scripts can act on the result without parsing text.
+/// The numbers must never change meaning; new outcomes get new numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Exit {
+    Success = 0,
+    Usage = 1,
+    Partial = 2,
+    Verification = 3,
+    Permission = 4,
+    Failed = 5,
+}
+
+impl Exit {
+    fn key(self) -> &'static str {
+        match self {
+            Exit::Success => "success",
+            Exit::Usage => "usage",
+            Exit::Partial => "partial",
+            Exit::Verification => "verification_failed",
+            Exit::Permission => "permission_denied",
+            Exit::Failed => "failed",
+        }
+    }
+}
+
+/// What the command line asked for.
+enum Command {
+    Paths(Vec<PathBuf>),
+    Descriptor(i64),
+    Socket(String),
+}
+
+/// How one file or descriptor went.
+struct Item {
+    name: String,
+    bytes: u64,
+    verified: u64,
+    error: Option<io::Error>,
+    /// How the file was erased; unknown for handed-over descriptors.
+    strategy: Option<Strategy>,
+    /// Account that handed the descriptor over through `--fd-socket`, as
+    /// the kernel reported it.
+    caller: Option<String>,
+}
+
+impl Item {
+    fn mismatch(&self) -> bool {
+        self.error
+            .as_ref()
+            .and_then(|e| e.get_ref())
+            .is_some_and(|inner| inner.is::<Mismatch>())
+    }
+}
+
+/// Loads the settings, honouring `--config`, `--set` and `FILE_ERASER_*`
+/// variables, and removes those options from `args`. They work for the GUI
+/// too, so fleet deployments can push a managed config. Command-line values
+/// win over the environment, which wins over the file; the policy always
+/// has the last word.
+pub fn settings(args: &mut Vec<String>, methods: &Registry, policy: &Policy) -> Result<Settings, ExitCode> {
+    let usage = |message: String| {
+        eprintln!("file-eraser: {}", message);
+        ExitCode::from(Exit::Usage as u8)
+    };
+    let mut config = std::env::var_os("FILE_ERASER_CONFIG").map(PathBuf::from);
+    let mut overrides = Vec::new();
+    let mut rest = Vec::new();
+    let mut iter = std::mem::take(args).into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--config" | "--set" => {
+                let value = iter.next().ok_or_else(|| usage(format!("{} needs a value", arg)))?;
+                if arg == "--config" {
+                    config = Some(PathBuf::from(value));
+                } else {
+                    let (key, value) = value
+                        .split_once('=')
+                        .ok_or_else(|| usage(format!("--set expects KEY=VALUE, not {}", value)))?;
+                    overrides.push((key.trim().to_string(), value.trim().to_string()));
+                }
+            }
+            "--simulate" | "--tag" | "--operator" => {
+                let value = iter.next().ok_or_else(|| usage(format!("{} needs a value", arg)))?;
+                overrides.push((arg.trim_start_matches('-').to_string(), value));
+            }
+            _ => rest.push(arg),
+        }
+    }
+    *args = rest;
+
+    let user = Settings::load(methods);
+    let mut settings = match config {
+        Some(path) => Settings::load_from(&path, methods)
+            .map_err(|e| usage(format!("Cannot read config {}: {}", path.display(), e)))?,
+        None => user.clone(),
+    };
+    settings.apply_environment(methods);
+    for (key, value) in overrides {
+        if policy.locks(&key) {
+            return Err(usage(format!("{} is locked by your organization's policy", key)));
+        }
+        settings.set(&key, &value, methods).map_err(usage)?;
+    }
+    settings.enforce(policy, methods);
+    settings.layer_over(user);
+    Ok(settings)
+}
+
+/// Takes `--gui` and the paths after it out of `args`, with folders
+/// expanded to the files in them, hidden ones included. `None` when the
+/// window was not asked for.
+pub fn queued(args: &mut Vec<String>) -> Option<Vec<PathBuf>> {
+    let index = args.iter().position(|arg| arg == "--gui")?;
+    let mut files = Vec::new();
+    for path in args.drain(index..).skip(1).map(PathBuf::from) {
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+        for (path, e) in picker::walk(&path, true, &mut files) {
+            eprintln!("{}: {}", path.display(), e);
+        }
+    }
+    Some(files)
+}
+
+/// Runs the headless mode when there are arguments. Returns `None` when the
+/// GUI should start instead.
+pub fn run(args: &[String], methods: &Registry, settings: &Settings, policy: &Policy) -> Option<ExitCode> {
+    if args.is_empty() {
+        return None;
+    }
+    let exit = match execute(args, methods, settings, policy) {
+        Ok(exit) => exit,
+        Err(e) => {
+            eprintln!("file-eraser: {}", e);
+            Exit::Usage
+        }
+    };
+    Some(ExitCode::from(exit as u8))
+}
+
+fn execute(args: &[String], methods: &Registry, settings: &Settings, policy: &Policy) -> Result<Exit, String> {
+    let mut method = settings.method.clone();
+    let mut command = None;
+    let mut paths = Vec::new();
+    let mut summary_json = false;
+    let mut tui = false;
+    let mut agent = None;
+    let mut purge = None;
+    let mut purge_shadows = false;
+    let mut region = None;
+    let mut with_siblings = false;
+    // Nothing being old enough is not an error when old reports were asked for.
+    let mut wiped_reports = false;
+    // Files from the holding area whose time is up, with where they came from.
+    let mut staged: Option<Vec<staging::Staged>> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(Exit::Success);
+            }
+            "--method" => {
+                let key = value()?;
+                method = methods.find(key).ok_or_else(|| format!("Unknown method {}", key))?;
+                policy.check_method(&method)?;
+            }
+            "--fd" | "--handle" => {
+                let number = value()?;
+                let number = number.parse().map_err(|_| format!("{} is not a descriptor number", number))?;
+                command = Some(Command::Descriptor(number));
+            }
+            "--fd-socket" => command = Some(Command::Socket(value()?.clone())),
+            "--pack" => {
+                let key = value()?;
+                let pack = pack::load()
+                    .into_iter()
+                    .find(|pack| pack.key == *key)
+                    .ok_or_else(|| format!("Unknown cleanup pack {}, see --list-packs", key))?;
+                let files = pack.files();
+                eprintln!("{}: {} file(s)", pack.name, files.len());
+                paths.extend(files);
+            }
+            "--list-packs" => {
+                for pack in pack::load() {
+                    println!("{:<12}{}\n            {}", pack.key, pack.name, pack.description);
+                }
+                return Ok(Exit::Success);
+            }
+            "--diagnostics" => {
+                for check in diagnostics::probe(&std::env::temp_dir()) {
+                    match check.result {
+                        Ok(how) => println!("yes  {:<20}{}", check.capability.to_string(), how),
+                        Err(why) => println!("no   {:<20}{}", check.capability.to_string(), why),
+                    }
+                }
+                return Ok(Exit::Success);
+            }
+            "--wipe-reports" => {
+                let days = value()?;
+                let days = days.parse().map_err(|_| format!("{} is not a number of days", days))?;
+                let old = crate::reports::older_than(days).map_err(|e| format!("Cannot list the reports: {}", e))?;
+                match old.len() {
+                    0 => eprintln!("No reports older than {} days", days),
+                    count => eprintln!("{} report(s) older than {} days", count, days),
+                }
+                paths.extend(old);
+                wiped_reports = true;
+            }
+            "--list-staged" => {
+                let area = staging::Area::open().map_err(|e| format!("Cannot open the staging area: {}", e))?;
+                for staged in area.items() {
+                    println!("{:<6}{} UTC  {}", staged.id, history::format_date(staged.due), staged.original.display());
+                }
+                return Ok(Exit::Success);
+            }
+            "--restore" => {
+                let id = value()?;
+                let id = id.parse().map_err(|_| format!("{} is not a staged file number, see --list-staged", id))?;
+                let mut area = staging::Area::open().map_err(|e| format!("Cannot open the staging area: {}", e))?;
+                let path = area.restore(id).map_err(|e| format!("Cannot restore {}: {}", id, e))?;
+                eprintln!("{} restored", path.display());
+                return Ok(Exit::Success);
+            }
+            "--destroy-staged" => {
+                let area = staging::Area::open().map_err(|e| format!("Cannot open the staging area: {}", e))?;
+                let (due, forbidden) = staging::permitted(area.due(history::unix_now()), policy);
+                for error in forbidden {
+                    eprintln!("{}", error);
+                }
+                eprintln!("{} staged file(s) are due", due.len());
+                paths.extend(due.iter().map(|staged| staged.held.clone()));
+                staged = Some(due);
+            }
+            "--summary-json" => summary_json = true,
+            "--purge-shadows" => purge_shadows = true,
+            "--with-siblings" => with_siblings = true,
+            "--region" => region = Some(Region::parse(value()?)?),
+            "--tui" => tui = true,
+            "--agent" => agent = Some(value()?.clone()),
+            "--purge-hashes" => purge = Some(PathBuf::from(value()?)),
+            other if other.starts_with('-') => return Err(format!("Unknown option {}\n\n{}", other, USAGE)),
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    if let Some(server) = agent {
+        if command.is_some() || !paths.is_empty() || tui {
+            return Err("--agent takes its jobs from the server and cannot be combined with other work".to_string());
+        }
+        // Kept out of the arguments, which other users can see in the process list.
+        let token = std::env::var("FILE_ERASER_AGENT_TOKEN")
+            .map_err(|_| "--agent needs the FILE_ERASER_AGENT_TOKEN environment variable".to_string())?;
+        let key = std::env::var("FILE_ERASER_AGENT_KEY")
+            .map_err(|_| "--agent needs the server's public key in the FILE_ERASER_AGENT_KEY environment variable".to_string())
+            .and_then(|key| crate::agent::parse_key(&key))?;
+        if let Err(e) = crate::agent::run(&server, &token, &key, methods, settings, policy) {
+            eprintln!("file-eraser: {}", e);
+            return Ok(Exit::Failed);
+        }
+        return Ok(Exit::Success);
+    }
+    if let Some(list) = purge {
+        if command.is_some() || tui || summary_json {
+            return Err("--purge-hashes cannot be combined with --fd, --fd-socket, --tui or --summary-json".to_string());
+        }
+        if paths.is_empty() {
+            return Err("--purge-hashes needs the folders to search".to_string());
+        }
+        return purge_hashes(&list, &paths, &method, settings, policy);
+    }
+    if tui {
+        if command.is_some() {
+            return Err("--tui cannot be combined with --fd or --fd-socket".to_string());
+        }
+        return run_tui(paths, methods, &method, settings, policy);
+    }
+    if region.is_some() && (command.is_some() || staged.is_some() || wiped_reports) {
+        return Err("--region works on FILEs named on the command line only".to_string());
+    }
+    let command = match (command, paths.is_empty()) {
+        (Some(_), false) => return Err("Files cannot be combined with --fd or --fd-socket".to_string()),
+        (Some(command), true) => command,
+        (None, false) => Command::Paths(paths),
+        (None, true) if wiped_reports || staged.is_some() => return Ok(Exit::Success),
+        (None, true) => return Err(format!("Nothing to do\n\n{}", USAGE)),
+    };
+
+    if let (Some(minutes), None, Command::Paths(paths), None, false, None) =
+        (settings.staging, settings.simulate, &command, &staged, wiped_reports, region)
+    {
+        return stage(paths, minutes, policy);
+    }
+
+    let options = options(&method, settings);
+    if settings.simulate.is_some() {
+        eprintln!("Simulation: nothing is written, deleted or recorded");
+    }
+    let started = Instant::now();
+    let mut items = match command {
+        Command::Paths(paths) if let Some(region) = region => overwrite_regions(&paths, region, &options, policy),
+        Command::Paths(mut paths) => {
+            siblings(&mut paths, with_siblings, policy);
+            shadow_copies(&paths, purge_shadows && settings.simulate.is_none());
+            for snapshots in snapshot::detect(&paths) {
+                match snapshots.found {
+                    Ok(found) => eprintln!(
+                        "Warning: {} {} has {} snapshot(s) that keep earlier contents of these files",
+                        snapshots.filesystem, snapshots.volume, found.len(),
+                    ),
+                    Err(e) => eprintln!("Cannot list the snapshots of {}: {}", snapshots.volume, e),
+                }
+            }
+            erase_paths(&paths, &options, policy)
+        }
+        Command::Descriptor(number) => vec![wipe_descriptor(number, &options)],
+        Command::Socket(path) => serve(&path, &options).map_err(|e| e.to_string())?,
+    };
+
+    for item in &items {
+        if let Some(e) = &item.error {
+            eprintln!("{}: {}", item.name, e);
+        }
+    }
+    if let Some(score) = score(&items) {
+        eprintln!("{}", score);
+    }
+    let exit = exit_code(&items);
+    let mut description = match region {
+        Some(region) => format!("{} of {} file(s) from the command line", region, items.len()),
+        None => format!("{} file(s) from the command line", items.len()),
+    };
+    if let Some(staged) = &staged {
+        // Recorded under the names the files had before they were staged.
+        for (item, staged) in items.iter_mut().zip(staged) {
+            item.name = staged.original.display().to_string();
+        }
+        if let Err(e) = staging::Area::open().and_then(|mut area| area.prune()) {
+            eprintln!("Error updating the staging area: {}", e);
+        }
+        description = format!("{} staged file(s) from the command line", items.len());
+    }
+    record(&items, description, &method, &options.verification, started, settings, policy);
+    if summary_json {
+        print!("{}", summary(&items, exit, &method.to_string(), &options.verification.to_string(), started));
+    }
+    Ok(exit)
+}
+
+/// Moves `paths` into the holding area instead of erasing them; a later
+/// `--destroy-staged`, from cron for example, erases them once they are due.
+fn stage(paths: &[PathBuf], minutes: u64, policy: &Policy) -> Result<Exit, String> {
+    let mut area = staging::Area::open().map_err(|e| format!("Cannot open the staging area: {}", e))?;
+    let failed = paths
+        .iter()
+        .filter(|path| match area.stage(path, minutes * 60, policy) {
+            Ok(()) => false,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                true
+            }
+        })
+        .count();
+    eprintln!(
+        "{} file(s) staged for {} minute(s); --list-staged shows them, --restore N takes one back",
+        paths.len() - failed,
+        minutes,
+    );
+    Ok(match failed {
+        0 => Exit::Success,
+        failed if failed < paths.len() => Exit::Partial,
+        _ => Exit::Failed,
+    })
+}
+
+/// Names the files beside databases, documents and archives among `paths`
+/// that may hold copies of their content, or adds them when `include` is set.
+fn siblings(paths: &mut Vec<PathBuf>, include: bool, policy: &Policy) {
+    for hint in container::hints(paths) {
+        let names: Vec<String> = hint.siblings.iter().map(|sibling| sibling.display().to_string()).collect();
+        if !include {
+            eprintln!("Warning: {}: {}; --with-siblings erases them too", hint, names.join(", "));
+            continue;
+        }
+        for sibling in hint.siblings {
+            let mut found = Vec::new();
+            if !sibling.is_dir() {
+                found.push(sibling);
+            } else {
+                for (path, e) in picker::walk(&sibling, true, &mut found) {
+                    eprintln!("Cannot list {}: {}", path.display(), e);
+                }
+            }
+            for file in found {
+                if !policy.forbids(&file) && !paths.contains(&file) {
+                    paths.push(file);
+                }
+            }
+        }
+        eprintln!("Also erasing {}", names.join(", "));
+    }
+}
+
+/// Warns when the files' drives have shadow copies that may keep earlier
+/// versions of them, or deletes those copies when `purge` is set.
+fn shadow_copies(paths: &[PathBuf], purge: bool) {
+    let shadows = match shadow::list() {
+        Ok(shadows) => shadows,
+        Err(e) => {
+            eprintln!("Cannot check for shadow copies: {}", e);
+            return;
+        }
+    };
+    let covering = shadow::covering(&shadows, paths);
+    if covering.is_empty() {
+        return;
+    }
+    if !purge {
+        eprintln!(
+            "Warning: {} shadow copies of these drives may keep earlier versions of the files; \
+             --purge-shadows deletes them",
+            covering.len(),
+        );
+        return;
+    }
+    let ids: Vec<String> = covering.iter().map(|shadow| shadow.id.clone()).collect();
+    match shadow::delete(&ids) {
+        Ok(deleted) => eprintln!("{} shadow copies deleted", deleted),
+        Err(e) => eprintln!("Shadow copies were not deleted: {}", e),
+    }
+}
+
+fn options(method: &Method, settings: &Settings) -> engine::Options {
+    engine::Options {
+        passes: method.passes(),
+        verification: settings.verification.at_least(if method.verify() {
+            engine::Verification::LastPass
+        } else {
+            engine::Verification::None
+        }),
+        timestamps: settings.timestamps,
+        churn_directories: settings.churn_directories,
+        simulate: settings.simulate,
+        checksums: settings.checksums,
+    }
+}
+
+/// Searches `dirs` for files whose SHA-256 is on the list at `list`, erases
+/// all of them and prints the purge report. Finding nothing is a success.
+fn purge_hashes(list: &Path, dirs: &[PathBuf], method: &Method, settings: &Settings, policy: &Policy) -> Result<Exit, String> {
+    let hashes = search::read_hashes(list).map_err(|e| format!("Cannot read {}: {}", list.display(), e))?;
+    eprintln!("Searching for {} hash(es)...", hashes.len());
+    let criterion = search::Criterion::Hashes(hashes.clone());
+    let outcome = search::search(dirs, &criterion, policy, &CancelToken::default()).map_err(|e| e.to_string())?;
+    let paths: Vec<PathBuf> = outcome.hits.iter().map(|hit| hit.path.clone()).collect();
+
+    let options = options(method, settings);
+    let started = Instant::now();
+    let items = erase_paths(&paths, &options, policy);
+    print!(
+        "{}",
+        search::purge_report(list, &hashes, &outcome, |hit| {
+            let name = hit.path.display().to_string();
+            match items.iter().find(|item| item.name == name).and_then(|item| item.error.as_ref()) {
+                Some(e) => search::Fate::Failed(e.to_string()),
+                None => search::Fate::Destroyed,
+            }
+        }),
+    );
+    if !items.is_empty() {
+        let description = format!("Hash purge: {} file(s) matching {}", items.len(), list.display());
+        record(&items, description, method, &options.verification, started, settings, policy);
+    }
+    Ok(exit_code(&items))
+}
+
+#[cfg(unix)]
+fn run_tui(
+    paths: Vec<PathBuf>,
+    methods: &Registry,
+    method: &Method,
+    settings: &Settings,
+    policy: &Policy,
+) -> Result<Exit, String> {
+    crate::tui::run(paths, methods, method, settings, policy).map_err(|e| format!("Terminal interface failed: {}", e))?;
+    Ok(Exit::Success)
+}
+
+#[cfg(not(unix))]
+fn run_tui(
+    _paths: Vec<PathBuf>,
+    _methods: &Registry,
+    _method: &Method,
+    _settings: &Settings,
+    _policy: &Policy,
+) -> Result<Exit, String> {
+    Err("--tui needs a Unix terminal".to_string())
+}
+
+fn exit_code(items: &[Item]) -> Exit {
+    let failed = items.iter().filter(|item| item.error.is_some()).count();
+    if items.iter().any(Item::mismatch) {
+        Exit::Verification
+    } else if failed == 0 {
+        Exit::Success
+    } else if failed < items.len() {
+        Exit::Partial
+    } else if items
+        .iter()
+        .any(|item| item.error.as_ref().is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied))
+    {
+        Exit::Permission
+    } else {
+        Exit::Failed
+    }
+}
+
+/// Keeps the job in the history, files the reports the policy makes
+/// mandatory and sends the webhook and mail, as the interface does. Without
+/// any of those the command line leaves no trace.
+fn record(
+    items: &[Item],
+    description: String,
+    method: &Method,
+    verification: &engine::Verification,
+    started: Instant,
+    settings: &Settings,
+    policy: &Policy,
+) {
+    if settings.simulate.is_some()
+        || !policy.certificate && !policy.audit && settings.webhook_url.is_empty() && settings.smtp_server.is_empty()
+    {
+        return;
+    }
+    let entries: Vec<history::FileEntry> = items
+        .iter()
+        .map(|item| history::FileEntry {
+            path: PathBuf::from(&item.name),
+            bytes: item.bytes,
+            error: item.error.as_ref().map(|e| e.to_string()),
+            strategy: item.strategy.as_ref().map(Strategy::to_string).unwrap_or_default(),
+        })
+        .collect();
+    let erased = items.iter().filter(|item| item.error.is_none());
+    let record = history::Record {
+        id: 0,
+        finished: history::unix_now(),
+        description,
+        method: method.to_string(),
+        preset: String::new(),
+        tag: settings.tag.clone(),
+        // Descriptors are attributed to whoever handed them over, everything
+        // else to the account running the command.
+        user: {
+            let mut callers: Vec<&str> = items.iter().filter_map(|item| item.caller.as_deref()).collect();
+            callers.dedup();
+            if callers.is_empty() { identity::os_user() } else { callers.join(", ") }
+        },
+        operator: settings.operator.clone(),
+        verification: verification.to_string(),
+        files: erased.clone().count(),
+        bytes: erased.map(|item| item.bytes).sum(),
+        duration: started.elapsed(),
+        outcome: match exit_code(items) {
+            Exit::Success => history::Outcome::Success,
+            Exit::Verification => history::Outcome::Unsanitized,
+            _ => history::Outcome::Failed,
+        },
+        entries,
+    };
+    let mut db = match db::Database::open() {
+        Ok(db) => db,
+        Err(e) => return eprintln!("Error opening history database: {}", e),
+    };
+    match db.insert(record) {
+        Ok(id) => {
+            let record = db.get(id).expect("record was just inserted");
+            policy.file_reports(record, db.key());
+            webhook::notify(settings, record);
+            mail::notify(settings, record);
+        }
+        Err(e) => eprintln!("Error saving history: {}", e),
+    }
+}
+
+fn summary(items: &[Item], exit: Exit, method: &str, verification: &str, started: Instant) -> String {
+    let erased = items.iter().filter(|item| item.error.is_none());
+    let mut out = String::from("{\n");
+    let _ = writeln!(out, "  \"status\": \"{}\",", exit.key());
+    let _ = writeln!(out, "  \"exit_code\": {},", exit as u8);
+    let _ = writeln!(out, "  \"method\": {},", quote(method));
+    let _ = writeln!(out, "  \"verification\": {},", quote(verification));
+    let _ = writeln!(out, "  \"files_erased\": {},", erased.clone().count());
+    let _ = writeln!(out, "  \"files_failed\": {},", items.len() - erased.clone().count());
+    let _ = writeln!(out, "  \"bytes_erased\": {},", erased.map(|item| item.bytes).sum::<u64>());
+    let _ = writeln!(out, "  \"duration_ms\": {},", started.elapsed().as_millis());
+    if let Some(score) = score(items) {
+        let reasons: Vec<String> = score.reasons.iter().map(|reason| quote(reason)).collect();
+        let _ = writeln!(out, "  \"confidence\": \"{}\",", score.confidence.key());
+        let _ = writeln!(out, "  \"confidence_reasons\": [{}],", reasons.join(", "));
+    }
+    out += "  \"files\": [";
+    for (index, item) in items.iter().enumerate() {
+        out += if index == 0 { "\n" } else { ",\n" };
+        let _ = write!(
+            out,
+            "    {{ \"path\": {}, \"bytes\": {}, \"verified_bytes\": {}, \"erased\": {}, \"error\": {} }}",
+            quote(&item.name),
+            item.bytes,
+            item.verified,
+            item.error.is_none(),
+            item.error.as_ref().map(|e| quote(&e.to_string())).unwrap_or_else(|| "null".to_string()),
+        );
+    }
+    out += if items.is_empty() { "]\n}\n" } else { "\n  ]\n}\n" };
+    out
+}
+
+/// How far the erase of `items` can be trusted, when the engine chose a
+/// strategy for any of them.
+fn score(items: &[Item]) -> Option<Score> {
+    let strategies: Vec<&Strategy> = items.iter().filter_map(|item| item.strategy.as_ref()).collect();
+    (!strategies.is_empty()).then(|| Score::of(strategies))
+}
+
+/// Runs `work` with a progress channel whose updates are printed to stderr.
+/// Also returns the strategy the engine chose, if it reported one.
+fn with_progress<T>(name: &str, work: impl FnOnce(&progress::Sender) -> T) -> (T, Option<Strategy>) {
+    let (tx, rx) = progress::channel();
+    let name = name.to_string();
+    let printer = runtime::blocking(move || {
+        let mut last = -10.0;
+        let mut strategy = None;
+        let mut phase = Phase::default();
+        for progress in rx.iter() {
+            // A line when a pass starts and when its read-back starts.
+            let title = phase.title();
+            phase.follow(&progress);
+            if matches!(phase, Phase::Writing { .. } | Phase::Verifying { .. }) && phase.title() != title {
+                eprintln!("{}: {}", name, phase.title());
+            }
+            match progress {
+                Progress::Strategy(chosen) => {
+                    eprintln!("{}: {}", name, chosen);
+                    strategy = Some(chosen);
+                }
+                Progress::Updated(value) if value - last >= 10.0 => {
+                    eprintln!("{}: {:.0}%", name, value);
+                    last = value;
+                }
+                Progress::MtpReplaced(mount) => {
+                    eprintln!(
+                        "{}: on an MTP device ({}), which cannot be overwritten in place; best effort: replacing with random data, then deleting",
+                        name, mount,
+                    );
+                }
+                _ => {}
+            }
+        }
+        strategy
+    });
+    let result = work(&tx);
+    drop(tx);
+    (result, runtime::block_on(printer).unwrap_or_default())
+}
+
+fn erase_paths(paths: &[PathBuf], options: &engine::Options, policy: &Policy) -> Vec<Item> {
+    let cancel = CancelToken::default();
+    let problems = match options.simulate {
+        Some(_) => Vec::new(),
+        None => space::check(paths, options.churn_directories),
+    };
+    for problem in problems.iter().filter(|problem| !problem.refuse) {
+        eprintln!("Warning: {}", problem.message);
+    }
+    let items: Vec<Item> = paths
+        .iter()
+        .map(|path| {
+            let name = path.display().to_string();
+            let bytes = std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+            let refused = problems.iter().find(|problem| problem.refuse && problem.files.contains(path));
+            let (result, strategy) = match (refused, policy.check_path(path)) {
+                (Some(problem), _) => (Err(problem.error()), None),
+                (None, Ok(())) => with_progress(&name, |tx| engine::securely_overwrite(path, options, &cancel, tx)),
+                (None, Err(e)) => (Err(e), None),
+            };
+            Item {
+                name,
+                bytes,
+                verified: *result.as_ref().unwrap_or(&0),
+                error: result.err(),
+                strategy,
+                caller: None,
+            }
+        })
+        .collect();
+    if options.churn_directories && options.simulate.is_none() {
+        let erased: Vec<&PathBuf> = paths.iter().zip(&items).filter(|(_, item)| item.error.is_none()).map(|(path, _)| path).collect();
+        // Nothing cancels a command line job halfway.
+        let _ = engine::churn_directories(&erased, &cancel);
+    }
+    items
+}
+
+/// Overwrites `region` of each file in place; nothing is renamed or deleted.
+fn overwrite_regions(paths: &[PathBuf], region: Region, options: &engine::Options, policy: &Policy) -> Vec<Item> {
+    let cancel = CancelToken::default();
+    paths
+        .iter()
+        .map(|path| {
+            let name = path.display().to_string();
+            let (result, _) = match policy.check_path(path) {
+                Ok(()) => with_progress(&name, |tx| engine::overwrite_region(path, region, options, &cancel, tx)),
+                Err(e) => (Err(e), None),
+            };
+            Item {
+                name,
+                bytes: region.length,
+                verified: *result.as_ref().unwrap_or(&0),
+                error: result.err(),
+                strategy: None,
+                caller: None,
+            }
+        })
+        .collect()
+}
+
+fn wipe_file(name: String, file: File, options: &engine::Options) -> Item {
+    let bytes = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    let (result, strategy) = with_progress(&name, |tx| engine::overwrite_open(file, options, &CancelToken::default(), tx));
+    Item {
+        name,
+        bytes,
+        verified: *result.as_ref().unwrap_or(&0),
+        error: result.err(),
+        strategy,
+        caller: None,
+    }
+}
+
+fn wipe_descriptor(number: i64, options: &engine::Options) -> Item {
+    let name = format!("descriptor {}", number);
+    match inherited(number) {
+        Ok(file) => wipe_file(name, file, options),
+        Err(e) => Item {
+            name,
+            bytes: 0,
+            verified: 0,
+            error: Some(e),
+            strategy: None,
+            caller: None,
+        },
+    }
+}
+
+#[cfg(unix)]
+fn inherited(fd: i64) -> io::Result<File> {
+    use std::os::fd::FromRawFd;
+    let fd = i32::try_from(fd).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Descriptor out of range"))?;
+    // SAFETY: F_GETFD only checks that the descriptor is open.
+    if fd < 0 || unsafe { libc::fcntl(fd, libc::F_GETFD) } < 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Descriptor {} is not open", fd)));
+    }
+    // SAFETY: the descriptor is open and was handed to this process to own.
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(windows)]
+fn inherited(handle: i64) -> io::Result<File> {
+    use std::os::windows::io::{FromRawHandle, RawHandle};
+    if handle <= 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Not a handle"));
+    }
+    // SAFETY: the caller duplicated this handle into our process for us to own.
+    Ok(unsafe { File::from_raw_handle(handle as usize as RawHandle) })
+}
+
+#[cfg(unix)]
+fn serve(path: &str, options: &engine::Options) -> io::Result<Vec<Item>> {
+    use std::io::Write;
+    use std::os::unix::net::UnixListener;
+
+    let listener = UnixListener::bind(path)?;
+    eprintln!("Waiting for descriptors on {}", path);
+    let (mut stream, _) = listener.accept()?;
+    // Callers are known by the credentials the kernel attached to the
+    // connection; one without them is turned away.
+    let caller = match identity::peer(&stream) {
+        Ok(peer) => {
+            eprintln!("Connection from {}", peer);
+            peer.name()
+        }
+        Err(e) => {
+            let _ = stream.write_all(format!("error: cannot identify the caller: {}\n", e).as_bytes());
+            let _ = std::fs::remove_file(path);
+            return Err(e);
+        }
+    };
+    let mut items = Vec::new();
+    let result = loop {
+        let files = match receive(&stream) {
+            Ok(files) if files.is_empty() => break Ok(()),
+            Ok(files) => files,
+            Err(e) => break Err(e),
+        };
+        for file in files {
+            let item = Item {
+                caller: Some(caller.clone()),
+                ..wipe_file(format!("descriptor #{}", items.len() + 1), file, options)
+            };
+            let status = match &item.error {
+                None => "ok\n".to_string(),
+                Some(e) => format!("error: {}\n", e),
+            };
+            if let Err(e) = stream.write_all(status.as_bytes()) {
+                eprintln!("Cannot report back: {}", e);
+            }
+            items.push(item);
+        }
+    };
+    let _ = std::fs::remove_file(path);
+    result.map(|()| items)
+}
+
+#[cfg(windows)]
+fn serve(_path: &str, _options: &engine::Options) -> io::Result<Vec<Item>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--fd-socket needs Unix sockets, duplicate a handle and use --handle instead",
+    ))
+}
+
+/// How many messages without descriptors in a row a caller may send
+/// before it is taken for something other than a descriptor sender.
+#[cfg(unix)]
+const EMPTY_MESSAGES: usize = 64;
+
+/// Receives the next message that carries descriptors, and those
+/// descriptors. Bytes sent without any are dropped. An empty list means
+/// the other side closed the connection.
+#[cfg(unix)]
+pub fn receive(stream: &std::os::unix::net::UnixStream) -> io::Result<Vec<File>> {
+    use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+
+    // Close the descriptors in the speech service, hdparm and any other
+    // child started while they are open; where the flag is missing they
+    // are marked right after they arrive instead.
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    const CLOEXEC: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    const CLOEXEC: libc::c_int = 0;
+
+    let mut data = [0u8; 256];
+    for _ in 0..EMPTY_MESSAGES {
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr().cast(),
+            iov_len: data.len(),
+        };
+        // u64 keeps the control buffer aligned for cmsghdr; room for 32 descriptors.
+        let mut control = [0u64; 20];
+        // SAFETY: msghdr is plain data and all-zero is a valid empty header.
+        let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr().cast();
+        message.msg_controllen = std::mem::size_of_val(&control) as _;
+
+        // SAFETY: every pointer in `message` points at live buffers of the stated size.
+        let received = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut message, CLOEXEC) };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut files = Vec::new();
+        // SAFETY: the CMSG macros walk the control buffer recvmsg just filled in.
+        unsafe {
+            let mut header = libc::CMSG_FIRSTHDR(&message);
+            while !header.is_null() {
+                if (*header).cmsg_level == libc::SOL_SOCKET && (*header).cmsg_type == libc::SCM_RIGHTS {
+                    let data = libc::CMSG_DATA(header) as *const RawFd;
+                    let count = ((*header).cmsg_len as usize - libc::CMSG_LEN(0) as usize) / std::mem::size_of::<RawFd>();
+                    for index in 0..count {
+                        let fd = data.add(index).read_unaligned();
+                        if CLOEXEC == 0 {
+                            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                        }
+                        files.push(File::from_raw_fd(fd));
+                    }
+                }
+                header = libc::CMSG_NXTHDR(&message, header);
+            }
+        }
+        if message.msg_flags & libc::MSG_CTRUNC != 0 {
+            eprintln!("Some descriptors did not fit and were dropped by the kernel");
+        }
+        // A message without descriptors is not the end of the stream.
+        if received == 0 || !files.is_empty() {
+            return Ok(files);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} messages in a row carried no descriptors", EMPTY_MESSAGES),
+    ))
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+use crate::engine::Verification;
+
+/// A named bundle of requirements an organization has to meet for a kind of
+/// deletion. Choosing one overrides the method and switches on whatever
+/// verification, certificate and audit trail it calls for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub method: &'static str,
+    pub verification: Verification,
+    pub certificate: bool,
+    pub audit: bool,
+}
+
+pub const PRESETS: [Preset; 3] = [
+    Preset {
+        name: "GDPR data subject deletion",
+        summary: "One random pass, read-back verification, certificate and audit entry.",
+        method: "random-1",
+        verification: Verification::LastPass,
+        certificate: true,
+        audit: true,
+    },
+    Preset {
+        name: "HIPAA media disposal",
+        summary: "DoD three-pass overwrite, verification, certificate and audit entry.",
+        method: "dod",
+        verification: Verification::LastPass,
+        certificate: true,
+        audit: true,
+    },
+    Preset {
+        name: "ISO 27001 information deletion",
+        summary: "Three random passes, verification and audit entry; certificate kept for the ISMS records.",
+        method: "random-3",
+        verification: Verification::LastPass,
+        certificate: true,
+        audit: true,
+    },
+];
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
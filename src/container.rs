@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use iced::widget;
+use crate::{App, Message};
+
+/// File formats whose content also ends up in files next to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Sqlite,
+    /// Word, Excel and PowerPoint, old and new formats.
+    Office,
+    /// LibreOffice and OpenOffice documents.
+    OpenDocument,
+    Zip,
+}
+
+impl Kind {
+    /// Why files next to one of these may hold its content.
+    fn note(self) -> &'static str {
+        match self {
+            Kind::Sqlite => "SQLite keeps recent changes in a write-ahead log or rollback journal beside the database",
+            Kind::Office => "Office keeps an owner file and LibreOffice a lock file beside open documents",
+            Kind::OpenDocument => "LibreOffice keeps a lock file beside open documents",
+            Kind::Zip => "the folder next to it looks like where it was extracted",
+        }
+    }
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kind::Sqlite => write!(f, "SQLite database"),
+            Kind::Office => write!(f, "Office document"),
+            Kind::OpenDocument => write!(f, "OpenDocument file"),
+            Kind::Zip => write!(f, "ZIP archive"),
+        }
+    }
+}
+
+/// What `path` is, from its first bytes and, for ZIP based formats, its
+/// extension.
+pub fn detect(path: &Path) -> Option<Kind> {
+    let mut header = [0u8; 16];
+    let read = File::open(path).and_then(|mut file| file.read(&mut header)).ok()?;
+    let header = &header[..read];
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    if header.starts_with(b"SQLite format 3\0") {
+        Some(Kind::Sqlite)
+    } else if header.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) {
+        // The compound file format of Office 97 to 2003.
+        Some(Kind::Office)
+    } else if header.starts_with(b"PK\x03\x04") {
+        Some(match extension.as_str() {
+            "docx" | "docm" | "xlsx" | "xlsm" | "pptx" | "pptm" => Kind::Office,
+            "odt" | "ods" | "odp" | "odg" => Kind::OpenDocument,
+            _ => Kind::Zip,
+        })
+    } else {
+        None
+    }
+}
+
+/// Names the files next to a container of `kind` called `name` go by.
+fn candidates(kind: Kind, name: &str, stem: &str) -> Vec<String> {
+    let lock = format!(".~lock.{}#", name);
+    match kind {
+        Kind::Sqlite => vec![format!("{}-wal", name), format!("{}-shm", name), format!("{}-journal", name)],
+        Kind::Office => {
+            // The owner file puts `~$` in front of short names and over the
+            // first characters of longer ones.
+            let dropped = match stem.chars().count() {
+                0..=6 => 0,
+                7 => 1,
+                _ => 2,
+            };
+            let rest = name.char_indices().nth(dropped).map_or(name, |(index, _)| &name[index..]);
+            vec![format!("~${}", rest), lock]
+        }
+        Kind::OpenDocument => vec![lock],
+        Kind::Zip => vec![stem.to_string()],
+    }
+}
+
+/// A container in the job and the files beside it that may hold copies of
+/// its content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub file: PathBuf,
+    pub kind: Kind,
+    pub siblings: Vec<PathBuf>,
+}
+
+impl std::fmt::Display for Hint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.file.display(), self.kind, self.kind.note())
+    }
+}
+
+/// The containers among `files` that have siblings not in `files` yet.
+pub fn hints(files: &[PathBuf]) -> Vec<Hint> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let kind = detect(file)?;
+            let name = file.file_name()?.to_string_lossy();
+            let stem = file.file_stem()?.to_string_lossy();
+            let dir = file.parent()?;
+            let siblings: Vec<PathBuf> = candidates(kind, &name, &stem)
+                .into_iter()
+                .map(|candidate| dir.join(candidate))
+                .filter(|sibling| sibling.symlink_metadata().is_ok() && !files.contains(sibling))
+                .collect();
+            (!siblings.is_empty()).then(|| Hint { file: file.clone(), kind, siblings })
+        })
+        .collect()
+}
+
+/// Offers the files next to containers in the queue, one button per
+/// container.
+pub fn warning(app: &App) -> Option<iced::Element<'_, Message>> {
+    if app.containers.is_empty() {
+        return None;
+    }
+    let hints = app.containers.iter().enumerate().map(|(index, hint)| {
+        let siblings = hint.siblings.iter().map(|sibling| widget::text!("    {}", sibling.display()).size(12).font(iced::Font::MONOSPACE).into());
+        widget::column![
+            widget::row![
+                widget::text(hint.to_string()).size(14),
+                widget::button(widget::text!("Add {} file(s)", hint.siblings.len()).size(14))
+                    .on_press_maybe((!app.erasing).then_some(Message::IncludeSiblings(index))),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+        ]
+            .extend(siblings)
+            .spacing(5)
+            .into()
+    });
+    Some(
+        widget::column![widget::text("Copies of what these files hold may also be in the files next to them.").size(14)]
+            .extend(hints)
+            .spacing(5)
+            .into(),
+    )
+}
@@ -0,0 +1,500 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::hash::{hex, unhex};
+use crate::history::{FileEntry, Outcome, Record};
+use crate::vault::{self, Key};
+use crate::{portable, reports};
+
+/// Version 1 was the flat `history.log`; version 2 is this file; version 3
+/// added the preset and verification columns to `job`; version 4 the
+/// strategy column to `file`; version 5 the tag column to `job`; version 6
+/// the user and operator columns to `job`.
+const SCHEMA_VERSION: u32 = 6;
+const HEADER: &str = "# file-eraser database";
+/// What the flat `history.log` is renamed to once imported.
+const IMPORTED: &str = "history.log.imported";
+
+/// Append-only job store. Every line is one row of the `job` or `file`
+/// table; a row that fails to parse (for example a write cut short by a
+/// crash) is skipped instead of losing the whole history.
+///
+/// Once the history is encrypted, each job and its files are appended as a
+/// single `sealed` row instead, `sealed <id> <hex>`, which stays locked
+/// until the database is given the key.
+pub struct Database {
+    path: Option<PathBuf>,
+    records: Vec<Record>,
+    next_id: u64,
+    /// Whether new rows are sealed, which is the case once a vault exists
+    /// next to the database.
+    sealed: bool,
+    /// Sealed rows that could not be opened yet, as written.
+    locked: Vec<String>,
+    key: Option<Key>,
+}
+
+impl Database {
+    /// Opens the user's history, unlocked with `FILE_ERASER_PASSPHRASE`
+    /// when it is encrypted and that is set.
+    pub fn open() -> io::Result<Self> {
+        let mut db = match portable::data_dir() {
+            Some(dir) => Self::open_at(&dir)?,
+            None => Self::in_memory(),
+        };
+        if db.sealed
+            && let Some(key) = vault::from_environment()
+        {
+            db.unlock(key);
+        }
+        Ok(db)
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            records: Vec::new(),
+            next_id: 1,
+            sealed: false,
+            locked: Vec::new(),
+            key: None,
+        }
+    }
+
+    pub fn open_at(dir: &Path) -> io::Result<Self> {
+        reports::private(dir)?;
+        let path = dir.join("history.db");
+        let mut db = Self {
+            path: Some(path.clone()),
+            sealed: dir.join(vault::FILE).exists(),
+            ..Self::in_memory()
+        };
+
+        let version = match fs::read_to_string(&path) {
+            Ok(text) => db.parse(&text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                reports::write(&path, format!("{}\nschema {}\n", HEADER, SCHEMA_VERSION).as_bytes())?;
+                1
+            }
+            Err(e) => return Err(e),
+        };
+        db.migrate(dir, version)?;
+        Ok(db)
+    }
+
+    fn parse(&mut self, text: &str) -> io::Result<u32> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a file-eraser database"));
+        }
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix("schema "))
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing schema version"))?;
+        if version == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Database has no valid schema version"));
+        }
+        if version > SCHEMA_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Database was written by a newer version"));
+        }
+
+        for line in lines {
+            self.row(line);
+        }
+        Ok(version)
+    }
+
+    fn row(&mut self, line: &str) {
+        match line.split_once(' ') {
+            Some(("job", row)) => match parse_job(row) {
+                Some(record) => {
+                    self.next_id = self.next_id.max(record.id + 1);
+                    self.records.push(record);
+                }
+                None => eprintln!("Skipping damaged job row"),
+            },
+            Some(("file", row)) => match parse_file(row) {
+                Some((id, entry)) => match self.records.iter_mut().rev().find(|record| record.id == id) {
+                    Some(record) => record.entries.push(entry),
+                    None => eprintln!("Skipping file row for unknown job {}", id),
+                },
+                None => eprintln!("Skipping damaged file row"),
+            },
+            Some(("sealed", row)) => match &self.key {
+                Some(key) => match open_sealed(key, row) {
+                    Some(rows) => rows.lines().for_each(|line| self.row(line)),
+                    None => eprintln!("Skipping damaged sealed row"),
+                },
+                // Nothing can be inserted while locked, so the ids inside
+                // can wait until then.
+                None => self.locked.push(row.to_string()),
+            },
+            _ => {}
+        }
+    }
+
+    fn migrate(&mut self, dir: &Path, mut version: u32) -> io::Result<()> {
+        while version < SCHEMA_VERSION {
+            match version {
+                1 => self.import_history_log(dir)?,
+                // Old rows simply lack the new columns, only the header changes.
+                2..=5 => self.write_header()?,
+                _ => unreachable!("no migration from schema {}", version),
+            }
+            version += 1;
+        }
+        Ok(())
+    }
+
+    /// Brings jobs from the old flat `history.log` into the database. The log
+    /// is kept under a new name rather than deleted.
+    fn import_history_log(&mut self, dir: &Path) -> io::Result<()> {
+        let log = dir.join("history.log");
+        let text = match fs::read_to_string(&log) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for line in text.lines() {
+            if let Some(record) = parse_legacy(line) {
+                self.insert(record)?;
+            }
+        }
+        fs::rename(&log, dir.join(IMPORTED))
+    }
+
+    fn write_header(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let text = fs::read_to_string(path)?;
+        let rows = text.splitn(3, '\n').nth(2).unwrap_or("");
+        reports::write(path, format!("{}\nschema {}\n{}", HEADER, SCHEMA_VERSION, rows).as_bytes())
+    }
+
+    /// How many jobs are hidden until the vault is unlocked.
+    pub fn locked(&self) -> usize {
+        self.locked.len()
+    }
+
+    /// Opens the sealed rows with `key`, from `vault::unlock`, and seals
+    /// new ones with it.
+    pub fn unlock(&mut self, key: Key) {
+        self.key = Some(key);
+        for row in std::mem::take(&mut self.locked) {
+            self.row(&format!("sealed {}", row));
+        }
+        self.records.sort_by_key(|record| record.id);
+    }
+
+    /// The key sealed rows are opened with, once there is one.
+    pub fn key(&self) -> Option<&Key> {
+        self.key.as_ref()
+    }
+
+    /// Rewrites the whole history sealed with `key`, from `vault::create`.
+    /// The old file is kept under a draft name until it is written over by
+    /// `reports::shred`, so the plain rows do not linger on the disk; the
+    /// same goes for the `history.log` imported from an old version.
+    pub fn seal(&mut self, key: Key) -> io::Result<()> {
+        self.sealed = true;
+        let Some(path) = &self.path else {
+            self.key = Some(key);
+            return Ok(());
+        };
+        let mut text = format!("{}\nschema {}\n", HEADER, SCHEMA_VERSION);
+        for record in &self.records {
+            text += &sealed_row(&key, record);
+        }
+        self.key = Some(key);
+        let plain = path.with_extension("db.plain.draft");
+        fs::hard_link(path, &plain)?;
+        reports::write(path, text.as_bytes())?;
+        reports::shred(&plain)?;
+        let imported = path.with_file_name(IMPORTED);
+        if imported.exists() {
+            reports::shred(&imported)?;
+        }
+        Ok(())
+    }
+
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Record> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    pub fn search<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a Record> {
+        self.records.iter().rev().filter(move |record| record.matches(query))
+    }
+
+    pub fn insert(&mut self, mut record: Record) -> io::Result<u64> {
+        record.id = self.next_id;
+        let mut rows = rows(&record);
+        if self.sealed && self.path.is_some() {
+            rows = sealed_row(self.key.as_ref().ok_or_else(vault::locked_error)?, &record);
+        }
+        self.next_id += 1;
+
+        let id = record.id;
+        self.records.push(record);
+        if let Some(path) = &self.path {
+            // One write per job so a crash cannot separate a job from its files.
+            let mut file = OpenOptions::new().append(true).open(path)?;
+            file.write_all(rows.as_bytes())?;
+            file.sync_data()?;
+        }
+        Ok(id)
+    }
+}
+
+/// What a sealed row of job `id` is sealed for, so it cannot be passed off
+/// as another job's.
+fn context(id: &str) -> String {
+    format!("file-eraser history job {}", id)
+}
+
+/// `record` as a single `sealed` row.
+fn sealed_row(key: &Key, record: &Record) -> String {
+    let id = record.id.to_string();
+    format!("sealed {} {}\n", id, hex(&key.seal(rows(record).as_bytes(), &context(&id))))
+}
+
+/// The rows inside a `sealed` row, as long as all of them belong to the
+/// job it was sealed for.
+fn open_sealed(key: &Key, row: &str) -> Option<String> {
+    let (id, data) = row.split_once(' ')?;
+    let rows = String::from_utf8(key.open(&unhex(data)?, &context(id))?).ok()?;
+    let belongs = |line: &str| line.split_once(' ').and_then(|(_, row)| row.split('\t').next()) == Some(id);
+    rows.lines().all(belongs).then_some(rows)
+}
+
+/// The `job` row of `record` followed by its `file` rows.
+fn rows(record: &Record) -> String {
+    let mut rows = format!(
+        "job {}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        record.id,
+        record.finished,
+        record.outcome.key(),
+        record.files,
+        record.bytes,
+        record.duration.as_millis(),
+        escape(&record.method),
+        escape(&record.description),
+        escape(&record.preset),
+        escape(&record.verification),
+        escape(&record.tag),
+        escape(&record.user),
+        escape(&record.operator),
+    );
+    for entry in &record.entries {
+        rows += &format!(
+            "file {}\t{}\t{}\t{}\t{}\n",
+            record.id,
+            entry.bytes,
+            escape(entry.error.as_deref().unwrap_or("")),
+            escape_path(&entry.path),
+            escape(&entry.strategy),
+        );
+    }
+    rows
+}
+
+fn parse_job(row: &str) -> Option<Record> {
+    let mut fields = row.split('\t');
+    let record = Record {
+        id: fields.next()?.parse().ok()?,
+        finished: fields.next()?.parse().ok()?,
+        outcome: Outcome::from_key(fields.next()?)?,
+        files: fields.next()?.parse().ok()?,
+        bytes: fields.next()?.parse().ok()?,
+        duration: Duration::from_millis(fields.next()?.parse().ok()?),
+        method: unescape(fields.next()?),
+        description: unescape(fields.next()?),
+        // Absent on rows written before schema 3.
+        preset: fields.next().map(unescape).unwrap_or_default(),
+        verification: fields.next().map(unescape).unwrap_or_default(),
+        // Absent on rows written before schema 5.
+        tag: fields.next().map(unescape).unwrap_or_default(),
+        // Absent on rows written before schema 6.
+        user: fields.next().map(unescape).unwrap_or_default(),
+        operator: fields.next().map(unescape).unwrap_or_default(),
+        entries: Vec::new(),
+    };
+    fields.next().is_none().then_some(record)
+}
+
+fn parse_file(row: &str) -> Option<(u64, FileEntry)> {
+    let mut fields = row.split('\t');
+    let id = fields.next()?.parse().ok()?;
+    let bytes = fields.next()?.parse().ok()?;
+    let error = unescape(fields.next()?);
+    let path = unescape_path(fields.next()?);
+    // Absent on rows written before schema 4.
+    let strategy = fields.next().map(unescape).unwrap_or_default();
+    let error = (!error.is_empty()).then_some(error);
+    fields.next().is_none().then_some((id, FileEntry { path, bytes, error, strategy }))
+}
+
+fn parse_legacy(line: &str) -> Option<Record> {
+    let mut fields = line.splitn(7, '\t');
+    Some(Record {
+        id: 0,
+        finished: fields.next()?.parse().ok()?,
+        outcome: Outcome::from_key(fields.next()?)?,
+        files: fields.next()?.parse().ok()?,
+        bytes: fields.next()?.parse().ok()?,
+        duration: Duration::from_millis(fields.next()?.parse().ok()?),
+        method: fields.next()?.to_string(),
+        description: fields.next()?.to_string(),
+        preset: String::new(),
+        tag: String::new(),
+        user: String::new(),
+        operator: String::new(),
+        verification: String::new(),
+        entries: Vec::new(),
+    })
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// `path` escaped like `escape` does, with the bytes (UTF-16 units on
+/// Windows) that are not valid Unicode written as `\xNN` (`\uNNNN`), so
+/// that a name in a legacy encoding comes back exactly as it was.
+#[cfg(unix)]
+fn escape_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut escaped = String::new();
+    for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+        escaped += &escape(chunk.valid());
+        for byte in chunk.invalid() {
+            escaped += &format!("\\x{:02x}", byte);
+        }
+    }
+    escaped
+}
+
+#[cfg(unix)]
+fn unescape_path(text: &str) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+
+    let mut bytes = Vec::new();
+    for piece in raw_escapes(text, 'x', 2) {
+        match piece {
+            Ok(text) => bytes.extend_from_slice(text.as_bytes()),
+            Err(byte) => bytes.push(byte as u8),
+        }
+    }
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(windows)]
+fn escape_path(path: &Path) -> String {
+    use std::os::windows::ffi::OsStrExt;
+
+    let mut escaped = String::new();
+    for unit in char::decode_utf16(path.as_os_str().encode_wide()) {
+        match unit {
+            Ok(c) => escaped += &escape(c.encode_utf8(&mut [0; 4])),
+            Err(e) => escaped += &format!("\\u{:04x}", e.unpaired_surrogate()),
+        }
+    }
+    escaped
+}
+
+#[cfg(windows)]
+fn unescape_path(text: &str) -> PathBuf {
+    use std::os::windows::ffi::OsStringExt;
+
+    let mut units = Vec::new();
+    for piece in raw_escapes(text, 'u', 4) {
+        match piece {
+            Ok(text) => units.extend(text.encode_utf16()),
+            Err(unit) => units.push(unit),
+        }
+    }
+    PathBuf::from(std::ffi::OsString::from_wide(&units))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn escape_path(path: &Path) -> String {
+    escape(&path.to_string_lossy())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unescape_path(text: &str) -> PathBuf {
+    PathBuf::from(unescape(text))
+}
+
+/// Splits an escaped path into unescaped text and the raw units written as
+/// a backslash, `marker` and `digits` hex digits. Rows from before raw
+/// escapes existed have every backslash doubled, so they never hold one.
+#[cfg(any(unix, windows))]
+fn raw_escapes(text: &str, marker: char, digits: usize) -> Vec<Result<String, u16>> {
+    let mut pieces = Vec::new();
+    let mut escaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            escaped.push(c);
+            continue;
+        }
+        let Some(next) = chars.next() else {
+            escaped.push(c);
+            break;
+        };
+        let hex: String = match next == marker {
+            true => chars.clone().take(digits).collect(),
+            false => String::new(),
+        };
+        match u16::from_str_radix(&hex, 16) {
+            Ok(unit) if hex.len() == digits && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                chars.nth(digits - 1);
+                pieces.push(Ok(unescape(&std::mem::take(&mut escaped))));
+                pieces.push(Err(unit));
+            }
+            _ => {
+                escaped.push(c);
+                escaped.push(next);
+            }
+        }
+    }
+    pieces.push(Ok(unescape(&escaped)));
+    pieces
+}
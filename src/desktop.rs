@@ -0,0 +1,75 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use iced::widget;
+use crate::{Message, Report};
+
+/// Opens `path` the way the desktop would on a double click: a folder in
+/// the file manager, a certificate in the PDF viewer.
+pub fn open(path: &Path) -> io::Result<()> {
+    open_command(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+}
+
+#[cfg(target_os = "macos")]
+fn open_command(path: &Path) -> Command {
+    let mut command = Command::new("open");
+    command.arg(path);
+    command
+}
+
+#[cfg(windows)]
+fn open_command(path: &Path) -> Command {
+    let mut command = Command::new("explorer");
+    command.arg(path);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn open_command(path: &Path) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    command
+}
+
+/// The folders `files` were in, each once and in order. A folder that was
+/// erased along with its files is replaced by the closest one still there.
+pub fn folders(files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = Vec::new();
+    for file in files {
+        let Some(folder) = file.ancestors().skip(1).find(|dir| !dir.as_os_str().is_empty() && dir.is_dir()) else {
+            continue;
+        };
+        if !folders.iter().any(|known| known == folder) {
+            folders.push(folder.to_path_buf());
+        }
+    }
+    folders
+}
+
+/// One path per line, for pasting into a ticket or a log.
+pub fn path_list(files: &[PathBuf]) -> String {
+    files.iter().map(|file| file.display().to_string()).collect::<Vec<_>>().join("\n")
+}
+
+/// Buttons for what people do next with a finished job: note down what
+/// was erased, look at the folder it was in, and open the certificate.
+pub fn actions(report: &Report) -> Option<iced::Element<'_, Message>> {
+    if report.erased.is_empty() && report.certificate.is_none() {
+        return None;
+    }
+    let folders = if folders(&report.erased).len() > 1 { "Open folders" } else { "Open folder" };
+    Some(
+        widget::row![
+            widget::button("Copy paths").on_press_maybe((!report.erased.is_empty()).then_some(Message::CopyErasedPaths)),
+            widget::button(folders).on_press_maybe((!report.erased.is_empty()).then_some(Message::OpenErasedFolders)),
+        ]
+            .push_maybe(report.certificate.as_ref().map(|_| widget::button("Open certificate").on_press(Message::OpenCertificate)))
+            .spacing(10)
+            .into(),
+    )
+}
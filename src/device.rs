@@ -0,0 +1,487 @@
+use std::fs::{self, File};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use iced_futures::futures::future::join_all;
+use toml_edit::DocumentMut;
+use crate::engine::{CancelToken, Progress};
+use crate::hidden::{self, HiddenAreas};
+use crate::method::Pass;
+use crate::progress::Sender;
+use crate::runtime;
+use crate::storage::{Simulated, Storage};
+
+/// Smallest unit a drive can fail on. Drives with 4K sectors still accept
+/// 512 byte writes through the kernel, only more slowly.
+pub const SECTOR_SIZE: u64 = 512;
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// Attempts per sector before it is given up as bad.
+const RETRIES: u32 = 3;
+/// How often an unfinished wipe records where it got to.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(180);
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Key of the method, so a checkpoint is only resumed with the same passes.
+    pub method: String,
+    pub passes: Vec<Pass>,
+    /// Continue from a checkpoint left by an interrupted wipe of the same drive.
+    pub resume: bool,
+    /// Crypto-erase self-encrypting drives that came with a PSID, overwriting
+    /// only if that fails.
+    pub crypto_erase: bool,
+    /// Open up HPA and DCO areas before overwriting so they get wiped too.
+    pub unlock_hidden: bool,
+    /// Retry failing writes sector by sector and carry on past sectors that
+    /// still fail, instead of aborting the whole wipe.
+    pub skip_bad_sectors: bool,
+    /// Only pretend, at this many bytes per second. Nothing is written and
+    /// checkpoints are neither used nor touched.
+    pub simulate: Option<u64>,
+    /// Where checkpoints are kept, normally `checkpoints()`. Without one an
+    /// interrupted wipe starts over.
+    pub checkpoints: Option<PathBuf>,
+}
+
+/// A drive to wipe. The PSID printed on the label of a self-encrypting
+/// drive allows a crypto-erase without knowing any password.
+#[derive(Clone, Debug)]
+pub struct Target {
+    pub path: PathBuf,
+    /// Serial number or WWID as read by `serial`. A checkpoint is only
+    /// written and resumed for a drive that has one.
+    pub serial: Option<String>,
+    pub psid: Option<String>,
+    pub hidden: Option<HiddenAreas>,
+}
+
+/// Sectors that could not be overwritten, kept as sorted, merged LBA ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BadSectors {
+    ranges: Vec<Range<u64>>,
+}
+
+impl BadSectors {
+    fn insert(&mut self, lba: u64) {
+        if self.contains(lba) {
+            return;
+        }
+        let index = self.ranges.partition_point(|range| range.end < lba);
+        match self.ranges.get_mut(index) {
+            Some(range) if range.end == lba => {
+                range.end += 1;
+                // Close the gap to the next range if this sector filled it.
+                if self.ranges.get(index + 1).is_some_and(|next| next.start == lba + 1) {
+                    let next = self.ranges.remove(index + 1);
+                    self.ranges[index].end = next.end;
+                }
+            }
+            Some(range) if range.start == lba + 1 => range.start = lba,
+            _ => self.ranges.insert(index, lba..lba + 1),
+        }
+    }
+
+    fn contains(&self, lba: u64) -> bool {
+        let index = self.ranges.partition_point(|range| range.end <= lba);
+        self.ranges.get(index).is_some_and(|range| range.contains(&lba))
+    }
+
+    pub fn count(&self) -> u64 {
+        self.ranges.iter().map(|range| range.end - range.start).sum()
+    }
+
+    /// Reads the list written by `Display`, for example `7, 100-107`.
+    fn parse(text: &str) -> Option<Self> {
+        let mut bad = Self::default();
+        for item in text.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (start, end) = item.split_once('-').unwrap_or((item, item));
+            let (start, end): (u64, u64) = (start.parse().ok()?, end.parse().ok()?);
+            // Ranges must come sorted and apart, as `Display` writes them.
+            if end < start || bad.ranges.last().is_some_and(|last| last.end >= start) {
+                return None;
+            }
+            bad.ranges.push(start..end + 1);
+        }
+        Some(bad)
+    }
+}
+
+impl std::fmt::Display for BadSectors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, range) in self.ranges.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            if range.end - range.start == 1 {
+                write!(f, "{}", range.start)?;
+            } else {
+                write!(f, "{}-{}", range.start, range.end - 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a finished device wipe could and could not reach.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub sectors: u64,
+    pub bad: BadSectors,
+}
+
+impl Summary {
+    pub fn unwritten_percent(&self) -> f64 {
+        if self.sectors == 0 {
+            return 0.0;
+        }
+        self.bad.count() as f64 * 100.0 / self.sectors as f64
+    }
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} sectors ({:.4}%) could not be overwritten; skipped LBAs: {}",
+            self.bad.count(),
+            self.sectors,
+            self.unwritten_percent(),
+            self.bad,
+        )
+    }
+}
+
+/// Where an interrupted wipe got to, stored in a folder that survives a
+/// crash or reboot, `checkpoints()` unless given another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Serial number and size, so a different disk that took over the same
+    /// device node is not mistaken for a half-wiped one. See `identity`.
+    pub identity: String,
+    pub method: String,
+    /// Zero based pass and byte offset of the next write.
+    pub pass: usize,
+    pub offset: u64,
+    pub bad: BadSectors,
+}
+
+impl Checkpoint {
+    fn path(dir: &Path, device: &Path) -> PathBuf {
+        let name: String = device
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        dir.join(format!("{}.toml", name))
+    }
+
+    pub fn load(dir: &Path, device: &Path) -> Option<Self> {
+        let text = fs::read_to_string(Self::path(dir, device)).ok()?;
+        let doc = text.parse::<DocumentMut>().ok()?;
+        Some(Self {
+            identity: doc.get("identity")?.as_str()?.to_string(),
+            method: doc.get("method")?.as_str()?.to_string(),
+            pass: usize::try_from(doc.get("pass")?.as_integer()?).ok()?,
+            offset: u64::try_from(doc.get("offset")?.as_integer()?).ok()?,
+            bad: BadSectors::parse(doc.get("bad")?.as_str()?)?,
+        })
+    }
+
+    fn save(&self, dir: &Path, device: &Path) -> io::Result<()> {
+        let path = Self::path(dir, device);
+        fs::create_dir_all(dir)?;
+        let text = format!(
+            "device = {:?}\nidentity = {:?}\nmethod = {:?}\npass = {}\noffset = {}\nbad = \"{}\"\n",
+            device.to_string_lossy(),
+            self.identity,
+            self.method,
+            self.pass,
+            self.offset,
+            self.bad,
+        );
+        // Written aside and renamed so a crash mid-save keeps the previous checkpoint.
+        let temp = path.with_extension("toml.tmp");
+        fs::write(&temp, text)?;
+        fs::rename(temp, path)
+    }
+
+    fn remove(dir: &Path, device: &Path) {
+        if let Err(e) = fs::remove_file(Self::path(dir, device))
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            eprintln!("Error removing checkpoint for {}: {}", device.display(), e);
+        }
+    }
+
+    /// Percentage of the whole job that was done when the checkpoint was taken.
+    pub fn percent(&self, passes: usize, size: u64) -> f32 {
+        if passes == 0 || size == 0 {
+            return 0.0;
+        }
+        (self.pass as f32 + self.offset as f32 / size as f32) * 100.0 / passes as f32
+    }
+}
+
+/// Where the app keeps checkpoints.
+pub fn checkpoints() -> Option<PathBuf> {
+    crate::portable::data_dir().map(|dir| dir.join("checkpoints"))
+}
+
+/// Serial number or WWID of the drive, where the OS exposes one.
+pub fn serial(device: &Path) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let sys = Path::new("/sys/class/block").join(device.file_name()?).join("device");
+        ["serial", "wwid"]
+            .into_iter()
+            .filter_map(|attribute| fs::read_to_string(sys.join(attribute)).ok())
+            .map(|serial| serial.trim().to_string())
+            .find(|serial| !serial.is_empty())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = device;
+        None
+    }
+}
+
+/// What a checkpoint has to match to be resumed: the serial number and the
+/// size. Without a serial another disk of the same size behind the same
+/// node would pass for this one, so there is no identity at all.
+pub fn identity(serial: Option<&str>, size: u64) -> Option<String> {
+    serial.map(|serial| format!("{} {}", serial, size))
+}
+
+/// Size of the drive in bytes, found by seeking to its end.
+pub fn size(device: &Path) -> io::Result<u64> {
+    File::open(device)?.seek(SeekFrom::End(0))
+}
+
+/// Wipes every drive at once, each on the blocking pool, so a batch takes
+/// as long as its slowest drive rather than the sum of all of them.
+pub fn wipe_all(targets: &[Target], options: &Options, cancel: &CancelToken, tx: &Sender) {
+    let wipes = targets.iter().cloned().enumerate().map(|(index, target)| {
+        let (options, cancel, tx) = (options.clone(), cancel.clone(), tx.clone());
+        runtime::blocking(move || wipe(index, &target, &options, &cancel, &tx))
+    });
+    let results: Vec<bool> = runtime::block_on(join_all(wipes))
+        .into_iter()
+        .map(|clean| clean.unwrap_or(false))
+        .collect();
+    if cancel.is_cancelled() {
+        let _ = tx.send(Progress::Cancelled);
+        return;
+    }
+    let _ = tx.send(Progress::Finished(results.iter().all(|clean| *clean)));
+}
+
+fn wipe(index: usize, target: &Target, options: &Options, cancel: &CancelToken, tx: &Sender) -> bool {
+    let device = target.path.as_path();
+    if options.crypto_erase
+        && options.simulate.is_none()
+        && let Some(psid) = &target.psid
+    {
+        match crate::sed::crypto_erase(device, psid) {
+            Ok(()) => {
+                println!("Crypto-erased {}", device.display());
+                if let Some(dir) = &options.checkpoints {
+                    Checkpoint::remove(dir, device);
+                }
+                let _ = tx.send(Progress::DriveUpdated(index, 100.0));
+                let _ = tx.send(Progress::CryptoErased(index));
+                let _ = tx.send(Progress::DriveFinished(index, true));
+                return true;
+            }
+            Err(e) => eprintln!("Crypto-erase of {} failed, overwriting instead: {}", device.display(), e),
+        }
+    }
+
+    // Whatever stays hidden is not overwritten, which the report has to say.
+    let mut hidden_left = target.hidden.map(|areas| format!("{} was not overwritten", areas));
+    let mut unlocked = None;
+    if options.unlock_hidden
+        && options.simulate.is_none()
+        && let Some(areas) = &target.hidden
+    {
+        match hidden::unlock(device, areas) {
+            Ok(()) => {
+                hidden_left = None;
+                unlocked = Some(areas);
+            }
+            Err(e) => {
+                eprintln!("Cannot unlock hidden areas of {}: {}", device.display(), e);
+                hidden_left = Some(format!("{} could not be unlocked and was not overwritten: {}", areas, e));
+            }
+        }
+    }
+
+    let clean = match overwrite(index, target, options, cancel, tx) {
+        Ok(summary) => {
+            let clean = summary.bad.count() == 0 && hidden_left.is_none();
+            let problems: Vec<String> = [(summary.bad.count() > 0).then(|| summary.to_string()), hidden_left]
+                .into_iter()
+                .flatten()
+                .collect();
+            if !clean {
+                let _ = tx.send(Progress::Failed(index, problems.join("; ")));
+            }
+            clean
+        }
+        Err(_) if cancel.is_cancelled() => false,
+        Err(e) => {
+            eprintln!("Error wiping {}: {}", device.display(), e);
+            let _ = tx.send(Progress::Failed(index, e.to_string()));
+            false
+        }
+    };
+    if let Some(areas) = unlocked
+        && let Err(e) = hidden::restore(device, areas)
+    {
+        eprintln!("Cannot restore the Host Protected Area of {}: {}", device.display(), e);
+    }
+    let _ = tx.send(Progress::DriveFinished(index, clean));
+    clean
+}
+
+fn open(device: &Path) -> io::Result<File> {
+    let mut options = File::options();
+    options.write(true);
+    // Without O_SYNC a failing sector only shows up at the final flush, long
+    // after the write that hit it.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_SYNC);
+    }
+    options.open(device)
+}
+
+fn overwrite(drive: usize, target: &Target, options: &Options, cancel: &CancelToken, tx: &Sender) -> io::Result<Summary> {
+    let device = target.path.as_path();
+    let mut file: Box<dyn Storage> = match options.simulate {
+        Some(speed) => Box::new(Simulated::new(size(device)?, speed)),
+        None => Box::new(open(device)?),
+    };
+    // Block devices report a zero length in their metadata, seeking finds the real size.
+    let size = file.seek(SeekFrom::End(0))?;
+    let mut summary = Summary {
+        sectors: size / SECTOR_SIZE,
+        bad: BadSectors::default(),
+    };
+    if size == 0 {
+        return Ok(summary);
+    }
+
+    // Checkpoints are only kept for a drive that can be told apart from
+    // any other, and never while simulating.
+    let identity = identity(target.serial.as_deref(), size);
+    let checkpoints = options.checkpoints.as_deref().filter(|_| options.simulate.is_none());
+    let mut start = (0, 0);
+    if let Some(dir) = checkpoints
+        && let Some(checkpoint) = Checkpoint::load(dir, device)
+    {
+        match &identity {
+            Some(identity)
+                if options.resume
+                    && checkpoint.identity == *identity
+                    && checkpoint.method == options.method
+                    && checkpoint.offset <= size =>
+            {
+                println!("Resuming {} at pass {}, offset {}", device.display(), checkpoint.pass + 1, checkpoint.offset);
+                start = (checkpoint.pass, checkpoint.offset);
+                summary.bad = checkpoint.bad;
+            }
+            Some(_) => {}
+            None => {
+                println!("Starting {} over, it has no serial number to match its checkpoint against", device.display());
+                Checkpoint::remove(dir, device);
+            }
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let total_work = size * options.passes.len() as u64;
+    let mut completed_work: u64 = start.0 as u64 * size + start.1;
+    let mut last_checkpoint = Instant::now();
+    let save_checkpoint = |pass: usize, offset: u64, bad: &BadSectors| {
+        let (Some(dir), Some(identity)) = (checkpoints, &identity) else {
+            return;
+        };
+        let checkpoint = Checkpoint {
+            identity: identity.clone(),
+            method: options.method.clone(),
+            pass,
+            offset,
+            bad: bad.clone(),
+        };
+        if let Err(e) = checkpoint.save(dir, device) {
+            eprintln!("Error saving checkpoint for {}: {}", device.display(), e);
+        }
+    };
+
+    for (index, pass) in options.passes.iter().enumerate().skip(start.0) {
+        let mut offset: u64 = if index == start.0 { start.1 } else { 0 };
+        while offset < size {
+            if let Err(e) = cancel.check() {
+                // A cancelled wipe can be picked up again later.
+                save_checkpoint(index, offset, &summary.bad);
+                return Err(e);
+            }
+            let current_chunk = (size - offset).min(CHUNK_SIZE as u64) as usize;
+            pass.fill(&mut buffer[..current_chunk], offset, &mut rng);
+            let written = file
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| file.write_all(&buffer[..current_chunk]));
+            match written {
+                Ok(()) => {}
+                Err(e) if !options.skip_bad_sectors => return Err(e),
+                Err(e) => {
+                    eprintln!("Write failed at offset {}: {}, retrying sector by sector", offset, e);
+                    write_sectors(file.as_mut(), &buffer[..current_chunk], offset, &mut summary.bad, cancel)?;
+                }
+            }
+            offset += current_chunk as u64;
+            completed_work += current_chunk as u64;
+            let progress = (completed_work as f32 / total_work as f32) * 100.0;
+            tx.send(Progress::DriveUpdated(drive, progress)).map_err(|_| io::Error::other("Channel error"))?;
+
+            if last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+                last_checkpoint = Instant::now();
+                save_checkpoint(index, offset, &summary.bad);
+            }
+        }
+        // Surface a flush failure but keep going, the writes already got through O_SYNC.
+        if let Err(e) = file.sync() {
+            eprintln!("Flushing {} failed: {}", device.display(), e);
+        }
+    }
+    if let Some(dir) = checkpoints {
+        Checkpoint::remove(dir, device);
+    }
+    Ok(summary)
+}
+
+/// Writes `chunk` one sector at a time, retrying each failing sector and
+/// recording those that never succeed.
+fn write_sectors(file: &mut dyn Storage, chunk: &[u8], offset: u64, bad: &mut BadSectors, cancel: &CancelToken) -> io::Result<()> {
+    for (index, sector) in chunk.chunks(SECTOR_SIZE as usize).enumerate() {
+        cancel.check()?;
+        let position = offset + index as u64 * SECTOR_SIZE;
+        let lba = position / SECTOR_SIZE;
+        // A sector that failed in an earlier pass is not retried again.
+        if bad.contains(lba) {
+            continue;
+        }
+        let written = (0..RETRIES).any(|_| {
+            file.seek(SeekFrom::Start(position))
+                .and_then(|_| file.write_all(sector))
+                .is_ok()
+        });
+        if !written {
+            bad.insert(lba);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::OnceLock;
+use iced::widget;
+use crate::{App, Message};
+
+/// Something the platform may or may not let the app do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Writes that bypass the page cache and go straight to the drive.
+    DirectIo,
+    /// Handing the blocks of a file back before it is unlinked.
+    PunchHole,
+    /// Asking a file system to discard its free blocks.
+    Trim,
+    /// The drive firmware's own erase command.
+    SecureErase,
+    /// Extended attributes, where names and metadata can linger.
+    Xattrs,
+    /// Paths longer than the 260 characters Windows allows by default.
+    LongPaths,
+    /// Running as root or administrator.
+    Elevated,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 7] = [
+        Capability::DirectIo,
+        Capability::PunchHole,
+        Capability::Trim,
+        Capability::SecureErase,
+        Capability::Xattrs,
+        Capability::LongPaths,
+        Capability::Elevated,
+    ];
+
+    /// What the capability changes for an erase, shown next to the result.
+    fn purpose(self) -> &'static str {
+        match self {
+            Capability::DirectIo => "passes reach the drive instead of stopping in the cache",
+            Capability::PunchHole => "blocks are released before the unlink (SSDs, copy-on-write)",
+            Capability::Trim => "free blocks are discarded right after a job on SSDs",
+            Capability::SecureErase => "a drive's own erase command can be sent with hdparm",
+            Capability::Xattrs => "extended attributes are removed with the file",
+            Capability::LongPaths => "files in deep folders can be reached",
+            Capability::Elevated => "drives, TRIM and shadow copies can be handled",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::DirectIo => "Direct I/O",
+            Capability::PunchHole => "Punching holes",
+            Capability::Trim => "TRIM",
+            Capability::SecureErase => "ATA secure erase",
+            Capability::Xattrs => "Extended attributes",
+            Capability::LongPaths => "Long paths",
+            Capability::Elevated => "Elevated",
+        })
+    }
+}
+
+/// The outcome of one probe: how the capability is available, or why not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub capability: Capability,
+    pub result: Result<String, String>,
+}
+
+/// Probes every capability, trying out the ones that touch files on
+/// throwaway files in `dir`.
+pub fn probe(dir: &Path) -> Vec<Check> {
+    Capability::ALL
+        .into_iter()
+        .map(|capability| Check {
+            capability,
+            result: match capability {
+                Capability::DirectIo => direct_io(dir),
+                Capability::PunchHole => punch_hole(dir),
+                Capability::Trim => trim(),
+                Capability::SecureErase => secure_erase(),
+                Capability::Xattrs => xattrs(dir),
+                Capability::LongPaths => long_paths(dir),
+                Capability::Elevated => elevated(),
+            },
+        })
+        .collect()
+}
+
+/// The capabilities of this process, probed in the temp folder the first
+/// time they are asked for. The strategy engine decides with these.
+pub fn capabilities() -> &'static [Check] {
+    static CAPABILITIES: OnceLock<Vec<Check>> = OnceLock::new();
+    CAPABILITIES.get_or_init(|| probe(&std::env::temp_dir()))
+}
+
+pub fn available(capability: Capability) -> bool {
+    capabilities().iter().any(|check| check.capability == capability && check.result.is_ok())
+}
+
+/// A file for one probe, removed again when dropped.
+struct Probe(std::path::PathBuf);
+
+impl Probe {
+    fn new(dir: &Path, name: &str) -> Self {
+        Self(dir.join(format!(".file-eraser-probe-{}-{}", name, std::process::id())))
+    }
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0).or_else(|_| fs::remove_dir_all(&self.0));
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn direct_io(dir: &Path) -> Result<String, String> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let probe = Probe::new(dir, "direct");
+    let mut file = File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(&probe.0)
+        .map_err(|e| format!("O_DIRECT refused: {}", e))?;
+    // O_DIRECT needs a buffer aligned to the logical block size.
+    let buffer = vec![0u8; 8192];
+    let start = buffer.as_ptr().align_offset(4096);
+    file.write_all(&buffer[start..start + 4096])
+        .map(|()| "O_DIRECT".to_string())
+        .map_err(|e| format!("O_DIRECT write failed: {}", e))
+}
+
+#[cfg(target_os = "macos")]
+fn direct_io(dir: &Path) -> Result<String, String> {
+    use std::os::fd::AsRawFd;
+
+    let probe = Probe::new(dir, "direct");
+    let file = File::create(&probe.0).map_err(|e| e.to_string())?;
+    // SAFETY: the descriptor is open for the duration of the call.
+    match unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } {
+        -1 => Err(format!("F_NOCACHE refused: {}", std::io::Error::last_os_error())),
+        _ => Ok("F_NOCACHE".to_string()),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn direct_io(_dir: &Path) -> Result<String, String> {
+    Err("not probed on this platform".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn punch_hole(dir: &Path) -> Result<String, String> {
+    use std::os::fd::AsRawFd;
+
+    let probe = Probe::new(dir, "punch");
+    fs::write(&probe.0, [1u8; 8192]).map_err(|e| e.to_string())?;
+    let file = File::options().write(true).open(&probe.0).map_err(|e| e.to_string())?;
+    let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+    // SAFETY: the descriptor is open for the duration of the call.
+    match unsafe { libc::fallocate(file.as_raw_fd(), mode, 0, 4096) } {
+        0 => Ok("fallocate on the temp folder's file system".to_string()),
+        _ => Err(format!("fallocate failed: {}", std::io::Error::last_os_error())),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn punch_hole(_dir: &Path) -> Result<String, String> {
+    Err("only on Linux, blocks are freed by truncation".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn trim() -> Result<String, String> {
+    // Issuing FITRIM here would discard for real, so it is judged by the
+    // privilege it needs.
+    elevated().map(|_| "FITRIM".to_string()).map_err(|_| "needs root".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn trim() -> Result<String, String> {
+    Err("left to the operating system's scheduled TRIM".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn secure_erase() -> Result<String, String> {
+    let output = std::process::Command::new("hdparm")
+        .arg("-V")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|_| "hdparm not found".to_string())?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(if version.is_empty() { "hdparm".to_string() } else { version })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn secure_erase() -> Result<String, String> {
+    Err("not supported on this platform".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn xattrs(dir: &Path) -> Result<String, String> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let probe = Probe::new(dir, "xattr");
+    File::create(&probe.0).map_err(|e| e.to_string())?;
+    let path = CString::new(probe.0.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    let value = b"probe";
+    // SAFETY: both strings are NUL terminated and `value` outlives the call.
+    match unsafe { libc::setxattr(path.as_ptr(), c"user.file-eraser".as_ptr(), value.as_ptr().cast(), value.len(), 0) } {
+        0 => Ok("user namespace".to_string()),
+        _ => Err(format!("setxattr failed: {}", std::io::Error::last_os_error())),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn xattrs(_dir: &Path) -> Result<String, String> {
+    Err("not probed on this platform".to_string())
+}
+
+fn long_paths(dir: &Path) -> Result<String, String> {
+    let probe = Probe::new(dir, "long");
+    // 300 characters below the temp folder, in components short enough for
+    // any file system.
+    let deep = (0..10).fold(probe.0.clone(), |path, _| path.join("a".repeat(29)));
+    fs::create_dir_all(crate::engine::extended_path(&deep)).map_err(|e| e.to_string())?;
+    Ok(format!("{} characters", deep.as_os_str().len()))
+}
+
+#[cfg(unix)]
+fn elevated() -> Result<String, String> {
+    // SAFETY: geteuid has no preconditions.
+    match unsafe { libc::geteuid() } {
+        0 => Ok("root".to_string()),
+        uid => Err(format!("running as user {}", uid)),
+    }
+}
+
+#[cfg(windows)]
+fn elevated() -> Result<String, String> {
+    // `net session` only succeeds in an elevated process.
+    let status = std::process::Command::new("net")
+        .arg("session")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() { Ok("administrator".to_string()) } else { Err("not elevated".to_string()) }
+}
+
+/// What this machine supports, with a way to probe again after changing
+/// something, such as restarting as administrator.
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    let checks = app.diagnostics.iter().map(|check| {
+        let (mark, color, detail) = match &check.result {
+            Ok(how) => ("Yes", iced::Color::from_rgb(0.1, 0.6, 0.1), how),
+            Err(why) => ("No", iced::Color::from_rgb(0.8, 0.1, 0.1), why),
+        };
+        widget::row![
+            widget::text(mark).color(color).width(40),
+            widget::column![
+                widget::text!("{}: {}", check.capability, detail),
+                widget::text(check.capability.purpose()).size(12),
+            ],
+        ]
+            .spacing(5)
+            .into()
+    });
+    widget::column![
+        widget::text(
+            "What this machine lets the app do. The strategy chosen for each file takes these into account."
+        ),
+        widget::column(checks).spacing(10),
+        widget::button("Check again").on_press(Message::RunDiagnostics),
+    ]
+        .spacing(20)
+        .into()
+}
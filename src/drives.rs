@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use iced::widget;
+use crate::{device, hidden, sed, units, App, Message};
+
+/// One drive of a batch wipe and how far it has got. Drives in a batch are
+/// wiped side by side, each on its own thread.
+#[derive(Debug, Clone)]
+pub struct Panel {
+    pub path: PathBuf,
+    /// `None` when the device could not be opened for reading.
+    pub size: Option<u64>,
+    /// Left behind by an interrupted wipe of this very disk.
+    pub checkpoint: Option<device::Checkpoint>,
+    /// Set when `sedutil-cli` recognizes the drive as self-encrypting.
+    pub sed: Option<sed::Sed>,
+    /// HPA or DCO areas that a plain overwrite of the device would miss.
+    pub hidden: Option<hidden::HiddenAreas>,
+    pub psid: String,
+    pub crypto_erased: bool,
+    pub progress: f32,
+    /// Progress and time of the first update, so a resumed drive's speed
+    /// does not count the part done before.
+    first_update: Option<(f32, Instant)>,
+    pub result: Option<bool>,
+}
+
+impl Panel {
+    pub fn new(path: PathBuf) -> Self {
+        let mut panel = Self {
+            path,
+            size: None,
+            checkpoint: None,
+            sed: None,
+            hidden: None,
+            psid: String::new(),
+            crypto_erased: false,
+            progress: 0.0,
+            first_update: None,
+            result: None,
+        };
+        panel.reload();
+        panel.sed = sed::detect(&panel.path).filter(sed::Sed::can_crypto_erase);
+        panel.hidden = hidden::detect(&panel.path);
+        panel
+    }
+
+    /// Reads the size and any checkpoint again, ignoring a checkpoint that
+    /// belongs to a different disk now behind the same device node.
+    pub fn reload(&mut self) {
+        self.size = device::size(&self.path).ok();
+        self.checkpoint = self.size.and_then(|size| {
+            let identity = device::identity(device::serial(&self.path).as_deref(), size)?;
+            let checkpoint = device::Checkpoint::load(&device::checkpoints()?, &self.path)?;
+            (checkpoint.identity == identity).then_some(checkpoint)
+        });
+    }
+
+    pub fn target(&self) -> device::Target {
+        device::Target {
+            path: self.path.clone(),
+            serial: device::serial(&self.path),
+            psid: sed::valid_psid(self.psid.trim()).then(|| self.psid.trim().to_string()),
+            hidden: self.hidden,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.progress = 0.0;
+        self.crypto_erased = false;
+        self.first_update = None;
+        self.result = None;
+    }
+
+    pub fn update(&mut self, progress: f32) {
+        self.first_update.get_or_insert((progress, Instant::now()));
+        self.progress = progress;
+    }
+
+    /// Bytes per second written since the first update, over all passes.
+    fn speed(&self, passes: usize) -> Option<f64> {
+        let (first, since) = self.first_update?;
+        let total = self.size? as f64 * passes as f64;
+        let elapsed = since.elapsed().as_secs_f64();
+        (elapsed >= 1.0).then(|| (self.progress - first) as f64 / 100.0 * total / elapsed)
+    }
+
+    fn eta(&self, passes: usize) -> Option<Duration> {
+        let speed = self.speed(passes).filter(|speed| *speed > 0.0)?;
+        let remaining = self.size? as f64 * passes as f64 * (100.0 - self.progress) as f64 / 100.0;
+        Some(Duration::from_secs_f64(remaining / speed))
+    }
+}
+
+/// PSID entry for self-encrypting drives; other drives get nothing.
+fn sed_row<'a>(app: &'a App, index: usize, panel: &'a Panel) -> iced::Element<'a, Message> {
+    let Some(sed) = &panel.sed else {
+        return widget::row![].into();
+    };
+    let hint = if !app.crypto_erase {
+        "crypto-erase is switched off, will be overwritten"
+    } else if sed::valid_psid(panel.psid.trim()) {
+        "will be crypto-erased in seconds"
+    } else {
+        "enter the PSID from the label to crypto-erase, otherwise overwritten"
+    };
+    widget::row![
+        widget::text!("{} self-encrypting drive", sed.standard()).size(14),
+        widget::text_input("PSID (32 characters on the drive label)", &panel.psid)
+            .on_input_maybe((!app.erasing).then_some(move |psid| Message::PsidChanged(index, psid)))
+            .width(300),
+        widget::text(hint).size(14),
+    ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
+
+fn hidden_row<'a>(app: &'a App, panel: &'a Panel) -> iced::Element<'a, Message> {
+    let Some(areas) = &panel.hidden else {
+        return widget::row![].into();
+    };
+    let action = if !app.unlock_hidden {
+        "a normal overwrite will not reach it"
+    } else if areas.dco() > 0 {
+        "it will be unlocked; removing the DCO is permanent"
+    } else {
+        "it will be unlocked for the wipe and restored afterwards"
+    };
+    widget::text!("Warning: {} ({}) is hidden, {}.", areas, units::size(areas.hidden_bytes()), action)
+        .size(14)
+        .color(iced::Color::from_rgb(0.8, 0.4, 0.0))
+        .into()
+}
+
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    if !app.policy.device_wipe {
+        return widget::text("Drive wipes are switched off by your organization's policy.").into();
+    }
+    let passes = app.settings.method.passes().len();
+    let panels = app.drives.iter().enumerate().map(|(index, panel)| {
+        let size = match panel.size {
+            Some(size) => units::size(size),
+            None => "size unknown".to_string(),
+        };
+        let resume = match &panel.checkpoint {
+            Some(checkpoint) if app.resume && checkpoint.method == app.settings.method.key() => format!(
+                "resumes at {:.1}%",
+                checkpoint.percent(passes, panel.size.unwrap_or(0)),
+            ),
+            Some(_) if app.resume => "earlier wipe used another method, starts over".to_string(),
+            _ => String::new(),
+        };
+        let status = match panel.result {
+            Some(true) if panel.crypto_erased => "Done, crypto-erased".to_string(),
+            Some(true) => "Done".to_string(),
+            Some(false) => app.failures
+                .iter()
+                .find(|(path, _)| *path == panel.path)
+                .map_or("Failed".to_string(), |(_, error)| error.clone()),
+            None if app.erasing => format!(
+                "{}/s, {} left",
+                panel.speed(passes).map_or("-".to_string(), units::speed),
+                panel.eta(passes).map_or("-".to_string(), units::clock),
+            ),
+            None => resume,
+        };
+
+        widget::column![
+            widget::row![
+                widget::text!("{} ({})", panel.path.display(), size).width(iced::Length::Fill),
+                widget::button("Remove").on_press_maybe((!app.erasing).then_some(Message::RemoveDrive(index))),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            sed_row(app, index, panel),
+            hidden_row(app, panel),
+            widget::progress_bar(0.0..=100.0, panel.progress).height(8),
+            widget::text(status).size(14),
+        ]
+            .spacing(4)
+            .into()
+    });
+
+    let wipe_button = if app.erasing {
+        widget::button("Cancel (Esc)").on_press(Message::Cancel)
+    } else {
+        widget::button("Wipe drives").on_press_maybe(
+            (app.drive_confirmed && !app.drives.is_empty()).then_some(Message::WipeDrives),
+        )
+    };
+
+    widget::column![
+        widget::text("Overwrites entire drives, including partition tables and every file system on them."),
+        widget::row![
+            widget::text_input(r"Device, for example /dev/sdb or \\.\PhysicalDrive1", &app.drive)
+                .on_input(Message::DriveChanged)
+                .on_submit(Message::AddDrive),
+            widget::button("Add").on_press_maybe(
+                (!app.erasing && !app.drive.trim().is_empty()).then_some(Message::AddDrive),
+            ),
+        ]
+            .spacing(10),
+        widget::scrollable(widget::column(panels).spacing(10)).height(iced::Length::Fill),
+        widget::text!("Method: {}", app.settings.method),
+        widget::checkbox("Resume interrupted wipes of these drives", app.resume)
+            .on_toggle(Message::ResumeToggled),
+        widget::checkbox("Crypto-erase self-encrypting drives (falls back to overwriting)", app.crypto_erase)
+            .on_toggle(Message::CryptoEraseToggled),
+        widget::checkbox("Unlock hidden areas (HPA/DCO) so the full native capacity is wiped", app.unlock_hidden)
+            .on_toggle(Message::UnlockHiddenToggled),
+        widget::checkbox("Skip bad sectors after retries instead of stopping", app.skip_bad_sectors)
+            .on_toggle(Message::SkipBadSectorsToggled),
+        widget::checkbox("I understand everything on these drives will be destroyed", app.drive_confirmed)
+            .on_toggle(Message::DriveConfirmToggled),
+        app.tag_row(),
+        app.progress_row(wipe_button),
+    ]
+        .spacing(10)
+        .into()
+}
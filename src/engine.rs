@@ -0,0 +1,1054 @@
+use std::fs::{File, FileTimes, remove_file};
+use std::io::{Write, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::chunks::{Chunks, BLOCK};
+use crate::hash::fnv1a;
+use crate::method::Pass;
+use crate::progress::Sender;
+use crate::region::Region;
+use crate::storage::{Simulated, Storage, Window};
+use crate::strategy::{Facts, Step, Strategy};
+
+#[derive(Clone, Debug)]
+pub enum Progress {
+    /// The files of the job are being looked at before any is touched,
+    /// this many of them.
+    ScanStarted(usize),
+    /// How many files have been looked at so far.
+    ScanUpdated(usize),
+    Started(usize),
+    /// Percentage of the whole file, over every pass and read-back.
+    Updated(f32),
+    /// Pass `.0` of `.1` over the current file begins, counting from one.
+    PassStarted(usize, usize),
+    /// Percentage of the current pass written.
+    WipeUpdated(f32),
+    /// Percentage of the current pass read back.
+    VerifyUpdated(f32),
+    /// Deleted names were pushed out of their folders and free blocks
+    /// trimmed, the last thing a job does before it finishes.
+    CleanupDone,
+    Failed(usize, String),
+    /// Bytes of a file that were read back and matched what was written.
+    Verified(u64),
+    /// Progress of one drive in a batch; drives are wiped side by side.
+    DriveUpdated(usize, f32),
+    DriveFinished(usize, bool),
+    /// The drive's media key was regenerated instead of overwriting it.
+    CryptoErased(usize),
+    /// Read-back did not match; the job stops and the file is left in place.
+    Unsanitized(usize, Mismatch),
+    /// The current file lives on a memory-backed file system (named here)
+    /// and was unlinked without overwriting: its data never reached a disk.
+    MemoryBacked(&'static str),
+    /// The current file is on a phone or camera mounted over MTP (how it is
+    /// mounted named here). MTP cannot write in place, so the file was only
+    /// replaced with random data of the same size and deleted: best effort,
+    /// the device may keep the old contents in flash.
+    MtpReplaced(&'static str),
+    /// Shadow copies deleted before the files were erased, or why they
+    /// could not be.
+    ShadowCopies(Result<usize, String>),
+    /// How the current file is erased, chosen from what it is stored on.
+    Strategy(Strategy),
+    Finished(bool),
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn check(&self) -> std::io::Result<()> {
+        if self.is_cancelled() {
+            Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "Cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// How much of the written data is read back and compared. Full read-back
+/// doubles the time of a pass, so cheaper levels exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verification {
+    None,
+    /// Read back this percentage of blocks of the last pass, chosen at random.
+    Sampled(u8),
+    LastPass,
+    EveryPass,
+}
+
+impl Verification {
+    pub const ALL: [Verification; 5] = [
+        Verification::None,
+        Verification::Sampled(5),
+        Verification::Sampled(25),
+        Verification::LastPass,
+        Verification::EveryPass,
+    ];
+
+    fn rank(self) -> u32 {
+        match self {
+            Verification::None => 0,
+            Verification::Sampled(percent) => u32::from(percent.min(99)),
+            Verification::LastPass => 100,
+            Verification::EveryPass => 101,
+        }
+    }
+
+    /// The stronger of the two, used when a method or preset demands read-back.
+    pub fn at_least(self, minimum: Self) -> Self {
+        if self.rank() >= minimum.rank() { self } else { minimum }
+    }
+
+    /// Percentage of blocks to check after pass `pass` (zero based) of `passes`.
+    fn coverage(self, pass: usize, passes: usize) -> u8 {
+        let last = pass + 1 == passes;
+        match self {
+            Verification::None => 0,
+            Verification::Sampled(percent) if last => percent.min(100),
+            Verification::LastPass if last => 100,
+            Verification::EveryPass => 100,
+            _ => 0,
+        }
+    }
+
+    /// How many passes are read back.
+    pub fn rounds(self, passes: usize) -> u64 {
+        (0..passes).filter(|pass| self.coverage(*pass, passes) > 0).count() as u64
+    }
+
+    pub fn key(self) -> String {
+        match self {
+            Verification::None => "none".to_string(),
+            Verification::Sampled(percent) => format!("sampled-{}", percent),
+            Verification::LastPass => "last-pass".to_string(),
+            Verification::EveryPass => "every-pass".to_string(),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "none" => Some(Verification::None),
+            "last-pass" => Some(Verification::LastPass),
+            "every-pass" => Some(Verification::EveryPass),
+            _ => key
+                .strip_prefix("sampled-")
+                .and_then(|percent| percent.parse().ok())
+                .filter(|percent| (1..=100).contains(percent))
+                .map(Verification::Sampled),
+        }
+    }
+}
+
+impl std::fmt::Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verification::None => write!(f, "No verification"),
+            Verification::Sampled(percent) => write!(f, "Sampled ({}% of blocks)", percent),
+            Verification::LastPass => write!(f, "Last pass"),
+            Verification::EveryPass => write!(f, "Every pass"),
+        }
+    }
+}
+
+/// Most offsets kept per mismatch, so a failing drive cannot flood the report.
+const MAX_OFFSETS: usize = 64;
+
+/// Storage returned different data than was written during a pass, so the
+/// file cannot be considered reliably sanitized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Pass number, starting at 1.
+    pub pass: usize,
+    /// Number of blocks that differed.
+    pub blocks: u64,
+    /// Offset of the first differing byte in each block, up to `MAX_OFFSETS`.
+    pub offsets: Vec<u64>,
+    pub cause: Cause,
+}
+
+/// Whose fault a mismatch is, as far as the checksums kept while writing
+/// can tell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cause {
+    /// No checksums were kept.
+    Unknown,
+    /// What was expected is what was written, so the storage acknowledged
+    /// writes it did not keep.
+    Storage,
+    /// What was expected is not what was written: the pattern was
+    /// regenerated wrongly, the fault is ours and the storage may be fine.
+    Pattern,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let offsets: Vec<String> = self.offsets.iter().map(|offset| offset.to_string()).collect();
+        write!(
+            f,
+            "Not reliably sanitized: pass {} read back different data in {} block(s) at offsets {}",
+            self.pass,
+            self.blocks,
+            offsets.join(", "),
+        )?;
+        if self.blocks > self.offsets.len() as u64 {
+            write!(f, ", ...")?;
+        }
+        match self.cause {
+            Cause::Unknown => Ok(()),
+            Cause::Storage => write!(f, " (the storage acknowledged writes it did not keep)"),
+            Cause::Pattern => write!(f, " (the expected pattern was regenerated wrongly, a fault in the eraser, not the storage)"),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// What to do with a file's timestamps before it is deleted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timestamps {
+    Keep,
+    /// A random moment between `FIXED_DATE` and now.
+    Random,
+    /// Always `FIXED_DATE`, so erased files all look alike.
+    Fixed,
+}
+
+/// 2000-01-01 00:00:00 UTC.
+const FIXED_DATE: u64 = 946_684_800;
+
+impl Timestamps {
+    pub const ALL: [Timestamps; 3] = [Timestamps::Keep, Timestamps::Random, Timestamps::Fixed];
+
+    fn pick(self) -> Option<SystemTime> {
+        let seconds = match self {
+            Timestamps::Keep => return None,
+            Timestamps::Random => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(FIXED_DATE, |now| now.as_secs());
+                rand::thread_rng().gen_range(FIXED_DATE..=now.max(FIXED_DATE))
+            }
+            Timestamps::Fixed => FIXED_DATE,
+        };
+        Some(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Timestamps::Keep => "keep",
+            Timestamps::Random => "random",
+            Timestamps::Fixed => "fixed",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|timestamps| timestamps.key() == key)
+    }
+}
+
+impl std::fmt::Display for Timestamps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timestamps::Keep => write!(f, "Leave timestamps alone"),
+            Timestamps::Random => write!(f, "Random past date"),
+            Timestamps::Fixed => write!(f, "Fixed date (1 January 2000)"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub passes: Vec<Pass>,
+    pub verification: Verification,
+    pub timestamps: Timestamps,
+    /// Push deleted names out of the directories the files were in.
+    pub churn_directories: bool,
+    /// Only pretend, at this many bytes per second: files are neither
+    /// written nor deleted, but progress is reported as if they were.
+    pub simulate: Option<u64>,
+    /// Keep a checksum of every block written in a verified pass, 4 bytes
+    /// per 4 KiB, so a failed read-back can tell a lying drive from a
+    /// pattern we got wrong ourselves.
+    pub checksums: bool,
+}
+
+/// Longest file name most file systems accept.
+const NAME_MAX: usize = 255;
+/// Throwaway entries per erased file, with a floor for small jobs since one
+/// directory block holds many names.
+const CHURN_PER_FILE: usize = 8;
+const CHURN_MIN: usize = 64;
+/// Random names a file goes through before it is unlinked.
+const SCRAMBLE_ROUNDS: usize = 3;
+
+pub fn erase_all(paths: &[PathBuf], options: &Options, cancel: &CancelToken, tx: &Sender) {
+    let mut result = true;
+    let mut erased: Vec<&Path> = Vec::new();
+    let mut trims: Vec<PathBuf> = Vec::new();
+    // Every file is looked at first, so the job knows what it is in for
+    // before anything is overwritten.
+    let _ = tx.send(Progress::ScanStarted(paths.len()));
+    let mut strategies = Vec::with_capacity(paths.len());
+    for path in paths {
+        if cancel.is_cancelled() {
+            let _ = tx.send(Progress::Cancelled);
+            return;
+        }
+        strategies.push(Strategy::choose(Facts::inspect(&extended_path(path))));
+        let _ = tx.send(Progress::ScanUpdated(strategies.len()));
+    }
+    for (index, (path, strategy)) in paths.iter().zip(strategies).enumerate() {
+        let _ = tx.send(Progress::Started(index));
+        let target = extended_path(path);
+        if let Some(point) = strategy.trim()
+            && !trims.iter().any(|trim| trim == point)
+        {
+            trims.push(point.to_path_buf());
+        }
+        match follow(&target, &strategy, options, cancel, tx) {
+            Ok(verified) => {
+                erased.push(path);
+                let _ = tx.send(Progress::Verified(verified));
+            }
+            Err(_) if cancel.is_cancelled() => {
+                let _ = tx.send(Progress::Cancelled);
+                return;
+            }
+            Err(e) if e.get_ref().is_some_and(|inner| inner.is::<Mismatch>()) => {
+                let mismatch = e.into_inner().and_then(|inner| inner.downcast::<Mismatch>().ok());
+                eprintln!("Verification failed for {}, stopping the job", path.display());
+                if let Some(mismatch) = mismatch {
+                    let _ = tx.send(Progress::Unsanitized(index, *mismatch));
+                }
+                // Storage that misreports writes cannot be trusted with the
+                // rest of the queue either.
+                result = false;
+                break;
+            }
+            Err(e) => {
+                eprintln!("Error erasing {}: {}", path.display(), e);
+                let _ = tx.send(Progress::Failed(index, e.to_string()));
+                result = false;
+            }
+        }
+    }
+
+    if options.churn_directories && options.simulate.is_none() && churn_directories(&erased, cancel).is_err() {
+        let _ = tx.send(Progress::Cancelled);
+        return;
+    }
+    for point in trims.into_iter().filter(|_| options.simulate.is_none()) {
+        if let Err(e) = trim(&point) {
+            eprintln!("Error trimming {}: {}", point.display(), e);
+        }
+    }
+    let _ = tx.send(Progress::CleanupDone);
+    let _ = tx.send(Progress::Finished(result));
+}
+
+/// Asks the file system mounted at `point` to discard its free blocks,
+/// which needs root.
+#[cfg(target_os = "linux")]
+pub fn trim(point: &Path) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    #[repr(C)]
+    struct Range {
+        start: u64,
+        len: u64,
+        minlen: u64,
+    }
+    const FITRIM: libc::Ioctl = 0xC018_5879_u32 as libc::Ioctl;
+
+    let dir = File::open(point)?;
+    let mut range = Range { start: 0, len: u64::MAX, minlen: 0 };
+    // SAFETY: the descriptor is open for the duration of the call and
+    // `range` is the struct FITRIM reads and updates.
+    if unsafe { libc::ioctl(dir.as_raw_fd(), FITRIM, &mut range) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn trim(_point: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::other("TRIM is only supported on Linux"))
+}
+
+/// Churns every folder the `erased` files were in. A failed churn leaves
+/// old names behind but the contents are gone, so it is logged rather than
+/// failing the job; only cancelling stops it.
+pub fn churn_directories<P: AsRef<Path>>(erased: &[P], cancel: &CancelToken) -> std::io::Result<()> {
+    let mut dirs: Vec<&Path> = erased.iter().filter_map(|path| path.as_ref().parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        let files = erased.iter().filter(|path| path.as_ref().parent() == Some(dir)).count();
+        if let Err(e) = churn_directory(dir, churn_entries(files), cancel) {
+            if cancel.is_cancelled() {
+                return Err(e);
+            }
+            eprintln!("Error churning directory {}: {}", dir.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Throwaway entries churned into a folder `files` were erased from.
+pub fn churn_entries(files: usize) -> usize {
+    (files * CHURN_PER_FILE).max(CHURN_MIN)
+}
+
+/// Directory blocks keep deleted names in slots that are only reused by new
+/// entries. Creating and deleting many maximum-length random names fills
+/// those slots so the old names are overwritten.
+fn churn_directory(dir: &Path, count: usize, cancel: &CancelToken) -> std::io::Result<()> {
+    let mut rng = rand::thread_rng();
+    let mut created = Vec::with_capacity(count);
+    let mut result = Ok(());
+    for _ in 0..count {
+        let name: String = (0..NAME_MAX).map(|_| rng.sample(rand::distributions::Alphanumeric) as char).collect();
+        let path = extended_path(&dir.join(name));
+        if let Err(e) = cancel.check().and_then(|_| File::create_new(&path)) {
+            result = Err(e);
+            break;
+        }
+        created.push(path);
+    }
+    // All entries exist at once before any is removed, otherwise they would
+    // keep reusing the same slot.
+    for path in created {
+        if let Err(e) = remove_file(&path) {
+            eprintln!("Error removing churn entry {}: {}", path.display(), e);
+        }
+    }
+    result
+}
+
+/// Rewrites `path` into the `\\?\` extended-length form so Windows accepts
+/// paths longer than `MAX_PATH`. Other platforms get the path back unchanged.
+#[cfg(windows)]
+pub fn extended_path(path: &Path) -> PathBuf {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    // Verbatim paths skip normalization, so resolve `..` and `/` first.
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let prefix = match absolute.components().next() {
+        Some(Component::Prefix(prefix)) => prefix.kind(),
+        _ => return absolute,
+    };
+
+    match prefix {
+        Prefix::Disk(_) => {
+            let mut extended = OsString::from(r"\\?\");
+            extended.push(absolute.as_os_str());
+            PathBuf::from(extended)
+        }
+        Prefix::UNC(server, share) => {
+            let mut extended = OsString::from(r"\\?\UNC\");
+            extended.push(server);
+            extended.push(r"\");
+            extended.push(share);
+            let mut extended = PathBuf::from(extended);
+            extended.extend(absolute.components().skip(2));
+            extended
+        }
+        _ => absolute,
+    }
+}
+
+#[cfg(not(windows))]
+pub fn extended_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// Overwrites and deletes `path` the way what it is stored on calls for,
+/// returning how many bytes were verified.
+pub fn securely_overwrite(path: &Path, options: &Options, cancel: &CancelToken, tx: &Sender) -> std::io::Result<u64> {
+    let path = &extended_path(path);
+    follow(path, &Strategy::choose(Facts::inspect(path)), options, cancel, tx)
+}
+
+/// Carries out `strategy` on `path`. A TRIM is left to the caller, which can
+/// do one per file system for the whole job.
+fn follow(path: &Path, strategy: &Strategy, options: &Options, cancel: &CancelToken, tx: &Sender) -> std::io::Result<u64> {
+    let send = |progress| tx.send(progress).map_err(|_| std::io::Error::other("Channel error"));
+    let mut strategy = strategy.clone();
+    if strategy.steps.contains(&Step::Decompress)
+        && options.simulate.is_none()
+        && let Err(e) = decompress(path)
+    {
+        eprintln!("Cannot switch off compression of {}: {}", path.display(), e);
+        strategy = strategy.without_decompression();
+    }
+    send(Progress::Strategy(strategy.clone()))?;
+    if let Some(speed) = options.simulate {
+        simulate(std::fs::metadata(path)?.len(), options, speed, cancel, tx)?;
+        send(Progress::Updated(100.0))?;
+        return Ok(0);
+    }
+    if strategy.steps.contains(&Step::Replace) {
+        send(Progress::MtpReplaced(strategy.facts.mtp.unwrap_or("MTP")))?;
+        replace_with_random(path, cancel, tx)?;
+        return Ok(0);
+    }
+    if let Some(filesystem) = strategy.facts.memory {
+        send(Progress::MemoryBacked(filesystem))?;
+    }
+
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .open(path)?;
+    let verified = if strategy.steps.contains(&Step::Overwrite) {
+        overwrite(&mut file, options, cancel, tx)?
+    } else {
+        0
+    };
+    if strategy.steps.contains(&Step::Release) {
+        release(&file)?;
+    }
+    delete(file, path, options.timestamps, strategy.steps.contains(&Step::Scramble))?;
+    send(Progress::Updated(100.0))?;
+    Ok(verified)
+}
+
+/// Switches off NTFS compression of `path`, which rewrites its data
+/// uncompressed so the passes that follow land on the clusters it is in.
+#[cfg(windows)]
+fn decompress(path: &Path) -> std::io::Result<()> {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+
+    const FSCTL_SET_COMPRESSION: u32 = 0x0009_C040;
+    const COMPRESSION_FORMAT_NONE: u16 = 0;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn DeviceIoControl(
+            device: *mut c_void,
+            code: u32,
+            input: *const c_void,
+            input_size: u32,
+            output: *mut c_void,
+            output_size: u32,
+            returned: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+    }
+
+    let file = File::options().read(true).write(true).open(path)?;
+    let format = COMPRESSION_FORMAT_NONE;
+    let mut returned = 0;
+    // SAFETY: the handle is open for the duration of the call, the input is
+    // the two byte format the control code takes and there is no output.
+    let done = unsafe {
+        DeviceIoControl(
+            file.as_raw_handle(),
+            FSCTL_SET_COMPRESSION,
+            (&format as *const u16).cast(),
+            2,
+            std::ptr::null_mut(),
+            0,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    };
+    if done == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Clears the file's compression flag, which is how the ntfs3 driver is
+/// told to store it uncompressed.
+#[cfg(target_os = "linux")]
+fn decompress(path: &Path) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    const FS_COMPR_FL: libc::c_int = 0x0000_0004;
+    let file = File::open(path)?;
+    let mut flags: libc::c_int = 0;
+    // SAFETY: the descriptor is open for the duration of both calls, which
+    // read and write one int.
+    unsafe {
+        if libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        flags &= !FS_COMPR_FL;
+        if libc::ioctl(file.as_raw_fd(), libc::FS_IOC_SETFLAGS, &flags) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn decompress(_path: &Path) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Hands the blocks of `file` back to the file system before it is
+/// unlinked, so a discard can reach them.
+fn release(file: &File) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::AsRawFd;
+
+        let length = file.metadata()?.len() as libc::off_t;
+        let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+        // SAFETY: the descriptor is open for the duration of the call.
+        if length > 0 && unsafe { libc::fallocate(file.as_raw_fd(), mode, 0, length) } != 0 {
+            let e = std::io::Error::last_os_error();
+            // Not every file system punches holes; truncating frees the blocks too.
+            if e.raw_os_error() != Some(libc::EOPNOTSUPP) {
+                return Err(e);
+            }
+        }
+    }
+    file.set_len(0)
+}
+
+/// Best effort for MTP devices, which move whole files: the contents are
+/// replaced by random bytes of the same length, which the device stores as
+/// a new object, and the file is deleted. Whether the old object's flash is
+/// reused is up to the device.
+fn replace_with_random(path: &Path, cancel: &CancelToken, tx: &Sender) -> std::io::Result<()> {
+    let total = std::fs::metadata(path)?.len();
+    let mut file = File::create(path)?;
+    let mut rng = rand::thread_rng();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut written: u64 = 0;
+    while written < total {
+        cancel.check()?;
+        let chunk = (total - written).min(buffer.len() as u64) as usize;
+        rng.fill(&mut buffer[..chunk]);
+        file.write_all(&buffer[..chunk])?;
+        written += chunk as u64;
+        tx.send(Progress::Updated(written as f32 / total as f32 * 99.0)).map_err(|_| std::io::Error::other("Channel error"))?;
+    }
+    // gvfs uploads the new object when the file is closed.
+    file.flush()?;
+    drop(file);
+    remove_file(path)?;
+    tx.send(Progress::Updated(100.0)).map_err(|_| std::io::Error::other("Channel error"))?;
+    Ok(())
+}
+
+/// Overwrites a file that was opened elsewhere, for example one handed over
+/// as a descriptor by a more privileged process. The file is left in place;
+/// removing it is up to whoever owns the name.
+pub fn overwrite_open(mut file: File, options: &Options, cancel: &CancelToken, tx: &Sender) -> std::io::Result<u64> {
+    let verified = match options.simulate {
+        Some(speed) => {
+            simulate(file.metadata()?.len(), options, speed, cancel, tx)?;
+            0
+        }
+        None => overwrite(&mut file, options, cancel, tx)?,
+    };
+    tx.send(Progress::Updated(100.0)).map_err(|_| std::io::Error::other("Channel error"))?;
+    Ok(verified)
+}
+
+/// Overwrites `region` of the file at `path` with every pass and leaves the
+/// rest of the file and its name alone, reporting like `erase_all` does for
+/// a single file.
+pub fn erase_region(path: &Path, region: Region, options: &Options, cancel: &CancelToken, tx: &Sender) {
+    let _ = tx.send(Progress::Started(0));
+    let result = match overwrite_region(path, region, options, cancel, tx) {
+        Ok(verified) => {
+            let _ = tx.send(Progress::Verified(verified));
+            true
+        }
+        Err(_) if cancel.is_cancelled() => {
+            let _ = tx.send(Progress::Cancelled);
+            return;
+        }
+        Err(e) if e.get_ref().is_some_and(|inner| inner.is::<Mismatch>()) => {
+            if let Some(mismatch) = e.into_inner().and_then(|inner| inner.downcast::<Mismatch>().ok()) {
+                let _ = tx.send(Progress::Unsanitized(0, *mismatch));
+            }
+            false
+        }
+        Err(e) => {
+            eprintln!("Error overwriting {} of {}: {}", region, path.display(), e);
+            let _ = tx.send(Progress::Failed(0, e.to_string()));
+            false
+        }
+    };
+    let _ = tx.send(Progress::Finished(result));
+}
+
+/// Overwrites `region` of the file at `path` in place, returning how many
+/// bytes were verified. The file is opened without truncating it, and a
+/// region reaching past its end is refused rather than growing the file.
+pub fn overwrite_region(path: &Path, region: Region, options: &Options, cancel: &CancelToken, tx: &Sender) -> std::io::Result<u64> {
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(extended_path(path))?;
+    let len = file.metadata()?.len();
+    if region.end() > len {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} reach past the end of the file ({} bytes)", region, len),
+        ));
+    }
+    let verified = match options.simulate {
+        Some(speed) => {
+            simulate(region.length, options, speed, cancel, tx)?;
+            0
+        }
+        None => overwrite(&mut Window::new(&mut file, region.offset, region.length), options, cancel, tx)?,
+    };
+    tx.send(Progress::Updated(100.0)).map_err(|_| std::io::Error::other("Channel error"))?;
+    Ok(verified)
+}
+
+/// Runs the passes over a stand-in of `len` bytes that only takes the time
+/// the writes would. Reading back is skipped, there is nothing to compare.
+fn simulate(len: u64, options: &Options, speed: u64, cancel: &CancelToken, tx: &Sender) -> std::io::Result<()> {
+    let options = Options { verification: Verification::None, ..options.clone() };
+    overwrite(&mut Simulated::new(len, speed), &options, cancel, tx)?;
+    Ok(())
+}
+
+/// Runs every pass over `file`, returning how many bytes were verified.
+pub(crate) fn overwrite(file: &mut impl Storage, options: &Options, cancel: &CancelToken, tx: &Sender) -> std::io::Result<u64> {
+    let file_size = file.len()? as usize;
+    if file_size == 0 {
+        return Ok(0);
+    }
+
+    // Grown to the chunk size as it grows, rather than to the largest up front.
+    let mut buffer = Vec::new();
+    let mut expected = Vec::new();
+    let mut writes = Chunks::default();
+    let mut reads = Chunks::default();
+
+    let passes = options.passes.len();
+    let rounds = passes as u64 + options.verification.rounds(passes);
+    let total_work = rounds * file_size as u64;
+    let mut completed_work: u64 = 0;
+    let send = |progress| tx.send(progress).map_err(|_| std::io::Error::other("Channel error"));
+    // Sent for every chunk: the channel keeps only the latest percentages.
+    let report = |completed_work: u64, phase: Progress| -> std::io::Result<()> {
+        send(phase)?;
+        send(Progress::Updated((completed_work as f32 / total_work as f32) * 100.0))
+    };
+    let percent = |remaining: usize| (file_size - remaining) as f32 / file_size as f32 * 100.0;
+
+    let mut verified: u64 = 0;
+    for (index, pass) in options.passes.iter().enumerate() {
+        // Each pass draws from its own seeded generator so the verification
+        // read-back can reproduce random data without keeping it in memory.
+        let seed = rand::random::<u64>();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let coverage = options.verification.coverage(index, passes);
+        let mut checksums = Vec::new();
+        let mut remaining = file_size;
+        send(Progress::PassStarted(index + 1, passes))?;
+        file.seek(SeekFrom::Start(0))?;
+
+        while remaining > 0 {
+            cancel.check()?;
+            let current_chunk = writes.size().min(remaining);
+            let offset = (file_size - remaining) as u64;
+            buffer.resize(buffer.len().max(current_chunk), 0);
+            fill_blocks(pass, &mut buffer[..current_chunk], offset, &mut rng);
+            if options.checksums && coverage > 0 {
+                checksums.extend(buffer[..current_chunk].chunks(BLOCK).map(fnv1a));
+            }
+            let started = Instant::now();
+            file.write_all(&buffer[..current_chunk])?;
+            writes.record(current_chunk, started.elapsed());
+            remaining -= current_chunk;
+            completed_work += current_chunk as u64;
+            report(completed_work, Progress::WipeUpdated(percent(remaining)))?;
+        }
+        file.sync()?;
+
+        if coverage == 0 {
+            continue;
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sampler = rand::thread_rng();
+        let mut mismatch = Mismatch {
+            pass: index + 1,
+            blocks: 0,
+            offsets: Vec::new(),
+            cause: Cause::Unknown,
+        };
+        let mut miswritten = false;
+        let mut remaining = file_size;
+        file.seek(SeekFrom::Start(0))?;
+
+        while remaining > 0 {
+            cancel.check()?;
+            let current_chunk = reads.size().min(remaining);
+            let offset = (file_size - remaining) as u64;
+            buffer.resize(buffer.len().max(current_chunk), 0);
+            expected.resize(expected.len().max(current_chunk), 0);
+            // The pattern has to be generated even for skipped blocks to keep
+            // the random stream in step with what was written.
+            fill_blocks(pass, &mut expected[..current_chunk], offset, &mut rng);
+            let started = Instant::now();
+            // Whole chunks are read in one go, samples block by block.
+            if coverage >= 100 {
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(&mut buffer[..current_chunk])?;
+            }
+            let blocks = buffer[..current_chunk].chunks_mut(BLOCK).zip(expected[..current_chunk].chunks(BLOCK));
+            for (number, (read, written)) in blocks.enumerate() {
+                let start = offset + (number * BLOCK) as u64;
+                if checksums.get(start as usize / BLOCK).is_some_and(|checksum| *checksum != fnv1a(written)) {
+                    miswritten = true;
+                }
+                if coverage < 100 {
+                    if sampler.gen_range(0..100) >= coverage {
+                        continue;
+                    }
+                    file.seek(SeekFrom::Start(start))?;
+                    file.read_exact(read)?;
+                }
+                // Keep reading after a mismatch so the report has every bad block.
+                match read.iter().zip(written).position(|(read, written)| read != written) {
+                    Some(position) => {
+                        mismatch.blocks += 1;
+                        if mismatch.offsets.len() < MAX_OFFSETS {
+                            mismatch.offsets.push(start + position as u64);
+                        }
+                    }
+                    None => verified += read.len() as u64,
+                }
+            }
+            reads.record(current_chunk, started.elapsed());
+            remaining -= current_chunk;
+            completed_work += current_chunk as u64;
+            report(completed_work, Progress::VerifyUpdated(percent(remaining)))?;
+        }
+        if miswritten || mismatch.blocks > 0 {
+            mismatch.cause = match (options.checksums, miswritten) {
+                (false, _) => Cause::Unknown,
+                (true, false) => Cause::Storage,
+                (true, true) => Cause::Pattern,
+            };
+            // The file is not deleted: it stays behind as evidence of what
+            // the storage did, and a user can retry it on other hardware.
+            return Err(std::io::Error::other(mismatch));
+        }
+    }
+
+    Ok(verified)
+}
+
+/// Fills `buffer`, which starts at `offset` of the file, one block at a
+/// time, so a random pass draws the same bytes whatever the chunk size.
+fn fill_blocks(pass: &Pass, buffer: &mut [u8], offset: u64, rng: &mut StdRng) {
+    for (number, block) in buffer.chunks_mut(BLOCK).enumerate() {
+        pass.fill(block, offset + (number * BLOCK) as u64, rng);
+    }
+}
+
+/// Removes the overwritten file. With timestamp fuzzing the file gets a past
+/// date first, and the parent directory keeps the times it had before the
+/// removal. The inode change time cannot be set on any platform.
+fn delete(file: File, path: &Path, timestamps: Timestamps, scramble: bool) -> std::io::Result<()> {
+    let unlink = |path: &Path| match scramble {
+        true => remove_file(scramble_name(path)),
+        false => remove_file(path),
+    };
+    let Some(time) = timestamps.pick() else {
+        drop(file);
+        return unlink(path);
+    };
+    let times = FileTimes::new().set_accessed(time).set_modified(time);
+    #[cfg(windows)]
+    let times = {
+        use std::os::windows::fs::FileTimesExt;
+        times.set_created(time)
+    };
+    file.set_times(times)?;
+    drop(file);
+
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+    let parent_times = parent.and_then(|parent| {
+        let meta = std::fs::metadata(parent).ok()?;
+        Some(FileTimes::new().set_accessed(meta.accessed().ok()?).set_modified(meta.modified().ok()?))
+    });
+    unlink(path)?;
+    if let (Some(parent), Some(times)) = (parent, parent_times)
+        && let Err(e) = open_directory(parent).and_then(|dir| dir.set_times(times))
+    {
+        eprintln!("Cannot restore timestamps of {}: {}", parent.display(), e);
+    }
+    Ok(())
+}
+
+/// Renames `path` a few times to random names as long as its own, so the
+/// directory entry left behind does not hold the real name. Returns where
+/// the file ended up; a rename that fails only stops the scrambling.
+fn scramble_name(path: &Path) -> PathBuf {
+    let length = path.file_name().map_or(8, |name| name.len()).min(NAME_MAX);
+    let mut rng = rand::thread_rng();
+    let mut current = path.to_path_buf();
+    for _ in 0..SCRAMBLE_ROUNDS {
+        let name: String = (0..length).map(|_| rng.sample(rand::distributions::Alphanumeric) as char).collect();
+        let next = current.with_file_name(name);
+        if next.exists() {
+            continue;
+        }
+        if let Err(e) = std::fs::rename(&current, &next) {
+            eprintln!("Cannot scramble the name of {}: {}", current.display(), e);
+            break;
+        }
+        current = next;
+    }
+    current
+}
+
+#[cfg(windows)]
+fn open_directory(path: &Path) -> std::io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    // FILE_FLAG_BACKUP_SEMANTICS is what allows opening a directory at all.
+    File::options().write(true).custom_flags(0x0200_0000).open(path)
+}
+
+#[cfg(not(windows))]
+fn open_directory(path: &Path) -> std::io::Result<File> {
+    File::open(path)
+}
+
+/// The name of the memory-backed file system `path` is on (tmpfs, ramfs),
+/// if it is on one.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn memory_backed(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+
+    const TMPFS_MAGIC: u32 = 0x0102_1994;
+    const RAMFS_MAGIC: u32 = 0x8584_58f6;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `path` is NUL-terminated and `stat` is a plain C struct that
+    // statfs fills in.
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    match stat.f_type as u32 {
+        TMPFS_MAGIC => Some("tmpfs"),
+        RAMFS_MAGIC => Some("ramfs"),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn memory_backed(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `path` is NUL-terminated and `stat` is a plain C struct that
+    // statfs fills in; `f_fstypename` is NUL-terminated by the kernel.
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    (name.to_bytes() == b"tmpfs").then_some("tmpfs")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+pub fn memory_backed(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+/// A line of `/proc/self/mounts`.
+#[cfg(target_os = "linux")]
+pub struct Mount {
+    /// The device, dataset or server the file system comes from.
+    pub source: String,
+    pub point: PathBuf,
+    pub fstype: String,
+    pub options: String,
+}
+
+/// The mount `path` is on: the one with the longest mount point above it.
+#[cfg(target_os = "linux")]
+pub fn mount(path: &Path) -> Option<Mount> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let path = std::path::absolute(path).ok()?;
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            Some(Mount {
+                source: unescape_mount(fields.next()?),
+                point: PathBuf::from(unescape_mount(fields.next()?)),
+                fstype: fields.next()?.to_string(),
+                options: fields.next()?.to_string(),
+            })
+        })
+        .filter(|mount| path.starts_with(&mount.point))
+        .max_by_key(|mount| mount.point.components().count())
+}
+
+/// How `path` is mounted when it is on a phone or camera over MTP: through
+/// gvfs (`/run/user/<uid>/gvfs/mtp:host=...`), jmtpfs, simple-mtpfs or
+/// go-mtpfs.
+#[cfg(target_os = "linux")]
+pub fn mtp_mount(path: &Path) -> Option<&'static str> {
+    let mount = mount(path)?;
+    match mount.fstype.as_str() {
+        "fuse.jmtpfs" => Some("jmtpfs"),
+        "fuse.simple-mtpfs" => Some("simple-mtpfs"),
+        "fuse.go-mtpfs" => Some("go-mtpfs"),
+        // One gvfs mount holds every GIO location; MTP ones are `mtp:host=...`.
+        "fuse.gvfsd-fuse" => std::path::absolute(path)
+            .ok()?
+            .strip_prefix(&mount.point)
+            .ok()?
+            .components()
+            .next()
+            .filter(|location| location.as_os_str().to_string_lossy().starts_with("mtp:"))
+            .map(|_| "gvfs"),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn mtp_mount(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+/// Undoes the octal escapes (`\040` for a space) of `/proc/self/mounts`.
+#[cfg(target_os = "linux")]
+fn unescape_mount(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4).and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match octal {
+            Some(byte) if bytes[i] == b'\\' => {
+                out.push(byte);
+                i += 4;
+            }
+            _ => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
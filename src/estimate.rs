@@ -0,0 +1,146 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use iced::widget;
+use rand::RngCore;
+use crate::history::{Outcome, Record};
+use crate::method::Method;
+use crate::{engine, space, units, App, Message};
+
+/// Jobs smaller than this spend more time opening, renaming and syncing
+/// than writing, so they say little about the drive's speed.
+const MIN_SAMPLE: u64 = 16 * 1024 * 1024;
+/// How many of the latest jobs on the volume the speed is taken from.
+const SAMPLES: usize = 20;
+/// Written by the write test when the volume has no history yet.
+const BENCHMARK_BYTES: usize = 32 * 1024 * 1024;
+const BENCHMARK_CHUNK: usize = 1024 * 1024;
+
+/// Where a speed came from, shown next to the estimates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Earlier jobs on the same volume.
+    History(usize),
+    /// A short write test beside the files.
+    Benchmark,
+    /// The speed simulation mode pretends to write at.
+    Simulated,
+}
+
+/// How fast one pass goes on the volume the files are on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Throughput {
+    /// A folder on the volume that was measured.
+    pub dir: PathBuf,
+    pub bytes_per_second: f64,
+    pub source: Source,
+}
+
+impl Throughput {
+    /// How long erasing `bytes` with `method` and `verification` takes.
+    /// Reading back a round costs about as much as writing one.
+    pub fn duration(&self, bytes: u64, method: &Method, verification: engine::Verification) -> Duration {
+        let passes = method.passes().len();
+        let rounds = passes as u64 + verification.rounds(passes);
+        Duration::from_secs_f64(bytes as f64 * rounds as f64 / self.bytes_per_second)
+    }
+}
+
+/// The speed of one pass over `dir`'s volume, from the latest successful
+/// jobs there. A job's time includes its verification, which only the
+/// method's own is known of, so jobs that verified more count as slower.
+pub fn from_history(records: &[Record], methods: &[Method], dir: &Path) -> Option<Throughput> {
+    let samples: Vec<(f64, Duration)> = records
+        .iter()
+        .rev()
+        .filter(|record| record.outcome == Outcome::Success && record.bytes >= MIN_SAMPLE && !record.duration.is_zero())
+        .filter(|record| {
+            record.entries.first().and_then(|entry| entry.path.parent()).is_some_and(|parent| space::same_volume(parent, dir))
+        })
+        .filter_map(|record| {
+            // Anything after the method's name is a note on how the job went.
+            let name = record.method.split(';').next().unwrap_or_default();
+            let method = methods.iter().find(|method| method.to_string() == name)?;
+            Some((record.bytes as f64 * method.duration_multiplier() as f64, record.duration))
+        })
+        .take(SAMPLES)
+        .collect();
+    if samples.is_empty() {
+        return None;
+    }
+    let bytes: f64 = samples.iter().map(|(bytes, _)| bytes).sum();
+    let time: Duration = samples.iter().map(|(_, time)| *time).sum();
+    Some(Throughput { dir: dir.to_path_buf(), bytes_per_second: bytes / time.as_secs_f64(), source: Source::History(samples.len()) })
+}
+
+/// Writes a few megabytes of random data beside the files, syncs them to
+/// the drive and removes them again. The data is random, so nothing of
+/// the files is in it should the blocks be read later.
+pub fn benchmark(dir: &Path) -> io::Result<Throughput> {
+    let path = dir.join(format!(".file-eraser-speed-test-{}", std::process::id()));
+    let result = write_test(&path);
+    let _ = fs::remove_file(&path);
+    let time = result?;
+    Ok(Throughput {
+        dir: dir.to_path_buf(),
+        bytes_per_second: BENCHMARK_BYTES as f64 / time.as_secs_f64().max(f64::EPSILON),
+        source: Source::Benchmark,
+    })
+}
+
+fn write_test(path: &Path) -> io::Result<Duration> {
+    let mut buffer = vec![0u8; BENCHMARK_CHUNK];
+    let mut rng = rand::thread_rng();
+    let started = Instant::now();
+    let mut file = File::create(path)?;
+    for _ in 0..BENCHMARK_BYTES / BENCHMARK_CHUNK {
+        rng.fill_bytes(&mut buffer);
+        file.write_all(&buffer)?;
+    }
+    file.sync_all()?;
+    Ok(started.elapsed())
+}
+
+/// Every method the policy allows side by side with how long it would take
+/// for the files of the job, for the confirmation step. Picking one there
+/// changes the method unless a preset or the policy fixes it.
+pub fn comparison(app: &App) -> Option<iced::Element<'_, Message>> {
+    let throughput = match &app.throughput {
+        Some(Ok(throughput)) => throughput,
+        Some(Err(e)) => return Some(widget::text!("No time estimates: {}", e).size(14).into()),
+        None => return Some(widget::text("Measuring how fast this drive writes...").size(14).into()),
+    };
+    let bytes: u64 = app.files.iter().filter_map(|file| fs::metadata(file).ok()).map(|meta| meta.len()).sum();
+    let source = match throughput.source {
+        Source::History(jobs) => format!("the last {} job(s) on this drive", jobs),
+        Source::Benchmark => "a short write test on this drive".to_string(),
+        Source::Simulated => "the simulated speed".to_string(),
+    };
+    let chosen = app.job_method();
+    let pickable = app.preset.is_none() && !app.policy.locks("method");
+    let rows = app.methods.methods().iter().enumerate().filter(|(_, method)| app.policy.allows_method(method)).map(|(index, method)| {
+        let estimate = units::about(throughput.duration(bytes, method, app.job_verification(method)));
+        let label = if *method == chosen {
+            format!("> {} - {} (chosen)", method, estimate)
+        } else {
+            format!("  {} - {}", method, estimate)
+        };
+        widget::button(widget::text(label).size(14))
+            .style(widget::button::text)
+            .padding(2)
+            .on_press_maybe((pickable && *method != chosen).then_some(Message::MethodPicked(index)))
+            .into()
+    });
+    Some(
+        widget::column![widget::text!(
+            "Time for {} with each method, going by {} ({} per pass):",
+            units::size(bytes),
+            source,
+            units::speed(throughput.bytes_per_second),
+        )
+            .size(14)]
+            .extend(rows)
+            .into(),
+    )
+}
@@ -0,0 +1,63 @@
+use std::fs::{File, remove_file};
+use std::io::Write;
+use std::path::Path;
+use rand::Rng;
+use crate::engine::{extended_path, CancelToken, Progress};
+use crate::progress::Sender;
+use crate::space;
+
+/// Runs a free-space wipe of the volume holding `dir` as a job of its own.
+pub fn wipe(dir: &Path, cancel: &CancelToken, tx: &Sender) {
+    let _ = tx.send(Progress::Started(0));
+    let result = fill(dir, cancel, tx);
+    if cancel.is_cancelled() {
+        let _ = tx.send(Progress::Cancelled);
+        return;
+    }
+    if let Err(e) = &result {
+        eprintln!("Error wiping free space in {}: {}", dir.display(), e);
+    }
+    let _ = tx.send(Progress::Finished(result.is_ok()));
+}
+
+/// Fills the volume holding `dir` with random data until it runs out of
+/// space, then deletes the filler so previously freed blocks are overwritten.
+fn fill(dir: &Path, cancel: &CancelToken, tx: &Sender) -> std::io::Result<()> {
+    let path = extended_path(&dir.join(format!(".file-eraser-fill-{:08x}", rand::random::<u32>())));
+    let total = space::room(dir).map_or(0, |room| room.bytes);
+
+    let mut file = File::create_new(&path)?;
+    let mut rng = rand::thread_rng();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut written: u64 = 0;
+    // One pass of random data, never read back.
+    tx.send(Progress::PassStarted(1, 1)).map_err(|_| std::io::Error::other("Channel error"))?;
+
+    let result = loop {
+        if let Err(e) = cancel.check() {
+            break Err(e);
+        }
+        rng.fill(&mut buffer[..]);
+        match file.write(&buffer) {
+            Ok(0) => break Ok(()),
+            Ok(n) => written += n as u64,
+            Err(e) if e.kind() == std::io::ErrorKind::StorageFull => break Ok(()),
+            Err(e) => break Err(e),
+        }
+        if total > 0 {
+            let progress = (written as f32 / total as f32 * 100.0).min(99.0);
+            if tx.send(Progress::WipeUpdated(progress)).and_then(|()| tx.send(Progress::Updated(progress))).is_err() {
+                break Err(std::io::Error::other("Channel error"));
+            }
+        }
+    };
+
+    // The disk is full at this point; get rid of the filler whatever happened.
+    let synced = file.sync_all();
+    drop(file);
+    remove_file(&path)?;
+    result.and(synced)?;
+    tx.send(Progress::Updated(100.0)).map_err(|_| std::io::Error::other("Channel error"))?;
+    tx.send(Progress::CleanupDone).map_err(|_| std::io::Error::other("Channel error"))?;
+    Ok(())
+}
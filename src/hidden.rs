@@ -0,0 +1,118 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use crate::device::SECTOR_SIZE;
+
+/// Sector counts a drive reports at its three layers. A Host Protected Area
+/// hides `native - visible` sectors from the OS, a Device Configuration
+/// Overlay hides another `real - native`. Neither is reached by writing the
+/// block device, so both have to be opened up first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HiddenAreas {
+    pub visible: u64,
+    pub native: u64,
+    pub real: u64,
+}
+
+impl HiddenAreas {
+    pub fn hpa(&self) -> u64 {
+        self.native.saturating_sub(self.visible)
+    }
+
+    pub fn dco(&self) -> u64 {
+        self.real.saturating_sub(self.native)
+    }
+
+    pub fn hidden_bytes(&self) -> u64 {
+        (self.hpa() + self.dco()) * SECTOR_SIZE
+    }
+}
+
+impl std::fmt::Display for HiddenAreas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut areas = Vec::new();
+        if self.hpa() > 0 {
+            areas.push(format!("Host Protected Area of {} sectors", self.hpa()));
+        }
+        if self.dco() > 0 {
+            areas.push(format!("Device Configuration Overlay of {} sectors", self.dco()));
+        }
+        write!(f, "{}", areas.join(" and "))
+    }
+}
+
+fn hdparm(args: &[&str], device: &Path) -> io::Result<String> {
+    let output = Command::new("hdparm")
+        .args(args)
+        .arg(device)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!("hdparm {} failed: {}", args.join(" "), stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Asks `hdparm` for the visible, native and DCO sector counts. Returns
+/// `None` when nothing is hidden, the drive is not ATA, or hdparm is missing.
+pub fn detect(device: &Path) -> Option<HiddenAreas> {
+    // " max sectors   = 976771055/976773168, HPA is enabled"
+    let max = hdparm(&["-N"], device).ok()?;
+    let counts = max.split_once('=')?.1.split(',').next()?;
+    let (visible, native) = counts.trim().split_once('/')?;
+    let (visible, native): (u64, u64) = (visible.parse().ok()?, native.parse().ok()?);
+    // " Real max sectors: 976773168"
+    let real = hdparm(&["--dco-identify"], device)
+        .ok()
+        .and_then(|text| {
+            text.lines()
+                .find_map(|line| line.trim().strip_prefix("Real max sectors:"))
+                .and_then(|count| count.trim().parse().ok())
+        })
+        .unwrap_or(native);
+
+    let areas = HiddenAreas {
+        visible,
+        native,
+        real: real.max(native),
+    };
+    (areas.hidden_bytes() > 0).then_some(areas)
+}
+
+/// Opens the hidden areas so the following overwrite reaches every sector.
+/// The HPA change is volatile and `restore` puts it back; removing a DCO is
+/// permanent, since ATA has no command to re-create one.
+pub fn unlock(device: &Path, areas: &HiddenAreas) -> io::Result<()> {
+    if areas.dco() > 0 {
+        hdparm(&["--yes-i-know-what-i-am-doing", "--dco-restore"], device)?;
+    }
+    if areas.hpa() > 0 || areas.dco() > 0 {
+        let max = areas.real.to_string();
+        hdparm(&["--yes-i-know-what-i-am-doing", "-N", &max], device)?;
+    }
+    rescan(device)
+}
+
+/// Puts the Host Protected Area back the way it was before `unlock`.
+pub fn restore(device: &Path, areas: &HiddenAreas) -> io::Result<()> {
+    if areas.hpa() == 0 {
+        return Ok(());
+    }
+    let max = areas.visible.to_string();
+    hdparm(&["--yes-i-know-what-i-am-doing", "-N", &max], device)?;
+    rescan(device)
+}
+
+/// The kernel keeps the old capacity until the device is rescanned.
+fn rescan(device: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    if let Some(name) = device.file_name() {
+        let path = Path::new("/sys/class/block").join(name).join("device").join("rescan");
+        if path.exists() {
+            std::fs::write(path, "1")?;
+        }
+    }
+    let _ = device;
+    Ok(())
+}
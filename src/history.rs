@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    Failed,
+    Cancelled,
+    /// Verification read back different data than was written.
+    Unsanitized,
+}
+
+impl Outcome {
+    pub fn key(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Failed => "failed",
+            Outcome::Cancelled => "cancelled",
+            Outcome::Unsanitized => "unsanitized",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "success" => Some(Outcome::Success),
+            "failed" => Some(Outcome::Failed),
+            "cancelled" => Some(Outcome::Cancelled),
+            "unsanitized" => Some(Outcome::Unsanitized),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub error: Option<String>,
+    /// How the file was erased given what it was stored on; empty for jobs
+    /// from before strategies were recorded and for handed-over descriptors.
+    pub strategy: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Assigned by the database when the record is inserted.
+    pub id: u64,
+    /// Seconds since the Unix epoch when the job ended.
+    pub finished: u64,
+    pub description: String,
+    pub method: String,
+    /// Compliance preset the job ran under, empty when none was chosen.
+    pub preset: String,
+    /// Case, ticket or work order number the job was tagged with, empty
+    /// when none was given.
+    pub tag: String,
+    /// Account that ran the job, or that handed the files over to it.
+    pub user: String,
+    /// Name the person at the keyboard typed in, empty when none was given.
+    pub operator: String,
+    pub verification: String,
+    /// Files and bytes that were actually destroyed.
+    pub files: usize,
+    pub bytes: u64,
+    pub duration: Duration,
+    pub outcome: Outcome,
+    pub entries: Vec<FileEntry>,
+}
+
+impl Record {
+    pub fn month(&self) -> (i64, u32) {
+        month_of(self.finished)
+    }
+
+    pub fn date(&self) -> String {
+        format_date(self.finished)
+    }
+
+    /// Case-insensitive match against the description, tag, people, method,
+    /// date and paths.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        query.is_empty()
+            || self.description.to_lowercase().contains(&query)
+            || self.tag.to_lowercase().contains(&query)
+            || self.user.to_lowercase().contains(&query)
+            || self.operator.to_lowercase().contains(&query)
+            || self.method.to_lowercase().contains(&query)
+            || self.date().contains(&query)
+            || self.entries
+                .iter()
+                .any(|entry| entry.path.to_string_lossy().to_lowercase().contains(&query))
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+/// `(year, month)` in UTC of a Unix timestamp.
+pub fn month_of(seconds: u64) -> (i64, u32) {
+    let (year, month, _) = civil_from_days((seconds / 86_400) as i64);
+    (year, month)
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC of a Unix timestamp.
+pub fn format_date(seconds: u64) -> String {
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let time = seconds % 86_400;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, time / 3600, time / 60 % 60)
+}
+
+/// RFC 5322 date of a Unix timestamp, as e-mail headers want it:
+/// `Thu, 15 Oct 2026 08:32:00 +0000`.
+pub fn mail_date(seconds: u64) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let days = seconds / 86_400;
+    let (year, month, day) = civil_from_days(days as i64);
+    let time = seconds % 86_400;
+    format!(
+        "{}, {} {} {} {:02}:{:02}:{:02} +0000",
+        // 1970-01-01 was a Thursday.
+        DAYS[(days % 7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+    )
+}
+
+/// Converts days since 1970-01-01 into a proleptic Gregorian `(year, month, day)`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
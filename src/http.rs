@@ -0,0 +1,114 @@
+use std::io;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An `http://` or `https://` address. HTTPS is verified against the
+/// Mozilla root certificates built into the binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    secure: bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Url {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (secure, rest) = match (text.strip_prefix("https://"), text.strip_prefix("http://")) {
+            (Some(rest), _) => (true, rest),
+            (None, Some(rest)) => (false, rest),
+            (None, None) => return Err(format!("{} is not an http:// or https:// address", text)),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| format!("Bad port in {}", text))?),
+            None => (authority, if secure { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(format!("No host in {}", text));
+        }
+        Ok(Self {
+            secure,
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    /// Whether requests to this address go over TLS.
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Appends `path` to this address, with exactly one slash between them.
+    pub fn join(&self, path: &str) -> Self {
+        if path.is_empty() {
+            return self.clone();
+        }
+        Self {
+            path: format!("{}/{}", self.path.trim_end_matches('/'), path.trim_start_matches('/')),
+            ..self.clone()
+        }
+    }
+}
+
+impl std::fmt::Display for Url {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scheme = if self.secure { "https" } else { "http" };
+        write!(f, "{}://{}:{}{}", scheme, self.host, self.port, self.path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// Header names in lower case, with their values.
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sends one request and reads the whole answer. Error statuses come back
+/// as a `Response` like any other; only failing to get one is an error.
+pub fn request(method: &str, url: &Url, headers: &[(&str, &str)], body: &[u8]) -> io::Result<Response> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .user_agent(&format!("file-eraser/{}", env!("CARGO_PKG_VERSION")))
+        .build();
+    let request = headers
+        .iter()
+        .fold(agent.request(method, &url.to_string()), |request, (name, value)| request.set(name, value));
+    let response = match request.send_bytes(body) {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(ureq::Error::Transport(e)) => return Err(io::Error::other(format!("{}: {}", url, e))),
+    };
+    let headers = response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.header(&name)?.to_string();
+            Some((name, value))
+        })
+        .collect();
+    Ok(Response {
+        status: response.status(),
+        headers,
+        body: response.into_string()?,
+    })
+}
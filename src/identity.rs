@@ -0,0 +1,107 @@
+use std::io;
+use std::sync::OnceLock;
+
+/// The account this process runs as, for the audit log and certificates.
+pub fn os_user() -> String {
+    static USER: OnceLock<String> = OnceLock::new();
+    USER.get_or_init(lookup).clone()
+}
+
+#[cfg(unix)]
+fn lookup() -> String {
+    // SAFETY: geteuid has no preconditions.
+    user_name(unsafe { libc::geteuid() })
+}
+
+#[cfg(windows)]
+fn lookup() -> String {
+    match (std::env::var("USERDOMAIN"), std::env::var("USERNAME")) {
+        (Ok(domain), Ok(name)) => format!("{}\\{}", domain, name),
+        (_, Ok(name)) => name,
+        _ => "unknown".to_string(),
+    }
+}
+
+/// The login name of `uid`, or the number when it has none.
+#[cfg(unix)]
+pub fn user_name(uid: libc::uid_t) -> String {
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    // SAFETY: passwd is plain data and all-zero is a valid empty entry.
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer points at live buffers of the stated size, and
+    // the strings in `entry` point into `buffer`, which outlives them.
+    unsafe {
+        if libc::getpwuid_r(uid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result) == 0 && !result.is_null() {
+            return std::ffi::CStr::from_ptr(entry.pw_name).to_string_lossy().into_owned();
+        }
+    }
+    format!("uid {}", uid)
+}
+
+/// Who is on the other end of a Unix socket, as the kernel vouches for it
+/// rather than anything the caller says.
+#[cfg(unix)]
+pub struct Peer {
+    pub uid: libc::uid_t,
+    /// Only Linux says which process connected.
+    pub pid: Option<libc::pid_t>,
+}
+
+#[cfg(unix)]
+impl Peer {
+    pub fn name(&self) -> String {
+        user_name(self.uid)
+    }
+}
+
+#[cfg(unix)]
+impl std::fmt::Display for Peer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (uid {}", self.name(), self.uid)?;
+        if let Some(pid) = self.pid {
+            write!(f, ", pid {}", pid)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn peer(stream: &std::os::unix::net::UnixStream) -> io::Result<Peer> {
+    use std::os::fd::AsRawFd;
+
+    // SAFETY: ucred is plain data and all-zero is a valid value.
+    let mut credentials: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut length = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: `credentials` is as large as `length` says.
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut credentials as *mut libc::ucred).cast(),
+            &mut length,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Peer { uid: credentials.uid, pid: Some(credentials.pid) })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn peer(stream: &std::os::unix::net::UnixStream) -> io::Result<Peer> {
+    use std::os::fd::AsRawFd;
+
+    let (mut uid, mut gid) = (0, 0);
+    // SAFETY: both out pointers point at live integers.
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Peer { uid, pid: None })
+}
+
+/// `text` on a single line, for the tab separated audit log.
+pub fn one_line(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
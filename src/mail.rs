@@ -0,0 +1,145 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use crate::certificate::{self, Format};
+use crate::history::{self, Record};
+use crate::settings::Settings;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Mails the certificate of `record` to the compliance mailbox when SMTP is
+/// configured. Failures are logged; the job itself is already over.
+pub fn notify(settings: &Settings, record: &Record) {
+    if settings.smtp_server.is_empty() || settings.smtp_to.is_empty() {
+        return;
+    }
+    if let Err(e) = send(settings, record) {
+        eprintln!("Error mailing the certificate of job {}: {}", record.id, e);
+    }
+}
+
+/// Speaks just enough SMTP to hand one message to a relay. There is no TLS
+/// or authentication, so this is meant for a relay on the machine or the
+/// local network (postfix, an Exchange connector) that does those itself.
+fn send(settings: &Settings, record: &Record) -> io::Result<()> {
+    let server = if settings.smtp_server.contains(':') {
+        settings.smtp_server.clone()
+    } else {
+        format!("{}:25", settings.smtp_server)
+    };
+    let address = server
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::other(format!("{} did not resolve", server)))?;
+    let stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut session = Session {
+        reader: BufReader::new(stream.try_clone()?),
+        writer: stream,
+    };
+
+    let recipients: Vec<&str> = settings.smtp_to.split(',').map(str::trim).filter(|to| !to.is_empty()).collect();
+    session.expect(220)?;
+    session.command("EHLO file-eraser", 250)?;
+    session.command(&format!("MAIL FROM:<{}>", settings.smtp_from), 250)?;
+    for to in &recipients {
+        session.command(&format!("RCPT TO:<{}>", to), 250)?;
+    }
+    session.command("DATA", 354)?;
+    let message = message(&settings.smtp_from, &recipients, record);
+    // Lines starting with a dot get a second one, see RFC 5321 4.5.2.
+    for line in message.trim_end_matches("\r\n").split("\r\n") {
+        let line = if line.starts_with('.') { format!(".{}", line) } else { line.to_string() };
+        session.writer.write_all(line.as_bytes())?;
+        session.writer.write_all(b"\r\n")?;
+    }
+    session.command(".", 250)?;
+    session.command("QUIT", 221)
+}
+
+struct Session {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Session {
+    /// Reads a possibly multi-line reply ("250-..." up to "250 ...") and
+    /// fails unless it has the expected code.
+    fn expect(&mut self, code: u16) -> io::Result<()> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::other("The mail server closed the connection"));
+            }
+            let received: u16 = line.get(..3).and_then(|code| code.parse().ok()).unwrap_or(0);
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+            if received != code {
+                return Err(io::Error::other(format!("The mail server answered: {}", line.trim())));
+            }
+            return Ok(());
+        }
+    }
+
+    fn command(&mut self, command: &str, code: u16) -> io::Result<()> {
+        self.writer.write_all(command.as_bytes())?;
+        self.writer.write_all(b"\r\n")?;
+        self.expect(code)
+    }
+}
+
+/// A MIME message with the certificate as text in the body and the JSON and
+/// PDF versions attached.
+fn message(from: &str, to: &[&str], record: &Record) -> String {
+    let boundary = format!("file-eraser-{}-{}", record.id, record.finished);
+    let mut out = format!(
+        "From: {}\r\nTo: {}\r\nSubject: Erasure certificate {}: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
+        from,
+        to.join(", "),
+        record.id,
+        record.outcome.key(),
+        history::mail_date(record.finished),
+        boundary,
+    );
+    out += &format!("--{}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n", boundary);
+    out += &base64_lines(certificate::text(record).as_bytes());
+    for (format, mime) in [(Format::Json, "application/json"), (Format::Pdf, "application/pdf")] {
+        out += &format!(
+            "--{}\r\nContent-Type: {}\r\nContent-Transfer-Encoding: base64\r\n\
+             Content-Disposition: attachment; filename=\"erasure-certificate-{}.{}\"\r\n\r\n",
+            boundary,
+            mime,
+            record.id,
+            format.extension(),
+        );
+        out += &base64_lines(&certificate::render(record, format));
+    }
+    out += &format!("--{}--\r\n", boundary);
+    out
+}
+
+/// Base64 in lines of 76 characters, each ending in CRLF.
+fn base64_lines(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for (index, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(n >> shift & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    let mut out = String::new();
+    for line in encoded.as_bytes().chunks(76) {
+        out += std::str::from_utf8(line).expect("base64 is ASCII");
+        out += "\r\n";
+    }
+    out
+}
@@ -1,217 +1,2665 @@
 use iced::widget;
-use iced::{Task, Theme};
-use std::fs::{File, remove_file};
-use std::io::{ Write, Seek, SeekFrom};
+use iced::{keyboard, window, Task, Theme};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 use iced::Subscription;  // جدید: برای Subscription
 use iced_futures::futures::StreamExt;  // جدید: برای map روی stream
-use rand::Rng;
-use flume::{Sender, Receiver};
+use engine::Progress;
+
+use method::{Method, Registry};
+use policy::Policy;
+use settings::Settings;
+
+mod agent;
+mod announce;
+mod audit;
+mod certificate;
+mod chunks;
+mod cli;
+mod compliance;
+mod container;
+mod db;
+mod desktop;
+mod device;
+mod diagnostics;
+mod drives;
+mod engine;
+mod estimate;
+mod free_space;
+mod hidden;
+mod hash;
+mod history;
+mod http;
+mod identity;
+mod mail;
+mod method;
+mod onboarding;
+mod pack;
+mod pattern;
+mod picker;
+mod policy;
+mod portable;
+mod reports;
+mod runtime;
+mod preview;
+mod progress;
+mod region;
+mod scan;
+mod search;
+mod sed;
+mod settings;
+mod shadow;
+mod shell;
+mod snapshot;
+mod space;
+mod staging;
+mod stats;
+mod storage;
+mod strategy;
+mod summary;
+mod swap;
+#[cfg(test)]
+mod tests;
+#[cfg(unix)]
+mod tui;
+mod units;
+mod vault;
+mod webhook;
+mod wizard;
+mod worker;
+
+/// Below this width the sidebar collapses into a tab bar above the content.
+const COMPACT_WIDTH: f32 = 640.0;
+/// How long closing the window waits for a running erase to stop.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Erase,
+    FreeSpace,
+    Region,
+    Drive,
+    Check,
+    Search,
+    History,
+    Statistics,
+    Staging,
+    Diagnostics,
+    Settings,
+}
+
+impl Tab {
+    const ALL: [Tab; 11] = [
+        Tab::Erase,
+        Tab::FreeSpace,
+        Tab::Region,
+        Tab::Drive,
+        Tab::Check,
+        Tab::Search,
+        Tab::History,
+        Tab::Statistics,
+        Tab::Staging,
+        Tab::Diagnostics,
+        Tab::Settings,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Tab::Erase => "Erase",
+            Tab::FreeSpace => "Free space",
+            Tab::Region => "Region",
+            Tab::Drive => "Drive",
+            Tab::Check => "Recoverability",
+            Tab::Search => "Search",
+            Tab::History => "History",
+            Tab::Statistics => "Statistics",
+            Tab::Staging => "Staged",
+            Tab::Diagnostics => "Diagnostics",
+            Tab::Settings => "Settings",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Report {
+    files: usize,
+    method: Method,
+    verification: String,
+    failures: Vec<(PathBuf, String)>,
+    duration: Duration,
+    outcome: history::Outcome,
+    /// How far the result can be trusted, for jobs that erased files.
+    score: Option<strategy::Score>,
+    /// Files that were destroyed.
+    erased: Vec<PathBuf>,
+    /// Files the job never got to, with why.
+    skipped: Vec<(PathBuf, String)>,
+    /// Size of the destroyed files.
+    bytes: u64,
+    /// The PDF certificate, unless none was issued or it is sealed.
+    certificate: Option<PathBuf>,
+    /// The job's history entry, to export a certificate from.
+    record: Option<u64>,
+    /// The job erased the Erase tab's queue, so what did not make it can
+    /// go back into it.
+    queue: bool,
+}
+
+impl Report {
+    fn tally(&self) -> summary::Tally {
+        summary::Tally { succeeded: self.erased.len(), skipped: self.skipped.len(), failed: self.failures.len() }
+    }
+}
+
+/// What the running job was asked to do, kept until its history record is written.
+struct Job {
+    description: String,
+    files: Vec<PathBuf>,
+    bytes: Vec<u64>,
+    size: usize,
+    method: Method,
+    preset: Option<compliance::Preset>,
+    /// Case or ticket number for the history and the certificate.
+    tag: String,
+    /// Name typed in by whoever started the job.
+    operator: String,
+    verification: engine::Verification,
+    verified: u64,
+    /// Set when read-back verification failed and the job was stopped.
+    unsanitized: bool,
+    /// Store a certificate when the job ends, whatever the preset says.
+    certificate: bool,
+    /// Drives whose media key was regenerated instead of being overwritten.
+    crypto_erased: Vec<usize>,
+    /// Files on tmpfs or a ramdisk, unlinked without overwriting.
+    memory_backed: Vec<usize>,
+    /// Files on an MTP device, only replaced with random data and deleted.
+    mtp_replaced: Vec<usize>,
+    /// How many shadow copies were deleted before erasing, if that was asked for.
+    shadow_copies: Option<Result<usize, String>>,
+    /// How each file was erased, by index into `files`.
+    strategies: Vec<(usize, strategy::Strategy)>,
+    /// Where staged files were before they went into the holding area, in
+    /// the order of `files`; empty for jobs that did not come from there.
+    originals: Vec<PathBuf>,
+    /// The last file the engine started on, by index into `files`.
+    reached: Option<usize>,
+}
+
+impl Job {
+    /// A job over `files` with nothing else set; the tag and operator are
+    /// filled in when it starts.
+    fn new(description: String, files: Vec<PathBuf>, method: Method) -> Self {
+        Self {
+            description,
+            bytes: Vec::new(),
+            size: files.len(),
+            files,
+            method,
+            preset: None,
+            tag: String::new(),
+            operator: String::new(),
+            verification: engine::Verification::None,
+            verified: 0,
+            unsanitized: false,
+            certificate: false,
+            crypto_erased: Vec::new(),
+            memory_backed: Vec::new(),
+            mtp_replaced: Vec::new(),
+            shadow_copies: None,
+            strategies: Vec::new(),
+            originals: Vec::new(),
+            reached: None,
+        }
+    }
+}
 
 struct App {
-    file: String,
+    tab: Tab,
+    settings: Settings,
+    methods: Registry,
+    policy: Policy,
+    packs: Vec<pack::Pack>,
+    files: Vec<PathBuf>,
+    free_space_dir: Option<PathBuf>,
+    /// The file and byte range of the Region tab.
+    region: region::Panel,
+    /// Block device node being typed in, before it is added to `drives`.
+    drive: String,
+    drives: Vec<drives::Panel>,
+    skip_bad_sectors: bool,
+    crypto_erase: bool,
+    unlock_hidden: bool,
+    drive_confirmed: bool,
+    resume: bool,
+    /// Device path typed into the recoverability check.
+    scan_target: String,
+    scanning: bool,
+    scan: Option<Result<scan::Report, String>>,
+    swap: swap::Report,
+    /// Shadow copies on this machine, or why they could not be listed.
+    shadows: Result<Vec<shadow::ShadowCopy>, String>,
+    /// Delete the shadow copies of the files' volumes before erasing them.
+    purge_shadows: bool,
+    /// Btrfs and ZFS snapshots holding the files in the queue.
+    snapshots: Vec<snapshot::Snapshots>,
+    /// The user chose to erase although snapshots keep the files.
+    snapshots_acknowledged: bool,
+    /// Databases and documents in the queue with journals or lock files
+    /// beside them that are not queued.
+    containers: Vec<container::Hint>,
+    /// Speed of the volume the files are on, for the confirmation step;
+    /// `None` while it is being measured.
+    throughput: Option<Result<estimate::Throughput, String>>,
+    /// Results of the diagnostics page, probed when it is first opened.
+    diagnostics: Vec<diagnostics::Check>,
+    /// The first-start walkthrough, until it is finished or skipped.
+    onboarding: Option<onboarding::Step>,
+    /// Where the file manager entries went, once they were added.
+    shell_integration: Option<Result<String, String>>,
+    /// Days after which certificates are wiped, as typed in the settings.
+    report_age: String,
+    /// Case or ticket number given to the jobs started from now on.
+    tag: String,
+    /// Name of the person at the keyboard, for the jobs started from now on.
+    operator: String,
+    /// Files waiting in the holding area, when it could be opened.
+    staging: Option<staging::Area>,
+    /// Minutes files are held, as typed in the settings.
+    staging_minutes: String,
+    /// The history and certificates are encrypted with a passphrase.
+    encrypted: bool,
+    /// The passphrase as typed in the settings, twice when it is new.
+    /// Cleared as soon as it has been used.
+    passphrase: String,
+    passphrase_again: String,
+    search_dirs: Vec<PathBuf>,
+    search_mode: search::Mode,
+    search_pattern: String,
+    search_case: bool,
+    search_hashes: Option<(PathBuf, HashSet<[u8; 32]>)>,
+    searching: bool,
+    search_cancel: engine::CancelToken,
+    search: Option<Result<search::Outcome, String>>,
+    search_confirmed: bool,
+    /// Written when matches from a hash list have been erased.
+    purge_report: Option<String>,
+    picker: picker::Options,
+    db: db::Database,
+    /// Why the history could not be opened, in which case `db` only lives
+    /// in memory and jobs are not recorded.
+    history_error: Option<String>,
+    history_query: String,
+    job: Option<Job>,
+    preset: Option<compliance::Preset>,
+    current: usize,
     progress: f32,
+    /// What the running job is doing, next to the progress bar.
+    phase: progress::Phase,
     erasing: bool,
-    receiver: Option<Receiver<Progress>>,
+    worker: Option<worker::Worker>,
+    receiver: Option<progress::Receiver>,
+    selected: Option<usize>,
+    status: String,
+    milestone: u32,
+    step: wizard::Step,
+    confirmed: bool,
+    /// Content of the first files, shown on the confirmation step.
+    previews: Vec<preview::Preview>,
+    started: Instant,
+    failures: Vec<(PathBuf, String)>,
+    report: Option<Report>,
 }
 
-#[derive(Clone, Debug)]
-enum Progress {
-    Updated(f32),
-    Finished(bool),
-}
+#[derive(Debug, Clone)]
+enum Message {
+    SelectFile(picker::Selection),
+    FileOpened(Result<picker::Picked, String>),
+    ProfileSelected(picker::Profile),
+    CustomFilterChanged(String),
+    ShowHiddenToggled(bool),
+    ClearFiles,
+    PackSelected(pack::Pack),
+    PackLoaded(String, Vec<PathBuf>),
+    PurgeShadowsToggled(bool),
+    CheckSnapshots,
+    SnapshotsAcknowledged(bool),
+    /// Queues the files beside the container at this index of `containers`.
+    IncludeSiblings(usize),
+    /// Queues the files of the last job that failed or were never reached.
+    RetryFailures,
+    RunDiagnostics,
+    OnboardingProfile(onboarding::Profile),
+    OnboardingNext,
+    OnboardingBack,
+    OnboardingSkip,
+    InstallShellIntegration,
+    ReportAgeChanged(String),
+    TagChanged(String),
+    OperatorChanged(String),
+    StagingToggled(bool),
+    StagingMinutesChanged(String),
+    RestoreStaged(u64),
+    DestroyStaged(u64),
+    /// Once a minute while files are staged, to destroy the ones that are due.
+    StagingTick,
+    WipeOldReports,
+    PassphraseChanged(String),
+    PassphraseAgainChanged(String),
+    EncryptHistory,
+    UnlockHistory,
+    EraseFile,
+    Progress(Progress),
+    ProgressTick,
+    TabSelected(Tab),
+    SelectFreeSpaceDir,
+    FreeSpaceDirOpened(Option<PathBuf>),
+    WipeFreeSpace,
+    SelectRegionFile,
+    RegionFileOpened(Option<PathBuf>),
+    RegionOffsetChanged(String),
+    RegionLengthChanged(String),
+    /// Shows the page of the hex view holding this offset.
+    RegionShow(u64),
+    RegionByteClicked(u64),
+    RegionConfirmToggled(bool),
+    OverwriteRegion,
+    DriveChanged(String),
+    AddDrive,
+    RemoveDrive(usize),
+    PsidChanged(usize, String),
+    CryptoEraseToggled(bool),
+    UnlockHiddenToggled(bool),
+    SkipBadSectorsToggled(bool),
+    DriveConfirmToggled(bool),
+    ResumeToggled(bool),
+    WipeDrives,
+    SelectScanFile,
+    ScanTargetChanged(String),
+    Scan(PathBuf),
+    ScanFinished(Result<scan::Report, String>),
+    ThroughputMeasured(Result<estimate::Throughput, String>),
+    CheckSwap,
+    WipeSwap(usize),
+    SelectSearchDir,
+    SearchDirOpened(Option<PathBuf>),
+    ClearSearchDirs,
+    SearchModeSelected(search::Mode),
+    SearchPatternChanged(String),
+    SearchCaseToggled(bool),
+    SelectHashList,
+    HashListOpened(Option<PathBuf>),
+    Search,
+    StopSearch,
+    SearchFinished(Result<search::Outcome, String>),
+    HitToggled(usize, bool),
+    SearchConfirmToggled(bool),
+    EraseHits,
+    SavePurgeReport,
+    PurgeReportSaved(Result<PathBuf, String>),
+    MethodSelected(Method),
+    MethodPicked(usize),
+    VerificationSelected(engine::Verification),
+    AdvancedToggled(bool),
+    WizardNext,
+    WizardBack,
+    WizardRestart,
+    ConfirmToggled(bool),
+    CopyErasedPaths,
+    OpenErasedFolders,
+    OpenCertificate,
+    Window(window::Id, window::Event),
+    FileSelected(usize),
+    RemoveSelected,
+    /// Moves the queue's selection down (`true`) or up.
+    MoveSelection(bool),
+    /// Moves keyboard focus to the next (`true`) or previous widget.
+    CycleFocus(bool),
+    Cancel,
+    AnnounceToggled(bool),
+    ChurnToggled(bool),
+    ChecksumsToggled(bool),
+    TimestampsSelected(engine::Timestamps),
+    UnitsSelected(units::Base),
+    WebhookUrlChanged(String),
+    WebhookSecretChanged(String),
+    SmtpServerChanged(String),
+    SmtpFromChanged(String),
+    SmtpToChanged(String),
+    HistorySearch(String),
+    PresetSelected(Option<compliance::Preset>),
+    ExportCertificate(u64, certificate::Format),
+    CertificateSaved(Result<PathBuf, String>),
+}
+
+impl App {
+    fn new(settings: Settings, methods: Registry, policy: Policy) -> Self {
+        let (db, history_error) = match db::Database::open() {
+            Ok(db) => (db, None),
+            Err(e) => {
+                eprintln!("Error opening history database: {}", e);
+                (db::Database::in_memory(), Some(e.to_string()))
+            }
+        };
+        Self {
+            tag: settings.tag.clone(),
+            operator: settings.operator.clone(),
+            staging: staging::Area::open()
+                .inspect_err(|e| eprintln!("Error opening the staging area: {}", e))
+                .ok(),
+            staging_minutes: settings.staging.unwrap_or(60).to_string(),
+            onboarding: (!settings.onboarded).then_some(onboarding::Step::Profile),
+            tab: Tab::Erase,
+            settings,
+            methods,
+            policy,
+            packs: pack::load(),
+            files: Vec::new(),
+            free_space_dir: None,
+            region: region::Panel::default(),
+            drive: String::new(),
+            skip_bad_sectors: false,
+            crypto_erase: true,
+            unlock_hidden: false,
+            drive_confirmed: false,
+            drives: Vec::new(),
+            resume: true,
+            scan_target: String::new(),
+            scanning: false,
+            scan: None,
+            swap: swap::detect(),
+            shadows: shadow::list().map_err(|e| e.to_string()),
+            purge_shadows: false,
+            snapshots: Vec::new(),
+            snapshots_acknowledged: false,
+            containers: Vec::new(),
+            throughput: None,
+            diagnostics: Vec::new(),
+            shell_integration: None,
+            report_age: "90".to_string(),
+            encrypted: vault::enabled(),
+            passphrase: String::new(),
+            passphrase_again: String::new(),
+            search_dirs: Vec::new(),
+            search_mode: search::Mode::Text,
+            search_pattern: String::new(),
+            search_case: false,
+            search_hashes: None,
+            searching: false,
+            search_cancel: engine::CancelToken::default(),
+            search: None,
+            search_confirmed: false,
+            purge_report: None,
+            picker: picker::Options::default(),
+            status: match &history_error {
+                Some(e) => format!("The history cannot be opened, jobs are not recorded: {}", e),
+                None => "Ready".to_string(),
+            },
+            db,
+            history_error,
+            history_query: String::new(),
+            job: None,
+            preset: None,
+            current: 0,
+            progress: 0.0,
+            phase: progress::Phase::default(),
+            erasing: false,
+            worker: None,
+            receiver: None,
+            selected: None,
+            milestone: 0,
+            step: wizard::Step::Pick,
+            confirmed: false,
+            previews: Vec::new(),
+            started: Instant::now(),
+            failures: Vec::new(),
+            report: None,
+        }
+    }
+
+    fn update(&mut self, message: Message) -> iced::Task<Message> {
+        match message {
+            Message::EraseFile => {
+                println!("Erasing file start");
+                // The guided flow only erases from its confirmation step.
+                let confirmed = self.settings.advanced || (self.step == wizard::Step::Confirm && self.confirmed);
+                // Ctrl+E reaches here from every tab, but only the queue's is meant.
+                if self.tab != Tab::Erase {
+                    return iced::Task::none();
+                }
+                if !self.erasing && !self.files.is_empty() && confirmed && !self.snapshots_acknowledged {
+                    // Snapshots may have been removed or taken since the last look.
+                    self.snapshots = snapshot::detect(&self.files);
+                    if snapshot::blocking(&self.snapshots) {
+                        self.announce("Snapshots still hold these files: remove them and check again, or choose to erase anyway".to_string());
+                        return iced::Task::none();
+                    }
+                }
+                if !self.erasing && !self.files.is_empty() && confirmed && self.settings.staging.is_some() && self.settings.simulate.is_none() {
+                    self.stage_files();
+                    return iced::Task::none();
+                }
+                if !self.erasing && !self.files.is_empty() && confirmed && self.erase(self.files.clone()) && !self.settings.advanced {
+                    self.step = wizard::Step::Progress;
+                }
+                iced::Task::none()
+            },
+            Message::ProgressTick => {
+                let latest = self.receiver.as_ref().map(progress::Receiver::latest).unwrap_or_default();
+                iced::Task::batch(latest.into_iter().map(|p| self.update(Message::Progress(p))))
+            }
+            Message::Progress(p) => {
+                println!("Progress received: {:?}", p);
+                self.phase.follow(&p);
+                match p {
+                    Progress::Started(index) => {
+                        self.current = index;
+                        if let Some(job) = &mut self.job {
+                            job.reached = Some(index);
+                        }
+                    }
+                    Progress::Updated(val) => {
+                        // Spread the per-file percentage over the whole queue.
+                        let total = self.job.as_ref().map_or(1, |job| job.size.max(1)) as f32;
+                        self.progress = (self.current as f32 * 100.0 + val) / total;
+                        let milestone = self.progress as u32 / 25;
+                        if milestone > self.milestone && milestone < 4 {
+                            self.milestone = milestone;
+                            self.announce(format!("{} percent complete", milestone * 25));
+                        }
+                    }
+                    Progress::Failed(index, error) => {
+                        if let Some(path) = self.job.as_ref().and_then(|job| job.files.get(index)) {
+                            self.failures.push((path.clone(), error));
+                        }
+                    }
+                    Progress::Strategy(strategy) => {
+                        if let Some(job) = &mut self.job {
+                            job.strategies.push((self.current, strategy));
+                        }
+                    }
+                    // Followed by `phase` above.
+                    Progress::ScanStarted(_) | Progress::ScanUpdated(_) | Progress::PassStarted(..)
+                    | Progress::WipeUpdated(_) | Progress::VerifyUpdated(_) | Progress::CleanupDone => {}
+                    Progress::Verified(bytes) => {
+                        if let Some(job) = &mut self.job {
+                            job.verified += bytes;
+                        }
+                    }
+                    Progress::DriveUpdated(index, progress) => {
+                        if let Some(panel) = self.drives.get_mut(index) {
+                            panel.update(progress);
+                        }
+                        let total: f32 = self.drives.iter().map(|panel| panel.progress).sum();
+                        self.progress = total / self.drives.len().max(1) as f32;
+                    }
+                    Progress::CryptoErased(index) => {
+                        if let Some(job) = &mut self.job {
+                            job.crypto_erased.push(index);
+                        }
+                        if let Some(panel) = self.drives.get_mut(index) {
+                            panel.crypto_erased = true;
+                        }
+                    }
+                    Progress::MemoryBacked(filesystem) => {
+                        if let Some(job) = &mut self.job {
+                            job.memory_backed.push(self.current);
+                        }
+                        self.announce(format!(
+                            "File is on {}, which lives in memory: deleted without overwriting, as passes would only waste time",
+                            filesystem,
+                        ));
+                    }
+                    Progress::MtpReplaced(mount) => {
+                        if let Some(job) = &mut self.job {
+                            job.mtp_replaced.push(self.current);
+                        }
+                        self.announce(format!(
+                            "File is on a phone or camera ({} MTP mount), which cannot be overwritten in place: best effort, replaced with random data and deleted",
+                            mount,
+                        ));
+                    }
+                    Progress::ShadowCopies(result) => {
+                        self.announce(match &result {
+                            Ok(deleted) => format!("{} shadow copies deleted", deleted),
+                            Err(e) => format!("Shadow copies were not deleted: {}", e),
+                        });
+                        if let Some(job) = &mut self.job {
+                            job.shadow_copies = Some(result);
+                        }
+                        self.shadows = shadow::list().map_err(|e| e.to_string());
+                    }
+                    Progress::DriveFinished(index, clean) => {
+                        if let Some(panel) = self.drives.get_mut(index) {
+                            panel.result = Some(clean);
+                        }
+                    }
+                    Progress::Unsanitized(index, mismatch) => {
+                        if let Some(job) = &mut self.job {
+                            job.unsanitized = true;
+                            if let Some(path) = job.files.get(index) {
+                                self.failures.push((path.clone(), mismatch.to_string()));
+                            }
+                        }
+                        self.announce("Verification failed, the job was stopped".to_string());
+                    }
+                    Progress::Finished(success) => {
+                        let outcome = match (success, self.job.as_ref().is_some_and(|job| job.unsanitized)) {
+                            (_, true) => history::Outcome::Unsanitized,
+                            (true, false) => history::Outcome::Success,
+                            (false, false) => history::Outcome::Failed,
+                        };
+                        self.finish_report(outcome);
+                        println!("Erasing file finished");
+                        self.erasing = false;
+                        self.receiver = None;
+                        self.join_worker();
+                        if !success {
+                            eprintln!("Error during file erasure");
+                        }
+                        let score = self.report.as_ref().and_then(|report| report.score.as_ref());
+                        self.announce(match (success, score) {
+                            (true, Some(score)) => format!("Erase finished. {}", score),
+                            (true, None) => "Erase finished".to_string(),
+                            (false, _) => "Erase failed".to_string(),
+                        });
+                        if self.tab == Tab::Erase {
+                            self.files.clear();
+                            self.selected = None;
+                            self.snapshots.clear();
+                            self.snapshots_acknowledged = false;
+                            self.containers.clear();
+                        }
+                        if self.tab == Tab::Drive {
+                            self.drives.iter_mut().for_each(drives::Panel::reload);
+                        }
+                        if self.tab == Tab::Check {
+                            self.swap = swap::detect();
+                        }
+                        self.drop_erased_hits();
+                        self.progress = 100.0;
+                    }
+                    Progress::Cancelled => {
+                        self.finish_report(history::Outcome::Cancelled);
+                        self.erasing = false;
+                        self.receiver = None;
+                        self.join_worker();
+                        self.announce("Erase cancelled".to_string());
+                        self.progress = 0.0;
+                        if self.tab == Tab::Drive {
+                            self.drives.iter_mut().for_each(drives::Panel::reload);
+                        }
+                        self.drop_erased_hits();
+                    }
+                }
+                iced::Task::none()
+            },
+            Message::SelectFile(selection) => {
+                Task::perform(picker::pick(self.picker.clone(), selection), Message::FileOpened)
+            }
+            Message::FileOpened(result) => {
+                match result {
+                    Ok(picked) => {
+                        for path in picked.files {
+                            if self.policy.forbids(&path) {
+                                self.announce(format!("{} is protected by your organization's policy", path.display()));
+                            } else if !self.files.contains(&path) {
+                                self.files.push(path);
+                            }
+                        }
+                        for (path, e) in &picked.unreadable {
+                            eprintln!("Cannot read {}: {}", path.display(), e);
+                        }
+                        if let Some((path, e)) = picked.unreadable.first() {
+                            self.announce(match picked.unreadable.len() {
+                                1 => format!("Skipped {}, it cannot be read: {}", path.display(), e),
+                                n => format!("Skipped {} entries that cannot be read, {} among them: {}", n, path.display(), e),
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error selecting file: {}", e);
+                    }
+                }
+                self.containers = container::hints(&self.files);
+                iced::Task::none()
+            }
+            Message::PackSelected(pack) => {
+                // Downloads folders can be large, keep the walk off the UI thread.
+                let name = pack.name.clone();
+                let files = runtime::blocking(move || pack.files());
+                Task::perform(async move { (name, files.await.unwrap_or_default()) }, |(name, files)| {
+                    Message::PackLoaded(name, files)
+                })
+            }
+            Message::PurgeShadowsToggled(purge) => {
+                if !self.erasing {
+                    self.purge_shadows = purge;
+                }
+                iced::Task::none()
+            }
+            Message::CheckSnapshots => {
+                if !self.erasing {
+                    self.snapshots = snapshot::detect(&self.files);
+                    self.announce(match self.snapshots.len() {
+                        0 => "No snapshots hold these files anymore".to_string(),
+                        volumes => format!("Snapshots found on {} volume(s)", volumes),
+                    });
+                }
+                iced::Task::none()
+            }
+            Message::SnapshotsAcknowledged(acknowledged) => {
+                if !self.erasing {
+                    self.snapshots_acknowledged = acknowledged;
+                }
+                iced::Task::none()
+            }
+            Message::IncludeSiblings(index) => {
+                if let (false, Some(hint)) = (self.erasing, self.containers.get(index).cloned()) {
+                    let mut siblings = Vec::new();
+                    for sibling in &hint.siblings {
+                        // An extracted archive comes in as its folder.
+                        if !sibling.is_dir() {
+                            siblings.push(sibling.clone());
+                            continue;
+                        }
+                        for (path, e) in picker::walk(sibling, true, &mut siblings) {
+                            eprintln!("Error listing {}: {}", path.display(), e);
+                        }
+                    }
+                    let before = self.files.len();
+                    for sibling in siblings {
+                        if !self.policy.forbids(&sibling) && !self.files.contains(&sibling) {
+                            self.files.push(sibling);
+                        }
+                    }
+                    self.announce(format!("{} file(s) beside {} added", self.files.len() - before, hint.file.display()));
+                    self.containers = container::hints(&self.files);
+                }
+                iced::Task::none()
+            }
+            Message::RetryFailures => {
+                if let (false, Some(report)) = (self.erasing, &self.report) {
+                    let before = self.files.len();
+                    let retry: Vec<PathBuf> = report.failures.iter().chain(&report.skipped).map(|(path, _)| path.clone()).collect();
+                    for path in retry {
+                        // Files a failed attempt got through to the end of are gone.
+                        if path.symlink_metadata().is_ok() && !self.files.contains(&path) {
+                            self.files.push(path);
+                        }
+                    }
+                    self.snapshots.clear();
+                    self.snapshots_acknowledged = false;
+                    self.containers = container::hints(&self.files);
+                    self.confirmed = false;
+                    if !self.settings.advanced {
+                        self.step = wizard::Step::Pick;
+                    }
+                    self.announce(format!("{} file(s) queued again", self.files.len() - before));
+                }
+                iced::Task::none()
+            }
+            Message::PackLoaded(name, files) => {
+                if self.erasing {
+                    return iced::Task::none();
+                }
+                let before = self.files.len();
+                for file in files {
+                    if !self.policy.forbids(&file) && !self.files.contains(&file) {
+                        self.files.push(file);
+                    }
+                }
+                self.announce(match self.files.len() - before {
+                    0 => format!("{}: nothing to clean on this computer", name),
+                    added => format!("{}: {} file(s) added", name, added),
+                });
+                self.containers = container::hints(&self.files);
+                iced::Task::none()
+            }
+            Message::ProfileSelected(profile) => {
+                self.picker.profile = profile;
+                iced::Task::none()
+            }
+            Message::CustomFilterChanged(custom) => {
+                self.picker.custom = custom;
+                iced::Task::none()
+            }
+            Message::ShowHiddenToggled(show_hidden) => {
+                self.picker.show_hidden = show_hidden;
+                iced::Task::none()
+            }
+            Message::ClearFiles => {
+                if !self.erasing {
+                    self.files.clear();
+                    self.selected = None;
+                    self.snapshots.clear();
+                    self.snapshots_acknowledged = false;
+                    self.containers.clear();
+                }
+                iced::Task::none()
+            }
+            Message::TabSelected(tab) => {
+                // The progress bar belongs to the tab that started the job.
+                if !self.erasing {
+                    self.tab = tab;
+                }
+                if tab == Tab::Diagnostics && self.diagnostics.is_empty() {
+                    self.diagnostics = diagnostics::capabilities().to_vec();
+                }
+                iced::Task::none()
+            }
+            Message::ReportAgeChanged(days) => {
+                self.report_age = days;
+                iced::Task::none()
+            }
+            Message::TagChanged(tag) => {
+                self.tag = tag;
+                iced::Task::none()
+            }
+            Message::OperatorChanged(operator) => {
+                self.operator = operator;
+                iced::Task::none()
+            }
+            Message::StagingToggled(on) => {
+                if !self.policy.locks("staging") {
+                    self.settings.staging = on.then(|| self.staging_minutes.trim().parse().unwrap_or(60).max(1));
+                }
+                iced::Task::none()
+            }
+            Message::StagingMinutesChanged(minutes) => {
+                if let (Ok(parsed @ 1..), Some(_), false) =
+                    (minutes.trim().parse::<u64>(), self.settings.staging, self.policy.locks("staging"))
+                {
+                    self.settings.staging = Some(parsed);
+                }
+                self.staging_minutes = minutes;
+                iced::Task::none()
+            }
+            Message::RestoreStaged(id) => {
+                if let Some(area) = &mut self.staging {
+                    match area.restore(id) {
+                        Ok(path) => self.announce(format!("{} restored", path.display())),
+                        Err(e) => self.announce(format!("Cannot restore the file: {}", e)),
+                    }
+                }
+                iced::Task::none()
+            }
+            Message::DestroyStaged(id) => {
+                let staged: Vec<staging::Staged> =
+                    self.staging.iter().flat_map(|area| area.items()).filter(|staged| staged.id == id).cloned().collect();
+                self.destroy_staged(staged);
+                iced::Task::none()
+            }
+            Message::StagingTick => {
+                let due = self.staging.as_ref().map(|area| area.due(history::unix_now())).unwrap_or_default();
+                if !due.is_empty() {
+                    self.destroy_staged(due);
+                }
+                iced::Task::none()
+            }
+            Message::WipeOldReports => {
+                let Ok(days) = self.report_age.trim().parse::<u64>() else {
+                    self.announce(format!("{} is not a number of days", self.report_age));
+                    return iced::Task::none();
+                };
+                match reports::older_than(days) {
+                    Ok(old) if old.is_empty() => self.announce(format!("No reports older than {} days", days)),
+                    Ok(old) => {
+                        if !self.erasing {
+                            self.erase(old);
+                        }
+                    }
+                    Err(e) => self.announce(format!("Cannot list the reports: {}", e)),
+                }
+                iced::Task::none()
+            }
+            Message::PassphraseChanged(passphrase) => {
+                self.passphrase = passphrase;
+                iced::Task::none()
+            }
+            Message::PassphraseAgainChanged(passphrase) => {
+                self.passphrase_again = passphrase;
+                iced::Task::none()
+            }
+            Message::EncryptHistory => {
+                if self.passphrase != self.passphrase_again {
+                    self.announce("The passphrases do not match".to_string());
+                    return iced::Task::none();
+                }
+                let passphrase = std::mem::take(&mut self.passphrase);
+                self.passphrase_again.clear();
+                let sealed = vault::create(&passphrase).and_then(|key| {
+                    certificate::seal_existing(&key)?;
+                    self.db.seal(key)
+                });
+                match sealed {
+                    Ok(()) => self.announce("The history and certificates are now encrypted".to_string()),
+                    Err(e) => self.announce(format!("Error encrypting the history: {}", e)),
+                }
+                self.encrypted = vault::enabled();
+                iced::Task::none()
+            }
+            Message::UnlockHistory => {
+                match vault::unlock(&std::mem::take(&mut self.passphrase)) {
+                    Ok(key) => {
+                        self.db.unlock(key);
+                        self.announce("History unlocked".to_string());
+                    }
+                    Err(e) => self.announce(format!("Cannot unlock the history: {}", e)),
+                }
+                iced::Task::none()
+            }
+            Message::RunDiagnostics => {
+                self.diagnostics = diagnostics::probe(&std::env::temp_dir());
+                iced::Task::none()
+            }
+            Message::OnboardingProfile(profile) => {
+                if let Some(method) = self.methods.find(profile.method())
+                    && !self.policy.locks("method")
+                    && self.policy.allows_method(&method)
+                {
+                    self.settings.method = method;
+                    if !self.policy.locks("verification") {
+                        self.settings.verification = profile.verification().at_least(self.policy.minimum_verification);
+                    }
+                }
+                iced::Task::none()
+            }
+            Message::OnboardingNext => {
+                self.onboarding = self.onboarding.and_then(onboarding::Step::next);
+                match self.onboarding {
+                    Some(onboarding::Step::Capabilities) if self.diagnostics.is_empty() => {
+                        self.diagnostics = diagnostics::capabilities().to_vec();
+                    }
+                    None => self.finish_onboarding(),
+                    _ => {}
+                }
+                iced::Task::none()
+            }
+            Message::OnboardingBack => {
+                self.onboarding = self.onboarding.map(onboarding::Step::back);
+                iced::Task::none()
+            }
+            Message::OnboardingSkip => {
+                self.onboarding = None;
+                self.finish_onboarding();
+                iced::Task::none()
+            }
+            Message::InstallShellIntegration => {
+                self.shell_integration = Some(
+                    std::env::current_exe()
+                        .and_then(|exe| shell::install(&exe))
+                        .map(|places| places.iter().map(|place| place.display().to_string()).collect::<Vec<_>>().join(", "))
+                        .map_err(|e| e.to_string()),
+                );
+                iced::Task::none()
+            }
+            Message::SelectFreeSpaceDir => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select a folder on the volume to clean...")
+                        .pick_folder()
+                        .await
+                        .map(|folder| folder.path().to_path_buf())
+                },
+                Message::FreeSpaceDirOpened,
+            ),
+            Message::FreeSpaceDirOpened(dir) => {
+                match dir {
+                    Some(dir) if self.policy.forbids(&dir) => {
+                        self.announce(format!("{} is protected by your organization's policy", dir.display()));
+                    }
+                    Some(dir) => self.free_space_dir = Some(dir),
+                    None => {}
+                }
+                iced::Task::none()
+            }
+            Message::WipeFreeSpace => {
+                if self.history_locked() || self.unrecorded(false) {
+                } else if self.settings.simulate.is_some() {
+                    self.announce("Free space wipes cannot be simulated".to_string());
+                } else if let (false, Some(dir)) = (self.erasing, self.free_space_dir.clone()) {
+                    let tx = self.start_job(Job {
+                        size: 1,
+                        ..Job::new(format!("Free space on {}", dir.display()), Vec::new(), Method::random(1))
+                    });
+                    self.worker = Some(worker::Worker::spawn(move |cancel| free_space::wipe(&dir, cancel, &tx)));
+                }
+                iced::Task::none()
+            }
+            Message::SelectRegionFile => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select the file to overwrite part of...")
+                        .pick_file()
+                        .await
+                        .map(|file| file.path().to_path_buf())
+                },
+                Message::RegionFileOpened,
+            ),
+            Message::RegionFileOpened(file) => {
+                match file {
+                    Some(file) if self.policy.forbids(&file) => {
+                        self.announce(format!("{} is protected by your organization's policy", file.display()));
+                    }
+                    Some(file) => match region::Panel::open(file.clone()) {
+                        Ok(panel) => self.region = panel,
+                        Err(e) => self.announce(format!("Cannot read {}: {}", file.display(), e)),
+                    },
+                    None => {}
+                }
+                iced::Task::none()
+            }
+            Message::RegionOffsetChanged(offset) => {
+                self.region.offset = offset;
+                self.region.anchor = None;
+                self.region.confirmed = false;
+                iced::Task::none()
+            }
+            Message::RegionLengthChanged(length) => {
+                self.region.length = length;
+                self.region.anchor = None;
+                self.region.confirmed = false;
+                iced::Task::none()
+            }
+            Message::RegionShow(offset) => {
+                if let Err(e) = self.region.show(offset) {
+                    self.announce(format!("Cannot read the file: {}", e));
+                }
+                iced::Task::none()
+            }
+            Message::RegionByteClicked(at) => {
+                self.region.click(at);
+                iced::Task::none()
+            }
+            Message::RegionConfirmToggled(confirmed) => {
+                self.region.confirmed = confirmed;
+                iced::Task::none()
+            }
+            Message::OverwriteRegion => {
+                self.overwrite_region();
+                iced::Task::none()
+            }
+            Message::DriveChanged(drive) => {
+                self.drive = drive;
+                iced::Task::none()
+            }
+            Message::AddDrive => {
+                let path = PathBuf::from(self.drive.trim());
+                if !self.policy.device_wipe || self.policy.forbids(&path) {
+                    self.announce(format!("Wiping {} is not allowed by your organization's policy", path.display()));
+                } else if !self.erasing && !self.drive.trim().is_empty() && !self.drives.iter().any(|panel| panel.path == path) {
+                    self.drives.push(drives::Panel::new(path));
+                    self.drive.clear();
+                    self.drive_confirmed = false;
+                }
+                iced::Task::none()
+            }
+            Message::RemoveDrive(index) => {
+                if !self.erasing && index < self.drives.len() {
+                    self.drives.remove(index);
+                }
+                iced::Task::none()
+            }
+            Message::PsidChanged(index, psid) => {
+                if let Some(panel) = self.drives.get_mut(index) {
+                    panel.psid = psid;
+                }
+                iced::Task::none()
+            }
+            Message::CryptoEraseToggled(crypto_erase) => {
+                self.crypto_erase = crypto_erase;
+                iced::Task::none()
+            }
+            Message::UnlockHiddenToggled(unlock) => {
+                self.unlock_hidden = unlock;
+                iced::Task::none()
+            }
+            Message::ResumeToggled(resume) => {
+                self.resume = resume;
+                iced::Task::none()
+            }
+            Message::SkipBadSectorsToggled(skip) => {
+                self.skip_bad_sectors = skip;
+                iced::Task::none()
+            }
+            Message::DriveConfirmToggled(confirmed) => {
+                self.drive_confirmed = confirmed;
+                iced::Task::none()
+            }
+            Message::WipeDrives => {
+                if !self.erasing && !self.history_locked() && !self.unrecorded(true) && self.drive_confirmed && !self.drives.is_empty() && self.policy.device_wipe {
+                    let method = self.settings.method.clone();
+                    let options = device::Options {
+                        method: method.key(),
+                        passes: method.passes(),
+                        resume: self.resume,
+                        crypto_erase: self.crypto_erase,
+                        unlock_hidden: self.unlock_hidden,
+                        skip_bad_sectors: self.skip_bad_sectors,
+                        simulate: self.settings.simulate,
+                        checkpoints: device::checkpoints(),
+                    };
+                    let targets: Vec<device::Target> = self.drives.iter().map(drives::Panel::target).collect();
+                    let devices: Vec<PathBuf> = targets.iter().map(|target| target.path.clone()).collect();
+                    self.drives.iter_mut().for_each(drives::Panel::start);
+                    let description = match devices.len() {
+                        1 => format!("Drive {}", devices[0].display()),
+                        count => format!("Batch of {} drives", count),
+                    };
+                    let tx = self.start_job(Job {
+                        bytes: self.drives.iter().map(|panel| panel.size.unwrap_or(0)).collect(),
+                        size: 1,
+                        // Decommissioned disks leave the building, so the batch always gets a certificate.
+                        certificate: true,
+                        ..Job::new(description, devices, method)
+                    });
+                    self.drive_confirmed = false;
+                    self.worker = Some(worker::Worker::spawn(move |cancel| device::wipe_all(&targets, &options, cancel, &tx)));
+                }
+                iced::Task::none()
+            }
+            Message::SelectScanFile => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select a file or disk image to check...")
+                        .pick_file()
+                        .await
+                        .map(|file| file.path().to_path_buf())
+                },
+                |path| match path {
+                    Some(path) => Message::Scan(path),
+                    None => Message::ScanFinished(Err("No file was selected.".to_string())),
+                },
+            ),
+            Message::ScanTargetChanged(target) => {
+                self.scan_target = target;
+                iced::Task::none()
+            }
+            Message::Scan(target) => {
+                if self.scanning {
+                    return iced::Task::none();
+                }
+                self.scanning = true;
+                self.scan = None;
+                // Reading a drive takes a while, keep it off the UI thread.
+                let scan = runtime::blocking(move || scan::scan(&target).map_err(|e| e.to_string()));
+                Task::perform(
+                    async move { scan.await.unwrap_or_else(|_| Err("The check stopped unexpectedly.".to_string())) },
+                    Message::ScanFinished,
+                )
+            }
+            Message::ScanFinished(result) => {
+                self.scanning = false;
+                if let Ok(report) = &result {
+                    self.announce(report.verdict());
+                }
+                self.scan = Some(result);
+                iced::Task::none()
+            }
+            Message::CheckSwap => {
+                self.swap = swap::detect();
+                iced::Task::none()
+            }
+            Message::WipeSwap(index) => {
+                let Some(swap) = self.swap.swaps.get(index).cloned() else {
+                    return iced::Task::none();
+                };
+                if self.erasing || self.history_locked() || self.unrecorded(false) {
+                    return iced::Task::none();
+                }
+                // swapoff, the overwrite and mkswap all act on the real swap.
+                if self.settings.simulate.is_some() {
+                    self.announce("Swap wipes cannot be simulated".to_string());
+                    return iced::Task::none();
+                }
+                let method = Method::random(1);
+                let allowed = self.policy.check_method(&method).and_then(|()| match swap.partition {
+                    true if !self.policy.device_wipe => Err("Drive wipes are switched off by your organization's policy".to_string()),
+                    true if self.policy.forbids(&swap.path) => Err(format!("{} is protected by your organization's policy", swap.path.display())),
+                    true => Ok(()),
+                    false => self.policy.check_path(&swap.path).map_err(|e| e.to_string()),
+                });
+                if let Err(e) = allowed {
+                    self.announce(e);
+                    return iced::Task::none();
+                }
+                let tx = self.start_job(Job {
+                    bytes: vec![swap.bytes.unwrap_or(0)],
+                    ..Job::new(format!("Swap {}", swap.path.display()), vec![swap.path.clone()], method)
+                });
+                self.worker = Some(worker::Worker::spawn(move |cancel| swap::wipe(&swap, cancel, &tx)));
+                iced::Task::none()
+            }
+            Message::SelectSearchDir => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select a folder to search...")
+                        .pick_folder()
+                        .await
+                        .map(|folder| folder.path().to_path_buf())
+                },
+                Message::SearchDirOpened,
+            ),
+            Message::SearchDirOpened(dir) => {
+                if let Some(dir) = dir
+                    && !self.search_dirs.contains(&dir)
+                {
+                    self.search_dirs.push(dir);
+                    self.search = None;
+                }
+                iced::Task::none()
+            }
+            Message::ClearSearchDirs => {
+                if !self.searching {
+                    self.search_dirs.clear();
+                    self.search = None;
+                }
+                iced::Task::none()
+            }
+            Message::SearchModeSelected(mode) => {
+                self.search_mode = mode;
+                iced::Task::none()
+            }
+            Message::SearchPatternChanged(pattern) => {
+                self.search_pattern = pattern;
+                iced::Task::none()
+            }
+            Message::SearchCaseToggled(case) => {
+                self.search_case = case;
+                iced::Task::none()
+            }
+            Message::SelectHashList => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select a list of SHA-256 hashes...")
+                        .pick_file()
+                        .await
+                        .map(|file| file.path().to_path_buf())
+                },
+                Message::HashListOpened,
+            ),
+            Message::HashListOpened(path) => {
+                if let Some(path) = path {
+                    match search::read_hashes(&path) {
+                        Ok(hashes) => {
+                            self.search_hashes = Some((path, hashes));
+                            // Matches and reports belong to the list they were found with.
+                            self.search = None;
+                            self.purge_report = None;
+                        }
+                        Err(e) => self.announce(format!("Cannot use {}: {}", path.display(), e)),
+                    }
+                }
+                iced::Task::none()
+            }
+            Message::Search => {
+                let dirs = self.search_dirs.clone();
+                if dirs.is_empty() || self.searching || self.erasing {
+                    return iced::Task::none();
+                }
+                let criterion = match self.search_mode {
+                    search::Mode::Hashes => match &self.search_hashes {
+                        Some((_, hashes)) => search::Criterion::Hashes(hashes.clone()),
+                        None => return iced::Task::none(),
+                    },
+                    _ if self.search_pattern.is_empty() => return iced::Task::none(),
+                    search::Mode::Text => {
+                        search::Criterion::Content(pattern::Pattern::literal(&self.search_pattern, !self.search_case))
+                    }
+                    search::Mode::Regex => match pattern::Pattern::regex(&self.search_pattern, !self.search_case) {
+                        Ok(pattern) => search::Criterion::Content(pattern),
+                        Err(e) => {
+                            self.search = Some(Err(format!("Invalid regular expression: {}", e)));
+                            return iced::Task::none();
+                        }
+                    },
+                };
+                self.searching = true;
+                self.search = None;
+                self.search_confirmed = false;
+                self.purge_report = None;
+                self.search_cancel = engine::CancelToken::default();
+                let (policy, cancel) = (self.policy.clone(), self.search_cancel.clone());
+                let search = runtime::blocking(move || search::search(&dirs, &criterion, &policy, &cancel).map_err(|e| e.to_string()));
+                Task::perform(
+                    async move { search.await.unwrap_or_else(|_| Err("The search stopped unexpectedly.".to_string())) },
+                    Message::SearchFinished,
+                )
+            }
+            Message::StopSearch => {
+                self.search_cancel.cancel();
+                iced::Task::none()
+            }
+            Message::SearchFinished(result) => {
+                self.searching = false;
+                if let Ok(outcome) = &result {
+                    self.announce(format!("Search finished, {} match(es)", outcome.hits.len()));
+                }
+                self.search = Some(result);
+                iced::Task::none()
+            }
+            Message::HitToggled(index, selected) => {
+                if let Some(Ok(outcome)) = &mut self.search
+                    && let Some(hit) = outcome.hits.get_mut(index)
+                    && !self.erasing
+                {
+                    hit.selected = selected;
+                }
+                iced::Task::none()
+            }
+            Message::SearchConfirmToggled(confirmed) => {
+                self.search_confirmed = confirmed;
+                iced::Task::none()
+            }
+            Message::EraseHits => {
+                let files: Vec<PathBuf> = match &self.search {
+                    Some(Ok(outcome)) => outcome.hits.iter().filter(|hit| hit.selected).map(|hit| hit.path.clone()).collect(),
+                    _ => Vec::new(),
+                };
+                if !self.erasing && !self.searching && self.search_confirmed && !files.is_empty() {
+                    self.erase(files);
+                }
+                iced::Task::none()
+            }
+            Message::SavePurgeReport => match &self.purge_report {
+                Some(report) => Task::perform(
+                    save_file("Save hash purge report...", "hash-purge-report.txt".to_string(), report.clone().into_bytes()),
+                    Message::PurgeReportSaved,
+                ),
+                None => iced::Task::none(),
+            },
+            Message::PurgeReportSaved(result) => {
+                match result {
+                    Ok(path) => self.announce(format!("Report saved to {}", path.display())),
+                    Err(e) => eprintln!("Error saving report: {}", e),
+                }
+                iced::Task::none()
+            }
+            Message::MethodSelected(method) => {
+                if !self.policy.locks("method") && self.policy.allows_method(&method) {
+                    self.settings.method = method;
+                }
+                iced::Task::none()
+            }
+            Message::VerificationSelected(verification) => {
+                if !self.policy.locks("verification") {
+                    self.settings.verification = verification.at_least(self.policy.minimum_verification);
+                }
+                iced::Task::none()
+            }
+            Message::MethodPicked(index) => {
+                if let Some(method) = self.methods.methods().get(index)
+                    && !self.policy.locks("method")
+                    && self.policy.allows_method(method)
+                {
+                    self.settings.method = method.clone();
+                }
+                iced::Task::none()
+            }
+            Message::AdvancedToggled(advanced) => {
+                if !self.erasing && !self.policy.locks("advanced") {
+                    self.settings.advanced = advanced;
+                    self.step = wizard::Step::Pick;
+                }
+                iced::Task::none()
+            }
+            Message::WizardNext => {
+                self.step = self.step.next();
+                if self.step == wizard::Step::Confirm {
+                    self.snapshots = snapshot::detect(&self.files);
+                    self.containers = container::hints(&self.files);
+                    self.previews = self.files
+                        .iter()
+                        .take(preview::MAX_PREVIEWS)
+                        .map(|file| preview::Preview::load(file))
+                        .collect();
+                    return self.measure_throughput();
+                }
+                iced::Task::none()
+            }
+            Message::ThroughputMeasured(result) => {
+                // Only if the files did not move to another volume meanwhile.
+                let current = space::volumes(&self.files).first().map(|volume| volume.dir.clone());
+                let stale = matches!(&result, Ok(throughput) if Some(&throughput.dir) != current.as_ref());
+                if !stale {
+                    self.throughput = Some(result);
+                }
+                iced::Task::none()
+            }
+            Message::WizardBack => {
+                self.step = self.step.back();
+                self.confirmed = false;
+                iced::Task::none()
+            }
+            Message::WizardRestart => {
+                if !self.erasing {
+                    self.files.clear();
+                    self.selected = None;
+                    self.snapshots.clear();
+                    self.snapshots_acknowledged = false;
+                    self.containers.clear();
+                    self.confirmed = false;
+                    self.step = wizard::Step::Pick;
+                }
+                iced::Task::none()
+            }
+            Message::ConfirmToggled(confirmed) => {
+                self.confirmed = confirmed;
+                iced::Task::none()
+            }
+            Message::CopyErasedPaths => {
+                let Some(report) = &self.report else {
+                    return iced::Task::none();
+                };
+                let (paths, count) = (desktop::path_list(&report.erased), report.erased.len());
+                self.announce(format!("Copied {} path(s) to the clipboard", count));
+                iced::clipboard::write(paths)
+            }
+            Message::OpenErasedFolders => {
+                let folders = self.report.as_ref().map(|report| desktop::folders(&report.erased)).unwrap_or_default();
+                for folder in folders {
+                    if let Err(e) = desktop::open(&folder) {
+                        self.announce(format!("Cannot open {}: {}", folder.display(), e));
+                    }
+                }
+                iced::Task::none()
+            }
+            Message::OpenCertificate => {
+                if let Some(path) = self.report.as_ref().and_then(|report| report.certificate.clone())
+                    && let Err(e) = desktop::open(&path)
+                {
+                    self.announce(format!("Cannot open {}: {}", path.display(), e));
+                }
+                iced::Task::none()
+            }
+            Message::Window(id, event) => {
+                match event {
+                    window::Event::Resized(size) => {
+                        self.settings.window.width = size.width;
+                        self.settings.window.height = size.height;
+                    }
+                    window::Event::Moved(position) => {
+                        self.settings.window.x = Some(position.x);
+                        self.settings.window.y = Some(position.y);
+                    }
+                    window::Event::CloseRequested => {
+                        if let Err(e) = self.settings.save() {
+                            eprintln!("Error saving settings: {}", e);
+                        }
+                        // Stop a running erase rather than let the process
+                        // end under it halfway through a write.
+                        if let Some(worker) = self.worker.take() {
+                            worker.shutdown(SHUTDOWN_GRACE);
+                        }
+                        return window::close(id);
+                    }
+                    _ => {}
+                }
+                iced::Task::none()
+            }
+            Message::FileSelected(index) => {
+                self.selected = Some(index);
+                iced::Task::none()
+            }
+            Message::RemoveSelected => {
+                // Delete is pressed on other tabs too; the selection is the queue's.
+                if let (false, Tab::Erase, Some(index)) = (self.erasing, self.tab, self.selected) {
+                    if index < self.files.len() {
+                        self.files.remove(index);
+                        self.containers = container::hints(&self.files);
+                    }
+                    // Keep the focus on the item that slid into the removed slot.
+                    self.selected = match self.files.len() {
+                        0 => None,
+                        len => Some(index.min(len - 1)),
+                    };
+                }
+                iced::Task::none()
+            }
+            Message::CycleFocus(true) => widget::focus_next(),
+            Message::CycleFocus(false) => widget::focus_previous(),
+            Message::MoveSelection(forward) => {
+                if self.tab == Tab::Erase && !self.files.is_empty() {
+                    let len = self.files.len();
+                    self.selected = Some(match (self.selected, forward) {
+                        (None, true) => 0,
+                        (None, false) => len - 1,
+                        (Some(index), true) => (index + 1) % len,
+                        (Some(index), false) => (index + len - 1) % len,
+                    });
+                }
+                iced::Task::none()
+            }
+            Message::Cancel => {
+                if let Some(worker) = &self.worker {
+                    worker.cancel();
+                }
+                iced::Task::none()
+            }
+            Message::TimestampsSelected(timestamps) => {
+                if !self.policy.locks("timestamps") {
+                    self.settings.timestamps = timestamps;
+                }
+                iced::Task::none()
+            }
+            Message::UnitsSelected(base) => {
+                if !self.policy.locks("units") {
+                    self.settings.units = base;
+                    units::apply(base);
+                }
+                iced::Task::none()
+            }
+            Message::ChurnToggled(churn) => {
+                if !self.policy.locks("churn_directories") {
+                    self.settings.churn_directories = churn;
+                }
+                iced::Task::none()
+            }
+            Message::ChecksumsToggled(checksums) => {
+                if !self.policy.locks("checksums") {
+                    self.settings.checksums = checksums;
+                }
+                iced::Task::none()
+            }
+            Message::WebhookUrlChanged(url) => {
+                if !self.policy.locks("webhook_url") {
+                    self.settings.webhook_url = url.trim().to_string();
+                }
+                iced::Task::none()
+            }
+            Message::WebhookSecretChanged(secret) => {
+                if !self.policy.locks("webhook_secret") {
+                    self.settings.webhook_secret = secret;
+                }
+                iced::Task::none()
+            }
+            Message::SmtpServerChanged(server) => {
+                if !self.policy.locks("smtp_server") {
+                    self.settings.smtp_server = server.trim().to_string();
+                }
+                iced::Task::none()
+            }
+            Message::SmtpFromChanged(from) => {
+                if !self.policy.locks("smtp_from") {
+                    self.settings.smtp_from = from.trim().to_string();
+                }
+                iced::Task::none()
+            }
+            Message::SmtpToChanged(to) => {
+                if !self.policy.locks("smtp_to") {
+                    self.settings.smtp_to = to;
+                }
+                iced::Task::none()
+            }
+            Message::AnnounceToggled(announce) => {
+                if !self.policy.locks("announce") {
+                    self.settings.announce = announce;
+                }
+                iced::Task::none()
+            }
+            Message::PresetSelected(preset) => {
+                self.preset = preset;
+                iced::Task::none()
+            }
+            Message::HistorySearch(query) => {
+                self.history_query = query;
+                iced::Task::none()
+            }
+            Message::ExportCertificate(id, format) => {
+                let Some(record) = self.db.get(id) else {
+                    return iced::Task::none();
+                };
+                let contents = certificate::render(record, format);
+                let name = format!("erasure-certificate-{}.{}", id, format.extension());
+                Task::perform(save_file("Save certificate...", name, contents), Message::CertificateSaved)
+            }
+            Message::CertificateSaved(result) => {
+                match result {
+                    Ok(path) => self.announce(format!("Certificate saved to {}", path.display())),
+                    Err(e) => eprintln!("Error saving certificate: {}", e),
+                }
+                iced::Task::none()
+            }
+        }
+    }
+
+    /// Whether the history is encrypted and still locked, in which case a
+    /// job could not be recorded and is not started.
+    fn history_locked(&mut self) -> bool {
+        let locked = self.encrypted && self.db.key().is_none();
+        if locked {
+            self.announce("Unlock the encrypted history in the settings first, so the job can be recorded".to_string());
+        }
+        locked
+    }
 
-#[derive(Debug, Clone)]
-enum Message {
-    SelectFile,
-    FileOpened(Result<String, String>),
-    EraseFile,
-    Progress(Progress),
-}
+    /// Whether the job has to leave a certificate or an audit entry, as
+    /// drive wipes, the policy or the preset ask, while the history could
+    /// not be opened. Its record would be numbered from scratch and pass
+    /// for an earlier job's, so such a job is not started.
+    fn unrecorded(&mut self, certificate: bool) -> bool {
+        let Some(error) = &self.history_error else {
+            return false;
+        };
+        let required = certificate
+            || self.policy.certificate
+            || self.policy.audit
+            || self.preset.is_some_and(|preset| preset.certificate || preset.audit);
+        if required {
+            self.announce(format!("Not started: the job needs a certificate or an audit entry, but the history cannot be opened: {}", error));
+        }
+        required
+    }
 
-impl App {
-    fn securely_overwrite(path: &str, passes: usize, tx: &Sender<Progress>) -> std::io::Result<()> {
-        let mut file = File::options()
-            .read(true)
-            .write(true)
-            .open(path)?;
+    /// Moves the queue into the holding area instead of erasing it.
+    fn stage_files(&mut self) {
+        let (Some(area), Some(minutes)) = (&mut self.staging, self.settings.staging) else {
+            self.announce("The staging area cannot be opened, nothing was moved".to_string());
+            return;
+        };
+        let mut staged = 0;
+        let mut failed = Vec::new();
+        for file in std::mem::take(&mut self.files) {
+            match area.stage(&file, minutes * 60, &self.policy) {
+                Ok(()) => staged += 1,
+                Err(e) => {
+                    eprintln!("Cannot stage {}: {}", file.display(), e);
+                    failed.push(file);
+                }
+            }
+        }
+        self.files = failed;
+        self.selected = None;
+        self.confirmed = false;
+        self.step = wizard::Step::Pick;
+        self.announce(match self.files.len() {
+            0 => format!("{} file(s) staged, they are destroyed in {} minute(s)", staged, minutes),
+            left => format!("{} file(s) staged, {} could not be moved and are still in the list", staged, left),
+        });
+    }
 
-        let file_size = file.metadata()?.len() as usize;
-        if file_size == 0 {
-            remove_file(path)?;
-            tx.send(Progress::Updated(100.0)).map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Channel error"))?;
-            return Ok(());
+    /// Erases files from the holding area, recorded under their old names.
+    fn destroy_staged(&mut self, staged: Vec<staging::Staged>) {
+        if self.erasing {
+            return;
+        }
+        let (staged, forbidden) = staging::permitted(staged, &self.policy);
+        if !forbidden.is_empty() {
+            self.announce(forbidden.join("; "));
         }
+        if staged.is_empty() {
+            return;
+        }
+        if self.erase(staged.iter().map(|staged| staged.held.clone()).collect())
+            && let Some(job) = &mut self.job
+        {
+            job.description = format!("{} staged file(s), {}", staged.len(), job.method);
+            job.originals = staged.into_iter().map(|staged| staged.original).collect();
+        }
+    }
 
-        let mut rng = rand::thread_rng();
-        let buffer_size = 4096;
-        let mut buffer = vec![0u8; buffer_size];
+    /// Starts erasing `files` with the chosen method, unless the policy
+    /// refuses it. Returns whether the job started.
+    fn erase(&mut self, files: Vec<PathBuf>) -> bool {
+        if self.history_locked() || self.unrecorded(false) {
+            return false;
+        }
+        let method = self.job_method();
+        if let Err(e) = self.policy.check_method(&method) {
+            self.announce(e);
+            return false;
+        }
+        let verification = self.job_verification(&method);
+        let problems = match self.settings.simulate {
+            Some(_) => Vec::new(),
+            None => space::check(&files, self.settings.churn_directories),
+        };
+        let messages: Vec<&str> = problems.iter().map(|problem| problem.message.as_str()).collect();
+        if problems.iter().any(|problem| problem.refuse) {
+            self.announce(format!("Not started: {}", messages.join("; ")));
+            return false;
+        }
+        let warning = (!messages.is_empty()).then(|| format!("Erase started. Warning: {}", messages.join("; ")));
+        let tx = self.start_job(Job {
+            preset: self.preset,
+            verification,
+            ..Job::new(format!("{} file(s), {}", files.len(), method), files.clone(), method.clone())
+        });
+        if let Some(warning) = warning {
+            self.announce(warning);
+        }
+        let options = engine::Options {
+            passes: method.passes(),
+            verification,
+            timestamps: self.settings.timestamps,
+            churn_directories: self.settings.churn_directories,
+            simulate: self.settings.simulate,
+            checksums: self.settings.checksums,
+        };
+        let shadows: Vec<String> = match (&self.shadows, self.purge_shadows && self.settings.simulate.is_none()) {
+            (Ok(shadows), true) => shadow::covering(shadows, &files).into_iter().map(|shadow| shadow.id.clone()).collect(),
+            _ => Vec::new(),
+        };
+        self.worker = Some(worker::Worker::spawn(move |cancel| {
+            if !shadows.is_empty() {
+                let _ = tx.send(Progress::ShadowCopies(shadow::delete(&shadows)));
+            }
+            engine::erase_all(&files, &options, cancel, &tx)
+        }));
+        true
+    }
 
-        let total_work = passes as u64 * file_size as u64;
-        let mut completed_work: u64 = 0;
-        let mut chunk_count = 0;
+    /// Overwrites the byte range picked in the Region tab, leaving the
+    /// rest of the file in place.
+    fn overwrite_region(&mut self) {
+        let (Some(file), Ok(region)) = (self.region.file.clone(), self.region.region()) else {
+            return;
+        };
+        if self.erasing || !self.region.confirmed || self.history_locked() || self.unrecorded(false) {
+            return;
+        }
+        let method = self.job_method();
+        if let Err(e) = self.policy.check_method(&method).and_then(|()| self.policy.check_path(&file).map_err(|e| e.to_string())) {
+            self.announce(e);
+            return;
+        }
+        let verification = self.job_verification(&method);
+        let tx = self.start_job(Job {
+            bytes: vec![region.length],
+            preset: self.preset,
+            verification,
+            ..Job::new(format!("{} of {}, {}", region, file.display(), method), vec![file.clone()], method.clone())
+        });
+        self.region.confirmed = false;
+        let options = engine::Options {
+            passes: method.passes(),
+            verification,
+            timestamps: self.settings.timestamps,
+            churn_directories: false,
+            simulate: self.settings.simulate,
+            checksums: self.settings.checksums,
+        };
+        self.worker = Some(worker::Worker::spawn(move |cancel| engine::erase_region(&file, region, &options, cancel, &tx)));
+    }
 
-        for _pass in 0..passes {
-            let mut remaining = file_size;
-            file.seek(SeekFrom::Start(0))?;
+    /// Finds the speed of the volume the (first) files are on: from earlier
+    /// jobs there if any, otherwise by a short write test off the UI thread.
+    fn measure_throughput(&mut self) -> iced::Task<Message> {
+        let Some(volume) = space::volumes(&self.files).into_iter().next() else {
+            self.throughput = Some(Err("no files selected".to_string()));
+            return iced::Task::none();
+        };
+        if let Some(speed) = self.settings.simulate {
+            self.throughput = Some(Ok(estimate::Throughput {
+                dir: volume.dir,
+                bytes_per_second: speed.max(1) as f64,
+                source: estimate::Source::Simulated,
+            }));
+            return iced::Task::none();
+        }
+        if let Some(throughput) = estimate::from_history(self.db.records(), self.methods.methods(), &volume.dir) {
+            self.throughput = Some(Ok(throughput));
+            return iced::Task::none();
+        }
+        self.throughput = None;
+        let test = runtime::blocking(move || estimate::benchmark(&volume.dir).map_err(|e| format!("the write test failed: {}", e)));
+        iced::Task::perform(
+            async move { test.await.unwrap_or_else(|_| Err("the write test stopped unexpectedly".to_string())) },
+            Message::ThroughputMeasured,
+        )
+    }
 
-            while remaining > 0 {
-                let current_chunk = buffer_size.min(remaining);
-                for i in 0..current_chunk {
-                    buffer[i] = rng.r#gen::<u8>();  
-                }
-                file.write_all(&buffer[..current_chunk])?;
-                remaining -= current_chunk;
-                completed_work += current_chunk as u64;
-                chunk_count += 1;
+    /// Remembers that the walkthrough was seen, so it does not come back.
+    fn finish_onboarding(&mut self) {
+        self.settings.onboarded = true;
+        if let Err(e) = self.settings.save() {
+            eprintln!("Error saving settings: {}", e);
+        }
+    }
 
-                // محدود کردن send: هر 100 chunk (برای فایل 200MB حدود 500 send)
-                if chunk_count % 100 == 0 {
-                    let progress = (completed_work as f32 / total_work as f32) * 100.0;
-                    tx.send(Progress::Updated(progress)).map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Channel error"))?;
-                }
-            }
-            file.sync_all()?;
+    /// The preset's method when a compliance preset is chosen, the user's otherwise.
+    fn job_method(&self) -> Method {
+        self.preset
+            .and_then(|preset| self.methods.find(preset.method))
+            .unwrap_or_else(|| self.settings.method.clone())
+    }
+
+    /// The user's verification policy, raised to whatever the method, preset or
+    /// organization requires.
+    fn job_verification(&self, method: &Method) -> engine::Verification {
+        let mut verification = self.settings.verification.at_least(self.policy.minimum_verification);
+        if let Some(preset) = self.preset {
+            verification = verification.at_least(preset.verification);
+        }
+        if method.verify() {
+            verification = verification.at_least(engine::Verification::LastPass);
         }
+        verification
+    }
 
-        drop(file);
-        remove_file(path)?;
-        tx.send(Progress::Updated(100.0)).map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Channel error"))?;
-        Ok(())
+    fn start_job(&mut self, mut job: Job) -> progress::Sender {
+        let (tx, rx) = progress::channel();
+        self.receiver = Some(rx);
+        self.erasing = true;
+        job.tag = self.tag.trim().to_string();
+        job.operator = self.operator.trim().to_string();
+        // Drives come with their sizes, metadata reports zero for block devices.
+        if job.bytes.is_empty() {
+            job.bytes = job.files
+                .iter()
+                .map(|file| std::fs::metadata(file).map(|meta| meta.len()).unwrap_or(0))
+                .collect();
+        }
+        self.job = Some(job);
+        self.current = 0;
+        self.progress = 0.0;
+        self.phase = progress::Phase::default();
+        self.milestone = 0;
+        self.status = "Erase started".to_string();
+        self.started = Instant::now();
+        self.failures.clear();
+        tx
     }
 
-    fn new() -> Self {
-        Self {
-            file: "".to_string(),
-            progress: 0.0,
-            erasing: false,
-            receiver: None,
+    /// Waits for the job's thread, which has sent its last message.
+    fn join_worker(&mut self) {
+        if let Some(Err(e)) = self.worker.take().map(worker::Worker::join) {
+            eprintln!("The erase thread crashed: {}", e);
         }
     }
 
-    fn update(&mut self, message: Message) -> iced::Task<Message> {
-        match message {
-            Message::EraseFile => {
-                println!("Erasing file start");
-                if !self.erasing {
-                    let (tx, rx) = flume::bounded(1000);  // ظرفیت بزرگ برای فایل‌های بزرگ
-                    self.receiver = Some(rx);
-                    self.erasing = true;
-                    self.progress = 0.0;
-
-                    let path = self.file.clone();
-                    std::thread::spawn(move || {
-                        let result = Self::securely_overwrite(&path, 3, &tx).is_ok();
-                        tx.send(Progress::Finished(result)).expect("Channel error in thread");
-                    });
+    fn finish_report(&mut self, outcome: history::Outcome) {
+        let Some(job) = self.job.take() else {
+            return;
+        };
+        let fates = summary::fates(&job.files, &self.failures, job.reached, outcome);
+        let entries: Vec<history::FileEntry> = job.files
+            .iter()
+            .zip(&job.bytes)
+            .zip(&fates)
+            .enumerate()
+            .map(|(index, ((path, bytes), fate))| history::FileEntry {
+                path: job.originals.get(index).unwrap_or(path).clone(),
+                bytes: *bytes,
+                error: fate.error(),
+                strategy: job.strategies
+                    .iter()
+                    .find(|(erased, _)| *erased == index)
+                    .map(|(_, strategy)| strategy.to_string())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        // Only files that made it all the way through count as destroyed.
+        let destroyed = entries.iter().filter(|entry| entry.error.is_none());
+        // Listed by the paths the engine was given, which are the ones a
+        // retry can find again.
+        let batch = summary::Batch::sort(&job.files, &fates);
+        self.failures.clear();
+        let bytes = destroyed.clone().map(|entry| entry.bytes).sum();
+        let verification = match (job.verification, outcome) {
+            (engine::Verification::None, _) => job.verification.to_string(),
+            (_, history::Outcome::Success) => format!(
+                "{}: {} read back, all matched",
+                job.verification,
+                units::size(job.verified),
+            ),
+            (_, history::Outcome::Unsanitized) => format!(
+                "{}: FAILED, read-back found mismatching data ({} matched)",
+                job.verification,
+                units::size(job.verified),
+            ),
+            _ => format!(
+                "{}: {} read back and matched; see file errors",
+                job.verification,
+                units::size(job.verified),
+            ),
+        };
+        let record = history::Record {
+            id: 0,
+            finished: history::unix_now(),
+            description: job.description,
+            method: {
+                let names = |indices: &[usize]| -> String {
+                    indices
+                        .iter()
+                        .filter_map(|index| job.files.get(*index))
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let mut method = job.method.to_string();
+                if !job.crypto_erased.is_empty() {
+                    method += &format!("; TCG crypto-erase (PSID revert) on {}", names(&job.crypto_erased));
                 }
-                iced::Task::none()
-            },
-            Message::Progress(p) => {
-                println!("Progress received: {:?}", p);
-                match p {
-                    Progress::Updated(val) => {
-                        self.progress = val;
-                    }
-                    Progress::Finished(success) => {
-                        println!("Erasing file finished");
-                        self.erasing = false;
-                        self.receiver = None;
-                        if !success {
-                            eprintln!("Error during file erasure");
-                        }
-                        self.progress = 100.0;
-                    }
+                if !job.memory_backed.is_empty() {
+                    method += &format!("; unlinked without overwriting (memory-backed) {}", names(&job.memory_backed));
                 }
-                iced::Task::none()
+                if !job.mtp_replaced.is_empty() {
+                    method += &format!("; best effort on MTP device, replaced with random data and deleted: {}", names(&job.mtp_replaced));
+                }
+                match &job.shadow_copies {
+                    Some(Ok(deleted)) => method += &format!("; {} shadow copies deleted first", deleted),
+                    Some(Err(e)) => method += &format!("; shadow copies NOT deleted: {}", e),
+                    None => {}
+                }
+                method
             },
-            Message::SelectFile => Task::perform(open_file(&["*"]), Message::FileOpened),
-            Message::FileOpened(result) => {
-                match result {
-                    Ok(file_path) => {
-                        self.file = file_path;
-                    }
-                    Err(e) => {
-                        eprintln!("Error selecting file: {}", e);
+            preset: job.preset.map(|preset| preset.name.to_string()).unwrap_or_default(),
+            tag: job.tag,
+            user: identity::os_user(),
+            operator: job.operator,
+            verification: verification.clone(),
+            files: destroyed.count(),
+            bytes,
+            duration: self.started.elapsed(),
+            outcome,
+            entries,
+        };
+        // A simulated job destroyed nothing, so it must not leave a record
+        // or a certificate saying it did.
+        let saved = match self.settings.simulate {
+            Some(_) => Err(std::io::Error::other("Simulated job, not recorded")),
+            None => self.db.insert(record),
+        };
+        let mut certificate_path = None;
+        let record = saved.as_ref().ok().copied();
+        match saved {
+            Ok(id) => {
+                let record = self.db.get(id).expect("record was just inserted");
+                if job.certificate || self.policy.certificate || job.preset.is_some_and(|preset| preset.certificate) {
+                    match certificate::store(record, self.db.key()) {
+                        // A sealed one only opens in the history tab.
+                        Ok(path) if !vault::enabled() => certificate_path = Some(path),
+                        Ok(_) => {}
+                        Err(e) => eprintln!("Error writing certificate: {}", e),
                     }
                 }
-                iced::Task::none()
+                if (self.policy.audit || job.preset.is_some_and(|preset| preset.audit))
+                    && let Err(e) = audit::append(record)
+                {
+                    eprintln!("Error writing audit log: {}", e);
+                }
+                // Receivers may be slow or down, keep them off the UI thread.
+                let (settings, record) = (self.settings.clone(), record.clone());
+                runtime::handle().spawn_blocking(move || {
+                    webhook::notify(&settings, &record);
+                    mail::notify(&settings, &record);
+                });
+            }
+            Err(e) if self.settings.simulate.is_some() => println!("{}", e),
+            Err(e) => eprintln!("Error saving history: {}", e),
+        }
+
+        if let Some(area) = &mut self.staging
+            && let Err(e) = area.prune()
+        {
+            eprintln!("Error updating the staging area: {}", e);
+        }
+
+        let score = (!job.strategies.is_empty()).then(|| {
+            let mut score = strategy::Score::of(job.strategies.iter().map(|(_, strategy)| strategy));
+            if self.snapshots_acknowledged && snapshot::blocking(&self.snapshots) {
+                score.lower(strategy::Confidence::BestEffort, "snapshots kept");
+            }
+            let shadows = self.shadows.as_deref().map(|shadows| shadow::covering(shadows, &job.files).len()).unwrap_or(0);
+            if shadows > 0 && !matches!(job.shadow_copies, Some(Ok(_))) {
+                score.lower(strategy::Confidence::BestEffort, "shadow copies kept");
+            }
+            score
+        });
+        self.report = Some(Report {
+            files: job.size,
+            method: job.method,
+            verification,
+            erased: batch.erased,
+            skipped: batch.skipped,
+            bytes,
+            failures: batch.failed,
+            duration: self.started.elapsed(),
+            outcome,
+            score,
+            certificate: certificate_path,
+            record,
+            queue: self.tab == Tab::Erase,
+        });
+        self.confirmed = false;
+        if self.step == wizard::Step::Progress {
+            self.step = wizard::Step::Report;
+        }
+    }
+
+    /// Takes the files a search job destroyed off the list of matches; what
+    /// is left failed or was not selected. Matches from a hash list get their
+    /// purge report first.
+    fn drop_erased_hits(&mut self) {
+        if self.tab == Tab::Search
+            && let Some(Ok(outcome)) = &mut self.search
+        {
+            if let Some((list, hashes)) = &self.search_hashes
+                && outcome.hits.iter().any(|hit| hit.digest.is_some())
+            {
+                let failures = self.report.as_ref().map_or(&[][..], |report| &report.failures[..]);
+                self.purge_report = Some(search::purge_report(list, hashes, outcome, |hit| {
+                    match failures.iter().find(|(path, _)| *path == hit.path) {
+                        _ if !hit.selected => search::Fate::Kept,
+                        Some((_, error)) => search::Fate::Failed(error.clone()),
+                        None if hit.path.exists() => search::Fate::Failed("Not erased, the job stopped".to_string()),
+                        None => search::Fate::Destroyed,
+                    }
+                }));
             }
+            outcome.hits.retain(|hit| hit.path.exists());
+            self.search_confirmed = false;
+        }
+    }
+
+    /// Shows `text` in the status line and, when enabled, speaks it aloud.
+    fn announce(&mut self, text: String) {
+        if self.settings.announce {
+            announce::speak(&text);
         }
+        self.status = text;
     }
 
     fn view(&self) -> iced::Element<'_, Message> {
-        let row = widget::container(
-            widget::row![
-                widget::button("Open file").on_press(Message::SelectFile),
-                widget::container(widget::text!(" File: {}", self.file)).padding(7),
+        if let Some(step) = self.onboarding {
+            return onboarding::view(self, step);
+        }
+        let content = match self.tab {
+            Tab::Erase if !self.settings.advanced => wizard::view(self),
+            Tab::Erase => self.erase_view(),
+            Tab::FreeSpace => self.free_space_view(),
+            Tab::Region => region::view(self),
+            Tab::Drive => drives::view(self),
+            Tab::Check => scan::view(self),
+            Tab::Search => search::view(self),
+            Tab::History => self.history_view(),
+            Tab::Statistics => stats::Statistics::compute(self.db.records(), history::month_of(history::unix_now())).view(),
+            Tab::Staging => staging::view(self),
+            Tab::Diagnostics => diagnostics::view(self),
+            Tab::Settings => self.settings_view(),
+        };
+        // Right-to-left locales get the tabs on the right and the text
+        // against the right edge; the words themselves are still English.
+        let rtl = units::right_to_left();
+        let content = widget::container(widget::column![
+            widget::container(content).height(iced::Length::Fill),
+        ]
+            .push_maybe(self.settings.simulate.map(|speed| {
+                widget::text!(
+                    "Simulation at {}: nothing is written, deleted or recorded",
+                    units::speed(speed as f64),
+                )
+                    .color(iced::Color::from_rgb(0.8, 0.4, 0.0))
+            }))
+            .push(widget::text!("Status: {}", self.status))
+            .spacing(10)
+            .align_x(if rtl { iced::Alignment::End } else { iced::Alignment::Start }))
+            .padding(10)
+            .width(iced::Length::Fill)
+            .height(iced::Length::Fill);
+
+        let tab_button = |tab: Tab| {
+            let button = widget::button(tab.label())
+                .on_press(Message::TabSelected(tab))
+                .style(if tab == self.tab { widget::button::primary } else { widget::button::text });
+            if self.settings.window.width < COMPACT_WIDTH {
+                button
+            } else {
+                button.width(iced::Length::Fill)
+            }
+        };
+
+        if self.settings.window.width < COMPACT_WIDTH {
+            let mut tabs = Tab::ALL;
+            if rtl {
+                tabs.reverse();
+            }
+            widget::column![
+                widget::row(tabs.map(|tab| tab_button(tab).into())).spacing(5).padding(5),
+                content,
+            ]
+                .into()
+        } else {
+            let tabs = widget::container(widget::column(Tab::ALL.map(|tab| tab_button(tab).into())).spacing(5))
+                .padding(10)
+                .width(150)
+                .height(iced::Length::Fill)
+                .style(widget::container::rounded_box);
+            match rtl {
+                true => widget::row![content, tabs].into(),
+                false => widget::row![tabs, content].into(),
+            }
+        }
+    }
+
+    fn progress_row(&self, button: widget::Button<'static, Message>) -> iced::Element<'static, Message> {
+        widget::row![
+            widget::tooltip(
+                widget::progress_bar(0.0..=100.0, self.progress),
+                widget::text!("Progress: {:.0}%", self.progress),
+                widget::tooltip::Position::Top,
+            ),
+        ]
+            .push_maybe(
+                (self.erasing && self.phase != progress::Phase::Waiting)
+                    .then(|| widget::text(self.phase.to_string()).size(14).width(240)),
+            )
+            .push(button)
+            .spacing(10)
+            .into()
+    }
+
+    /// Adds what an app cleanup pack covers to the list of files.
+    fn pack_list(&self) -> iced::Element<'_, Message> {
+        widget::pick_list(self.packs.as_slice(), None::<pack::Pack>, Message::PackSelected)
+            .placeholder("App cleanup...")
+            .into()
+    }
+
+    fn tag_row(&self) -> iced::Element<'_, Message> {
+        widget::row![
+            widget::text("Case or ticket"),
+            widget::text_input("Optional, printed on the certificate", &self.tag)
+                .on_input_maybe((!self.erasing).then_some(Message::TagChanged))
+                .width(300),
+            widget::text("Operator"),
+            widget::text_input(&identity::os_user(), &self.operator)
+                .on_input_maybe((!self.erasing).then_some(Message::OperatorChanged))
+                .width(200),
+        ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+    }
+
+    fn preset_row(&self) -> iced::Element<'_, Message> {
+        widget::row![
+            widget::text("Compliance preset"),
+            widget::pick_list(compliance::PRESETS, self.preset, |preset| Message::PresetSelected(Some(preset)))
+                .placeholder("None"),
+            widget::button("None").on_press_maybe(self.preset.map(|_| Message::PresetSelected(None))),
+            widget::text(self.preset.map_or("", |preset| preset.summary)).size(14),
+        ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+    }
+
+    fn erase_view(&self) -> iced::Element<'_, Message> {
+        let compact = self.settings.window.width < COMPACT_WIDTH + 300.0;
+        let buttons = [
+            widget::button("Open file").on_press(Message::SelectFile(picker::Selection::Single)).into(),
+            widget::button("Open files (Ctrl+O)").on_press(Message::SelectFile(picker::Selection::Multiple)).into(),
+            widget::button("Open folder").on_press(Message::SelectFile(picker::Selection::Directory)).into(),
+            self.pack_list(),
+        ];
+        let filters = [
+            widget::pick_list(picker::Profile::ALL, Some(self.picker.profile), Message::ProfileSelected).into(),
+            widget::text_input("Extra extensions, e.g. pem, key", &self.picker.custom)
+                .on_input(Message::CustomFilterChanged)
+                .into(),
+            widget::checkbox("Show hidden", self.picker.show_hidden)
+                .on_toggle(Message::ShowHiddenToggled)
+                .into(),
+        ];
+        // Narrow windows stack the filters under the buttons instead of squeezing them.
+        let picker: iced::Element<'_, Message> = if compact {
+            widget::column![
+                widget::row(buttons).spacing(10),
+                widget::row(filters).spacing(10).align_y(iced::Alignment::Center),
             ]
+                .spacing(10)
+                .into()
+        } else {
+            widget::row(buttons.into_iter().chain(filters))
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into()
+        };
+
+        let files = widget::row![
+            widget::scrollable(widget::column(
+                self.files.iter().enumerate().map(|(index, file)| {
+                    widget::button(widget::text!(" File: {}", file.display()))
+                        .on_press(Message::FileSelected(index))
+                        .style(if self.selected == Some(index) {
+                            widget::button::primary
+                        } else {
+                            widget::button::text
+                        })
+                        .width(iced::Length::Fill)
+                        .into()
+                })
+            ))
                 .width(iced::Length::Fill)
-                .height(50)
+                .height(iced::Length::Fill),
+            widget::column![
+                widget::button("Remove").on_press_maybe(
+                    (!self.erasing && self.selected.is_some()).then_some(Message::RemoveSelected)
+                ),
+                widget::button("Clear").on_press_maybe((!self.erasing).then_some(Message::ClearFiles)),
+            ]
                 .spacing(10),
-        )
-            .center_x(iced::Length::Fill);
+        ]
+            .spacing(10);
 
         let erase_button = if self.erasing {
-            widget::button("Erasing...")
+            widget::button("Cancel (Esc)").on_press(Message::Cancel)
+        } else {
+            widget::button("Erase file (Ctrl+E)").on_press(Message::EraseFile)
+        };
+
+        // How the last batch went, until the next files are picked.
+        let summary = self.report.as_ref().filter(|_| !self.erasing && self.files.is_empty()).map(|report| summary::view(report, self.erasing));
+
+        widget::column![picker, files, self.preset_row(), self.tag_row()]
+            .push_maybe(summary)
+            .push_maybe(container::warning(self))
+            .push_maybe(shadow::warning(self))
+            .push_maybe(snapshot::warning(self))
+            .push(self.progress_row(erase_button))
+            .spacing(10)
+            .into()
+    }
+
+    fn free_space_view(&self) -> iced::Element<'_, Message> {
+        let dir = match &self.free_space_dir {
+            Some(dir) => dir.display().to_string(),
+            None => "No folder selected".to_string(),
+        };
+        let wipe_button = if self.erasing {
+            widget::button("Cancel (Esc)").on_press(Message::Cancel)
         } else {
-            widget::button("Erase file").on_press(Message::EraseFile)
+            widget::button("Wipe free space")
+                .on_press_maybe(self.free_space_dir.as_ref().map(|_| Message::WipeFreeSpace))
         };
 
-        widget::container(widget::column![
-            row,
+        widget::column![
+            widget::text("Overwrites the unused space of a volume so files deleted earlier cannot be recovered."),
             widget::row![
-                widget::progress_bar(0.0..=100.0, self.progress),
-                erase_button,
-            ].spacing(10)
-        ])
-            .padding(10)
-            .width(iced::Length::Fill)
-            .height(iced::Length::Fill)
+                widget::button("Select folder").on_press(Message::SelectFreeSpaceDir),
+                widget::text(dir),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::vertical_space(),
+            self.tag_row(),
+            self.progress_row(wipe_button),
+        ]
+            .spacing(10)
+            .into()
+    }
+
+    fn history_view(&self) -> iced::Element<'_, Message> {
+        let locked = (self.db.locked() > 0).then(|| {
+            widget::text!("{} job(s) are encrypted. Unlock them with your passphrase in the settings.", self.db.locked())
+        });
+        if self.db.records().is_empty() {
+            return match locked {
+                Some(locked) => locked.into(),
+                None => widget::text("No jobs have run yet.").into(),
+            };
+        }
+        widget::column![
+            widget::text_input("Search by name, method, date or path", &self.history_query)
+                .on_input(Message::HistorySearch),
+            widget::scrollable(
+                widget::column(self.db.search(&self.history_query).map(|record| {
+                    let status = match record.outcome {
+                        history::Outcome::Success => "Done",
+                        history::Outcome::Failed => "Failed",
+                        history::Outcome::Cancelled => "Cancelled",
+                        history::Outcome::Unsanitized => "NOT SANITIZED",
+                    };
+                    let tag = if record.tag.is_empty() { String::new() } else { format!(" [{}]", record.tag) };
+                    widget::row![
+                        widget::text!("{}  {}: {}{}", record.date(), status, record.description, tag)
+                            .width(iced::Length::Fill),
+                        widget::button("JSON")
+                            .on_press(Message::ExportCertificate(record.id, certificate::Format::Json)),
+                        widget::button("PDF")
+                            .on_press(Message::ExportCertificate(record.id, certificate::Format::Pdf)),
+                    ]
+                        .spacing(5)
+                        .align_y(iced::Alignment::Center)
+                        .into()
+                }))
+                    .spacing(5),
+            ),
+        ]
+            .push_maybe(locked)
+            .spacing(10)
+            .into()
+    }
+
+    /// Encrypting the history and certificates, or unlocking them for this
+    /// session once they are.
+    fn encryption_view(&self) -> iced::Element<'_, Message> {
+        let passphrase = widget::text_input("Passphrase", &self.passphrase)
+            .secure(true)
+            .on_input(Message::PassphraseChanged)
+            .width(200);
+        if !self.encrypted {
+            return widget::column![
+                widget::row![
+                    widget::text("Encrypt history and certificates"),
+                    passphrase,
+                    widget::text_input("Passphrase again", &self.passphrase_again)
+                        .secure(true)
+                        .on_input(Message::PassphraseAgainChanged)
+                        .width(200),
+                    widget::button("Encrypt")
+                        .on_press_maybe((!self.passphrase.is_empty() && !self.erasing).then_some(Message::EncryptHistory)),
+                ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                widget::text(
+                    "The passphrase is asked for once per session and never stored. Without it the history \
+                     and certificates cannot be read, not even by you.",
+                )
+                    .size(14),
+            ]
+                .spacing(10)
+                .into();
+        }
+        if self.db.key().is_none() {
+            return widget::row![
+                widget::text("The history is encrypted"),
+                passphrase.on_submit(Message::UnlockHistory),
+                widget::button("Unlock").on_press(Message::UnlockHistory),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into();
+        }
+        widget::text("The history and certificates are encrypted, and unlocked for this session.").into()
+    }
+
+    fn settings_view(&self) -> iced::Element<'_, Message> {
+        let locked = |key: &str, value: String| -> Option<iced::Element<'_, Message>> {
+            self.policy.locks(key).then(|| widget::text!("{} (set by your organization)", value).into())
+        };
+        let allowed = |methods: Vec<Method>| -> Vec<Method> {
+            methods.into_iter().filter(|method| self.policy.allows_method(method)).collect()
+        };
+        let method: iced::Element<'_, Message> = match locked("method", self.settings.method.to_string()) {
+            Some(text) => text,
+            None => widget::row![
+                widget::pick_list(
+                    allowed(self.methods.methods().to_vec()),
+                    Some(self.settings.method.clone()),
+                    Message::MethodSelected,
+                ),
+                widget::text("Random passes"),
+                widget::pick_list(
+                    allowed((1..=35).map(Method::random).collect()),
+                    self.settings.method.key().starts_with("random-").then(|| self.settings.method.clone()),
+                    Message::MethodSelected,
+                ),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into(),
+        };
+        let verification = locked("verification", self.settings.verification.to_string()).unwrap_or_else(|| {
+            widget::pick_list(
+                engine::Verification::ALL
+                    .into_iter()
+                    .filter(|verification| verification.at_least(self.policy.minimum_verification) == *verification)
+                    .collect::<Vec<_>>(),
+                Some(self.settings.verification),
+                Message::VerificationSelected,
+            )
+                .into()
+        });
+        let timestamps = locked("timestamps", self.settings.timestamps.to_string()).unwrap_or_else(|| {
+            widget::pick_list(engine::Timestamps::ALL, Some(self.settings.timestamps), Message::TimestampsSelected).into()
+        });
+        let sizes = locked("units", self.settings.units.to_string()).unwrap_or_else(|| {
+            widget::pick_list(units::Base::ALL, Some(self.settings.units), Message::UnitsSelected).into()
+        });
+        let unlocked = |key: &str| !self.policy.locks(key);
+        let webhook_hint = match http::Url::parse(&self.settings.webhook_url) {
+            _ if self.settings.webhook_url.is_empty() => "Leave empty for no notifications.".to_string(),
+            Ok(_) if self.settings.webhook_secret.is_empty() => "Sent unsigned, set a secret to sign it.".to_string(),
+            Ok(_) => "Signed with HMAC-SHA256 in X-File-Eraser-Signature.".to_string(),
+            Err(e) => e,
+        };
+
+        widget::column![
+            widget::row![widget::text("Erase method"), method]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::text!(
+                "{} Takes about {}x as long as a single pass.",
+                self.settings.method.explanation(),
+                self.settings.method.duration_multiplier(),
+            )
+                .size(14),
+            widget::row![
+                widget::text("Verification"),
+                verification,
+                widget::text("Reading data back costs up to one extra pass per verified pass.").size(14),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::row![
+                widget::text("Timestamps before deletion"),
+                timestamps,
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::row![
+                widget::text("Sizes"),
+                sizes,
+                widget::text("Decimal separators and the layout follow your locale.").size(14),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::checkbox(
+                "Shred file names: refill directory entries after erasing so deleted names cannot be recovered",
+                self.settings.churn_directories,
+            )
+                .on_toggle_maybe(unlocked("churn_directories").then_some(Message::ChurnToggled)),
+            widget::checkbox(
+                "Checksum written blocks: when verification fails, tell a drive that dropped writes from a fault in the eraser",
+                self.settings.checksums,
+            )
+                .on_toggle_maybe(unlocked("checksums").then_some(Message::ChecksumsToggled)),
+            widget::row![
+                widget::text("Webhook"),
+                widget::text_input("https://siem.example/hooks/erasure", &self.settings.webhook_url)
+                    .on_input_maybe(unlocked("webhook_url").then_some(Message::WebhookUrlChanged)),
+                widget::text_input("Signing secret", &self.settings.webhook_secret)
+                    .secure(true)
+                    .on_input_maybe(unlocked("webhook_secret").then_some(Message::WebhookSecretChanged))
+                    .width(200),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::text(webhook_hint).size(14),
+            widget::row![
+                widget::text("Mail certificates"),
+                widget::text_input("SMTP relay, e.g. localhost:25", &self.settings.smtp_server)
+                    .on_input_maybe(unlocked("smtp_server").then_some(Message::SmtpServerChanged)),
+                widget::text_input("From", &self.settings.smtp_from)
+                    .on_input_maybe(unlocked("smtp_from").then_some(Message::SmtpFromChanged)),
+                widget::text_input("To (comma separated)", &self.settings.smtp_to)
+                    .on_input_maybe(unlocked("smtp_to").then_some(Message::SmtpToChanged)),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::text("Sent without TLS or login, so use a relay on this machine or network.").size(14),
+            widget::checkbox("Advanced mode (single screen instead of the step-by-step guide)", self.settings.advanced)
+                .on_toggle_maybe(unlocked("advanced").then_some(Message::AdvancedToggled)),
+            widget::checkbox("Announce progress with the system speech service", self.settings.announce)
+                .on_toggle_maybe(unlocked("announce").then_some(Message::AnnounceToggled)),
+            widget::row![
+                widget::text("Wipe certificates older than"),
+                widget::text_input("90", &self.report_age).on_input(Message::ReportAgeChanged).width(60),
+                widget::text("days"),
+                widget::button("Wipe now").on_press_maybe((!self.erasing).then_some(Message::WipeOldReports)),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::text(
+                "Certificates and the history name every file that was erased. They are kept readable by you \
+                 only, and old certificates are erased with the current method like any other file.",
+            )
+                .size(14),
+            self.encryption_view(),
+            widget::row![
+                widget::checkbox("Hold erased files for", self.settings.staging.is_some())
+                    .on_toggle_maybe(unlocked("staging").then_some(Message::StagingToggled)),
+                widget::text_input("60", &self.staging_minutes)
+                    .on_input_maybe(unlocked("staging").then_some(Message::StagingMinutesChanged))
+                    .width(60),
+                widget::text("minutes before destroying them"),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            widget::text(
+                "Held files are moved out of their folders right away and can be restored from the Staged tab until \
+                 the time is up. They keep their contents until then, so do not use this for files that must be \
+                 gone immediately.",
+            )
+                .size(14),
+            widget::text("Window size and position are remembered between sessions."),
+            widget::text(match portable::active() {
+                Some(root) => format!(
+                    "Portable: settings, history and certificates are kept in {} and go wherever this copy of the app goes.",
+                    root.display(),
+                ),
+                None => String::new(),
+            })
+                .size(14),
+            widget::text(if self.policy.settings.is_empty()
+                && self.policy.minimum_method.is_none()
+                && self.policy.minimum_verification == engine::Verification::None
+            {
+                String::new()
+            } else {
+                format!("Some choices are limited by your organization's policy in {}.", Policy::path().display())
+            })
+                .size(14),
+        ]
+            .spacing(10)
             .into()
     }
 
     fn subscription(&self) -> Subscription<Message> {
+        let window = window::events().map(|(id, event)| Message::Window(id, event));
+        let keyboard = keyboard::on_key_press(shortcut);
+        let staging = match self.staging.as_ref().is_some_and(|area| !area.items().is_empty()) {
+            true => iced::time::every(Duration::from_secs(60)).map(|_| Message::StagingTick),
+            false => Subscription::none(),
+        };
         if let Some(receiver) = self.receiver.clone() {
-            Subscription::run_with_id(
-                "erase_subscription",
-                Box::pin(receiver.into_stream().map(Message::Progress)),
-            )
+            Subscription::batch([
+                window,
+                keyboard,
+                staging,
+                Subscription::run_with_id(
+                    "erase_subscription",
+                    Box::pin(receiver.events().map(Message::Progress)),
+                ),
+                // Percentages are not queued, they are picked up on a timer.
+                iced::time::every(progress::TICK).map(|_| Message::ProgressTick),
+            ])
         } else {
-            Subscription::none()
+            Subscription::batch([window, keyboard, staging])
         }
     }
 }
 
-async fn open_file(support_ext: &[impl ToString]) -> Result<String, String> {
-    println!("Opening file..., {}", support_ext.len());
-    let picked_file = rfd::AsyncFileDialog::new()
-        .set_title("Open file...")
-        .add_filter("All files", &["*"])
-        .pick_file()
-        .await;
-
-    let picked_file = match picked_file {
-        Some(file) => file,
-        None => return Err("No file was selected.".to_string()),
-    };
+async fn save_file(title: &'static str, name: String, contents: Vec<u8>) -> Result<PathBuf, String> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title(title)
+        .set_file_name(name)
+        .save_file()
+        .await
+        .ok_or_else(|| "No file was selected.".to_string())?;
+    std::fs::write(file.path(), contents).map_err(|e| e.to_string())?;
+    Ok(file.path().to_path_buf())
+}
 
-    let path = match picked_file.path().to_str() {
-        Some(path) => path,
-        None => return Err("File path is not valid UTF-8.".to_string()),
-    };
+fn shortcut(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Message> {
+    use keyboard::key::Named;
 
-    Ok(path.to_string())
+    match key.as_ref() {
+        keyboard::Key::Character("o") if modifiers.command() => {
+            Some(Message::SelectFile(picker::Selection::Multiple))
+        }
+        keyboard::Key::Character("e") if modifiers.command() => Some(Message::EraseFile),
+        keyboard::Key::Named(Named::Delete) => Some(Message::RemoveSelected),
+        keyboard::Key::Named(Named::Escape) => Some(Message::Cancel),
+        keyboard::Key::Named(Named::Tab) => Some(Message::CycleFocus(!modifiers.shift())),
+        keyboard::Key::Named(Named::ArrowDown) => Some(Message::MoveSelection(true)),
+        keyboard::Key::Named(Named::ArrowUp) => Some(Message::MoveSelection(false)),
+        _ => None,
+    }
 }
 
 fn theme(_state: &App) -> Theme {
     Theme::Nord
 }
 
-fn main() -> Result<(), iced::Error> {
-    iced::application("File Eraser", App::update, App::view)
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Before anything is read from the config or data folders.
+    portable::init(&mut args);
+    let queued = cli::queued(&mut args).unwrap_or_default();
+    let methods = Registry::load();
+    let policy = match Policy::load(&methods) {
+        Ok(policy) => policy,
+        Err(e) => {
+            eprintln!("file-eraser: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let settings = match cli::settings(&mut args, &methods, &policy) {
+        Ok(settings) => settings,
+        Err(code) => return code,
+    };
+    units::apply(settings.units);
+    if let Some(code) = cli::run(&args, &methods, &settings, &policy) {
+        return code;
+    }
+
+    let geometry = settings.window;
+    let position = match (geometry.x, geometry.y) {
+        (Some(x), Some(y)) => window::Position::Specific(iced::Point::new(x, y)),
+        _ => window::Position::Centered,
+    };
+
+    let result = iced::application("File Eraser", App::update, App::view)
         .subscription(App::subscription)  // اضافه کردن subscription به application
         .theme(theme)
-        .window_size(iced::Size::new(750.0, 100.0))
-        .position(iced::window::Position::Centered)
-        .run_with(|| (App::new(), iced::Task::none()))
+        .window(window::Settings {
+            size: iced::Size::new(geometry.width, geometry.height),
+            min_size: Some(iced::Size::new(480.0, 320.0)),
+            position,
+            resizable: true,
+            // Closing goes through `Message::Window` so the geometry gets saved.
+            exit_on_close_request: false,
+            ..window::Settings::default()
+        })
+        .run_with(move || {
+            let task = match queued.is_empty() {
+                true => iced::Task::none(),
+                false => iced::Task::done(Message::FileOpened(Ok(picker::Picked { files: queued, unreadable: Vec::new() }))),
+            };
+            (App::new(settings, methods, policy), task)
+        });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error running the interface: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,345 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pass {
+    Random,
+    /// A byte pattern repeated over the whole file.
+    Pattern(Vec<u8>),
+}
+
+impl Pass {
+    fn byte(value: u8) -> Self {
+        Pass::Pattern(vec![value])
+    }
+
+    /// Parses `"random"`, `"0xFF"`, `"92 49 24"` or `"0x924924"`.
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("random") {
+            return Ok(Pass::Random);
+        }
+        let digits: String = text
+            .split_whitespace()
+            .map(|part| part.trim_start_matches("0x").trim_start_matches("0X"))
+            .collect();
+        if digits.is_empty() || !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("invalid pass `{}`", text));
+        }
+        (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| format!("invalid pass `{}`", text)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Pass::Pattern)
+    }
+
+    /// Fills `buffer` with the pass contents for the bytes starting at `offset`,
+    /// keeping multi-byte patterns aligned across chunk boundaries.
+    pub fn fill(&self, buffer: &mut [u8], offset: u64, rng: &mut impl rand::Rng) {
+        match self {
+            Pass::Random => rng.fill(buffer),
+            Pass::Pattern(pattern) => {
+                let start = (offset % pattern.len() as u64) as usize;
+                for (byte, value) in buffer.iter_mut().zip(pattern.iter().cycle().skip(start)) {
+                    *byte = *value;
+                }
+            }
+        }
+    }
+}
+
+/// A sanitization scheme: an ordered list of passes plus the text shown in
+/// the method picker. Built-in schemes and the ones loaded from `methods.d`
+/// both implement it.
+pub trait WipeMethod: fmt::Debug + Send + Sync {
+    /// Stable identifier stored in settings and history.
+    fn key(&self) -> String;
+    fn name(&self) -> String;
+    /// Plain-language explanation shown to people who do not know the standards.
+    fn explanation(&self) -> String;
+    fn passes(&self) -> Vec<Pass>;
+
+    /// Whether the standard itself demands a read-back of the final pass.
+    fn verify(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    Random(usize),
+    Dod,
+    Gutmann,
+    Schneier,
+    Vsitr,
+    HmgBaseline,
+    HmgEnhanced,
+    Pfitzner,
+}
+
+impl WipeMethod for Builtin {
+    fn key(&self) -> String {
+        match self {
+            Builtin::Random(count) => format!("random-{}", count),
+            Builtin::Dod => "dod".to_string(),
+            Builtin::Gutmann => "gutmann".to_string(),
+            Builtin::Schneier => "schneier".to_string(),
+            Builtin::Vsitr => "vsitr".to_string(),
+            Builtin::HmgBaseline => "hmg-is5-baseline".to_string(),
+            Builtin::HmgEnhanced => "hmg-is5-enhanced".to_string(),
+            Builtin::Pfitzner => "pfitzner".to_string(),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Builtin::Random(1) => "Quick (1 random pass)".to_string(),
+            Builtin::Random(count) => format!("Random ({} passes)", count),
+            Builtin::Dod => "DoD 5220.22-M (3 passes)".to_string(),
+            Builtin::Gutmann => "Gutmann (35 passes)".to_string(),
+            Builtin::Schneier => "Schneier (7 passes)".to_string(),
+            Builtin::Vsitr => "German VSITR (7 passes)".to_string(),
+            Builtin::HmgBaseline => "HMG IS5 Baseline (1 pass)".to_string(),
+            Builtin::HmgEnhanced => "HMG IS5 Enhanced (3 passes + verify)".to_string(),
+            Builtin::Pfitzner => "Pfitzner (33 random passes)".to_string(),
+        }
+    }
+
+    fn explanation(&self) -> String {
+        match self {
+            Builtin::Random(1) => "Fast. Overwrites the file once with random data. Enough for modern drives.",
+            Builtin::Random(_) => "Balanced. Overwrites the file several times with random data.",
+            Builtin::Dod => "Thorough. The US Department of Defense three-pass scheme used by many companies.",
+            Builtin::Gutmann => "Paranoid. 35 passes designed for old magnetic disks. Very slow.",
+            Builtin::Schneier => "Bruce Schneier's scheme: ones, zeros, then five random passes.",
+            Builtin::Vsitr => "German federal standard: alternating zeros and ones six times, then 0xAA.",
+            Builtin::HmgBaseline => "British government baseline: a single pass of zeros.",
+            Builtin::HmgEnhanced => "British government enhanced: zeros, ones, random, then a verification read.",
+            Builtin::Pfitzner => "Roy Pfitzner's scheme: 33 random passes. Extremely slow.",
+        }
+        .to_string()
+    }
+
+    fn passes(&self) -> Vec<Pass> {
+        match *self {
+            Builtin::Random(count) => vec![Pass::Random; count.max(1)],
+            // DoD 5220.22-M: zeros, ones, then random.
+            Builtin::Dod => vec![Pass::byte(0x00), Pass::byte(0xFF), Pass::Random],
+            Builtin::Gutmann => {
+                let mut passes = vec![Pass::Random; 4];
+                passes.push(Pass::byte(0x55));
+                passes.push(Pass::byte(0xAA));
+                passes.extend(MFM_PATTERNS.iter().map(|pattern| Pass::Pattern(pattern.to_vec())));
+                passes.extend((0..=0xFF).step_by(0x11).map(Pass::byte));
+                passes.extend(MFM_PATTERNS.iter().map(|pattern| Pass::Pattern(pattern.to_vec())));
+                passes.extend(RLL_PATTERNS.iter().map(|pattern| Pass::Pattern(pattern.to_vec())));
+                passes.extend(vec![Pass::Random; 4]);
+                passes
+            }
+            Builtin::Schneier => {
+                let mut passes = vec![Pass::byte(0xFF), Pass::byte(0x00)];
+                passes.extend(vec![Pass::Random; 5]);
+                passes
+            }
+            Builtin::Vsitr => {
+                let mut passes: Vec<Pass> = [0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF].map(Pass::byte).to_vec();
+                passes.push(Pass::byte(0xAA));
+                passes
+            }
+            Builtin::HmgBaseline => vec![Pass::byte(0x00)],
+            Builtin::HmgEnhanced => vec![Pass::byte(0x00), Pass::byte(0xFF), Pass::Random],
+            Builtin::Pfitzner => vec![Pass::Random; 33],
+        }
+    }
+
+    fn verify(&self) -> bool {
+        *self == Builtin::HmgEnhanced
+    }
+}
+
+/// A method read from a TOML file in `methods.d`:
+///
+/// ```toml
+/// name = "ACME media policy 4.2"
+/// description = "Zeros, then random, as required by ACME IT."
+/// passes = ["0x00", "random"]
+/// ```
+///
+/// A pass is `"random"` or a byte pattern such as `"0xFF"` or `"92 49 24"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Defined {
+    key: String,
+    name: String,
+    description: String,
+    passes: Vec<Pass>,
+}
+
+impl Defined {
+    pub fn parse(key: &str, text: &str) -> Result<Self, String> {
+        let doc = text.parse::<toml_edit::DocumentMut>().map_err(|e| e.to_string())?;
+        let string = |field: &str| doc.get(field).and_then(|item| item.as_str()).map(str::to_string);
+
+        let passes = doc
+            .get("passes")
+            .and_then(|item| item.as_array())
+            .ok_or("missing `passes` array")?
+            .iter()
+            .map(|pass| pass.as_str().ok_or("passes must be strings".to_string()).and_then(Pass::parse))
+            .collect::<Result<Vec<_>, _>>()?;
+        if passes.is_empty() {
+            return Err("`passes` is empty".to_string());
+        }
+
+        Ok(Self {
+            key: format!("custom-{}", key),
+            name: string("name").unwrap_or_else(|| key.to_string()),
+            description: string("description").unwrap_or_default(),
+            passes,
+        })
+    }
+}
+
+impl WipeMethod for Defined {
+    fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    fn name(&self) -> String {
+        format!("{} ({} passes)", self.name, self.passes.len())
+    }
+
+    fn explanation(&self) -> String {
+        if self.description.is_empty() {
+            "Defined by your organization.".to_string()
+        } else {
+            self.description.clone()
+        }
+    }
+
+    fn passes(&self) -> Vec<Pass> {
+        self.passes.clone()
+    }
+}
+
+/// Cheap, cloneable handle to any `WipeMethod`, compared by key.
+#[derive(Debug, Clone)]
+pub struct Method(Arc<dyn WipeMethod>);
+
+impl Method {
+    pub fn new(method: impl WipeMethod + 'static) -> Self {
+        Self(Arc::new(method))
+    }
+
+    pub fn random(count: usize) -> Self {
+        Self::new(Builtin::Random(count))
+    }
+
+    pub fn key(&self) -> String {
+        self.0.key()
+    }
+
+    pub fn explanation(&self) -> String {
+        self.0.explanation()
+    }
+
+    pub fn passes(&self) -> Vec<Pass> {
+        self.0.passes()
+    }
+
+    pub fn verify(&self) -> bool {
+        self.0.verify()
+    }
+
+    /// How long the method takes compared with a single overwrite pass.
+    pub fn duration_multiplier(&self) -> usize {
+        self.passes().len() + usize::from(self.verify())
+    }
+}
+
+impl PartialEq for Method {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.name())
+    }
+}
+
+/// Every method the user can pick: the built-in presets followed by the
+/// definitions found in `<config dir>/file-eraser/methods.d/*.toml`.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    methods: Vec<Method>,
+}
+
+impl Registry {
+    const BUILTIN: [Builtin; 9] = [
+        Builtin::Random(1),
+        Builtin::Random(3),
+        Builtin::HmgBaseline,
+        Builtin::Dod,
+        Builtin::HmgEnhanced,
+        Builtin::Schneier,
+        Builtin::Vsitr,
+        Builtin::Pfitzner,
+        Builtin::Gutmann,
+    ];
+
+    pub fn builtin() -> Self {
+        Self {
+            methods: Self::BUILTIN.into_iter().map(Method::new).collect(),
+        }
+    }
+
+    pub fn load() -> Self {
+        let mut registry = Self::builtin();
+        if let Some(dir) = crate::portable::config_dir().map(|dir| dir.join("methods.d")) {
+            registry.load_dir(&dir);
+        }
+        registry
+    }
+
+    fn load_dir(&mut self, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let key = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let defined = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| Defined::parse(&key, &text));
+            match defined {
+                Ok(defined) => self.methods.push(Method::new(defined)),
+                Err(e) => eprintln!("Ignoring method definition {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    pub fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
+    pub fn find(&self, key: &str) -> Option<Method> {
+        if let Some(method) = self.methods.iter().find(|method| method.key() == key) {
+            return Some(method.clone());
+        }
+        key.strip_prefix("random-")
+            .and_then(|count| count.parse().ok())
+            .filter(|count| (1..=35).contains(count))
+            .map(Method::random)
+    }
+}
+
+const MFM_PATTERNS: [[u8; 3]; 3] = [[0x92, 0x49, 0x24], [0x49, 0x24, 0x92], [0x24, 0x92, 0x49]];
+const RLL_PATTERNS: [[u8; 3]; 3] = [[0x6D, 0xB6, 0xDB], [0xB6, 0xDB, 0x6D], [0xDB, 0x6D, 0xB6]];
@@ -0,0 +1,200 @@
+use iced::widget;
+use crate::engine::Verification;
+use crate::strategy::Facts;
+use crate::{diagnostics, portable, shell, App, Message};
+
+/// How thorough erasing is out of the box, picked on the first start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Quick,
+    Standard,
+    Thorough,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 3] = [Profile::Quick, Profile::Standard, Profile::Thorough];
+
+    pub fn method(self) -> &'static str {
+        match self {
+            Profile::Quick => "random-1",
+            Profile::Standard => "random-3",
+            Profile::Thorough => "dod",
+        }
+    }
+
+    pub fn verification(self) -> Verification {
+        match self {
+            Profile::Quick => Verification::None,
+            Profile::Standard => Verification::LastPass,
+            Profile::Thorough => Verification::EveryPass,
+        }
+    }
+
+    fn summary(self) -> &'static str {
+        match self {
+            Profile::Quick => "One random pass, nothing read back. Fast, and on SSDs as good as any other.",
+            Profile::Standard => "Three random passes, the last one read back to prove it reached the drive.",
+            Profile::Thorough => "DoD 5220.22-M with every pass read back. Slow, for policies that name a standard.",
+        }
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Profile::Quick => write!(f, "Quick"),
+            Profile::Standard => write!(f, "Standard"),
+            Profile::Thorough => write!(f, "Thorough"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Profile,
+    Storage,
+    Capabilities,
+    Integration,
+}
+
+impl Step {
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Step::Profile => Some(Step::Storage),
+            Step::Storage => Some(Step::Capabilities),
+            Step::Capabilities => Some(Step::Integration),
+            Step::Integration => None,
+        }
+    }
+
+    pub fn back(self) -> Self {
+        match self {
+            Step::Profile | Step::Storage => Step::Profile,
+            Step::Capabilities => Step::Storage,
+            Step::Integration => Step::Capabilities,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Step::Profile => "Welcome 1 of 4: How thorough should erasing be?",
+            Step::Storage => "Welcome 2 of 4: Hard disks and SSDs",
+            Step::Capabilities => "Welcome 3 of 4: What this machine allows",
+            Step::Integration => "Welcome 4 of 4: File manager",
+        }
+    }
+}
+
+/// What the storage the user's files are on means for erasing them.
+fn storage(facts: &Facts) -> &'static str {
+    match facts.rotational {
+        Some(true) => "Your home folder is on a hard disk, where overwriting a file reaches its old contents.",
+        Some(false) => {
+            "Your home folder is on an SSD. Overwriting a file there does its best, but the drive may keep old copies \
+             out of reach; the strategy shown for each file and the confidence score say how far to trust it."
+        }
+        None => "The drive under your home folder could not be told apart, the strategy for each file will say more.",
+    }
+}
+
+pub fn view(app: &App, step: Step) -> iced::Element<'_, Message> {
+    let body: iced::Element<'_, Message> = match step {
+        Step::Profile => profile(app),
+        Step::Storage => {
+            let home = dirs::home_dir().unwrap_or_else(std::env::temp_dir);
+            widget::column![
+                widget::text(
+                    "On a hard disk a file's blocks are overwritten in place, so one pass of random data leaves \
+                     nothing to recover and more passes only take longer."
+                ),
+                widget::text(
+                    "SSDs, USB sticks and memory cards write somewhere new every time and move data around on \
+                     their own, so an overwrite cannot be sure to reach every old copy, and extra passes only wear \
+                     the drive. For them one pass is enough; to be certain, erase the whole drive from the Drive \
+                     tab, which uses the drive's own erase command or encryption key where it can."
+                ),
+                widget::text(storage(&Facts::inspect(&home))).size(14),
+            ]
+                .spacing(15)
+                .into()
+        }
+        Step::Capabilities => diagnostics::view(app),
+        Step::Integration => integration(app),
+    };
+
+    let next = match step.next() {
+        Some(_) => widget::button("Next").on_press(Message::OnboardingNext),
+        None => widget::button("Finish").on_press(Message::OnboardingNext),
+    };
+    widget::column![
+        widget::text(step.title()).size(20),
+        widget::container(widget::scrollable(body)).height(iced::Length::Fill),
+        widget::row![
+            widget::button("Back").on_press_maybe((step != Step::Profile).then_some(Message::OnboardingBack)),
+            widget::horizontal_space(),
+            widget::button("Skip setup").style(widget::button::text).on_press(Message::OnboardingSkip),
+            next,
+        ]
+            .spacing(10),
+    ]
+        .spacing(15)
+        .padding(20)
+        .into()
+}
+
+fn profile(app: &App) -> iced::Element<'_, Message> {
+    if app.policy.locks("method") {
+        return widget::text!(
+            "Your organization's policy sets the method to {}. Everything else can be changed in Settings later.",
+            app.settings.method,
+        )
+            .into();
+    }
+    let chosen = Profile::ALL
+        .into_iter()
+        .find(|profile| profile.method() == app.settings.method.key() && profile.verification() == app.settings.verification);
+    widget::column![widget::text("You can still pick another method for each job, and change the default in Settings.")]
+        .extend(Profile::ALL.into_iter().map(|profile| {
+            let allowed = app.methods.find(profile.method()).is_some_and(|method| app.policy.allows_method(&method));
+            let radio = widget::radio(profile.to_string(), profile, chosen, Message::OnboardingProfile);
+            widget::column![
+                radio,
+                widget::text(match allowed {
+                    true => profile.summary().to_string(),
+                    false => format!("{} Not enough for your organization's policy.", profile.summary()),
+                })
+                    .size(14),
+            ]
+                .spacing(5)
+                .into()
+        }))
+        .spacing(15)
+        .into()
+}
+
+fn integration(app: &App) -> iced::Element<'_, Message> {
+    if let Some(root) = portable::active() {
+        return widget::text!(
+            "This copy runs portable from {}, so nothing is added to this machine's file manager.",
+            root.display(),
+        )
+            .into();
+    }
+    let result = match &app.shell_integration {
+        None => None,
+        Some(Ok(places)) => Some(format!("Added: {}", places)),
+        Some(Err(e)) => Some(format!("Could not add it: {}", e)),
+    };
+    widget::column![
+        widget::text!(
+            "Add \"{}\" to the menu you get when right-clicking files and folders. It opens this window with them \
+             queued; nothing is erased until you confirm.",
+            shell::LABEL,
+        ),
+        widget::button("Add to the file manager")
+            .on_press_maybe(app.shell_integration.is_none().then_some(Message::InstallShellIntegration)),
+    ]
+        .push_maybe(result.map(|result| widget::text(result).size(14)))
+        .spacing(15)
+        .into()
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use crate::{picker, portable};
+
+/// Packs that ship with the app, from the `packs/` folder of the source tree.
+const BUILTIN: [(&str, &str); 5] = [
+    ("telegram", include_str!("../packs/telegram.toml")),
+    ("signal", include_str!("../packs/signal.toml")),
+    ("slack", include_str!("../packs/slack.toml")),
+    ("downloads", include_str!("../packs/downloads.toml")),
+    ("torrents", include_str!("../packs/torrents.toml")),
+];
+
+const PLACEHOLDERS: [&str; 8] = ["~", "$HOME", "$CONFIG", "$CACHE", "$DATA", "$LOCALDATA", "$DOWNLOADS", "$TEMP"];
+
+/// Where the known folders are on this machine, for the `$NAME` a path in a
+/// pack may start with.
+fn placeholder(name: &str) -> Option<PathBuf> {
+    match name {
+        "$HOME" | "~" => dirs::home_dir(),
+        "$CONFIG" => dirs::config_dir(),
+        "$CACHE" => dirs::cache_dir(),
+        "$DATA" => dirs::data_dir(),
+        "$LOCALDATA" => dirs::data_local_dir(),
+        "$DOWNLOADS" => dirs::download_dir(),
+        "$TEMP" => Some(std::env::temp_dir()),
+        _ => None,
+    }
+}
+
+/// An app-level cleanup read from TOML: the cache, download and temp
+/// folders one application leaves behind.
+///
+/// ```toml
+/// name = "Slack cache"
+/// description = "Cached images, files and logs of the Slack desktop app."
+/// paths = ["$CONFIG/Slack/Cache", "$DOWNLOADS/*.part"]
+/// ```
+///
+/// Paths use `/` on every platform and may start with `$HOME`, `$CONFIG`,
+/// `$CACHE`, `$DATA`, `$LOCALDATA`, `$DOWNLOADS` or `$TEMP`. `*` and `?`
+/// match within one path component. Folders are taken with everything below
+/// them; paths that do not exist are skipped, so a pack can list where an
+/// app keeps its files on each platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pack {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    paths: Vec<String>,
+}
+
+impl Pack {
+    pub fn parse(key: &str, text: &str) -> Result<Self, String> {
+        let doc = text.parse::<toml_edit::DocumentMut>().map_err(|e| e.to_string())?;
+        let string = |field: &str| doc.get(field).and_then(|item| item.as_str()).map(str::to_string);
+
+        let paths = doc
+            .get("paths")
+            .and_then(|item| item.as_array())
+            .ok_or("missing `paths` array")?
+            .iter()
+            .map(|path| path.as_str().map(str::to_string).ok_or("paths must be strings".to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if paths.is_empty() {
+            return Err("`paths` is empty".to_string());
+        }
+        for path in &paths {
+            let first = path.split('/').next().unwrap_or_default();
+            if first.starts_with(['$', '~']) && !PLACEHOLDERS.contains(&first) {
+                return Err(format!("unknown placeholder {} in `{}`", first, path));
+            }
+            let drive = first.len() == 2 && first.ends_with(':');
+            if !first.starts_with(['$', '~']) && !drive && !path.starts_with('/') {
+                return Err(format!("`{}` must be absolute or start with a placeholder", path));
+            }
+        }
+
+        Ok(Self {
+            key: key.to_string(),
+            name: string("name").unwrap_or_else(|| key.to_string()),
+            description: string("description").unwrap_or_default(),
+            paths,
+        })
+    }
+
+    /// The files the pack covers on this machine right now.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for path in self.paths.iter().flat_map(|path| expand(path)) {
+            match fs::symlink_metadata(&path) {
+                Ok(meta) if meta.is_dir() => {
+                    for (path, e) in picker::walk(&path, true, &mut files) {
+                        eprintln!("Error reading {}: {}", path.display(), e);
+                    }
+                }
+                Ok(meta) if meta.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+        files.sort();
+        files.dedup();
+        files
+    }
+}
+
+impl fmt::Display for Pack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// The built-in packs followed by the ones in
+/// `<config dir>/file-eraser/packs.d/*.toml`. A user pack named like a
+/// built-in one (`slack.toml`) replaces it.
+pub fn load() -> Vec<Pack> {
+    let mut packs: Vec<Pack> = BUILTIN
+        .iter()
+        .map(|(key, text)| Pack::parse(key, text).expect("built-in packs are valid"))
+        .collect();
+    let Some(dir) = portable::config_dir().map(|dir| dir.join("packs.d")) else {
+        return packs;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return packs;
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let key = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let pack = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Pack::parse(&key, &text));
+        match pack {
+            Ok(pack) => match packs.iter_mut().find(|builtin| builtin.key == pack.key) {
+                Some(builtin) => *builtin = pack,
+                None => packs.push(pack),
+            },
+            Err(e) => eprintln!("Ignoring cleanup pack {}: {}", path.display(), e),
+        }
+    }
+    packs
+}
+
+/// The existing paths `path` stands for, placeholders resolved and
+/// wildcards expanded.
+fn expand(path: &str) -> Vec<PathBuf> {
+    let mut components = path.split('/').filter(|component| !component.is_empty());
+    let mut current = if path.starts_with('$') || path.starts_with('~') {
+        match components.next().and_then(placeholder) {
+            Some(base) => vec![base],
+            None => return Vec::new(),
+        }
+    } else if path.starts_with('/') {
+        vec![PathBuf::from("/")]
+    } else {
+        // Windows paths such as `C:/Temp`: the drive is the first component.
+        match components.next() {
+            Some(drive) => vec![PathBuf::from(format!("{}/", drive))],
+            None => return Vec::new(),
+        }
+    };
+    for component in components {
+        current = if component.contains(['*', '?']) {
+            current
+                .iter()
+                .filter_map(|dir| fs::read_dir(dir).ok())
+                .flatten()
+                .flatten()
+                .filter(|entry| wildcard(component, &entry.file_name().to_string_lossy()))
+                .map(|entry| entry.path())
+                .collect()
+        } else {
+            current.into_iter().map(|dir| dir.join(component)).collect()
+        };
+    }
+    current.retain(|path| fs::symlink_metadata(path).is_ok());
+    current
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any run of
+/// characters and `?` for exactly one.
+fn wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was and how much of the name it has taken so far.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((at, taken)) => {
+                    p = at + 1;
+                    n = taken + 1;
+                    star = Some((at, taken + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
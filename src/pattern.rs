@@ -0,0 +1,338 @@
+/// A small regular expression engine over bytes, enough for incident
+/// response searches: literals, `.`, `[a-z]` and `[^...]` classes, `\d \w \s`
+/// (and their negations), `* + ?`, groups with `|`, and `^ $` at line
+/// boundaries. It compiles to a Thompson NFA and simulates it with a Pike
+/// VM, so matching is linear in the input and no pattern can hang a scan.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    program: Vec<Instruction>,
+    classes: Vec<Class>,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Instruction {
+    Byte(u8),
+    Any,
+    Class(usize),
+    Split(usize, usize),
+    Jump(usize),
+    LineStart,
+    LineEnd,
+    Match,
+}
+
+#[derive(Debug, Clone)]
+struct Class {
+    ranges: Vec<(u8, u8)>,
+    negated: bool,
+}
+
+impl Class {
+    fn contains(&self, byte: u8) -> bool {
+        self.ranges.iter().any(|(low, high)| (*low..=*high).contains(&byte)) != self.negated
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Byte(u8),
+    Any,
+    Class(Class),
+    Group(Vec<Vec<Piece>>),
+    LineStart,
+    LineEnd,
+}
+
+#[derive(Debug, Clone)]
+struct Piece {
+    node: Node,
+    repeat: Repeat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Repeat {
+    One,
+    Optional,
+    Star,
+    Plus,
+}
+
+impl Pattern {
+    /// A pattern that matches `text` literally.
+    pub fn literal(text: &str, ignore_case: bool) -> Self {
+        let pieces = text.bytes().map(|byte| Piece { node: Node::Byte(byte), repeat: Repeat::One }).collect();
+        Self::compile(vec![pieces], ignore_case)
+    }
+
+    pub fn regex(text: &str, ignore_case: bool) -> Result<Self, String> {
+        let mut parser = Parser { input: text.as_bytes(), position: 0 };
+        let alternatives = parser.alternatives()?;
+        if parser.position < parser.input.len() {
+            return Err(format!("Unmatched ) at position {}", parser.position + 1));
+        }
+        Ok(Self::compile(alternatives, ignore_case))
+    }
+
+    fn compile(alternatives: Vec<Vec<Piece>>, ignore_case: bool) -> Self {
+        let mut pattern = Self {
+            program: Vec::new(),
+            classes: Vec::new(),
+            ignore_case,
+        };
+        pattern.emit_alternatives(&alternatives);
+        pattern.program.push(Instruction::Match);
+        pattern
+    }
+
+    fn emit_alternatives(&mut self, alternatives: &[Vec<Piece>]) {
+        let mut jumps = Vec::new();
+        for (index, pieces) in alternatives.iter().enumerate() {
+            if index + 1 < alternatives.len() {
+                let split = self.program.len();
+                self.program.push(Instruction::Split(split + 1, 0));
+                self.emit_sequence(pieces);
+                jumps.push(self.program.len());
+                self.program.push(Instruction::Jump(0));
+                let next = self.program.len();
+                self.program[split] = Instruction::Split(split + 1, next);
+            } else {
+                self.emit_sequence(pieces);
+            }
+        }
+        let end = self.program.len();
+        for jump in jumps {
+            self.program[jump] = Instruction::Jump(end);
+        }
+    }
+
+    fn emit_sequence(&mut self, pieces: &[Piece]) {
+        for piece in pieces {
+            match piece.repeat {
+                Repeat::One => self.emit_node(&piece.node),
+                Repeat::Optional => {
+                    let split = self.program.len();
+                    self.program.push(Instruction::Split(0, 0));
+                    self.emit_node(&piece.node);
+                    self.program[split] = Instruction::Split(split + 1, self.program.len());
+                }
+                Repeat::Star => {
+                    let split = self.program.len();
+                    self.program.push(Instruction::Split(0, 0));
+                    self.emit_node(&piece.node);
+                    self.program.push(Instruction::Jump(split));
+                    self.program[split] = Instruction::Split(split + 1, self.program.len());
+                }
+                Repeat::Plus => {
+                    let start = self.program.len();
+                    self.emit_node(&piece.node);
+                    self.program.push(Instruction::Split(start, self.program.len() + 1));
+                }
+            }
+        }
+    }
+
+    fn emit_node(&mut self, node: &Node) {
+        let instruction = match node {
+            Node::Byte(byte) => Instruction::Byte(*byte),
+            Node::Any => Instruction::Any,
+            Node::Class(class) => {
+                self.classes.push(class.clone());
+                Instruction::Class(self.classes.len() - 1)
+            }
+            Node::LineStart => Instruction::LineStart,
+            Node::LineEnd => Instruction::LineEnd,
+            Node::Group(alternatives) => return self.emit_alternatives(alternatives),
+        };
+        self.program.push(instruction);
+    }
+
+    fn accepts(&self, instruction: Instruction, byte: u8) -> bool {
+        let test = |byte: u8| match instruction {
+            Instruction::Byte(expected) => byte == expected,
+            Instruction::Any => byte != b'\n',
+            Instruction::Class(index) => self.classes[index].contains(byte),
+            _ => false,
+        };
+        test(byte) || (self.ignore_case && byte.is_ascii_alphabetic() && test(byte ^ 0x20))
+    }
+
+    /// Adds the thread at `pc` and everything reachable from it without
+    /// consuming input.
+    fn add(&self, threads: &mut Vec<(usize, usize)>, seen: &mut [bool], pc: usize, start: usize, input: &[u8], at: usize) {
+        if seen[pc] {
+            return;
+        }
+        seen[pc] = true;
+        match self.program[pc] {
+            Instruction::Split(a, b) => {
+                self.add(threads, seen, a, start, input, at);
+                self.add(threads, seen, b, start, input, at);
+            }
+            Instruction::Jump(target) => self.add(threads, seen, target, start, input, at),
+            Instruction::LineStart if at == 0 || input[at - 1] == b'\n' => {
+                self.add(threads, seen, pc + 1, start, input, at);
+            }
+            Instruction::LineEnd if at == input.len() || input[at] == b'\n' || input[at] == b'\r' => {
+                self.add(threads, seen, pc + 1, start, input, at);
+            }
+            Instruction::LineStart | Instruction::LineEnd => {}
+            _ => threads.push((pc, start)),
+        }
+    }
+
+    /// The byte range of the leftmost match in `input`, if any.
+    pub fn find(&self, input: &[u8]) -> Option<(usize, usize)> {
+        let mut current: Vec<(usize, usize)> = Vec::new();
+        let mut next = Vec::new();
+        let mut seen = vec![false; self.program.len()];
+        let mut found: Option<(usize, usize)> = None;
+        for at in 0..=input.len() {
+            // Start a new attempt here unless an earlier one already matched.
+            if found.is_none() {
+                seen.iter_mut().for_each(|seen| *seen = false);
+                for (pc, _) in &current {
+                    seen[*pc] = true;
+                }
+                self.add(&mut current, &mut seen, 0, at, input, at);
+            }
+            if current.is_empty() {
+                if found.is_some() {
+                    break;
+                }
+                continue;
+            }
+            seen.iter_mut().for_each(|seen| *seen = false);
+            next.clear();
+            for &(pc, start) in &current {
+                match self.program[pc] {
+                    Instruction::Match => {
+                        // Threads are in priority order, so lower ones are cut off.
+                        if found.is_none_or(|(earliest, _)| start <= earliest) {
+                            found = Some((start, at));
+                        }
+                        break;
+                    }
+                    instruction if at < input.len() && self.accepts(instruction, input[at]) => {
+                        self.add(&mut next, &mut seen, pc + 1, start, input, at + 1);
+                    }
+                    _ => {}
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        found
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Piece>>, String> {
+        let mut alternatives = vec![Vec::new()];
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'|' => {
+                    self.position += 1;
+                    alternatives.push(Vec::new());
+                }
+                b')' => break,
+                _ => {
+                    let node = self.node()?;
+                    let repeat = match self.peek() {
+                        Some(b'?') => Repeat::Optional,
+                        Some(b'*') => Repeat::Star,
+                        Some(b'+') => Repeat::Plus,
+                        _ => Repeat::One,
+                    };
+                    if repeat != Repeat::One {
+                        self.position += 1;
+                    }
+                    alternatives.last_mut().expect("never empty").push(Piece { node, repeat });
+                }
+            }
+        }
+        Ok(alternatives)
+    }
+
+    fn node(&mut self) -> Result<Node, String> {
+        let byte = self.input[self.position];
+        self.position += 1;
+        Ok(match byte {
+            b'.' => Node::Any,
+            b'^' => Node::LineStart,
+            b'$' => Node::LineEnd,
+            b'(' => {
+                let alternatives = self.alternatives()?;
+                if self.peek() != Some(b')') {
+                    return Err("Missing )".to_string());
+                }
+                self.position += 1;
+                Node::Group(alternatives)
+            }
+            b'[' => Node::Class(self.class()?),
+            b'\\' => self.escape()?,
+            b'*' | b'+' | b'?' => return Err(format!("Nothing to repeat before {}", byte as char)),
+            byte => Node::Byte(byte),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let byte = self.peek().ok_or("Pattern ends with \\")?;
+        self.position += 1;
+        let class = |ranges: &[(u8, u8)], negated| Node::Class(Class { ranges: ranges.to_vec(), negated });
+        Ok(match byte {
+            b'd' => class(&[(b'0', b'9')], false),
+            b'D' => class(&[(b'0', b'9')], true),
+            b'w' => class(&[(b'a', b'z'), (b'A', b'Z'), (b'0', b'9'), (b'_', b'_')], false),
+            b'W' => class(&[(b'a', b'z'), (b'A', b'Z'), (b'0', b'9'), (b'_', b'_')], true),
+            b's' => class(&[(b' ', b' '), (b'\t', b'\r')], false),
+            b'S' => class(&[(b' ', b' '), (b'\t', b'\r')], true),
+            b'n' => Node::Byte(b'\n'),
+            b't' => Node::Byte(b'\t'),
+            byte => Node::Byte(byte),
+        })
+    }
+
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = self.peek() == Some(b'^');
+        if negated {
+            self.position += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let byte = self.peek().ok_or("Missing ]")?;
+            self.position += 1;
+            if byte == b']' && !first {
+                break;
+            }
+            first = false;
+            let low = if byte == b'\\' {
+                let escaped = self.peek().ok_or("Missing ]")?;
+                self.position += 1;
+                escaped
+            } else {
+                byte
+            };
+            if self.peek() == Some(b'-') && self.input.get(self.position + 1).is_some_and(|next| *next != b']') {
+                let high = self.input[self.position + 1];
+                self.position += 2;
+                if high < low {
+                    return Err(format!("Bad range {}-{}", low as char, high as char));
+                }
+                ranges.push((low, high));
+            } else {
+                ranges.push((low, low));
+            }
+        }
+        Ok(Class { ranges, negated })
+    }
+}
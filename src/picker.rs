@@ -0,0 +1,210 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Any,
+    Documents,
+    Logs,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 3] = [Profile::Any, Profile::Documents, Profile::Logs];
+
+    fn filters(self) -> Vec<Filter> {
+        match self {
+            Profile::Any => vec![Filter::new("All files", &["*"])],
+            Profile::Documents => vec![
+                Filter::new("Documents", &["pdf", "doc", "docx", "odt", "rtf", "txt", "md"]),
+                Filter::new("Spreadsheets", &["xls", "xlsx", "ods", "csv"]),
+                Filter::new("All files", &["*"]),
+            ],
+            Profile::Logs => vec![
+                Filter::new("Logs", &["log", "out", "err", "gz"]),
+                Filter::new("All files", &["*"]),
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Profile::Any => "Any file",
+            Profile::Documents => "Documents",
+            Profile::Logs => "Logs",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+impl Filter {
+    pub fn new(name: &str, extensions: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        }
+    }
+
+    /// Parses a user supplied list such as `"key, .pem;crt"` into a filter.
+    pub fn custom(list: &str) -> Option<Self> {
+        let extensions: Vec<String> = list
+            .split([',', ';', ' '])
+            .map(|ext| ext.trim().trim_start_matches("*.").trim_start_matches('.'))
+            .filter(|ext| !ext.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        if extensions.is_empty() {
+            None
+        } else {
+            Some(Self {
+                name: "Custom".to_string(),
+                extensions,
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Single,
+    Multiple,
+    Directory,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub profile: Profile,
+    pub custom: String,
+    pub show_hidden: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            profile: Profile::Any,
+            custom: String::new(),
+            show_hidden: false,
+        }
+    }
+}
+
+impl Options {
+    fn dialog(&self) -> rfd::AsyncFileDialog {
+        let mut dialog = rfd::AsyncFileDialog::new();
+        // The custom filter goes first so it is the one selected by default.
+        let custom = Filter::custom(&self.custom);
+        for filter in custom.into_iter().chain(self.profile.filters()) {
+            dialog = dialog.add_filter(filter.name, &filter.extensions);
+        }
+        dialog
+    }
+}
+
+/// What the dialog came back with.
+#[derive(Debug, Clone, Default)]
+pub struct Picked {
+    pub files: Vec<PathBuf>,
+    /// Entries of a chosen folder that could not be read, with why.
+    pub unreadable: Vec<(PathBuf, String)>,
+}
+
+pub async fn pick(options: Options, selection: Selection) -> Result<Picked, String> {
+    let dialog = options.dialog();
+    let mut unreadable = Vec::new();
+    // Files chosen one by one are taken as they are, hidden or not: the
+    // switch only decides what a folder walk picks up.
+    let files: Vec<PathBuf> = match selection {
+        Selection::Single => dialog
+            .set_title("Open file...")
+            .pick_file()
+            .await
+            .iter()
+            .map(|file| file.path().to_path_buf())
+            .collect(),
+        Selection::Multiple => dialog
+            .set_title("Open files...")
+            .pick_files()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|file| file.path().to_path_buf())
+            .collect(),
+        Selection::Directory => match dialog.set_title("Open folder...").pick_folder().await {
+            Some(folder) => {
+                let mut files = Vec::new();
+                unreadable = walk(folder.path(), options.show_hidden, &mut files)
+                    .into_iter()
+                    .map(|(path, e)| (path, e.to_string()))
+                    .collect();
+                files
+            }
+            None => Vec::new(),
+        },
+    };
+
+    if files.is_empty() && unreadable.is_empty() {
+        return Err("No file was selected.".to_string());
+    }
+    Ok(Picked { files, unreadable })
+}
+
+/// Adds the files under `dir` to `files`, leaving out hidden ones unless
+/// `show_hidden` is set. An entry that cannot be read is skipped and
+/// returned with its error; the rest of the folder is still walked.
+pub fn walk(dir: &Path, show_hidden: bool, files: &mut Vec<PathBuf>) -> Vec<(PathBuf, std::io::Error)> {
+    let mut unreadable = Vec::new();
+    // Deep trees (node_modules and friends) overflow MAX_PATH on Windows.
+    let entries = match fs::read_dir(crate::engine::extended_path(dir)) {
+        Ok(entries) => entries,
+        Err(e) => return vec![(dir.to_path_buf(), e)],
+    };
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                unreadable.push((dir.to_path_buf(), e));
+                continue;
+            }
+        };
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                unreadable.push((path, e));
+                continue;
+            }
+        };
+        if !show_hidden && is_hidden(&path, &metadata) {
+            continue;
+        }
+        if metadata.is_dir() {
+            unreadable.extend(walk(&path, show_hidden, files));
+        } else if metadata.is_file() {
+            files.push(path);
+        }
+    }
+    unreadable
+}
+
+fn is_hidden(path: &Path, metadata: &fs::Metadata) -> bool {
+    path.file_name().is_some_and(|name| name.as_encoded_bytes().starts_with(b".")) || hidden_attribute(metadata)
+}
+
+#[cfg(windows)]
+fn hidden_attribute(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn hidden_attribute(_metadata: &fs::Metadata) -> bool {
+    false
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+use crate::engine::Verification;
+use crate::history::Record;
+use crate::method::{Method, Registry};
+use crate::settings::Settings;
+use crate::vault::Key;
+use crate::{audit, certificate};
+
+/// Guardrails an administrator puts in a machine-wide file that users cannot
+/// edit. Settings named in its `[settings]` table are forced and locked; the
+/// rest limits what the interface and the command line will do.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// Forced settings as `key = value` pairs, in the form `--set` takes.
+    pub settings: Vec<(String, String)>,
+    /// Methods with fewer passes than this one are refused.
+    pub minimum_method: Option<Method>,
+    pub minimum_verification: Verification,
+    /// Every job stores a certificate, whatever preset is chosen.
+    pub certificate: bool,
+    /// Every job is appended to the audit log.
+    pub audit: bool,
+    /// Paths that may not be erased, nor anything below them.
+    pub forbidden: Vec<PathBuf>,
+    pub device_wipe: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            settings: Vec::new(),
+            minimum_method: None,
+            minimum_verification: Verification::None,
+            certificate: false,
+            audit: false,
+            forbidden: Vec::new(),
+            device_wipe: true,
+        }
+    }
+}
+
+impl Policy {
+    /// Only writable by administrators, and deliberately not overridable
+    /// from the environment or the command line.
+    pub fn path() -> PathBuf {
+        if cfg!(windows) {
+            let data = std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+            PathBuf::from(data).join("file-eraser").join("policy.toml")
+        } else {
+            PathBuf::from("/etc/file-eraser/policy.toml")
+        }
+    }
+
+    /// A missing file means no policy. A file that cannot be understood is an
+    /// error: starting without the guardrails would defeat their purpose.
+    pub fn load(methods: &Registry) -> Result<Self, String> {
+        let path = Self::path();
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text, methods).map_err(|e| format!("Invalid policy {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Cannot read policy {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(text: &str, methods: &Registry) -> Result<Self, String> {
+        let doc = text.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+        let mut policy = Self::default();
+        let string = |key: &str| doc.get(key).and_then(|item| item.as_str());
+        let boolean = |key: &str| doc.get(key).and_then(|item| item.as_bool());
+
+        if let Some(key) = string("minimum_method") {
+            policy.minimum_method = Some(methods.find(key).ok_or_else(|| format!("unknown method {}", key))?);
+        }
+        if let Some(key) = string("minimum_verification") {
+            policy.minimum_verification =
+                Verification::from_key(key).ok_or_else(|| format!("unknown verification {}", key))?;
+        }
+        policy.certificate = boolean("certificate").unwrap_or(false);
+        policy.audit = boolean("audit").unwrap_or(false);
+        policy.device_wipe = boolean("device_wipe").unwrap_or(true);
+        if let Some(forbidden) = doc.get("forbidden").and_then(|item| item.as_array()) {
+            policy.forbidden = forbidden.iter().filter_map(|value| value.as_str()).map(PathBuf::from).collect();
+        }
+        if let Some(table) = doc.get("settings").and_then(|item| item.as_table()) {
+            for (key, item) in table {
+                let value = match (item.as_str(), item.as_bool()) {
+                    (Some(text), _) => text.to_string(),
+                    (_, Some(flag)) => flag.to_string(),
+                    _ => return Err(format!("settings.{} must be a string or a boolean", key)),
+                };
+                // Catch typos here rather than on every start.
+                Settings::default().set(key, &value, methods)?;
+                policy.settings.push((key.to_string(), value));
+            }
+        }
+        Ok(policy)
+    }
+
+    pub fn locks(&self, key: &str) -> bool {
+        self.settings.iter().any(|(locked, _)| locked == key)
+    }
+
+    pub fn allows_method(&self, method: &Method) -> bool {
+        self.minimum_method
+            .as_ref()
+            .is_none_or(|minimum| method.passes().len() >= minimum.passes().len())
+    }
+
+    /// Refuses methods below the minimum with a message for the user.
+    pub fn check_method(&self, method: &Method) -> Result<(), String> {
+        match &self.minimum_method {
+            Some(minimum) if !self.allows_method(method) => Err(format!(
+                "{} is not allowed here, your organization requires at least {}",
+                method, minimum,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `path` is one of the forbidden paths or lies below one.
+    pub fn forbids(&self, path: &Path) -> bool {
+        let resolved = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.forbidden.iter().any(|forbidden| resolved.starts_with(forbidden) || path.starts_with(forbidden))
+    }
+
+    pub fn check_path(&self, path: &Path) -> io::Result<()> {
+        if self.forbids(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is protected by your organization's policy", path.display()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the certificate and audit entry this policy requires for a
+    /// finished job, sealing the certificate with `key` when the history is
+    /// encrypted. Failures are logged, the job itself is already done.
+    pub fn file_reports(&self, record: &Record, key: Option<&Key>) {
+        if self.certificate
+            && let Err(e) = certificate::store(record, key)
+        {
+            eprintln!("Error writing certificate: {}", e);
+        }
+        if self.audit
+            && let Err(e) = audit::append(record)
+        {
+            eprintln!("Error writing audit log: {}", e);
+        }
+    }
+}
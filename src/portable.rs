@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// An empty file of this name beside the executable makes every run from
+/// that folder portable, without having to pass `--portable`.
+pub const MARKER: &str = "portable";
+/// Beside the executable, holds `config` and `data` in portable mode.
+const FOLDER: &str = "file-eraser-data";
+
+static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Decides, before any settings, methods or history are read, whether this
+/// run keeps them beside the executable: asked for with `--portable` (taken
+/// out of `args`) or `FILE_ERASER_PORTABLE`, marked with a `portable` file,
+/// or built with the `portable` feature.
+pub fn init(args: &mut Vec<String>) {
+    let flag = args.iter().position(|arg| arg == "--portable").map(|index| args.remove(index)).is_some();
+    let environment = std::env::var("FILE_ERASER_PORTABLE").is_ok_and(|value| !matches!(value.trim(), "" | "0" | "false" | "no"));
+    let requested = flag || environment || cfg!(feature = "portable");
+    let root = std::env::current_exe().ok().and_then(|exe| root(&exe, requested));
+    let _ = ROOT.set(root);
+}
+
+/// Where a portable run started from `exe` keeps its files, or `None` when
+/// it was not asked to be portable.
+pub fn root(exe: &Path, requested: bool) -> Option<PathBuf> {
+    let dir = exe.parent()?;
+    (requested || dir.join(MARKER).is_file()).then(|| dir.join(FOLDER))
+}
+
+/// The folder a portable run keeps everything in.
+pub fn active() -> Option<&'static Path> {
+    ROOT.get().and_then(|root| root.as_deref())
+}
+
+/// `<config dir>/file-eraser`, or `config` beside the executable.
+pub fn config_dir() -> Option<PathBuf> {
+    match active() {
+        Some(root) => Some(root.join("config")),
+        None => dirs::config_dir().map(|dir| dir.join("file-eraser")),
+    }
+}
+
+/// `<data dir>/file-eraser`, or `data` beside the executable.
+pub fn data_dir() -> Option<PathBuf> {
+    match active() {
+        Some(root) => Some(root.join("data")),
+        None => dirs::data_dir().map(|dir| dir.join("file-eraser")),
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use iced::widget;
+use crate::{history, scan, units, Message};
+
+/// Only the first few files of a confirmation get a preview, the rest are
+/// listed by name.
+pub const MAX_PREVIEWS: usize = 5;
+const HEAD_BYTES: usize = 4096;
+const TEXT_LINES: usize = 6;
+/// Larger PNGs are not decoded just for a thumbnail.
+const MAX_DECODE: u64 = 20 * 1024 * 1024;
+const THUMBNAIL_CELLS: u32 = 24;
+const CELL_SIZE: f32 = 4.0;
+
+/// What a file looks like inside, so the user can tell `report-final.docx`
+/// from `report-final (2).docx` before it is gone for good.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Text(Vec<String>),
+    Image {
+        kind: &'static str,
+        width: u32,
+        height: u32,
+        /// Only PNGs can be decoded without an image library.
+        thumbnail: Option<Thumbnail>,
+    },
+    Binary {
+        kind: Option<&'static str>,
+        head: String,
+    },
+    Unreadable(String),
+}
+
+/// A downscaled image as a grid of RGB cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+    columns: u32,
+    rows: u32,
+    cells: Vec<[u8; 3]>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preview {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub modified: Option<u64>,
+    pub content: Content,
+}
+
+impl Preview {
+    pub fn load(path: &Path) -> Self {
+        let meta = std::fs::metadata(path).ok();
+        Self {
+            path: path.to_path_buf(),
+            bytes: meta.as_ref().map_or(0, |meta| meta.len()),
+            modified: meta
+                .and_then(|meta| meta.modified().ok())
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_secs()),
+            content: content(path).unwrap_or_else(|e| Content::Unreadable(e.to_string())),
+        }
+    }
+}
+
+fn content(path: &Path) -> io::Result<Content> {
+    let mut head = Vec::with_capacity(HEAD_BYTES);
+    File::open(path)?.take(HEAD_BYTES as u64).read_to_end(&mut head)?;
+
+    if head.starts_with(b"\x89PNG\r\n\x1A\n") {
+        let (width, height, thumbnail) = png_thumbnail(path)?;
+        return Ok(Content::Image { kind: "PNG image", width, height, thumbnail });
+    }
+    if head.starts_with(b"GIF8") && head.len() >= 10 {
+        let width = u16::from_le_bytes([head[6], head[7]]) as u32;
+        let height = u16::from_le_bytes([head[8], head[9]]) as u32;
+        return Ok(Content::Image { kind: "GIF image", width, height, thumbnail: None });
+    }
+    if let Some((width, height)) = jpeg_size(path)? {
+        return Ok(Content::Image { kind: "JPEG image", width, height, thumbnail: None });
+    }
+    if let Some(lines) = text(&head) {
+        return Ok(Content::Text(lines));
+    }
+    Ok(Content::Binary {
+        kind: scan::SIGNATURES.iter().find(|(_, signature)| head.starts_with(signature)).map(|(name, _)| *name),
+        head: head.iter().take(24).map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(" "),
+    })
+}
+
+/// The first lines when `head` looks like text: no NUL bytes and valid
+/// UTF-8, apart from a character cut in half at the end.
+fn text(head: &[u8]) -> Option<Vec<String>> {
+    if head.contains(&0) {
+        return None;
+    }
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    Some(
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(TEXT_LINES)
+            .map(|line| line.chars().take(100).collect())
+            .collect(),
+    )
+}
+
+fn png_thumbnail(path: &Path) -> io::Result<(u32, u32, Option<Thumbnail>)> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let info = reader.info();
+    let (width, height) = (info.width, info.height);
+    if std::fs::metadata(path)?.len() > MAX_DECODE || width == 0 || height == 0 {
+        return Ok((width, height, None));
+    }
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(io::Error::other)?;
+    let channels = frame.color_type.samples();
+    let pixels = &buffer[..frame.buffer_size()];
+
+    let scale = (width.max(height) as f32 / THUMBNAIL_CELLS as f32).max(1.0);
+    let columns = ((width as f32 / scale) as u32).max(1);
+    let rows = ((height as f32 / scale) as u32).max(1);
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            // Nearest pixel to the middle of the cell; good enough to recognize a picture.
+            let x = ((column as f32 + 0.5) * scale) as usize;
+            let y = ((row as f32 + 0.5) * scale) as usize;
+            let start = (y.min(height as usize - 1) * frame.line_size) + x.min(width as usize - 1) * channels;
+            let pixel = &pixels[start..start + channels];
+            let (rgb, alpha) = match channels {
+                1 => ([pixel[0]; 3], 255),
+                2 => ([pixel[0]; 3], pixel[1]),
+                3 => ([pixel[0], pixel[1], pixel[2]], 255),
+                _ => ([pixel[0], pixel[1], pixel[2]], pixel[3]),
+            };
+            // Transparent parts show as white.
+            cells.push(rgb.map(|value| ((value as u32 * alpha as u32 + 255 * (255 - alpha as u32)) / 255) as u8));
+        }
+    }
+    Ok((width, height, Some(Thumbnail { columns, rows, cells })))
+}
+
+/// Walks the JPEG markers up to the start-of-frame that holds the size.
+fn jpeg_size(path: &Path) -> io::Result<Option<(u32, u32)>> {
+    let mut data = Vec::new();
+    File::open(path)?.take(256 * 1024).read_to_end(&mut data)?;
+    if !data.starts_with(b"\xFF\xD8") {
+        return Ok(None);
+    }
+    let mut offset = 2;
+    while offset + 9 < data.len() {
+        if data[offset] != 0xFF {
+            return Ok(None);
+        }
+        let marker = data[offset + 1];
+        let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        // SOF0 to SOF15, except DHT (C4), JPG (C8) and DAC (CC).
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            let height = u16::from_be_bytes([data[offset + 5], data[offset + 6]]) as u32;
+            let width = u16::from_be_bytes([data[offset + 7], data[offset + 8]]) as u32;
+            return Ok(Some((width, height)));
+        }
+        offset += 2 + length;
+    }
+    Ok(None)
+}
+
+fn thumbnail(thumbnail: &Thumbnail) -> iced::Element<'static, Message> {
+    widget::column((0..thumbnail.rows).map(|row| {
+        widget::row((0..thumbnail.columns).map(|column| {
+            let [r, g, b] = thumbnail.cells[(row * thumbnail.columns + column) as usize];
+            widget::container(widget::Space::new(CELL_SIZE, CELL_SIZE))
+                .style(move |_| widget::container::Style {
+                    background: Some(iced::Color::from_rgb8(r, g, b).into()),
+                    ..widget::container::Style::default()
+                })
+                .into()
+        }))
+            .into()
+    }))
+        .into()
+}
+
+pub fn view(preview: &Preview) -> iced::Element<'_, Message> {
+    let modified = preview.modified.map_or("unknown".to_string(), history::format_date);
+    let content: iced::Element<'_, Message> = match &preview.content {
+        Content::Text(lines) if lines.is_empty() => widget::text("Empty text file").size(14).into(),
+        Content::Text(lines) => widget::container(
+            widget::column(lines.iter().map(|line| widget::text(line).size(12).font(iced::Font::MONOSPACE).into())),
+        )
+            .padding(5)
+            .style(widget::container::rounded_box)
+            .into(),
+        Content::Image { kind, width, height, thumbnail: Some(cells) } => widget::row![
+            thumbnail(cells),
+            widget::text!("{}, {} x {} pixels", kind, width, height).size(14),
+        ]
+            .spacing(10)
+            .into(),
+        Content::Image { kind, width, height, thumbnail: None } => {
+            widget::text!("{}, {} x {} pixels", kind, width, height).size(14).into()
+        }
+        Content::Binary { kind, head } => widget::text!(
+            "{}: {}",
+            kind.unwrap_or("Binary data"),
+            head,
+        )
+            .size(12)
+            .font(iced::Font::MONOSPACE)
+            .into(),
+        Content::Unreadable(error) => widget::text!("Cannot be previewed: {}", error).size(14).into(),
+    };
+    widget::column![
+        widget::text!(
+            "{} - {}, modified {} UTC",
+            preview.path.display(),
+            units::size(preview.bytes),
+            modified,
+        ),
+        content,
+    ]
+        .spacing(5)
+        .into()
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use flume::RecvTimeoutError;
+use crate::engine::Progress;
+
+/// How often a blocking reader hands out the latest percentage when no
+/// other event comes first.
+pub const TICK: Duration = Duration::from_millis(100);
+
+/// Percentages not yet seen by the reader. Only the newest one matters, so
+/// a new one replaces the old instead of queueing behind it.
+#[derive(Default)]
+struct Latest {
+    /// How far the scan, the pass being written or its read-back got.
+    phase: Option<Progress>,
+    file: Option<f32>,
+    drives: BTreeMap<usize, f32>,
+}
+
+impl Latest {
+    fn pop(&mut self) -> Option<Progress> {
+        if let Some(phase) = self.phase.take() {
+            return Some(phase);
+        }
+        if let Some(percent) = self.file.take() {
+            return Some(Progress::Updated(percent));
+        }
+        self.drives.pop_first().map(|(drive, percent)| Progress::DriveUpdated(drive, percent))
+    }
+
+    fn take(&mut self) -> Vec<Progress> {
+        std::iter::from_fn(|| self.pop()).collect()
+    }
+}
+
+/// The worker's end. Percentages are coalesced, so the engine can report
+/// every chunk without flooding the reader or waiting on it; every other
+/// event is queued in order, after the percentage that came before it.
+/// The reader is gone, so nobody wants to hear about the job any more.
+#[derive(Debug)]
+pub struct Disconnected;
+
+#[derive(Clone)]
+pub struct Sender {
+    events: flume::Sender<Progress>,
+    latest: Option<Arc<Mutex<Latest>>>,
+}
+
+impl Sender {
+    pub fn send(&self, progress: Progress) -> Result<(), Disconnected> {
+        let Some(latest) = &self.latest else {
+            return self.events.send(progress).map_err(|_| Disconnected);
+        };
+        if self.events.is_disconnected() {
+            return Err(Disconnected);
+        }
+        let mut latest = latest.lock().expect("progress lock");
+        match progress {
+            Progress::Updated(percent) => latest.file = Some(percent),
+            phase @ (Progress::ScanUpdated(_) | Progress::WipeUpdated(_) | Progress::VerifyUpdated(_)) => {
+                latest.phase = Some(phase);
+            }
+            Progress::DriveUpdated(drive, percent) => {
+                latest.drives.insert(drive, percent);
+            }
+            event => {
+                for pending in latest.take() {
+                    self.events.send(pending).map_err(|_| Disconnected)?;
+                }
+                self.events.send(event).map_err(|_| Disconnected)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Passes every event through as is, percentages included, for readers
+/// that want to see each one or hold the worker back with a bounded channel.
+impl From<flume::Sender<Progress>> for Sender {
+    fn from(events: flume::Sender<Progress>) -> Self {
+        Self { events, latest: None }
+    }
+}
+
+#[derive(Clone)]
+pub struct Receiver {
+    events: flume::Receiver<Progress>,
+    latest: Arc<Mutex<Latest>>,
+}
+
+impl Receiver {
+    /// The events other than percentages, for the window to subscribe to
+    /// while it polls `latest` on a timer.
+    pub fn events(&self) -> flume::r#async::RecvStream<'static, Progress> {
+        self.events.clone().into_stream()
+    }
+
+    /// The newest percentage of the file and of each drive since the last
+    /// call.
+    pub fn latest(&self) -> Vec<Progress> {
+        self.latest.lock().expect("progress lock").take()
+    }
+
+    /// Everything that arrived since the last call, without waiting.
+    pub fn try_iter(&self) -> impl Iterator<Item = Progress> {
+        // Taken under the lock, so no event queued after a percentage can
+        // be read before it.
+        let mut latest = self.latest.lock().expect("progress lock");
+        let mut events: Vec<Progress> = self.events.try_iter().collect();
+        events.extend(latest.take());
+        events.into_iter()
+    }
+
+    /// Waits for the next event, or at most `TICK` for a new percentage.
+    /// `None` once the worker is gone and everything was read.
+    pub fn recv(&self) -> Option<Progress> {
+        loop {
+            match self.events.recv_timeout(TICK) {
+                Ok(event) => return Some(event),
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(percent) = self.latest.lock().expect("progress lock").pop() {
+                        return Some(percent);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return self.latest.lock().expect("progress lock").pop(),
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Progress> + '_ {
+        std::iter::from_fn(|| self.recv())
+    }
+}
+
+/// A progress channel that keeps only the latest percentage, however fast
+/// or slow the worker goes.
+pub fn channel() -> (Sender, Receiver) {
+    let (tx, rx) = flume::unbounded();
+    let latest = Arc::new(Mutex::new(Latest::default()));
+    (Sender { events: tx, latest: Some(latest.clone()) }, Receiver { events: rx, latest })
+}
+
+/// What the running job is doing, followed from its events, for the
+/// status lines of the window, the terminal interface and the agent.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Phase {
+    #[default]
+    Waiting,
+    Scanning { done: usize, total: usize },
+    Writing { pass: usize, passes: usize, percent: f32 },
+    Verifying { pass: usize, passes: usize, percent: f32 },
+    CleanedUp,
+}
+
+impl Phase {
+    pub fn follow(&mut self, event: &Progress) {
+        *self = match (*self, event) {
+            (_, Progress::ScanStarted(total)) => Phase::Scanning { done: 0, total: *total },
+            (Phase::Scanning { total, .. }, Progress::ScanUpdated(done)) => Phase::Scanning { done: *done, total },
+            (_, Progress::PassStarted(pass, passes)) => Phase::Writing { pass: *pass, passes: *passes, percent: 0.0 },
+            (Phase::Writing { pass, passes, .. }, Progress::WipeUpdated(percent)) => Phase::Writing { pass, passes, percent: *percent },
+            (Phase::Writing { pass, passes, .. } | Phase::Verifying { pass, passes, .. }, Progress::VerifyUpdated(percent)) => {
+                Phase::Verifying { pass, passes, percent: *percent }
+            }
+            (_, Progress::CleanupDone) => Phase::CleanedUp,
+            (phase, _) => phase,
+        };
+    }
+
+    /// The phase without its percentage, which changes far less often.
+    pub fn title(&self) -> String {
+        match *self {
+            Phase::Waiting => "Waiting".to_string(),
+            Phase::Scanning { total, .. } => format!("Looking at {} file(s)", total),
+            Phase::Writing { pass, passes, .. } => format!("Pass {} of {}: writing", pass, passes),
+            Phase::Verifying { pass, passes, .. } => format!("Pass {} of {}: reading back", pass, passes),
+            Phase::CleanedUp => "Cleaned up".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Phase::Scanning { done, total } => write!(f, "Looked at {} of {} file(s)", done, total),
+            Phase::Writing { percent, .. } | Phase::Verifying { percent, .. } => write!(f, "{} {:.0}%", self.title(), percent),
+            _ => write!(f, "{}", self.title()),
+        }
+    }
+}
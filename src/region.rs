@@ -0,0 +1,235 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use iced::widget;
+use crate::strategy::Facts;
+use crate::{units, App, Message};
+
+/// Bytes on one line of the hex view.
+pub const LINE: usize = 16;
+/// Lines of the hex view shown at a time.
+const LINES: usize = 16;
+pub const PAGE: u64 = (LINE * LINES) as u64;
+
+/// A byte range of a file: `length` bytes from `offset` on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl Region {
+    /// Reads `OFFSET:LENGTH`, each decimal or hexadecimal with `0x`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (offset, length) = text
+            .split_once(':')
+            .ok_or_else(|| format!("{} is not OFFSET:LENGTH", text))?;
+        Self::from_fields(offset, length)
+    }
+
+    /// The offset and length as typed into two separate fields.
+    pub fn from_fields(offset: &str, length: &str) -> Result<Self, String> {
+        let offset = number(offset).ok_or_else(|| format!("{} is not an offset", offset.trim()))?;
+        let length = number(length).ok_or_else(|| format!("{} is not a length", length.trim()))?;
+        if length == 0 {
+            return Err("The region is empty".to_string());
+        }
+        offset
+            .checked_add(length)
+            .map(|_| Region { offset, length })
+            .ok_or_else(|| "The region ends past the largest possible file".to_string())
+    }
+
+    /// The first byte after the region.
+    pub fn end(self) -> u64 {
+        self.offset + self.length
+    }
+
+    pub fn contains(self, at: u64) -> bool {
+        (self.offset..self.end()).contains(&at)
+    }
+}
+
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bytes {} to {}", self.offset, self.end() - 1)
+    }
+}
+
+fn number(text: &str) -> Option<u64> {
+    let text = text.trim().replace('_', "");
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// The printable characters of `bytes`, dots for the rest, as the last
+/// column of a hex dump.
+pub fn ascii(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }).collect()
+}
+
+/// The file whose bytes are being picked in the Region tab.
+#[derive(Debug, Clone, Default)]
+pub struct Panel {
+    pub file: Option<PathBuf>,
+    pub size: u64,
+    /// Offset and length as typed or picked in the hex view.
+    pub offset: String,
+    pub length: String,
+    /// First byte of the page shown in the hex view.
+    pub view: u64,
+    pub page: Vec<u8>,
+    /// The byte clicked first, until a second click picks the other end.
+    pub anchor: Option<u64>,
+    pub confirmed: bool,
+    /// What the file is stored on, for the warning about SSDs.
+    pub facts: Option<Facts>,
+}
+
+impl Panel {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let size = std::fs::metadata(&path)?.len();
+        let mut panel = Panel { facts: Some(Facts::inspect(&path)), file: Some(path), size, ..Panel::default() };
+        panel.show(0)?;
+        Ok(panel)
+    }
+
+    /// Shows the page holding `at`, clamped to the file.
+    pub fn show(&mut self, at: u64) -> io::Result<()> {
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        self.view = at.min(self.size.saturating_sub(1)) / PAGE * PAGE;
+        self.page = read_page(path, self.view)?;
+        Ok(())
+    }
+
+    /// The first click marks a single byte, the second stretches the region
+    /// from the first to it, in whichever order they were clicked.
+    pub fn click(&mut self, at: u64) {
+        let (start, end) = match self.anchor.take() {
+            Some(anchor) => (anchor.min(at), anchor.max(at)),
+            None => {
+                self.anchor = Some(at);
+                (at, at)
+            }
+        };
+        self.offset = start.to_string();
+        self.length = (end - start + 1).to_string();
+        self.confirmed = false;
+    }
+
+    /// The region typed in, checked against the file.
+    pub fn region(&self) -> Result<Region, String> {
+        let region = Region::from_fields(&self.offset, &self.length)?;
+        match region.end() > self.size {
+            true => Err(format!("The file ends at byte {}", self.size.saturating_sub(1))),
+            false => Ok(region),
+        }
+    }
+}
+
+fn read_page(path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut page = Vec::with_capacity(PAGE as usize);
+    file.take(PAGE).read_to_end(&mut page)?;
+    Ok(page)
+}
+
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    let panel = &app.region;
+    let intro = widget::text(
+        "Overwrites only part of a file, for a secret stored inside a large container, database or disk image that \
+         has to stay in use. The rest of the file and its name are left as they are.",
+    );
+    let Some(file) = &panel.file else {
+        return widget::column![intro, widget::button("Select file").on_press_maybe((!app.erasing).then_some(Message::SelectRegionFile))]
+            .spacing(10)
+            .into();
+    };
+    let region = panel.region();
+
+    let fields = widget::row![
+        widget::button("Select file").on_press_maybe((!app.erasing).then_some(Message::SelectRegionFile)),
+        widget::text!("{} ({})", file.display(), units::size(panel.size)),
+        widget::horizontal_space(),
+        widget::text("Offset"),
+        widget::text_input("0 or 0x1000", &panel.offset)
+            .on_input_maybe((!app.erasing).then_some(Message::RegionOffsetChanged))
+            .width(150),
+        widget::text("Length"),
+        widget::text_input("bytes", &panel.length)
+            .on_input_maybe((!app.erasing).then_some(Message::RegionLengthChanged))
+            .width(150),
+        widget::button("Show").on_press_maybe(region.as_ref().ok().map(|region| Message::RegionShow(region.offset))),
+    ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+    let selected = region.as_ref().ok().copied();
+    let lines = panel.page.chunks(LINE).enumerate().map(|(line, bytes)| {
+        let start = panel.view + (line * LINE) as u64;
+        widget::row![widget::text!("{:010x}  ", start).font(iced::Font::MONOSPACE).size(14)]
+            .extend(bytes.iter().enumerate().map(|(index, byte)| {
+                let at = start + index as u64;
+                let marked = selected.is_some_and(|region| region.contains(at)) || panel.anchor == Some(at);
+                widget::button(widget::text!("{:02x}", byte).font(iced::Font::MONOSPACE).size(14))
+                    .style(if marked { widget::button::primary } else { widget::button::text })
+                    .padding([0, 2])
+                    .on_press_maybe((!app.erasing).then_some(Message::RegionByteClicked(at)))
+                    .into()
+            }))
+            .push(widget::text!("  {}", ascii(bytes)).font(iced::Font::MONOSPACE).size(14))
+            .align_y(iced::Alignment::Center)
+            .into()
+    });
+    let paging = widget::row![
+        widget::button("Previous page").on_press_maybe((panel.view > 0).then(|| Message::RegionShow(panel.view - PAGE))),
+        widget::button("Next page")
+            .on_press_maybe((panel.view + PAGE < panel.size).then_some(Message::RegionShow(panel.view + PAGE))),
+        widget::text(match panel.anchor {
+            Some(anchor) => format!("Click the last byte of the region, it starts at {}", anchor),
+            None => "Click a byte to start the region there".to_string(),
+        })
+            .size(14),
+    ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+    // Only a drive that writes in place is sure to lose the old bytes.
+    let warning = panel.facts.as_ref().and_then(|facts| match (facts.copy_on_write, facts.rotational) {
+        (true, _) => Some("This file system writes changed blocks somewhere new, so the old bytes may stay on the drive."),
+        (false, Some(false)) => Some("This file is on an SSD, which may keep the old bytes in blocks it has set aside."),
+        _ => None,
+    });
+    let summary = match &region {
+        Ok(region) => format!("Overwrites {} ({}) of this file with {}.", region, units::size(region.length), app.job_method()),
+        Err(e) => e.clone(),
+    };
+    let button = if app.erasing {
+        widget::button("Cancel (Esc)").on_press(Message::Cancel)
+    } else {
+        widget::button("Overwrite region")
+            .on_press_maybe((region.is_ok() && panel.confirmed).then_some(Message::OverwriteRegion))
+    };
+
+    widget::column![
+        intro,
+        fields,
+        widget::container(widget::scrollable(widget::column(lines))).height(iced::Length::Fill),
+        paging,
+        widget::text(summary),
+    ]
+        .push_maybe(warning.map(|warning| widget::text(warning).color(iced::Color::from_rgb(0.8, 0.4, 0.0))))
+        .push(
+            widget::checkbox("I have a backup and know these bytes cannot be recovered", panel.confirmed)
+                .on_toggle_maybe((!app.erasing && region.is_ok()).then_some(Message::RegionConfirmToggled)),
+        )
+        .push(app.tag_row())
+        .push(app.progress_row(button))
+        .spacing(10)
+        .into()
+}
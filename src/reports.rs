@@ -0,0 +1,117 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::engine::{self, CancelToken, Timestamps, Verification};
+use crate::method::Pass;
+use crate::{portable, progress};
+
+/// Appended to the name of a report while it is written. Drafts a crash left
+/// behind are wiped together with old reports.
+const DRAFT: &str = ".draft";
+
+/// `<data dir>/file-eraser`, where the history, certificates and audit log
+/// are kept. They name every file that was erased, so the folder is made
+/// readable by the user only.
+pub fn data_dir() -> io::Result<PathBuf> {
+    let dir = portable::data_dir().ok_or_else(|| io::Error::other("No data directory"))?;
+    private(&dir)?;
+    Ok(dir)
+}
+
+pub fn certificates_dir() -> io::Result<PathBuf> {
+    let dir = data_dir()?.join("certificates");
+    private(&dir)?;
+    Ok(dir)
+}
+
+/// Creates `dir` if needed and takes away everyone else's access to it.
+pub fn private(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Options for a report file that only the user can read, from the moment
+/// it is created.
+pub fn private_file() -> fs::OpenOptions {
+    let mut options = File::options();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// Writes `contents` to `path` through a draft next to it that is synced
+/// and then renamed over it, so a crash leaves the old report or the new
+/// one and never half of it.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut name = path.file_name().ok_or_else(|| io::Error::other("No file name"))?.to_os_string();
+    name.push(DRAFT);
+    let draft = path.with_file_name(name);
+    let mut file = private_file().write(true).create(true).truncate(true).open(&draft)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&draft, path)?;
+    // The rename itself only survives a crash once the folder is synced.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// The moment `days` days ago, or `None` when that is before the clock can
+/// go back to, where nothing is old enough.
+pub fn cutoff(days: u64) -> Option<SystemTime> {
+    let age = days.checked_mul(24 * 60 * 60).map(Duration::from_secs)?;
+    SystemTime::now().checked_sub(age)
+}
+
+/// Certificates last written more than `days` days ago, and any drafts a
+/// crash left behind.
+pub fn older_than(days: u64) -> io::Result<Vec<PathBuf>> {
+    let cutoff = cutoff(days);
+    let certificates = certificates_dir()?;
+    let mut old = Vec::new();
+    for dir in [data_dir()?, certificates.clone()] {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let meta = entry.metadata()?;
+            if !meta.is_file() {
+                continue;
+            }
+            let draft = entry.file_name().to_string_lossy().ends_with(DRAFT);
+            let expired = match cutoff {
+                Some(cutoff) if dir == certificates => meta.modified()? < cutoff,
+                _ => false,
+            };
+            if draft || expired {
+                old.push(entry.path());
+            }
+        }
+    }
+    old.sort();
+    Ok(old)
+}
+
+/// Erases a report that encryption replaced, with a single random pass.
+pub fn shred(path: &Path) -> io::Result<()> {
+    let options = engine::Options {
+        passes: vec![Pass::Random],
+        verification: Verification::None,
+        timestamps: Timestamps::Keep,
+        churn_directories: false,
+        simulate: None,
+        checksums: false,
+    };
+    let (tx, _rx) = progress::channel();
+    engine::securely_overwrite(path, &options, &CancelToken::default(), &tx).map(|_| ())
+}
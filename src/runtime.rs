@@ -0,0 +1,51 @@
+use std::any::Any;
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::{Handle, Runtime};
+use tokio::task::JoinError;
+
+/// The tokio runtime the window already runs on, or one of our own for the
+/// command line, the terminal interface and agents, which have none.
+pub fn handle() -> Handle {
+    static OWN: OnceLock<Runtime> = OnceLock::new();
+    Handle::try_current().unwrap_or_else(|_| {
+        OWN.get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_time()
+                .build()
+                .expect("async runtime")
+        })
+            .handle()
+            .clone()
+    })
+}
+
+/// Runs `work`, file or device I/O that blocks, on the runtime's blocking
+/// pool. Resolves to what it returned, or to the panic message if it
+/// crashed.
+pub fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> impl Future<Output = Result<T, String>> {
+    let task = handle().spawn_blocking(work);
+    async move { task.await.map_err(crash) }
+}
+
+/// Waits for `future` from code that is not async itself, such as the
+/// window's update or a job running on the blocking pool.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    handle().block_on(future)
+}
+
+/// What a task that did not finish left behind.
+pub fn crash(error: JoinError) -> String {
+    match error.try_into_panic() {
+        Ok(panic) => panic_message(&*panic),
+        Err(_) => "cancelled".to_string(),
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "unknown panic".to_string(),
+    }
+}
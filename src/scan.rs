@@ -0,0 +1,208 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use iced::widget;
+use crate::{swap, units, App, Message};
+
+const BLOCK_SIZE: usize = 4096;
+/// Targets larger than this are sampled rather than read in full, so a
+/// check of a whole drive finishes in seconds.
+const FULL_SCAN_LIMIT: u64 = 256 * 1024 * 1024;
+const SAMPLES: u64 = 4096;
+const SAMPLE_SIZE: usize = 64 * 1024;
+/// Above this many bits per byte a block looks like random fill (or
+/// encrypted or compressed data, which a carver cannot use either).
+const RANDOM_ENTROPY: f64 = 7.0;
+
+/// File headers that carving tools look for at sector boundaries.
+pub const SIGNATURES: [(&str, &[u8]); 8] = [
+    ("JPEG image", b"\xFF\xD8\xFF"),
+    ("PNG image", b"\x89PNG\r\n\x1A\n"),
+    ("GIF image", b"GIF8"),
+    ("PDF document", b"%PDF-"),
+    ("ZIP / Office document", b"PK\x03\x04"),
+    ("Legacy Office document", b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1"),
+    ("SQLite database", b"SQLite format 3\0"),
+    ("RAR archive", b"Rar!\x1A\x07"),
+];
+
+/// What a read-only scan found. Zero, pattern and random blocks are what a
+/// wipe leaves behind; structured blocks and file headers are what a
+/// recovery tool would find.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub target: PathBuf,
+    pub bytes: u64,
+    /// Whether only part of the target was read.
+    pub sampled: bool,
+    pub zero: u64,
+    pub pattern: u64,
+    pub random: u64,
+    pub structured: u64,
+    /// Up to 20 signatures with their byte offsets.
+    pub headers: Vec<(u64, &'static str)>,
+    pub header_count: u64,
+}
+
+impl Report {
+    fn blocks(&self) -> u64 {
+        self.zero + self.pattern + self.random + self.structured
+    }
+
+    fn percent(&self, blocks: u64) -> f64 {
+        blocks as f64 * 100.0 / self.blocks().max(1) as f64
+    }
+
+    pub fn recoverable(&self) -> bool {
+        self.header_count > 0 || self.percent(self.structured) >= 1.0
+    }
+
+    pub fn verdict(&self) -> String {
+        if self.recoverable() {
+            format!(
+                "Content looks recoverable: {} file header(s) and {:.1}% structured data were found.",
+                self.header_count,
+                self.percent(self.structured),
+            )
+        } else {
+            "No recoverable content was found in the scanned blocks.".to_string()
+        }
+    }
+}
+
+fn entropy(block: &[u8]) -> f64 {
+    let mut counts = [0u32; 256];
+    for byte in block {
+        counts[*byte as usize] += 1;
+    }
+    let len = block.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn classify(report: &mut Report, block: &[u8], offset: u64) {
+    for (sector, data) in block.chunks(512).enumerate() {
+        for (name, signature) in SIGNATURES {
+            if data.starts_with(signature) {
+                report.header_count += 1;
+                if report.headers.len() < 20 {
+                    report.headers.push((offset + sector as u64 * 512, name));
+                }
+            }
+        }
+    }
+
+    let entropy = entropy(block);
+    if block.iter().all(|byte| *byte == 0) {
+        report.zero += 1;
+    } else if (1..=3).any(|period| block.iter().skip(period).zip(block).all(|(a, b)| a == b)) {
+        // Wipe patterns repeat every one to three bytes.
+        report.pattern += 1;
+    } else if entropy > RANDOM_ENTROPY {
+        report.random += 1;
+    } else {
+        report.structured += 1;
+    }
+}
+
+/// Reads `target` (a file, a disk image or a block device) without changing
+/// it and sorts its blocks into what a wipe leaves and what it does not.
+pub fn scan(target: &Path) -> io::Result<Report> {
+    let mut file = File::open(target)?;
+    let size = file.seek(SeekFrom::End(0))?;
+    let mut report = Report {
+        target: target.to_path_buf(),
+        bytes: size,
+        sampled: size > FULL_SCAN_LIMIT,
+        ..Report::default()
+    };
+
+    let (regions, region_size) = if report.sampled {
+        (SAMPLES, SAMPLE_SIZE as u64)
+    } else {
+        (1, size)
+    };
+    let stride = size / regions;
+    let mut buffer = vec![0u8; BLOCK_SIZE];
+    for region in 0..regions {
+        // Keep samples block aligned, as a carver would read them.
+        let start = region * stride / BLOCK_SIZE as u64 * BLOCK_SIZE as u64;
+        let end = (start + region_size).min(size);
+        file.seek(SeekFrom::Start(start))?;
+        let mut offset = start;
+        while offset < end {
+            let len = (end - offset).min(BLOCK_SIZE as u64) as usize;
+            file.read_exact(&mut buffer[..len])?;
+            classify(&mut report, &buffer[..len], offset);
+            offset += len as u64;
+        }
+    }
+    Ok(report)
+}
+
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    let result: iced::Element<'_, Message> = match &app.scan {
+        None if app.scanning => widget::text("Scanning...").into(),
+        None => widget::text("Nothing checked yet.").into(),
+        Some(Err(e)) => widget::text!("The check failed: {}", e).into(),
+        Some(Ok(report)) => {
+            let verdict = widget::text(report.verdict()).size(18);
+            let verdict = if report.recoverable() {
+                verdict.color(iced::Color::from_rgb(0.8, 0.1, 0.1))
+            } else {
+                verdict
+            };
+            widget::column![
+                verdict,
+                widget::text!(
+                    "{}: {} {}",
+                    report.target.display(),
+                    units::size(report.bytes),
+                    if report.sampled { "(sampled)" } else { "(read in full)" },
+                ),
+                widget::text!(
+                    "Zero blocks {:.1}%, wipe patterns {:.1}%, random {:.1}%, structured {:.1}%",
+                    report.percent(report.zero),
+                    report.percent(report.pattern),
+                    report.percent(report.random),
+                    report.percent(report.structured),
+                ),
+                widget::scrollable(widget::column(report.headers.iter().map(|(offset, name)| {
+                    widget::text!(" {} at offset {}", name, offset).size(14).into()
+                }))),
+            ]
+                .spacing(10)
+                .into()
+        }
+    };
+
+    widget::column![
+        widget::text(
+            "Reads a file, disk image or drive without changing it and looks for content a recovery tool \
+             could find. Run it on a drive after a free-space wipe to see the wipe worked."
+        ),
+        widget::row![
+            widget::button("Check a file").on_press_maybe((!app.scanning).then_some(Message::SelectScanFile)),
+            widget::text_input(r"or a device, for example /dev/sdb or \\.\PhysicalDrive1", &app.scan_target)
+                .on_input(Message::ScanTargetChanged)
+                .on_submit(Message::Scan(PathBuf::from(app.scan_target.trim()))),
+            widget::button("Check").on_press_maybe(
+                (!app.scanning && !app.scan_target.trim().is_empty())
+                    .then(|| Message::Scan(PathBuf::from(app.scan_target.trim()))),
+            ),
+        ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+        widget::container(result).height(iced::Length::Fill),
+        widget::horizontal_rule(1),
+        swap::view(app),
+    ]
+        .spacing(10)
+        .into()
+}
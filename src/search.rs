@@ -0,0 +1,396 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use iced::widget;
+use crate::engine::CancelToken;
+use crate::hash::{self, Sha256};
+use crate::pattern::Pattern;
+use crate::policy::Policy;
+use crate::{history, units, App, Message};
+
+/// Files larger than this are not searched for text; reading them whole
+/// would take too much memory. Hash lists have no limit, files are hashed
+/// in chunks.
+const MAX_CONTENT: u64 = 64 * 1024 * 1024;
+const CHUNK_SIZE: usize = 1024 * 1024;
+const CONTEXT_CHARS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Text,
+    Regex,
+    Hashes,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Text, Mode::Regex, Mode::Hashes];
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Text => write!(f, "Contains text"),
+            Mode::Regex => write!(f, "Matches regular expression"),
+            Mode::Hashes => write!(f, "SHA-256 is on a hash list"),
+        }
+    }
+}
+
+/// What makes a file a match.
+#[derive(Debug, Clone)]
+pub enum Criterion {
+    Content(Pattern),
+    Hashes(HashSet<[u8; 32]>),
+}
+
+/// A file that matched, with the line it matched on or its hash, so the
+/// user can judge it before it is erased.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub detail: String,
+    /// The listed hash the file matched, when searching by hash list.
+    pub digest: Option<[u8; 32]>,
+    /// Matches start selected, the user unticks the false positives.
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outcome {
+    pub dirs: Vec<PathBuf>,
+    pub hits: Vec<Hit>,
+    pub searched: u64,
+    /// Files and folders that could not be read or were too large.
+    pub skipped: Vec<(PathBuf, String)>,
+    pub cancelled: bool,
+}
+
+/// Reads a hash list: one SHA-256 in hex per line, optionally followed by a
+/// file name as `sha256sum` writes it. Blank lines and `#` comments are
+/// ignored.
+pub fn read_hashes(path: &Path) -> io::Result<HashSet<[u8; 32]>> {
+    let mut hashes = HashSet::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let digest = line.split_whitespace().next().and_then(parse_hex).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Line {} is not a SHA-256 hash", number + 1))
+        })?;
+        hashes.insert(digest);
+    }
+    Ok(hashes)
+}
+
+fn parse_hex(text: &str) -> Option<[u8; 32]> {
+    if text.len() != 64 || !text.is_ascii() {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(text.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(digest)
+}
+
+/// Walks `dirs` and everything below them, without following symbolic
+/// links, and collects the files that match. Paths the policy protects are
+/// left out, there is no point offering what cannot be erased.
+pub fn search(dirs: &[PathBuf], criterion: &Criterion, policy: &Policy, cancel: &CancelToken) -> io::Result<Outcome> {
+    let mut outcome = Outcome {
+        dirs: dirs.to_vec(),
+        ..Outcome::default()
+    };
+    let mut pending = dirs.to_vec();
+    while let Some(current) = pending.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            // The chosen folders must be readable, below them the search carries on.
+            Err(e) if dirs.contains(&current) => {
+                return Err(io::Error::new(e.kind(), format!("{}: {}", current.display(), e)));
+            }
+            Err(e) => {
+                outcome.skipped.push((current, e.to_string()));
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            if cancel.is_cancelled() {
+                outcome.cancelled = true;
+                return Ok(outcome);
+            }
+            let path = entry.path();
+            let Ok(meta) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if policy.forbids(&path) {
+                continue;
+            }
+            if meta.is_dir() {
+                pending.push(path);
+            } else if meta.is_file() {
+                outcome.searched += 1;
+                match check(&path, meta.len(), criterion) {
+                    Ok(Some((detail, digest))) => outcome.hits.push(Hit {
+                        path,
+                        bytes: meta.len(),
+                        detail,
+                        digest,
+                        selected: true,
+                    }),
+                    Ok(None) => {}
+                    Err(e) => outcome.skipped.push((path, e.to_string())),
+                }
+            }
+        }
+    }
+    outcome.hits.sort_by(|a, b| a.path.cmp(&b.path));
+    // Folders inside each other would list the same file twice.
+    outcome.hits.dedup_by(|a, b| a.path == b.path);
+    Ok(outcome)
+}
+
+/// The reason `path` matches and the listed hash it matched, or `None`.
+fn check(path: &Path, bytes: u64, criterion: &Criterion) -> io::Result<Option<(String, Option<[u8; 32]>)>> {
+    match criterion {
+        Criterion::Content(pattern) => {
+            if bytes > MAX_CONTENT {
+                return Err(io::Error::other(format!(
+                    "Larger than {}, not searched",
+                    units::size(MAX_CONTENT),
+                )));
+            }
+            let data = fs::read(path)?;
+            Ok(pattern.find(&data).map(|(start, end)| (context(&data, start, end), None)))
+        }
+        Criterion::Hashes(hashes) => {
+            let digest = sha256_file(path)?;
+            Ok(hashes
+                .contains(&digest)
+                .then(|| (format!("SHA-256 {} is on the list", hash::hex(&digest)), Some(digest))))
+        }
+    }
+}
+
+fn sha256_file(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+/// What became of a matched file.
+pub enum Fate {
+    /// Erased, and the name is gone from the folder.
+    Destroyed,
+    Failed(String),
+    /// Unticked by the user.
+    Kept,
+}
+
+/// A plain-text account of a hash-list purge for the incident file: every
+/// listed hash, whether it was found, and for each copy whether it was
+/// destroyed. A file only counts as destroyed when the erase succeeded and
+/// the path no longer exists.
+pub fn purge_report(list: &Path, hashes: &HashSet<[u8; 32]>, outcome: &Outcome, fate: impl Fn(&Hit) -> Fate) -> String {
+    let mut out = String::from("Hash purge report\n");
+    let _ = writeln!(out, "Date: {} UTC", history::format_date(history::unix_now()));
+    let _ = writeln!(out, "Hash list: {} ({} hashes)", list.display(), hashes.len());
+    for dir in &outcome.dirs {
+        let _ = writeln!(out, "Searched: {}", dir.display());
+    }
+    let _ = writeln!(out, "Files searched: {}{}", outcome.searched, if outcome.cancelled { " (stopped early)" } else { "" });
+    out += "\n";
+
+    let mut sorted: Vec<&[u8; 32]> = hashes.iter().collect();
+    sorted.sort();
+    let (mut found, mut destroyed, mut failed) = (0, 0, 0);
+    for digest in sorted {
+        let copies: Vec<&Hit> = outcome.hits.iter().filter(|hit| hit.digest.as_ref() == Some(digest)).collect();
+        if copies.is_empty() {
+            let _ = writeln!(out, "{}  not found", hash::hex(digest));
+            continue;
+        }
+        found += 1;
+        let _ = writeln!(out, "{}  found {} time(s)", hash::hex(digest), copies.len());
+        for hit in copies {
+            let status = match fate(hit) {
+                Fate::Destroyed if !hit.path.exists() => {
+                    destroyed += 1;
+                    "destroyed".to_string()
+                }
+                Fate::Destroyed => {
+                    failed += 1;
+                    "FAILED: the file still exists".to_string()
+                }
+                Fate::Failed(error) => {
+                    failed += 1;
+                    format!("FAILED: {}", error)
+                }
+                Fate::Kept => "kept, not selected for erasure".to_string(),
+            };
+            let _ = writeln!(out, "    {}  {}", hit.path.display(), status);
+        }
+    }
+    let _ = writeln!(
+        out,
+        "\n{} of {} hashes found, {} file(s) destroyed, {} failed, {} unreadable file(s) skipped.",
+        found,
+        hashes.len(),
+        destroyed,
+        failed,
+        outcome.skipped.len(),
+    );
+    for (path, error) in &outcome.skipped {
+        let _ = writeln!(out, "    skipped {}: {}", path.display(), error);
+    }
+    out
+}
+
+/// The line around a match, with control characters blanked out so binary
+/// files do not garble the list.
+fn context(data: &[u8], start: usize, end: usize) -> String {
+    let line_start = data[..start].iter().rposition(|byte| *byte == b'\n').map_or(0, |at| at + 1);
+    let line_end = data[end..].iter().position(|byte| *byte == b'\n').map_or(data.len(), |at| end + at);
+    // Keep the match in view when the line is long.
+    let from = line_start.max(start.saturating_sub(CONTEXT_CHARS / 2));
+    let line = String::from_utf8_lossy(&data[from..line_end]);
+    let mut text: String = line
+        .chars()
+        .take(CONTEXT_CHARS)
+        .map(|c| if c.is_control() { '.' } else { c })
+        .collect();
+    if from > line_start {
+        text.insert_str(0, "...");
+    }
+    format!("Offset {}: {}", start, text.trim())
+}
+
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    let dirs = if app.search_dirs.is_empty() {
+        "No folder selected".to_string()
+    } else {
+        app.search_dirs.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", ")
+    };
+    let criterion: iced::Element<'_, Message> = if app.search_mode == Mode::Hashes {
+        widget::row![
+            widget::button("Load hash list").on_press(Message::SelectHashList),
+            widget::text(match &app.search_hashes {
+                Some((path, hashes)) => format!("{} hash(es) from {}", hashes.len(), path.display()),
+                None => "No hash list loaded".to_string(),
+            }),
+        ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+    } else {
+        widget::row![
+            widget::text_input(
+                if app.search_mode == Mode::Regex { r"For example password\s*=|BEGIN RSA PRIVATE KEY" } else { "Text to look for" },
+                &app.search_pattern,
+            )
+                .on_input(Message::SearchPatternChanged)
+                .on_submit(Message::Search),
+            widget::checkbox("Match case", app.search_case).on_toggle(Message::SearchCaseToggled),
+        ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+    };
+    let search_button = if app.searching {
+        widget::button("Stop").on_press(Message::StopSearch)
+    } else {
+        widget::button("Search").on_press_maybe((!app.search_dirs.is_empty() && !app.erasing).then_some(Message::Search))
+    };
+
+    let results: iced::Element<'_, Message> = match &app.search {
+        None if app.searching => widget::text("Searching...").into(),
+        None => widget::text("Nothing searched yet.").into(),
+        Some(Err(e)) => widget::text!("The search failed: {}", e).into(),
+        Some(Ok(outcome)) => widget::column![
+            widget::text!(
+                "{}{} file(s) searched, {} match(es), {} skipped.",
+                if outcome.cancelled { "Stopped early: " } else { "" },
+                outcome.searched,
+                outcome.hits.len(),
+                outcome.skipped.len(),
+            ),
+            widget::scrollable(widget::column(outcome.hits.iter().enumerate().map(|(index, hit)| {
+                widget::column![
+                    widget::checkbox(
+                        format!("{} ({})", hit.path.display(), units::size(hit.bytes)),
+                        hit.selected,
+                    )
+                        .on_toggle_maybe((!app.erasing).then_some(move |selected| Message::HitToggled(index, selected))),
+                    widget::text(&hit.detail).size(12).font(iced::Font::MONOSPACE),
+                ]
+                    .spacing(2)
+                    .into()
+            })).spacing(8))
+                .height(iced::Length::Fill),
+        ]
+            .spacing(10)
+            .into(),
+    };
+
+    let selected = app.search.as_ref().and_then(|result| result.as_ref().ok()).map_or(0, |outcome| {
+        outcome.hits.iter().filter(|hit| hit.selected).count()
+    });
+    let erase_button = if app.erasing {
+        widget::button("Cancel (Esc)").on_press(Message::Cancel)
+    } else {
+        widget::button("Erase selected")
+            .on_press_maybe((app.search_confirmed && selected > 0 && !app.searching).then_some(Message::EraseHits))
+    };
+
+    widget::column![
+        widget::text(
+            "Finds files below the chosen folders that contain a string or pattern, or whose content is on a \
+             hash list, so leaked credentials or a specific document can be tracked down and destroyed."
+        ),
+        widget::row![
+            widget::button("Add folder").on_press_maybe((!app.searching).then_some(Message::SelectSearchDir)),
+            widget::button("Clear").on_press_maybe(
+                (!app.searching && !app.search_dirs.is_empty()).then_some(Message::ClearSearchDirs),
+            ),
+            widget::text(dirs),
+        ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+        widget::row![
+            widget::pick_list(Mode::ALL, Some(app.search_mode), Message::SearchModeSelected),
+            search_button,
+        ]
+            .spacing(10),
+        criterion,
+        widget::container(results).height(iced::Length::Fill),
+    ]
+        .push_maybe(app.purge_report.as_ref().map(|_| {
+            widget::row![
+                widget::text("The hash purge report lists every hash and what became of each copy."),
+                widget::button("Save report").on_press(Message::SavePurgeReport),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+        }))
+        .push(
+            widget::checkbox(
+                format!("I have reviewed the matches; the {} selected file(s) will be destroyed", selected),
+                app.search_confirmed,
+            )
+                .on_toggle(Message::SearchConfirmToggled),
+        )
+        .push(app.progress_row(erase_button))
+        .spacing(10)
+        .into()
+}
@@ -0,0 +1,81 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A self-encrypting drive as reported by `sedutil-cli`. Such drives encrypt
+/// everything with a media key, so replacing the key makes all data
+/// unreadable in seconds instead of hours of overwriting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sed {
+    /// Feature flags printed by `sedutil-cli --isValidSED`, e.g. `-2----`.
+    pub flags: String,
+}
+
+impl Sed {
+    /// Pyrite drives speak the same protocol but do not encrypt, so only
+    /// Opal 1, Opal 2, Opalite and Enterprise can be crypto-erased.
+    pub fn can_crypto_erase(&self) -> bool {
+        self.flags.chars().any(|flag| matches!(flag, '1' | '2' | 'L' | 'E'))
+    }
+
+    pub fn standard(&self) -> &'static str {
+        if self.flags.contains('2') {
+            "TCG Opal 2"
+        } else if self.flags.contains('1') {
+            "TCG Opal 1"
+        } else if self.flags.contains('E') {
+            "TCG Enterprise"
+        } else if self.flags.contains('L') {
+            "TCG Opalite"
+        } else {
+            "TCG Pyrite"
+        }
+    }
+}
+
+/// Asks `sedutil-cli` whether `device` is a self-encrypting drive. Returns
+/// `None` when it is not, or when sedutil is not installed.
+pub fn detect(device: &Path) -> Option<Sed> {
+    let output = Command::new("sedutil-cli")
+        .arg("--isValidSED")
+        .arg(device)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    // "/dev/sdb SED -2---- Samsung SSD 860 ..." or "/dev/sdb is not a valid SED ..."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut words = stdout.split_whitespace().skip_while(|word| *word != "SED");
+    words.next()?;
+    Some(Sed {
+        flags: words.next()?.to_string(),
+    })
+}
+
+/// A PSID is the 32 character code printed on the drive label.
+pub fn valid_psid(psid: &str) -> bool {
+    psid.len() == 32 && psid.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Reverts the drive to factory state with its PSID, which regenerates the
+/// media encryption key. Needs no password, only physical access to the label.
+pub fn crypto_erase(device: &Path, psid: &str) -> io::Result<()> {
+    if !valid_psid(psid) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "A PSID has 32 letters and digits"));
+    }
+    let output = Command::new("sedutil-cli")
+        .arg("--yesIreallywanttoERASEALLmydatausingthePSID")
+        .arg(psid)
+        .arg(device)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        // sedutil reports most errors on stdout.
+        let message = [&output.stderr, &output.stdout]
+            .map(|text| String::from_utf8_lossy(text).trim().to_string())
+            .into_iter()
+            .find(|text| !text.is_empty())
+            .unwrap_or_else(|| output.status.to_string());
+        return Err(io::Error::other(format!("sedutil-cli failed: {}", message)));
+    }
+    Ok(())
+}
@@ -0,0 +1,420 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+use crate::certificate::quote;
+use crate::engine::{Timestamps, Verification};
+use crate::method::{Method, Registry};
+use crate::policy::Policy;
+use crate::reports;
+use crate::units::Base;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    pub width: f32,
+    pub height: f32,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Self {
+            width: 900.0,
+            height: 480.0,
+            x: None,
+            y: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub method: Method,
+    pub verification: Verification,
+    /// Overwrite deleted names in directory blocks after erasing files.
+    pub churn_directories: bool,
+    /// Checksum every block written in a verified pass, to tell apart
+    /// storage that dropped writes from a fault in the eraser.
+    pub checksums: bool,
+    pub timestamps: Timestamps,
+    /// Whether sizes are shown in KiB or kB. The decimal separator and
+    /// the layout follow the locale.
+    pub units: Base,
+    /// Receives a JSON notification when a job ends; empty for none.
+    pub webhook_url: String,
+    /// Key for the HMAC signature of webhook bodies; empty to send unsigned.
+    pub webhook_secret: String,
+    /// Relay for mailing certificates, `host` or `host:port`; empty for none.
+    pub smtp_server: String,
+    pub smtp_from: String,
+    /// Comma separated recipients.
+    pub smtp_to: String,
+    pub advanced: bool,
+    pub announce: bool,
+    /// Simulation mode at this many bytes per second: jobs go through the
+    /// motions without writing or deleting anything and are not recorded.
+    /// Only set for one run, never read from or saved to the file.
+    pub simulate: Option<u64>,
+    /// Case, ticket or work order number attached to the jobs of this run.
+    /// Like `simulate`, only set for one run.
+    pub tag: String,
+    /// Name of the person erasing, for the audit log and certificates. Only
+    /// set for one run, since the next one may be someone else.
+    pub operator: String,
+    /// Minutes erased files wait in the holding area before they are
+    /// destroyed; `None` destroys them right away.
+    pub staging: Option<u64>,
+    /// The first-start walkthrough was finished or skipped.
+    pub onboarded: bool,
+    pub window: Geometry,
+    /// The user's own settings file as read, which `save` writes the
+    /// changes of this run into. `None` until `layer_over` is called.
+    user: Option<Box<Settings>>,
+    /// The values this run started with, `--config`, the environment, the
+    /// command line and the policy applied; what still equals them at save
+    /// time is left as the user's file has it.
+    started: Option<Box<Settings>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            method: Method::random(3),
+            verification: Verification::None,
+            churn_directories: false,
+            checksums: false,
+            timestamps: Timestamps::Keep,
+            units: Base::Binary,
+            webhook_url: String::new(),
+            webhook_secret: String::new(),
+            smtp_server: String::new(),
+            smtp_from: String::new(),
+            smtp_to: String::new(),
+            advanced: false,
+            announce: false,
+            simulate: None,
+            tag: String::new(),
+            operator: String::new(),
+            staging: None,
+            onboarded: false,
+            window: Geometry::default(),
+            user: None,
+            started: None,
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        crate::portable::config_dir().map(|dir| dir.join("settings.toml"))
+    }
+
+    /// Keys that `set` accepts, which are also the names of the
+    /// `FILE_ERASER_*` environment variables.
+    pub const KEYS: [&str; 18] = [
+        "method",
+        "verification",
+        "timestamps",
+        "units",
+        "churn_directories",
+        "checksums",
+        "webhook_url",
+        "webhook_secret",
+        "smtp_server",
+        "smtp_from",
+        "smtp_to",
+        "advanced",
+        "announce",
+        "simulate",
+        "tag",
+        "operator",
+        "staging",
+        "onboarded",
+    ];
+
+    pub fn load(methods: &Registry) -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text, methods),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Loads an alternate settings file, for example one pushed by an
+    /// administrator. Unlike the default file it has to exist, and it is
+    /// only ever read: changes still go to the user's own file.
+    pub fn load_from(path: &Path, methods: &Registry) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Ok(Self::parse(&text, methods))
+    }
+
+    /// Takes the current values as where this run starts, on top of `user`,
+    /// the user's own file. From then on `save` writes only what changes
+    /// during the run, and writes it into that file.
+    pub fn layer_over(&mut self, user: Settings) {
+        self.user = Some(Box::new(user));
+        self.started = None;
+        self.started = Some(Box::new(self.clone()));
+    }
+
+    /// Changes one setting by name, the way `--set` and the environment do.
+    pub fn set(&mut self, key: &str, value: &str, methods: &Registry) -> Result<(), String> {
+        let boolean = || match value {
+            "true" | "1" | "yes" => Ok(true),
+            "false" | "0" | "no" => Ok(false),
+            _ => Err(format!("{} must be true or false, not {}", key, value)),
+        };
+        match key {
+            "method" => self.method = methods.find(value).ok_or_else(|| format!("Unknown method {}", value))?,
+            "verification" => {
+                self.verification = Verification::from_key(value)
+                    .ok_or_else(|| format!("Unknown verification {}", value))?;
+            }
+            "timestamps" => {
+                self.timestamps = Timestamps::from_key(value)
+                    .ok_or_else(|| format!("timestamps must be keep, random or fixed, not {}", value))?;
+            }
+            "units" => {
+                self.units = Base::from_key(value).ok_or_else(|| format!("units must be binary or decimal, not {}", value))?;
+            }
+            "churn_directories" => self.churn_directories = boolean()?,
+            "checksums" => self.checksums = boolean()?,
+            "webhook_url" => {
+                if !value.is_empty() {
+                    crate::http::Url::parse(value)?;
+                }
+                self.webhook_url = value.to_string();
+            }
+            "webhook_secret" => self.webhook_secret = value.to_string(),
+            "smtp_server" => self.smtp_server = value.to_string(),
+            "smtp_from" => self.smtp_from = value.to_string(),
+            "smtp_to" => self.smtp_to = value.to_string(),
+            "advanced" => self.advanced = boolean()?,
+            "announce" => self.announce = boolean()?,
+            "simulate" => {
+                self.simulate = match value {
+                    "off" | "0" => None,
+                    _ => Some(
+                        value
+                            .parse::<u64>()
+                            .ok()
+                            .filter(|speed| *speed > 0)
+                            .ok_or_else(|| format!("simulate must be a speed in MB/s or off, not {}", value))?
+                            * 1_000_000,
+                    ),
+                };
+            }
+            "tag" => self.tag = value.to_string(),
+            "operator" => self.operator = value.to_string(),
+            "staging" => {
+                self.staging = match value {
+                    "off" | "0" => None,
+                    _ => Some(
+                        value
+                            .parse::<u64>()
+                            .ok()
+                            .filter(|minutes| *minutes > 0)
+                            .ok_or_else(|| format!("staging must be a number of minutes or off, not {}", value))?,
+                    ),
+                };
+            }
+            "onboarded" => self.onboarded = boolean()?,
+            _ => return Err(format!("Unknown setting {}, expected one of {}", key, Self::KEYS.join(", "))),
+        }
+        Ok(())
+    }
+
+    /// Applies `FILE_ERASER_METHOD=gutmann` style variables. A bad value is
+    /// reported and skipped rather than keeping the app from starting.
+    pub fn apply_environment(&mut self, methods: &Registry) {
+        for key in Self::KEYS {
+            let name = format!("FILE_ERASER_{}", key.to_uppercase());
+            if let Ok(value) = std::env::var(&name)
+                && let Err(e) = self.set(key, value.trim(), methods)
+            {
+                eprintln!("Ignoring {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Reads whatever keys are present and falls back to defaults for the rest,
+    /// so a hand-edited or older file never prevents the app from starting.
+    pub fn parse(text: &str, methods: &Registry) -> Self {
+        let mut settings = Self::default();
+        let Ok(doc) = text.parse::<DocumentMut>() else {
+            eprintln!("Ignoring malformed settings file");
+            return settings;
+        };
+
+        // Older versions only stored a number of random passes.
+        if let Some(passes) = doc.get("passes").and_then(|item| item.as_integer()) {
+            settings.method = Method::random(passes.clamp(1, 35) as usize);
+        }
+        if let Some(method) = doc.get("method").and_then(|item| item.as_str()).and_then(|key| methods.find(key)) {
+            settings.method = method;
+        }
+        if let Some(verification) = doc.get("verification").and_then(|item| item.as_str()).and_then(Verification::from_key) {
+            settings.verification = verification;
+        }
+        if let Some(churn) = doc.get("churn_directories").and_then(|item| item.as_bool()) {
+            settings.churn_directories = churn;
+        }
+        if let Some(checksums) = doc.get("checksums").and_then(|item| item.as_bool()) {
+            settings.checksums = checksums;
+        }
+        if let Some(timestamps) = doc.get("timestamps").and_then(|item| item.as_str()).and_then(Timestamps::from_key) {
+            settings.timestamps = timestamps;
+        }
+        if let Some(units) = doc.get("units").and_then(|item| item.as_str()).and_then(Base::from_key) {
+            settings.units = units;
+        }
+        if let Some(url) = doc.get("webhook_url").and_then(|item| item.as_str()) {
+            settings.webhook_url = url.to_string();
+        }
+        if let Some(secret) = doc.get("webhook_secret").and_then(|item| item.as_str()) {
+            settings.webhook_secret = secret.to_string();
+        }
+        let string = |key: &str| doc.get(key).and_then(|item| item.as_str()).map(str::to_string);
+        settings.smtp_server = string("smtp_server").unwrap_or_default();
+        settings.smtp_from = string("smtp_from").unwrap_or_default();
+        settings.smtp_to = string("smtp_to").unwrap_or_default();
+        if let Some(advanced) = doc.get("advanced").and_then(|item| item.as_bool()) {
+            settings.advanced = advanced;
+        }
+        if let Some(announce) = doc.get("announce").and_then(|item| item.as_bool()) {
+            settings.announce = announce;
+        }
+        if let Some(minutes) = doc.get("staging").and_then(|item| item.as_integer()).filter(|minutes| *minutes > 0) {
+            settings.staging = Some(minutes as u64);
+        }
+        // A file from before the walkthrough existed belongs to someone
+        // who has used the app already.
+        settings.onboarded = doc.get("onboarded").and_then(|item| item.as_bool()).unwrap_or(true);
+        if let Some(window) = doc.get("window") {
+            let float = |key: &str| window.get(key).and_then(|item| item.as_float()).map(|v| v as f32);
+            let window = &mut settings.window;
+            window.width = float("width").unwrap_or(window.width).max(320.0);
+            window.height = float("height").unwrap_or(window.height).max(240.0);
+            window.x = float("x");
+            window.y = float("y");
+        }
+        settings
+    }
+
+    /// Applies what an administrator's policy forces, on top of the file, the
+    /// environment and the command line.
+    pub fn enforce(&mut self, policy: &Policy, methods: &Registry) {
+        for (key, value) in &policy.settings {
+            if let Err(e) = self.set(key, value, methods) {
+                eprintln!("Ignoring policy setting {}: {}", key, e);
+            }
+        }
+        if let Some(minimum) = &policy.minimum_method
+            && !policy.allows_method(&self.method)
+        {
+            self.method = minimum.clone();
+        }
+        self.verification = self.verification.at_least(policy.minimum_verification);
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut text = format!("method = \"{}\"\n", self.method.key());
+        text += &format!("verification = \"{}\"\n", self.verification.key());
+        text += &format!("churn_directories = {}\n", self.churn_directories);
+        text += &format!("checksums = {}\n", self.checksums);
+        text += &format!("timestamps = \"{}\"\n", self.timestamps.key());
+        text += &format!("units = \"{}\"\n", self.units.key());
+        text += &format!("webhook_url = {}\n", quote(&self.webhook_url));
+        text += &format!("webhook_secret = {}\n", quote(&self.webhook_secret));
+        text += &format!("smtp_server = {}\n", quote(&self.smtp_server));
+        text += &format!("smtp_from = {}\n", quote(&self.smtp_from));
+        text += &format!("smtp_to = {}\n", quote(&self.smtp_to));
+        text += &format!("advanced = {}\nannounce = {}\n", self.advanced, self.announce);
+        if let Some(minutes) = self.staging {
+            text += &format!("staging = {}\n", minutes);
+        }
+        text += &format!("onboarded = {}\n", self.onboarded);
+        text += "\n[window]\n";
+        text += &format!("width = {:.1}\nheight = {:.1}\n", self.window.width, self.window.height);
+        if let (Some(x), Some(y)) = (self.window.x, self.window.y) {
+            text += &format!("x = {:.1}\ny = {:.1}\n", x, y);
+        }
+        text
+    }
+
+    /// What `save` writes: the user's own file with the values changed
+    /// during this run. Overrides nobody changed since stay out of it.
+    pub fn saved(&self) -> Settings {
+        let (Some(user), Some(started)) = (&self.user, &self.started) else {
+            return self.clone();
+        };
+        let mut saved = (**user).clone();
+        if self.method != started.method {
+            saved.method = self.method.clone();
+        }
+        if self.verification != started.verification {
+            saved.verification = self.verification;
+        }
+        if self.churn_directories != started.churn_directories {
+            saved.churn_directories = self.churn_directories;
+        }
+        if self.checksums != started.checksums {
+            saved.checksums = self.checksums;
+        }
+        if self.timestamps != started.timestamps {
+            saved.timestamps = self.timestamps;
+        }
+        if self.units != started.units {
+            saved.units = self.units;
+        }
+        if self.webhook_url != started.webhook_url {
+            saved.webhook_url = self.webhook_url.clone();
+        }
+        if self.webhook_secret != started.webhook_secret {
+            saved.webhook_secret = self.webhook_secret.clone();
+        }
+        if self.smtp_server != started.smtp_server {
+            saved.smtp_server = self.smtp_server.clone();
+        }
+        if self.smtp_from != started.smtp_from {
+            saved.smtp_from = self.smtp_from.clone();
+        }
+        if self.smtp_to != started.smtp_to {
+            saved.smtp_to = self.smtp_to.clone();
+        }
+        if self.advanced != started.advanced {
+            saved.advanced = self.advanced;
+        }
+        if self.announce != started.announce {
+            saved.announce = self.announce;
+        }
+        if self.staging != started.staging {
+            saved.staging = self.staging;
+        }
+        if self.onboarded != started.onboarded {
+            saved.onboarded = self.onboarded;
+        }
+        if self.window != started.window {
+            saved.window = self.window;
+        }
+        saved
+    }
+
+    /// Writes the changes of this run into the user's own settings file,
+    /// never into a `--config` file.
+    pub fn save(&self) -> io::Result<()> {
+        self.save_to(&Self::path().ok_or_else(|| io::Error::other("No config directory"))?)
+    }
+
+    /// Writes what `saved` returns to `path`. The file holds the webhook
+    /// secret, so only the user can read it.
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        reports::write(path, self.saved().to_toml().as_bytes())
+    }
+}
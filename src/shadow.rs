@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Component, Path, PathBuf, Prefix};
+use iced::widget;
+use crate::{App, Message};
+
+/// A Volume Shadow Copy: a snapshot Windows keeps for restore points and
+/// "Previous Versions". Earlier versions of a wiped file survive in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowCopy {
+    pub id: String,
+    /// Drive letter of the volume it is a snapshot of, such as `C:`.
+    pub volume: String,
+    pub created: String,
+}
+
+/// The shadow copies on this machine. Listing them needs an elevated
+/// process, so a normal user gets an error rather than an empty list.
+#[cfg(windows)]
+pub fn list() -> io::Result<Vec<ShadowCopy>> {
+    Ok(parse(&vssadmin(&["list", "shadows"])?))
+}
+
+#[cfg(not(windows))]
+pub fn list() -> io::Result<Vec<ShadowCopy>> {
+    Ok(Vec::new())
+}
+
+#[cfg(windows)]
+fn vssadmin(args: &[&str]) -> io::Result<String> {
+    let output = std::process::Command::new("vssadmin").args(args).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        // vssadmin explains itself on stdout, the last line is the reason.
+        let reason = stdout.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default();
+        return Err(io::Error::other(format!("vssadmin {} failed: {}", args.join(" "), reason.trim())));
+    }
+    Ok(stdout)
+}
+
+/// Reads the output of `vssadmin list shadows` (English Windows):
+///
+/// ```text
+/// Contents of shadow copy set ID: {...}
+///    Contained 1 shadow copies at creation time: 2/3/2024 4:05:06 AM
+///       Shadow Copy ID: {c5946237-af12-3f23-af80-51aadb3b20d5}
+///          Original Volume: (C:)\\?\Volume{...}\
+/// ```
+#[cfg(windows)]
+fn parse(text: &str) -> Vec<ShadowCopy> {
+    let mut shadows = Vec::new();
+    let mut created = String::new();
+    for line in text.lines().map(str::trim) {
+        if let Some((_, time)) = line.split_once("creation time:") {
+            created = time.trim().to_string();
+        } else if let Some(id) = line.strip_prefix("Shadow Copy ID:") {
+            shadows.push(ShadowCopy {
+                id: id.trim().to_string(),
+                volume: String::new(),
+                created: created.clone(),
+            });
+        } else if let Some(volume) = line.strip_prefix("Original Volume:")
+            && let Some(shadow) = shadows.last_mut()
+        {
+            let volume = volume.trim();
+            shadow.volume = match volume.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+                Some((letter, _)) => letter.to_uppercase(),
+                None => volume.to_string(),
+            };
+        }
+    }
+    shadows
+}
+
+/// The drive `path` is on, as `C:`.
+fn volume(path: &Path) -> Option<String> {
+    let path = std::path::absolute(path).ok()?;
+    match path.components().next()? {
+        Component::Prefix(prefix) => match prefix.kind() {
+            Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => Some(format!("{}:", letter.to_ascii_uppercase() as char)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The shadow copies of the volumes `files` are on.
+pub fn covering<'a>(shadows: &'a [ShadowCopy], files: &[PathBuf]) -> Vec<&'a ShadowCopy> {
+    if shadows.is_empty() {
+        return Vec::new();
+    }
+    let volumes: BTreeSet<String> = files.iter().filter_map(|file| volume(file)).collect();
+    shadows.iter().filter(|shadow| volumes.contains(&shadow.volume)).collect()
+}
+
+/// Deletes the shadow copies with the given ids, stopping at the first one
+/// that cannot be deleted. Needs an elevated process.
+#[cfg(windows)]
+pub fn delete(ids: &[String]) -> Result<usize, String> {
+    for (deleted, id) in ids.iter().enumerate() {
+        if let Err(e) = vssadmin(&["delete", "shadows", &format!("/shadow={}", id), "/quiet"]) {
+            return Err(format!("{} of {} deleted, then {}", deleted, ids.len(), e));
+        }
+    }
+    Ok(ids.len())
+}
+
+#[cfg(not(windows))]
+pub fn delete(_ids: &[String]) -> Result<usize, String> {
+    Err("Shadow copies only exist on Windows".to_string())
+}
+
+/// A warning when the files in the queue are on a volume with shadow
+/// copies, with the choice to delete them as part of the job.
+pub fn warning(app: &App) -> Option<iced::Element<'_, Message>> {
+    let shadows = match &app.shadows {
+        Ok(shadows) => covering(shadows, &app.files),
+        // Only worth a word when there are files on a Windows drive.
+        Err(e) if app.files.iter().any(|file| volume(file).is_some()) => {
+            return Some(
+                widget::text!("Could not check for shadow copies, earlier versions may survive in them: {}", e)
+                    .size(14)
+                    .into(),
+            );
+        }
+        Err(_) => return None,
+    };
+    if shadows.is_empty() {
+        return None;
+    }
+    let volumes: BTreeSet<&str> = shadows.iter().map(|shadow| shadow.volume.as_str()).collect();
+    let oldest = shadows.iter().map(|shadow| shadow.created.as_str()).next().unwrap_or_default();
+    Some(
+        widget::column![
+            widget::text!(
+                "{} has {} shadow copies (restore points, oldest from {}). Earlier versions of these files \
+                 may survive in them after the erase.",
+                volumes.into_iter().collect::<Vec<_>>().join(", "),
+                shadows.len(),
+                oldest,
+            )
+                .size(14),
+            widget::checkbox("Delete these shadow copies before erasing (needs administrator)", app.purge_shadows)
+                .on_toggle_maybe((!app.erasing).then_some(Message::PurgeShadowsToggled)),
+        ]
+            .spacing(5)
+            .into(),
+    )
+}
@@ -0,0 +1,80 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The entry the file manager shows for files and folders.
+pub const LABEL: &str = "Erase with File Eraser";
+
+/// Adds `LABEL` to the file manager's menu for files and folders. Picking
+/// it opens the window with them queued, nothing is erased before the
+/// usual confirmation. Returns where the entries went.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn install(exe: &Path) -> io::Result<Vec<PathBuf>> {
+    let data = dirs::data_dir().ok_or_else(|| io::Error::other("No data directory"))?;
+    install_at(&data, exe)
+}
+
+/// Writes a Nautilus script and a Dolphin service menu below `data`, the
+/// user's `~/.local/share`.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn install_at(data: &Path, exe: &Path) -> io::Result<Vec<PathBuf>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = exe.display().to_string();
+    let script = data.join("nautilus").join("scripts").join(LABEL);
+    std::fs::create_dir_all(script.parent().expect("script folder"))?;
+    std::fs::write(&script, format!("#!/bin/sh\nexec '{}' --gui \"$@\"\n", path.replace('\'', "'\\''")))?;
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+
+    let menu = data.join("kio").join("servicemenus").join("file-eraser.desktop");
+    std::fs::create_dir_all(menu.parent().expect("service menu folder"))?;
+    std::fs::write(
+        &menu,
+        format!(
+            "[Desktop Entry]\nType=Service\nMimeType=all/allfiles;inode/directory;\nActions=erase\n\n\
+             [Desktop Action erase]\nName={}\nIcon=edit-delete-shred\nExec=\"{}\" --gui %F\n",
+            LABEL,
+            // Desktop entries quote with double quotes only.
+            path.chars().fold(String::new(), |mut quoted, c| {
+                if matches!(c, '"' | '`' | '$' | '\\') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+                quoted
+            }),
+        ),
+    )?;
+    std::fs::set_permissions(&menu, std::fs::Permissions::from_mode(0o755))?;
+    Ok(vec![script, menu])
+}
+
+/// Registers a verb for every file and for folders under
+/// `HKEY_CURRENT_USER\Software\Classes`, which needs no elevation.
+#[cfg(windows)]
+pub fn install(exe: &Path) -> io::Result<Vec<PathBuf>> {
+    let command = format!("\"{}\" --gui \"%1\"", exe.display());
+    let mut keys = Vec::new();
+    for class in ["*", "Directory"] {
+        let key = format!("HKCU\\Software\\Classes\\{}\\shell\\FileEraser", class);
+        reg(&[&key, "/ve", "/d", LABEL])?;
+        reg(&[&format!("{}\\command", key), "/ve", "/d", &command])?;
+        keys.push(PathBuf::from(key));
+    }
+    Ok(keys)
+}
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> io::Result<()> {
+    let output = std::process::Command::new("reg").arg("add").args(args).arg("/f").output()?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string())),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn install(_exe: &Path) -> io::Result<Vec<PathBuf>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Finder entries are added as a Quick Action in Automator that runs the app with --gui",
+    ))
+}
@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+use iced::widget;
+use crate::{App, Message};
+
+/// A read-only snapshot that still holds the blocks of the files as they
+/// were when it was taken. Overwriting a file writes new blocks (both file
+/// systems copy on write), so the snapshot keeps the old contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub name: String,
+    pub created: Option<String>,
+}
+
+/// The snapshots of one btrfs subvolume or ZFS dataset that files in the
+/// queue live on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshots {
+    pub filesystem: &'static str,
+    /// The subvolume's root folder, or the dataset's name.
+    pub volume: String,
+    /// Listing btrfs snapshots needs root, so this can be an error.
+    pub found: Result<Vec<Snapshot>, String>,
+}
+
+/// Snapshots of the subvolumes and datasets `files` are on. Volumes
+/// without snapshots are left out.
+#[cfg(target_os = "linux")]
+pub fn detect(files: &[PathBuf]) -> Vec<Snapshots> {
+    use std::collections::BTreeSet;
+
+    let mut volumes = BTreeSet::new();
+    for file in files {
+        let Some(mount) = crate::engine::mount(file) else {
+            continue;
+        };
+        match mount.fstype.as_str() {
+            "btrfs" => {
+                if let Some(root) = subvolume(file) {
+                    volumes.insert(("btrfs", root.display().to_string()));
+                }
+            }
+            "zfs" => {
+                volumes.insert(("zfs", mount.source));
+            }
+            _ => {}
+        }
+    }
+    volumes
+        .into_iter()
+        .map(|(filesystem, volume)| Snapshots {
+            found: match filesystem {
+                "btrfs" => btrfs(&volume),
+                _ => zfs(&volume),
+            },
+            filesystem,
+            volume,
+        })
+        .filter(|snapshots| snapshots.found.as_ref().map_or(true, |found| !found.is_empty()))
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn detect(_files: &[PathBuf]) -> Vec<Snapshots> {
+    Vec::new()
+}
+
+/// The root folder of the btrfs subvolume `file` is in. Subvolume roots are
+/// the only folders with inode number 256.
+#[cfg(target_os = "linux")]
+fn subvolume(file: &std::path::Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    const SUBVOLUME_ROOT: u64 = 256;
+    let file = std::path::absolute(file).ok()?;
+    file.ancestors()
+        .skip(1)
+        .find(|dir| std::fs::metadata(dir).is_ok_and(|meta| meta.ino() == SUBVOLUME_ROOT))
+        .map(|dir| dir.to_path_buf())
+}
+
+#[cfg(target_os = "linux")]
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| format!("{} could not be run: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} {} failed: {}", program, args.join(" "), stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Snapshots taken of the subvolume at `root`, from the `Snapshot(s):`
+/// section of `btrfs subvolume show`, one indented path per line.
+#[cfg(target_os = "linux")]
+fn btrfs(root: &str) -> Result<Vec<Snapshot>, String> {
+    let text = run("btrfs", &["subvolume", "show", root])?;
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let mut lines = text.lines().skip_while(|line| line.trim() != "Snapshot(s):");
+    let Some(header) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    Ok(lines
+        .take_while(|line| indent(line) > indent(header))
+        .map(|line| Snapshot { name: line.trim().to_string(), created: None })
+        .collect())
+}
+
+/// Snapshots of the ZFS dataset `dataset`, as `dataset@name` with their
+/// creation time.
+#[cfg(target_os = "linux")]
+fn zfs(dataset: &str) -> Result<Vec<Snapshot>, String> {
+    let text = run("zfs", &["list", "-H", "-t", "snapshot", "-o", "name,creation", "-d", "1", dataset])?;
+    Ok(text
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, created)| Snapshot { name: name.to_string(), created: Some(created.to_string()) })
+        .collect())
+}
+
+/// Whether snapshots were found that the user has to remove or accept
+/// before the files are erased.
+pub fn blocking(found: &[Snapshots]) -> bool {
+    found.iter().any(|snapshots| snapshots.found.as_ref().is_ok_and(|found| !found.is_empty()))
+}
+
+/// Lists the snapshots holding the files in the queue, with the choice to
+/// check again after removing them or to erase anyway.
+pub fn warning(app: &App) -> Option<iced::Element<'_, Message>> {
+    if app.snapshots.is_empty() {
+        return None;
+    }
+    let lines = app.snapshots.iter().flat_map(|snapshots| {
+        let heading = match &snapshots.found {
+            Ok(found) => format!("{} {} has {} snapshot(s):", snapshots.filesystem, snapshots.volume, found.len()),
+            Err(e) => format!("Could not list the snapshots of {} {}: {}", snapshots.filesystem, snapshots.volume, e),
+        };
+        let found = snapshots.found.as_deref().unwrap_or_default().iter().take(10).map(|snapshot| match &snapshot.created {
+            Some(created) => format!("    {} ({})", snapshot.name, created),
+            None => format!("    {}", snapshot.name),
+        });
+        std::iter::once(heading).chain(found)
+    });
+    let mut column = widget::column![
+        widget::text(
+            "These files are on a copy-on-write file system. Snapshots keep the contents they had when \
+             the snapshot was taken, and erasing the files does not touch them.",
+        )
+            .size(14),
+    ]
+        .extend(lines.map(|line| widget::text(line).size(12).font(iced::Font::MONOSPACE).into()))
+        .spacing(5);
+    if blocking(&app.snapshots) {
+        column = column.push(
+            widget::row![
+                widget::button("Check again").on_press_maybe((!app.erasing).then_some(Message::CheckSnapshots)),
+                widget::checkbox("Erase anyway, the snapshots keep their copies", app.snapshots_acknowledged)
+                    .on_toggle_maybe((!app.erasing).then_some(Message::SnapshotsAcknowledged)),
+            ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+        );
+    }
+    Some(column.into())
+}
@@ -0,0 +1,190 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::{engine, reports};
+
+/// Free space kept for the history, reports and certificates a job writes.
+const RECORD_SPACE: u64 = 1024 * 1024;
+/// Below this a volume counts as nearly full: renaming a file can need a
+/// new directory block, and the file system's own bookkeeping needs room.
+const LOW_SPACE: u64 = 1024 * 1024;
+
+/// What is left on a volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Room {
+    pub bytes: u64,
+    /// Free inodes; `None` on file systems that do not count them.
+    pub inodes: Option<u64>,
+    pub read_only: bool,
+}
+
+#[cfg(unix)]
+pub fn room(dir: &Path) -> Option<Room> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs is a plain C struct of integers, for which all-zero
+    // is a valid value; it only serves as the out-parameter below.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a NUL-terminated CString that outlives the call, and
+    // `stat` is an initialised, exclusively borrowed statvfs for the kernel to
+    // fill in; nothing is read from it unless the call succeeds.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(Room {
+        bytes: stat.f_bavail as u64 * stat.f_frsize as u64,
+        // Btrfs and others report zero total inodes: they make them as needed.
+        inodes: (stat.f_files > 0).then_some(stat.f_favail as u64),
+        read_only: stat.f_flag & libc::ST_RDONLY != 0,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn room(_dir: &Path) -> Option<Room> {
+    None
+}
+
+/// The files of a job that live on one volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    /// A folder on the volume, the first file's.
+    pub dir: PathBuf,
+    pub files: Vec<PathBuf>,
+}
+
+/// Groups `files` by the volume they are on, in the order each volume first
+/// appears.
+pub fn volumes(files: &[PathBuf]) -> Vec<Volume> {
+    let mut volumes: Vec<(Option<u64>, Volume)> = Vec::new();
+    for file in files {
+        let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let device = device(dir);
+        match volumes.iter_mut().find(|(known, volume)| match (known, device) {
+            (Some(known), Some(device)) => *known == device,
+            _ => volume.dir == dir,
+        }) {
+            Some((_, volume)) => volume.files.push(file.clone()),
+            None => volumes.push((device, Volume { dir: dir.to_path_buf(), files: vec![file.clone()] })),
+        }
+    }
+    volumes.into_iter().map(|(_, volume)| volume).collect()
+}
+
+/// Whether `a` and `b` are on the same volume. Where devices cannot be
+/// told apart, paths with the same root are taken to be.
+pub fn same_volume(a: &Path, b: &Path) -> bool {
+    match (device(a), device(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.components().next() == b.components().next(),
+    }
+}
+
+#[cfg(unix)]
+fn device(dir: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(dir).ok().map(|meta| meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Something found before the job started that would make it fail, or
+/// leave part of it undone, halfway through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The files it concerns; empty for the job as a whole.
+    pub files: Vec<PathBuf>,
+    pub message: String,
+    /// The files are not worth trying; otherwise it is only a warning.
+    pub refuse: bool,
+    pub kind: io::ErrorKind,
+}
+
+impl Problem {
+    pub fn error(&self) -> io::Error {
+        io::Error::new(self.kind, self.message.clone())
+    }
+}
+
+/// Looks at every volume of the job and at the one the history goes to.
+pub fn check(files: &[PathBuf], churn_directories: bool) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = volumes(files)
+        .into_iter()
+        .filter_map(|volume| {
+            let room = room(&volume.dir)?;
+            judge(&volume, room, churn_directories, copy_on_write(&volume.dir))
+        })
+        .collect();
+    if let Ok(dir) = reports::data_dir()
+        && let Some(room) = room(&dir)
+        && (room.read_only || room.bytes < RECORD_SPACE)
+    {
+        problems.push(Problem {
+            files: Vec::new(),
+            message: format!(
+                "{} is {}, so the history and certificates of this job cannot be written",
+                dir.display(),
+                if room.read_only { "read-only" } else { "full" },
+            ),
+            refuse: false,
+            kind: if room.read_only { io::ErrorKind::ReadOnlyFilesystem } else { io::ErrorKind::StorageFull },
+        });
+    }
+    problems
+}
+
+/// What, if anything, stands in the way of erasing `volume` with `room` left.
+pub fn judge(volume: &Volume, room: Room, churn_directories: bool, copy_on_write: bool) -> Option<Problem> {
+    let problem = |message: String, refuse, kind| Some(Problem { files: volume.files.clone(), message, refuse, kind });
+    let dir = volume.dir.display();
+    if room.read_only {
+        return problem(
+            format!("{} is mounted read-only, nothing on it can be overwritten", dir),
+            true,
+            io::ErrorKind::ReadOnlyFilesystem,
+        );
+    }
+    // Copy-on-write file systems put every overwrite in new blocks and only
+    // free the old ones afterwards, so the largest file has to fit again.
+    let largest = volume.files.iter().filter_map(|file| std::fs::metadata(file).ok()).map(|meta| meta.len()).max();
+    if copy_on_write && let Some(largest) = largest.filter(|largest| *largest > room.bytes) {
+        return problem(
+            format!(
+                "{} is copy-on-write and has {} bytes free, but overwriting a {} byte file needs that much again",
+                dir, room.bytes, largest,
+            ),
+            true,
+            io::ErrorKind::StorageFull,
+        );
+    }
+    if room.bytes < LOW_SPACE {
+        return problem(
+            format!("{} is nearly full ({} bytes free), renaming the files before deletion may fail", dir, room.bytes),
+            false,
+            io::ErrorKind::StorageFull,
+        );
+    }
+    // Each folder is churned on its own, so the busiest one sets the need.
+    let entries = volume.files
+        .iter()
+        .map(|file| volume.files.iter().filter(|other| other.parent() == file.parent()).count())
+        .max()
+        .map_or(0, |files| engine::churn_entries(files) as u64);
+    if churn_directories && let Some(inodes) = room.inodes.filter(|inodes| *inodes < entries) {
+        return problem(
+            format!(
+                "{} has {} free inodes but shredding the file names needs {}, the names may survive",
+                dir, inodes, entries,
+            ),
+            false,
+            io::ErrorKind::StorageFull,
+        );
+    }
+    None
+}
+
+fn copy_on_write(dir: &Path) -> bool {
+    crate::strategy::Facts::inspect(dir).copy_on_write
+}
@@ -0,0 +1,206 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use iced::widget;
+use crate::db::{escape, unescape};
+use crate::history::{format_date, unix_now};
+use crate::policy::Policy;
+use crate::{reports, App, Message};
+
+const HEADER: &str = "# file-eraser staging";
+/// Holding folder next to the files, for those on another volume than the
+/// data folder: moving them there would mean copying, and the original
+/// blocks would be left behind unerased.
+const BESIDE: &str = ".file-eraser-staging";
+
+/// A file moved out of its folder and waiting to be destroyed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Staged {
+    pub id: u64,
+    pub original: PathBuf,
+    pub held: PathBuf,
+    /// Seconds since the Unix epoch from which it may be destroyed.
+    pub due: u64,
+}
+
+/// The holding area: `<data dir>/file-eraser/staging`, readable by the user
+/// only, and `staging.list`, which remembers where everything came from.
+pub struct Area {
+    dir: PathBuf,
+    list: PathBuf,
+    items: Vec<Staged>,
+    next_id: u64,
+}
+
+impl Area {
+    pub fn open() -> io::Result<Self> {
+        Self::open_at(&reports::data_dir()?)
+    }
+
+    pub fn open_at(dir: &Path) -> io::Result<Self> {
+        let mut area = Self {
+            dir: dir.join("staging"),
+            list: dir.join("staging.list"),
+            items: Vec::new(),
+            next_id: 1,
+        };
+        reports::private(&area.dir)?;
+        let text = match fs::read_to_string(&area.list) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(area),
+            Err(e) => return Err(e),
+        };
+        for line in text.lines().skip(1) {
+            match parse(line) {
+                Some(staged) => {
+                    area.next_id = area.next_id.max(staged.id + 1);
+                    area.items.push(staged);
+                }
+                None => eprintln!("Skipping damaged staging entry"),
+            }
+        }
+        Ok(area)
+    }
+
+    pub fn items(&self) -> &[Staged] {
+        &self.items
+    }
+
+    /// Entries whose delay is over.
+    pub fn due(&self, now: u64) -> Vec<Staged> {
+        self.items.iter().filter(|staged| staged.due <= now).cloned().collect()
+    }
+
+    /// Moves `path` into the holding area, to be destroyed `delay` seconds
+    /// from now, unless the policy forbids erasing it. The folder is the
+    /// user's alone, so other users lose access to the file on the way in,
+    /// and get it back with a restore.
+    pub fn stage(&mut self, path: &Path, delay: u64, policy: &Policy) -> io::Result<()> {
+        // Once held, the file no longer matches the forbidden paths.
+        policy.check_path(path)?;
+        let id = self.next_id;
+        let original = std::path::absolute(path)?;
+        let mut held = self.dir.join(id.to_string());
+        if let Err(e) = fs::rename(path, &held) {
+            if e.kind() != io::ErrorKind::CrossesDevices {
+                return Err(e);
+            }
+            let beside = path.parent().unwrap_or(Path::new(".")).join(BESIDE);
+            reports::private(&beside)?;
+            held = beside.join(id.to_string());
+            fs::rename(path, &held)?;
+        }
+        self.next_id += 1;
+        self.items.push(Staged { id, original, held, due: unix_now() + delay });
+        self.save()
+    }
+
+    /// Moves a staged file back where it came from, unless something new
+    /// has taken its place in the meantime.
+    pub fn restore(&mut self, id: u64) -> io::Result<PathBuf> {
+        let index = self.items.iter().position(|staged| staged.id == id).ok_or(io::ErrorKind::NotFound)?;
+        let staged = &self.items[index];
+        if staged.original.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists again, move it away first", staged.original.display()),
+            ));
+        }
+        fs::rename(&staged.held, &staged.original)?;
+        let staged = self.items.remove(index);
+        self.save()?;
+        Ok(staged.original)
+    }
+
+    /// Forgets the entries whose files are gone, once they were erased.
+    pub fn prune(&mut self) -> io::Result<()> {
+        let (kept, gone): (Vec<Staged>, Vec<Staged>) =
+            std::mem::take(&mut self.items).into_iter().partition(|staged| staged.held.exists());
+        self.items = kept;
+        if gone.is_empty() {
+            return Ok(());
+        }
+        for staged in &gone {
+            if let Some(beside) = staged.held.parent().filter(|dir| dir.ends_with(BESIDE)) {
+                // Only succeeds once the last file held there is gone.
+                let _ = fs::remove_dir(beside);
+            }
+        }
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let mut text = format!("{}\n", HEADER);
+        for staged in &self.items {
+            text += &format!(
+                "{}\t{}\t{}\t{}\n",
+                staged.id,
+                staged.due,
+                escape(&staged.original.to_string_lossy()),
+                escape(&staged.held.to_string_lossy()),
+            );
+        }
+        reports::write(&self.list, text.as_bytes())
+    }
+}
+
+/// Splits `staged` into what may be destroyed and an error for each file
+/// the policy protects, judged by where the files came from rather than
+/// where they are held.
+pub fn permitted(staged: Vec<Staged>, policy: &Policy) -> (Vec<Staged>, Vec<String>) {
+    let (allowed, forbidden): (Vec<Staged>, Vec<Staged>) =
+        staged.into_iter().partition(|staged| !policy.forbids(&staged.original));
+    let errors = forbidden
+        .iter()
+        .map(|staged| {
+            format!(
+                "{} is protected by your organization's policy and stays staged as number {}",
+                staged.original.display(),
+                staged.id,
+            )
+        })
+        .collect();
+    (allowed, errors)
+}
+
+fn parse(line: &str) -> Option<Staged> {
+    let mut fields = line.split('\t');
+    let staged = Staged {
+        id: fields.next()?.parse().ok()?,
+        due: fields.next()?.parse().ok()?,
+        original: PathBuf::from(unescape(fields.next()?)),
+        held: PathBuf::from(unescape(fields.next()?)),
+    };
+    fields.next().is_none().then_some(staged)
+}
+
+/// What is waiting to be destroyed, with a way to take each file back or
+/// not wait any longer.
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    let intro = match app.settings.staging {
+        Some(minutes) => format!(
+            "Erased files are held here for {} minute(s) before they are destroyed. Until then they can be restored.",
+            minutes,
+        ),
+        None => "Staging is off, so erased files are destroyed right away. It can be turned on in the settings.".to_string(),
+    };
+    let staged = app.staging.as_ref().map_or(&[][..], Area::items);
+    let items = staged.iter().map(|staged| {
+        widget::row![
+            widget::text!("{}  (destroyed after {} UTC)", staged.original.display(), format_date(staged.due))
+                .width(iced::Length::Fill),
+            widget::button("Restore").on_press_maybe((!app.erasing).then_some(Message::RestoreStaged(staged.id))),
+            widget::button("Destroy now").on_press_maybe((!app.erasing).then_some(Message::DestroyStaged(staged.id))),
+        ]
+            .spacing(5)
+            .align_y(iced::Alignment::Center)
+            .into()
+    });
+    widget::column![
+        widget::text(intro),
+        widget::scrollable(widget::column(items).spacing(5)),
+    ]
+        .push_maybe(staged.is_empty().then(|| widget::text("Nothing is waiting to be destroyed.")))
+        .spacing(20)
+        .into()
+}
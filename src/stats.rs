@@ -0,0 +1,78 @@
+use iced::widget;
+use std::time::Duration;
+use crate::history::{Outcome, Record};
+use crate::{units, Message};
+
+const MONTHS: usize = 12;
+const CHART_HEIGHT: f32 = 120.0;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statistics {
+    pub jobs: usize,
+    pub failed_jobs: usize,
+    pub files: usize,
+    pub bytes: u64,
+    pub time: Duration,
+    /// Bytes destroyed per `(year, month)`, oldest first, always `MONTHS` long.
+    pub per_month: Vec<((i64, u32), u64)>,
+}
+
+impl Statistics {
+    pub fn compute(records: &[Record], current: (i64, u32)) -> Self {
+        let mut stats = Self::default();
+        let (mut year, mut month) = current;
+        for _ in 0..MONTHS {
+            stats.per_month.push(((year, month), 0));
+            (year, month) = if month == 1 { (year - 1, 12) } else { (year, month - 1) };
+        }
+        stats.per_month.reverse();
+
+        for record in records {
+            stats.jobs += 1;
+            stats.time += record.duration;
+            if record.outcome != Outcome::Success {
+                stats.failed_jobs += 1;
+            }
+            stats.files += record.files;
+            stats.bytes += record.bytes;
+            if let Some((_, bytes)) = stats.per_month.iter_mut().find(|(key, _)| *key == record.month()) {
+                *bytes += record.bytes;
+            }
+        }
+        stats
+    }
+
+    pub fn view(self) -> iced::Element<'static, Message> {
+        let busiest = self.per_month.iter().map(|(_, bytes)| *bytes).max().unwrap_or(0).max(1);
+        let bars = self.per_month.into_iter().map(|((_, month), bytes)| {
+            let height = (bytes as f32 / busiest as f32 * CHART_HEIGHT).max(1.0);
+            widget::column![
+                widget::vertical_space(),
+                widget::tooltip(
+                    widget::container(widget::Space::new(24, height)).style(bar),
+                    widget::text(units::size(bytes)),
+                    widget::tooltip::Position::Top,
+                ),
+                widget::text!("{:02}", month).size(12),
+            ]
+                .align_x(iced::Alignment::Center)
+                .height(CHART_HEIGHT + 20.0)
+                .into()
+        });
+
+        widget::column![
+            widget::text!("Jobs run: {} ({} not fully successful)", self.jobs, self.failed_jobs),
+            widget::text!("Files erased: {}", self.files),
+            widget::text!("Data destroyed: {}", units::size(self.bytes)),
+            widget::text!("Time spent erasing: {}", units::duration(self.time)),
+            widget::text("Space freed per month (last 12 months)"),
+            widget::row(bars).spacing(8),
+        ]
+            .spacing(10)
+            .into()
+    }
+}
+
+fn bar(theme: &iced::Theme) -> widget::container::Style {
+    widget::container::Style::default().background(theme.extended_palette().primary.base.color)
+}
@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
+
+/// What the engine overwrites: a file on disk in the app, `Simulated` in
+/// simulation mode, an in-memory fake in tests.
+pub trait Storage: Read + Write + Seek {
+    fn len(&self) -> io::Result<u64>;
+    /// Flushes everything written so far to the medium.
+    fn sync(&mut self) -> io::Result<()>;
+}
+
+impl Storage for File {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+}
+
+/// Stands in for a file of `len` bytes without touching it, for demos and
+/// for working on the interface. Writes are dropped once they took as long
+/// as on a drive doing `speed` bytes per second; reads return zeros.
+pub struct Simulated {
+    len: u64,
+    position: u64,
+    speed: u64,
+    started: Instant,
+    moved: u64,
+}
+
+impl Simulated {
+    pub fn new(len: u64, speed: u64) -> Self {
+        Self { len, position: 0, speed: speed.max(1), started: Instant::now(), moved: 0 }
+    }
+
+    /// Sleeps until `bytes` more would have been moved at the set speed.
+    fn take_time(&mut self, bytes: usize) {
+        self.moved += bytes as u64;
+        let due = Duration::from_secs_f64(self.moved as f64 / self.speed as f64);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+impl Read for Simulated {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = (self.len.saturating_sub(self.position)).min(buf.len() as u64) as usize;
+        buf[..count].fill(0);
+        self.take_time(count);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Write for Simulated {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.take_time(buf.len());
+        self.position += buf.len() as u64;
+        self.len = self.len.max(self.position);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Simulated {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before start"))?;
+        Ok(self.position)
+    }
+}
+
+impl Storage for Simulated {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.len)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `length` bytes of `inner` from `offset` on, seen as a whole file, so the
+/// passes and their read-back reach that range and nothing around it.
+pub struct Window<'a, S: Storage> {
+    inner: &'a mut S,
+    offset: u64,
+    length: u64,
+    position: u64,
+}
+
+impl<'a, S: Storage> Window<'a, S> {
+    pub fn new(inner: &'a mut S, offset: u64, length: u64) -> Self {
+        Self { inner, offset, length, position: 0 }
+    }
+
+    /// How much of `wanted` fits before the end of the window, with the
+    /// inner storage moved to the current position.
+    fn prepare(&mut self, wanted: usize) -> io::Result<usize> {
+        let count = self.length.saturating_sub(self.position).min(wanted as u64) as usize;
+        if count > 0 {
+            self.inner.seek(SeekFrom::Start(self.offset + self.position))?;
+        }
+        Ok(count)
+    }
+}
+
+impl<S: Storage> Read for Window<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.prepare(buf.len())?;
+        let read = match count {
+            0 => 0,
+            count => self.inner.read(&mut buf[..count])?,
+        };
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<S: Storage> Write for Window<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Nothing past the end is written, `write_all` reports it.
+        let count = self.prepare(buf.len())?;
+        let written = match count {
+            0 => 0,
+            count => self.inner.write(&buf[..count])?,
+        };
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: Storage> Seek for Window<'_, S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.length.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before start"))?;
+        Ok(self.position)
+    }
+}
+
+impl<S: Storage> Storage for Window<'_, S> {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.length)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.inner.sync()
+    }
+}
@@ -0,0 +1,375 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::diagnostics::{self, Capability};
+use crate::engine;
+
+/// Names NTFS goes by: what Windows reports and the two Linux drivers.
+const NTFS: [&str; 3] = ["NTFS", "ntfs", "ntfs3"];
+/// File systems that write changed data to new blocks instead of in place.
+const COPY_ON_WRITE: [&str; 5] = ["btrfs", "zfs", "bcachefs", "apfs", "refs"];
+const NETWORK: [&str; 13] = [
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "afs", "ceph", "glusterfs", "davfs", "webdav", "fuse.sshfs", "fuse.rclone",
+];
+/// Folder names sync clients put their files under, and the service.
+const CLOUD: [(&str, &str); 8] = [
+    ("Dropbox", "Dropbox"),
+    ("OneDrive", "OneDrive"),
+    ("Google Drive", "Google Drive"),
+    ("My Drive", "Google Drive"),
+    ("Mobile Documents", "iCloud"),
+    ("iCloud Drive", "iCloud"),
+    ("Nextcloud", "Nextcloud"),
+    ("MEGA", "MEGA"),
+];
+
+/// What a file is stored on, as far as erasing it goes. Anything that could
+/// not be found out is left at its default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Facts {
+    pub filesystem: Option<String>,
+    pub mount_point: Option<PathBuf>,
+    /// `None` when the drive under the file system is not known, as for
+    /// network shares or device mapper stacks.
+    pub rotational: Option<bool>,
+    pub copy_on_write: bool,
+    pub network: bool,
+    pub memory: Option<&'static str>,
+    pub mtp: Option<&'static str>,
+    pub compressed: bool,
+    /// Encrypted by the file system (fscrypt, eCryptfs).
+    pub encrypted: bool,
+    /// Allowed to ask the file system to discard its free blocks.
+    pub can_trim: bool,
+    /// The sync service whose folder the file is in.
+    pub cloud: Option<&'static str>,
+}
+
+impl Facts {
+    #[cfg(target_os = "linux")]
+    pub fn inspect(path: &Path) -> Self {
+        const FS_COMPR_FL: libc::c_int = 0x0000_0004;
+        const FS_ENCRYPT_FL: libc::c_int = 0x0000_0800;
+        const FS_NOCOW_FL: libc::c_int = 0x0080_0000;
+
+        let mut facts = Self {
+            memory: engine::memory_backed(path),
+            mtp: engine::mtp_mount(path),
+            cloud: cloud(path),
+            ..Self::default()
+        };
+        if let Some(mount) = engine::mount(path) {
+            let fstype = mount.fstype.as_str();
+            facts.copy_on_write = COPY_ON_WRITE.contains(&fstype);
+            facts.network = NETWORK.contains(&fstype);
+            facts.encrypted = fstype == "ecryptfs";
+            facts.compressed = mount.options.split(',').any(|option| option.starts_with("compress"));
+            if mount.source.starts_with("/dev/") {
+                facts.rotational = rotational(&mount.source);
+            }
+            facts.filesystem = Some(mount.fstype);
+            facts.mount_point = Some(mount.point);
+        }
+        facts.can_trim = facts.rotational == Some(false) && diagnostics::available(Capability::Trim);
+
+        if let Ok(file) = std::fs::File::open(path) {
+            use std::os::fd::AsRawFd;
+
+            let mut flags: libc::c_int = 0;
+            // SAFETY: the descriptor is open for the duration of the call and
+            // FS_IOC_GETFLAGS writes one int to `flags`.
+            if unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) } == 0 {
+                facts.compressed |= flags & FS_COMPR_FL != 0;
+                facts.encrypted |= flags & FS_ENCRYPT_FL != 0;
+                // btrfs files marked nodatacow are rewritten in place.
+                facts.copy_on_write &= flags & FS_NOCOW_FL == 0;
+            }
+        }
+        facts
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn inspect(path: &Path) -> Self {
+        let (compressed, encrypted) = attributes(path);
+        Self {
+            memory: engine::memory_backed(path),
+            mtp: engine::mtp_mount(path),
+            cloud: cloud(path),
+            compressed,
+            encrypted,
+            // Only NTFS compresses or encrypts single files on Windows.
+            filesystem: (compressed || encrypted).then(|| "NTFS".to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn ntfs(&self) -> bool {
+        self.filesystem.as_deref().is_some_and(|filesystem| NTFS.contains(&filesystem))
+    }
+}
+
+/// Whether `path` is NTFS-compressed and whether it is EFS-encrypted.
+#[cfg(windows)]
+fn attributes(path: &Path) -> (bool, bool) {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x0800;
+    const FILE_ATTRIBUTE_ENCRYPTED: u32 = 0x4000;
+    match std::fs::metadata(path) {
+        Ok(meta) => (
+            meta.file_attributes() & FILE_ATTRIBUTE_COMPRESSED != 0,
+            meta.file_attributes() & FILE_ATTRIBUTE_ENCRYPTED != 0,
+        ),
+        Err(_) => (false, false),
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn attributes(_path: &Path) -> (bool, bool) {
+    (false, false)
+}
+
+/// The sync service `path` is synced to, going by the folders above it.
+/// `OneDrive - Contoso` counts as OneDrive.
+fn cloud(path: &Path) -> Option<&'static str> {
+    path.ancestors()
+        .filter_map(|dir| dir.file_name()?.to_str())
+        .find_map(|name| CLOUD.iter().find(|(folder, _)| name == *folder || name.starts_with(&format!("{} - ", folder))))
+        .map(|(_, service)| *service)
+}
+
+/// Whether the block device `source` (a partition, a whole disk or a device
+/// mapper node) sits on a spinning disk.
+#[cfg(target_os = "linux")]
+fn rotational(source: &str) -> Option<bool> {
+    let device = std::fs::canonicalize(source).ok()?;
+    let mut sys = std::fs::canonicalize(Path::new("/sys/class/block").join(device.file_name()?)).ok()?;
+    // Partitions have no queue of their own; their disk is the parent folder.
+    if !sys.join("queue").exists() {
+        sys.pop();
+    }
+    let value = std::fs::read_to_string(sys.join("queue").join("rotational")).ok()?;
+    Some(value.trim() == "1")
+}
+
+/// One thing done to a file, in the order they are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// NTFS compression switched off for the file, so the passes are
+    /// written over its clusters instead of being compressed into new ones.
+    Decompress,
+    /// The method's passes, written over the file in place.
+    Overwrite,
+    /// Random data written as a new copy of the file, for storage that
+    /// cannot write in place. The file is gone afterwards.
+    Replace,
+    /// The file's blocks handed back before the unlink (a hole punched over
+    /// them, then truncation), so a discard can reach them right away.
+    Release,
+    /// The name replaced with random characters before the unlink, so the
+    /// directory does not keep it.
+    Scramble,
+    Unlink,
+    /// The file system asked to discard all its free blocks once the job is
+    /// done, so the SSD erases them instead of keeping them around.
+    Trim,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Step::Decompress => "switch off compression",
+            Step::Overwrite => "overwrite",
+            Step::Replace => "replace with random data",
+            Step::Release => "release blocks",
+            Step::Scramble => "scramble name",
+            Step::Unlink => "unlink",
+            Step::Trim => "TRIM",
+        })
+    }
+}
+
+/// How a file is erased, composed from what it is stored on, with the
+/// reasons it may not be the whole story.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Strategy {
+    pub facts: Facts,
+    pub steps: Vec<Step>,
+    pub remarks: Vec<&'static str>,
+}
+
+impl Strategy {
+    pub fn choose(facts: Facts) -> Self {
+        let mut remarks = Vec::new();
+        let steps = if facts.memory.is_some() {
+            remarks.push("memory-backed, the data never reached a disk");
+            vec![Step::Unlink]
+        } else if facts.mtp.is_some() {
+            remarks.push("MTP cannot write in place, the device may keep the old copy in its flash");
+            vec![Step::Replace]
+        } else if facts.network {
+            remarks.push("network share, the server may keep snapshots, backups or a recycle bin");
+            vec![Step::Overwrite, Step::Scramble, Step::Unlink]
+        } else {
+            let mut steps = vec![Step::Overwrite];
+            if facts.copy_on_write {
+                remarks.push("copy-on-write, the passes land on new blocks; wipe free space afterwards");
+            }
+            if facts.compressed && facts.ntfs() {
+                remarks.push("NTFS-compressed, decompressed first; clusters the compressed data used may remain until free space is wiped");
+                steps.insert(0, Step::Decompress);
+            } else if facts.compressed {
+                remarks.push("compressed, random passes take more room than the original and move to new extents");
+            }
+            if facts.encrypted && facts.ntfs() {
+                // Decrypting first would write the plaintext out again.
+                remarks.push("EFS-encrypted, old blocks only hold ciphertext, but encrypting it in place may have left a plaintext copy in free space");
+            } else if facts.encrypted {
+                remarks.push("encrypted by the file system, old blocks only hold ciphertext");
+            }
+            if facts.rotational == Some(false) {
+                remarks.push("solid-state, wear leveling may keep copies the passes cannot reach");
+                steps.push(Step::Release);
+            } else if facts.copy_on_write {
+                steps.push(Step::Release);
+            }
+            steps.extend([Step::Scramble, Step::Unlink]);
+            if facts.can_trim {
+                steps.push(Step::Trim);
+            } else if facts.rotational == Some(false) {
+                remarks.push("TRIM needs root, the blocks are discarded at the next scheduled fstrim");
+            }
+            steps
+        };
+        if facts.cloud.is_some() {
+            remarks.push("in a synced folder, the service keeps its own copy and version history");
+        }
+        Self { facts, steps, remarks }
+    }
+
+    /// What keeps this erase from being trusted fully, worst first.
+    pub fn limits(&self) -> Vec<(Confidence, &'static str)> {
+        let facts = &self.facts;
+        if facts.memory.is_some() {
+            return Vec::new();
+        }
+        let mut limits = Vec::new();
+        if facts.mtp.is_some() {
+            limits.push((Confidence::BestEffort, "MTP device"));
+        }
+        if facts.network {
+            limits.push((Confidence::BestEffort, "network share"));
+        }
+        if let Some(cloud) = facts.cloud {
+            limits.push((Confidence::BestEffort, cloud));
+        }
+        if facts.rotational == Some(false) {
+            limits.push((Confidence::BestEffort, "SSD wear leveling"));
+        }
+        if facts.copy_on_write {
+            limits.push((Confidence::BestEffort, "copy-on-write"));
+        }
+        if facts.compressed && facts.ntfs() && !self.steps.contains(&Step::Decompress) {
+            limits.push((Confidence::BestEffort, "NTFS compression"));
+        } else if facts.compressed {
+            limits.push((Confidence::Reduced, "compression"));
+        }
+        if facts.encrypted && facts.ntfs() {
+            limits.push((Confidence::Reduced, "EFS"));
+        }
+        if facts.rotational.is_none() && !facts.network && facts.mtp.is_none() {
+            limits.push((Confidence::Reduced, "unknown drive type"));
+        }
+        limits.sort();
+        limits
+    }
+
+    /// The fallback for when compression could not be switched off: the
+    /// passes still run, but NTFS compresses them into new clusters.
+    pub fn without_decompression(mut self) -> Self {
+        self.steps.retain(|step| *step != Step::Decompress);
+        self.remarks.push("compression could not be switched off, the passes go to new clusters; wipe free space afterwards");
+        self
+    }
+
+    /// Whether the file system should be trimmed after the job.
+    pub fn trim(&self) -> Option<&Path> {
+        self.facts.mount_point.as_deref().filter(|_| self.steps.contains(&Step::Trim))
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut storage = vec![self.facts.filesystem.as_deref().or(self.facts.memory).unwrap_or("unknown file system")];
+        match self.facts.rotational {
+            Some(true) => storage.push("hard disk"),
+            Some(false) => storage.push("SSD"),
+            None => {}
+        }
+        let steps: Vec<String> = self.steps.iter().map(Step::to_string).collect();
+        write!(f, "{}: {}", storage.join(", "), steps.join(", "))?;
+        for remark in &self.remarks {
+            write!(f, "; {}", remark)?;
+        }
+        Ok(())
+    }
+}
+
+/// How far the result of a job can be trusted, worst first so the lowest
+/// file decides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    BestEffort,
+    Reduced,
+    High,
+}
+
+impl Confidence {
+    pub fn key(self) -> &'static str {
+        match self {
+            Confidence::BestEffort => "best_effort",
+            Confidence::Reduced => "reduced",
+            Confidence::High => "high",
+        }
+    }
+}
+
+/// An honest verdict on a whole job, so a full progress bar is not read as
+/// "gone for good" when the storage cannot promise that.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Score {
+    pub confidence: Confidence,
+    pub reasons: Vec<&'static str>,
+}
+
+impl Score {
+    pub fn of<'a>(strategies: impl IntoIterator<Item = &'a Strategy>) -> Self {
+        let mut score = Self { confidence: Confidence::High, reasons: Vec::new() };
+        for (confidence, reason) in strategies.into_iter().flat_map(Strategy::limits) {
+            score.lower(confidence, reason);
+        }
+        score
+    }
+
+    /// Records something found outside the files themselves, such as
+    /// snapshots that were left in place.
+    pub fn lower(&mut self, confidence: Confidence, reason: &'static str) {
+        self.confidence = self.confidence.min(confidence);
+        if !self.reasons.contains(&reason) {
+            self.reasons.push(reason);
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.confidence {
+            Confidence::High => "High confidence",
+            Confidence::Reduced => "Reduced confidence",
+            Confidence::BestEffort => "Best effort",
+        })?;
+        if !self.reasons.is_empty() {
+            write!(f, " - {}", self.reasons.join(", "))?;
+        }
+        Ok(())
+    }
+}
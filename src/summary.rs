@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use iced::widget;
+use crate::{certificate, desktop, history::Outcome, units, Message, Report};
+
+/// How the files of a finished queue went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub succeeded: usize,
+    /// Never reached, because the job was cancelled or stopped before them.
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// What became of one file of a finished job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fate {
+    Destroyed,
+    /// Processed, but not to the end.
+    Failed(String),
+    /// Never reached.
+    Skipped(String),
+}
+
+impl Fate {
+    /// The error the history records for the file, if it was not destroyed.
+    pub fn error(&self) -> Option<String> {
+        match self {
+            Fate::Destroyed => None,
+            Fate::Failed(error) | Fate::Skipped(error) => Some(error.clone()),
+        }
+    }
+}
+
+/// What became of each of `files` once a job ended with `outcome`. The
+/// engine works through the queue in order, so every file up to the one it
+/// had `reached` was processed and the rest were not; `failures` are the
+/// errors it reported along the way.
+pub fn fates(files: &[PathBuf], failures: &[(PathBuf, String)], reached: Option<usize>, outcome: Outcome) -> Vec<Fate> {
+    files
+        .iter()
+        .enumerate()
+        .map(|(index, path)| match failures.iter().find(|(failed, _)| failed == path) {
+            Some((_, error)) => Fate::Failed(error.clone()),
+            // Drive wipes and jobs cancelled while scanning never name a file.
+            None if outcome == Outcome::Cancelled && reached.is_none() => Fate::Skipped("Cancelled".to_string()),
+            None if outcome == Outcome::Cancelled && reached == Some(index) => {
+                Fate::Failed("Cancelled while it was being erased".to_string())
+            }
+            None if reached.is_some_and(|reached| index <= reached) => Fate::Destroyed,
+            None if outcome == Outcome::Cancelled => Fate::Skipped("Not attempted: the job was cancelled".to_string()),
+            None if outcome == Outcome::Unsanitized => {
+                Fate::Skipped("Not attempted: the job stopped after a verification failure".to_string())
+            }
+            None => Fate::Destroyed,
+        })
+        .collect()
+}
+
+/// The files of a finished job sorted the way the summary lists them.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Batch {
+    pub erased: Vec<PathBuf>,
+    pub skipped: Vec<(PathBuf, String)>,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl Batch {
+    pub fn sort(files: &[PathBuf], fates: &[Fate]) -> Self {
+        let mut batch = Batch::default();
+        for (path, fate) in files.iter().zip(fates) {
+            match fate {
+                Fate::Destroyed => batch.erased.push(path.clone()),
+                Fate::Failed(error) => batch.failed.push((path.clone(), error.clone())),
+                Fate::Skipped(reason) => batch.skipped.push((path.clone(), reason.clone())),
+            }
+        }
+        batch
+    }
+}
+
+impl std::fmt::Display for Tally {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} succeeded, {} skipped, {} failed", self.succeeded, self.skipped, self.failed)
+    }
+}
+
+/// Bytes destroyed per second over the whole job, passes and read-back
+/// included. Jobs that destroyed nothing or took no measurable time have
+/// no speed worth showing.
+pub fn speed(bytes: u64, duration: Duration) -> Option<f64> {
+    (bytes > 0 && duration >= Duration::from_millis(100)).then(|| bytes as f64 / duration.as_secs_f64())
+}
+
+/// The whole batch at a glance once it is done, with what can be done
+/// about the files that did not make it.
+pub fn view(report: &Report, erasing: bool) -> iced::Element<'_, Message> {
+    let tally = report.tally();
+    let retry = tally.failed + tally.skipped;
+    let mut totals = format!("Destroyed {} in {}", units::size(report.bytes), units::duration(report.duration));
+    if let Some(speed) = speed(report.bytes, report.duration) {
+        totals += &format!(", {} on average", units::speed(speed));
+    }
+    let problems = report
+        .failures
+        .iter()
+        .map(|(path, error)| format!(" Failed: {}: {}", path.display(), error))
+        .chain(report.skipped.iter().map(|(path, reason)| format!(" Skipped: {}: {}", path.display(), reason)))
+        .map(|line| widget::text(line).size(14).into());
+
+    let buttons = widget::row![]
+        .push_maybe(report.queue.then(|| {
+            widget::button(widget::text!("Retry {} file(s)", retry))
+                .on_press_maybe((retry > 0 && !erasing).then_some(Message::RetryFailures))
+        }))
+        .extend([certificate::Format::Pdf, certificate::Format::Json].into_iter().filter_map(|format| {
+            let id = report.record?;
+            let label = format!("Export certificate ({})", format.extension().to_uppercase());
+            Some(widget::button(widget::text(label)).on_press(Message::ExportCertificate(id, format)).into())
+        }))
+        .spacing(10);
+
+    widget::column![
+        widget::text(tally.to_string()).size(18),
+        widget::text(totals),
+        buttons,
+    ]
+        .push_maybe(desktop::actions(report))
+        .push(widget::scrollable(widget::column(problems)))
+        .spacing(10)
+        .into()
+}
@@ -0,0 +1,310 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use iced::widget;
+use crate::engine::{CancelToken, Progress};
+use crate::progress::Sender;
+use crate::{units, App, Message};
+
+/// Where the operating system pages memory out to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Swap {
+    pub path: PathBuf,
+    pub partition: bool,
+    /// `None` when the size could not be read, as with a locked pagefile.
+    pub bytes: Option<u64>,
+    pub used: Option<u64>,
+    /// Encrypted with a key that does not survive a reboot, or kept in RAM
+    /// (zram); either way nothing readable is left on a disk.
+    pub protected: Option<&'static str>,
+}
+
+/// A file or device the whole of memory is written to when the machine
+/// hibernates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hibernation {
+    pub path: PathBuf,
+    pub bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub swaps: Vec<Swap>,
+    pub hibernation: Vec<Hibernation>,
+}
+
+impl Report {
+    pub fn at_risk(&self) -> bool {
+        self.swaps.iter().any(|swap| swap.protected.is_none()) || !self.hibernation.is_empty()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn detect() -> Report {
+    let mut report = Report::default();
+    // Filename  Type  Size  Used  Priority, sizes in KiB, spaces in names as \040.
+    for line in fs::read_to_string("/proc/swaps").unwrap_or_default().lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [path, kind, size, used, ..] = fields[..] else {
+            continue;
+        };
+        let path = PathBuf::from(path.replace("\\040", " "));
+        let kib = |value: &str| value.parse::<u64>().ok().map(|kib| kib * 1024);
+        report.swaps.push(Swap {
+            protected: protection(&path),
+            partition: kind == "partition",
+            bytes: kib(size),
+            used: kib(used),
+            path,
+        });
+    }
+    // MAJOR:MINOR of the device hibernation writes to, "0:0" when there is none.
+    let resume = fs::read_to_string("/sys/power/resume").unwrap_or_default();
+    let resume = resume.trim();
+    if !resume.is_empty()
+        && resume != "0:0"
+        && let Ok(device) = fs::read_link(format!("/sys/dev/block/{}", resume))
+        && let Some(name) = device.file_name()
+    {
+        let path = Path::new("/dev").join(name);
+        // Hibernating into swap that is already listed only adds to its risk.
+        if report.swaps.iter().all(|swap| swap.path != path) {
+            report.hibernation.push(Hibernation { bytes: None, path });
+        }
+    }
+    report
+}
+
+/// Why pages written to `device` are not readable later, if they are not.
+#[cfg(target_os = "linux")]
+fn protection(device: &Path) -> Option<&'static str> {
+    let resolved = fs::canonicalize(device).ok()?;
+    let name = resolved.file_name()?.to_string_lossy().to_string();
+    if name.starts_with("zram") {
+        return Some("compressed in RAM (zram), never written to disk");
+    }
+    let uuid = fs::read_to_string(format!("/sys/block/{}/dm/uuid", name)).ok()?;
+    uuid.starts_with("CRYPT-").then_some("encrypted with dm-crypt")
+}
+
+#[cfg(windows)]
+pub fn detect() -> Report {
+    let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+    let root = Path::new(&drive).join("\\");
+    // The files are locked while Windows runs, but their directory entries can be read.
+    let existing = |name: &str| {
+        let path = root.join(name);
+        match fs::metadata(&path) {
+            Ok(meta) => Some((path, Some(meta.len()))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(_) => Some((path, None)),
+        }
+    };
+    Report {
+        swaps: ["pagefile.sys", "swapfile.sys"]
+            .into_iter()
+            .filter_map(existing)
+            .map(|(path, bytes)| Swap { path, partition: false, bytes, used: None, protected: None })
+            .collect(),
+        hibernation: existing("hiberfil.sys").map(|(path, bytes)| Hibernation { path, bytes }).into_iter().collect(),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn detect() -> Report {
+    let mut report = Report::default();
+    for entry in fs::read_dir(Path::new("/private/var/vm")).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let bytes = entry.metadata().ok().map(|meta| meta.len());
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("swapfile") {
+            // macOS has encrypted swap unconditionally since 10.7.
+            report.swaps.push(Swap { path, partition: false, bytes, used: None, protected: Some("encrypted by macOS") });
+        } else if name == "sleepimage" {
+            report.hibernation.push(Hibernation { path, bytes });
+        }
+    }
+    report
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+pub fn detect() -> Report {
+    Report::default()
+}
+
+/// What to do about it on this platform.
+fn advice() -> &'static str {
+    if cfg!(windows) {
+        "Windows can clear the pagefile at every shutdown: set \"Shutdown: Clear virtual memory pagefile\" in \
+         the local security policy. `powercfg /hibernate off` turns hibernation off and deletes hiberfil.sys. \
+         BitLocker on the system drive protects both."
+    } else if cfg!(target_os = "macos") {
+        "macOS encrypts swap. The sleep image is protected by FileVault; without FileVault, \
+         `sudo pmset -a hibernatemode 0` and deleting /private/var/vm/sleepimage remove it."
+    } else {
+        "Swap that is not encrypted can be wiped below: it is turned off, overwritten with random data and set \
+         up again with the same UUID, which needs administrator rights and enough free memory to hold what is \
+         swapped out. Encrypted swap (for example cryptsetup with a random key in /etc/crypttab) avoids the \
+         problem for good."
+    }
+}
+
+/// The swap header keeps the UUID at offset 1036 and the label at 1052, both
+/// 16 bytes; see `include/linux/swap.h`.
+#[cfg(target_os = "linux")]
+fn header(path: &Path) -> io::Result<(String, String)> {
+    use std::io::Read;
+
+    let mut header = [0u8; 1068];
+    fs::File::open(path)?.read_exact(&mut header)?;
+    let uuid = &header[1036..1052];
+    let uuid = format!(
+        "{}-{}-{}-{}-{}",
+        crate::hash::hex(&uuid[..4]),
+        crate::hash::hex(&uuid[4..6]),
+        crate::hash::hex(&uuid[6..8]),
+        crate::hash::hex(&uuid[8..10]),
+        crate::hash::hex(&uuid[10..]),
+    );
+    let label = &header[1052..1068];
+    let label = String::from_utf8_lossy(&label[..label.iter().position(|byte| *byte == 0).unwrap_or(16)]).to_string();
+    Ok((uuid, label))
+}
+
+#[cfg(target_os = "linux")]
+fn run(program: &str, args: &[&str]) -> io::Result<()> {
+    let output = std::process::Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        )));
+    }
+    Ok(())
+}
+
+/// Turns `swap` off, overwrites it once with random data and sets it up
+/// again with the same UUID and label, so `/etc/fstab` and a hibernation
+/// resume setting keep working. A swap file is overwritten in place, its
+/// blocks do not move.
+#[cfg(target_os = "linux")]
+pub fn wipe(swap: &Swap, cancel: &CancelToken, tx: &Sender) {
+    let _ = tx.send(Progress::Started(0));
+    let result = overwrite_swap(&swap.path, cancel, tx);
+    if cancel.is_cancelled() {
+        let _ = tx.send(Progress::Cancelled);
+        return;
+    }
+    if let Err(e) = &result {
+        eprintln!("Error wiping swap {}: {}", swap.path.display(), e);
+        let _ = tx.send(Progress::Failed(0, e.to_string()));
+    }
+    let _ = tx.send(Progress::Finished(result.is_ok()));
+}
+
+#[cfg(target_os = "linux")]
+fn overwrite_swap(path: &Path, cancel: &CancelToken, tx: &Sender) -> io::Result<()> {
+    // SAFETY: geteuid has no preconditions.
+    if unsafe { libc::geteuid() } != 0 {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Wiping swap needs administrator rights"));
+    }
+    let (uuid, label) = header(path)?;
+    let device = path.to_string_lossy().to_string();
+    run("swapoff", &[&device])?;
+
+    let written = fill(path, cancel, tx);
+    // Put swap back whatever happened, a machine without it may run out of memory.
+    let mut mkswap = vec!["-U", &uuid];
+    if !label.is_empty() {
+        mkswap.extend(["-L", &label]);
+    }
+    mkswap.push(&device);
+    let restored = run("mkswap", &mkswap).and_then(|()| run("swapon", &[&device]));
+    written.and(restored)
+}
+
+#[cfg(target_os = "linux")]
+fn fill(path: &Path, cancel: &CancelToken, tx: &Sender) -> io::Result<()> {
+    use std::io::{Seek, SeekFrom, Write};
+    use rand::Rng;
+
+    let mut file = fs::File::options().write(true).open(path)?;
+    let size = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    let mut rng = rand::thread_rng();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut written: u64 = 0;
+    while written < size {
+        cancel.check()?;
+        let len = (size - written).min(buffer.len() as u64) as usize;
+        rng.fill(&mut buffer[..len]);
+        file.write_all(&buffer[..len])?;
+        written += len as u64;
+        let _ = tx.send(Progress::Updated(written as f32 / size as f32 * 100.0));
+    }
+    file.sync_all()?;
+    tx.send(Progress::Updated(100.0)).map_err(|_| io::Error::other("Channel error"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn wipe(_swap: &Swap, _cancel: &CancelToken, tx: &Sender) {
+    let _ = tx.send(Progress::Started(0));
+    let _ = tx.send(Progress::Failed(0, "Swap can only be wiped from here on Linux".to_string()));
+    let _ = tx.send(Progress::Finished(false));
+}
+
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    let size = |bytes: Option<u64>| bytes.map_or("size unknown".to_string(), units::size);
+    let verdict = if app.swap.at_risk() {
+        widget::text(
+            "Memory is paged out to disk here. Contents of files you opened, including ones erased since, \
+             can survive in swap or a hibernation image long after the file is gone.",
+        )
+            .color(iced::Color::from_rgb(0.8, 0.4, 0.0))
+    } else if app.swap.swaps.is_empty() {
+        widget::text("No swap or hibernation file was found.")
+    } else {
+        widget::text("Swap is encrypted or kept in memory, nothing readable is left on a disk.")
+    };
+    let swaps = app.swap.swaps.iter().enumerate().map(|(index, swap)| {
+        let status = match (swap.protected, swap.used) {
+            (Some(protection), _) => protection.to_string(),
+            (None, Some(used)) => format!("NOT encrypted, {} in use", units::size(used)),
+            (None, None) => "NOT encrypted".to_string(),
+        };
+        widget::row![
+            widget::text!(
+                "Swap {} {} ({}): {}",
+                if swap.partition { "partition" } else { "file" },
+                swap.path.display(),
+                size(swap.bytes),
+                status,
+            )
+                .width(iced::Length::Fill),
+        ]
+            .push_maybe((cfg!(target_os = "linux") && swap.protected.is_none()).then(|| {
+                widget::button("Wipe").on_press_maybe((!app.erasing).then_some(Message::WipeSwap(index)))
+            }))
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+    });
+    let hibernation = app.swap.hibernation.iter().map(|image| {
+        widget::text!("Hibernation image {} ({})", image.path.display(), size(image.bytes)).into()
+    });
+
+    widget::column![
+        widget::row![
+            widget::text("Swap and hibernation").size(18).width(iced::Length::Fill),
+            widget::button("Check again").on_press(Message::CheckSwap),
+        ]
+            .align_y(iced::Alignment::Center),
+        verdict,
+        widget::column(swaps.chain(hibernation)).spacing(5),
+    ]
+        .push_maybe(app.swap.at_risk().then(|| widget::text(advice()).size(14)))
+        .push_maybe(app.erasing.then(|| app.progress_row(widget::button("Cancel (Esc)").on_press(Message::Cancel))))
+        .spacing(10)
+        .into()
+}
@@ -0,0 +1,41 @@
+use ed25519_dalek::{Signer, SigningKey};
+use crate::agent;
+use crate::hash::hex;
+use crate::http::Url;
+
+const JOB: &str = "id 7\npath /srv/old/customers.csv\n";
+
+fn signed(key: &SigningKey, name: &str, body: &str) -> String {
+    format!("ed25519={}", hex(&key.sign(format!("{}\n{}", name, body).as_bytes()).to_bytes()))
+}
+
+#[test]
+fn only_jobs_signed_for_this_agent_run() {
+    let server = SigningKey::from_bytes(&[7; 32]);
+    let pinned = agent::parse_key(&hex(server.verifying_key().as_bytes())).unwrap();
+    let signature = signed(&server, "host-a", JOB);
+    assert!(agent::verify(&pinned, "host-a", JOB, Some(&signature)).is_ok());
+
+    assert!(agent::verify(&pinned, "host-a", JOB, None).is_err());
+    // The same job sent to another machine, or changed on the way.
+    assert!(agent::verify(&pinned, "host-b", JOB, Some(&signature)).is_err());
+    assert!(agent::verify(&pinned, "host-a", "id 7\npath /\n", Some(&signature)).is_err());
+    // Signed by someone else.
+    let other = signed(&SigningKey::from_bytes(&[8; 32]), "host-a", JOB);
+    assert!(agent::verify(&pinned, "host-a", JOB, Some(&other)).is_err());
+}
+
+#[test]
+fn server_keys_are_64_hex_digits() {
+    assert!(agent::parse_key("abcd").is_err());
+    assert!(agent::parse_key(&"zz".repeat(32)).is_err());
+}
+
+#[test]
+fn addresses_say_whether_they_are_encrypted() {
+    let url = Url::parse("https://fleet.example/api").unwrap();
+    assert!(url.secure());
+    assert_eq!(url.join("agents").to_string(), "https://fleet.example:443/api/agents");
+    assert!(!Url::parse("http://fleet.example:8080").unwrap().secure());
+    assert!(Url::parse("ftp://fleet.example").is_err());
+}
@@ -0,0 +1,12 @@
+use crate::announce;
+
+#[test]
+fn announced_text_never_reaches_the_command_line() {
+    let text = "C:\\Users\\me\\$(Remove-Item -Recurse ~); rm -rf ~ --help.txt is protected by your organization's policy";
+    let (command, input) = announce::speech(text);
+    assert_eq!(input, text);
+    for arg in command.get_args() {
+        let arg = arg.to_string_lossy();
+        assert!(!arg.contains("Remove-Item") && !arg.contains("rm -rf"), "{} is on the command line", arg);
+    }
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+use crate::chunks::{self, Chunks};
+
+#[test]
+fn fast_chunks_grow_by_a_step_up_to_the_largest() {
+    let mut chunks = Chunks::default();
+    assert_eq!(chunks.size(), chunks::START);
+    chunks.record(chunks.size(), Duration::from_millis(5));
+    assert_eq!(chunks.size(), chunks::START + chunks::STEP);
+
+    // A short last chunk says nothing about the drive.
+    chunks.record(4096, Duration::from_millis(1));
+    assert_eq!(chunks.size(), chunks::START + chunks::STEP);
+
+    for _ in 0..200 {
+        chunks.record(chunks.size(), Duration::ZERO);
+    }
+    assert_eq!(chunks.size(), chunks::MAX);
+}
+
+#[test]
+fn slow_chunks_halve_down_to_the_smallest() {
+    let mut chunks = Chunks::default();
+    chunks.record(chunks.size(), chunks::TARGET * 2);
+    assert_eq!(chunks.size(), chunks::START / 2);
+
+    for _ in 0..20 {
+        chunks.record(chunks.size(), Duration::from_secs(1));
+        assert_eq!(chunks.size() % chunks::BLOCK, 0);
+    }
+    assert_eq!(chunks.size(), chunks::MIN);
+}
@@ -0,0 +1,25 @@
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use crate::cli;
+
+#[test]
+fn a_peer_streaming_plain_bytes_is_turned_away() {
+    let (mut peer, ours) = UnixStream::pair().unwrap();
+    let sender = std::thread::spawn(move || {
+        // Far more than the reader accepts, a byte at a time would have
+        // been a stack frame each.
+        let _ = peer.write_all(&vec![b'x'; 1024 * 1024]);
+    });
+    let error = cli::receive(&ours).expect_err("bytes without descriptors accepted");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    drop(ours);
+    sender.join().unwrap();
+}
+
+#[test]
+fn a_few_stray_bytes_before_hanging_up_end_the_stream() {
+    let (mut peer, ours) = UnixStream::pair().unwrap();
+    peer.write_all(b"hello").unwrap();
+    drop(peer);
+    assert!(cli::receive(&ours).unwrap().is_empty());
+}
@@ -0,0 +1,43 @@
+use std::fs;
+use crate::container::{self, Kind};
+use super::Scratch;
+
+#[test]
+fn containers_are_told_by_their_first_bytes() {
+    let scratch = Scratch::new("container-kinds");
+    let database = scratch.file("notes", b"SQLite format 3\0rest of the header");
+    let document = scratch.file("Budget.XLSX", b"PK\x03\x04zip entries");
+    let archive = scratch.file("photos.zip", b"PK\x03\x04zip entries");
+    let legacy = scratch.file("letter.doc", &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1, 0]);
+    let text = scratch.file("readme.docx", b"not really a document");
+
+    assert_eq!(container::detect(&database), Some(Kind::Sqlite));
+    assert_eq!(container::detect(&document), Some(Kind::Office));
+    assert_eq!(container::detect(&archive), Some(Kind::Zip));
+    assert_eq!(container::detect(&legacy), Some(Kind::Office));
+    assert_eq!(container::detect(&text), None);
+}
+
+#[test]
+fn siblings_that_exist_and_are_not_queued_are_offered() {
+    let scratch = Scratch::new("container-siblings");
+    let database = scratch.file("history.db", b"SQLite format 3\0");
+    let wal = scratch.file("history.db-wal", b"recent pages");
+    let shm = scratch.file("history.db-shm", b"index");
+    // Word replaces the first two characters of longer names.
+    let report = scratch.file("Quarterly.docx", b"PK\x03\x04");
+    let owner = scratch.file("~$arterly.docx", b"owner");
+    let short = scratch.file("memo.docx", b"PK\x03\x04");
+    let lock = scratch.file(".~lock.memo.docx#", b"lock");
+    let archive = scratch.file("dump.zip", b"PK\x03\x04");
+    fs::create_dir(scratch.path().join("dump")).unwrap();
+
+    let hints = container::hints(&[database.clone(), wal.clone(), report.clone(), short.clone(), archive.clone()]);
+    let siblings = |file: &std::path::Path| hints.iter().find(|hint| hint.file == file).map(|hint| hint.siblings.clone());
+    // The log is queued already, only the shared memory file is missing.
+    assert_eq!(siblings(&database), Some(vec![shm]));
+    assert_eq!(siblings(&report), Some(vec![owner]));
+    assert_eq!(siblings(&short), Some(vec![lock]));
+    assert_eq!(siblings(&archive), Some(vec![scratch.path().join("dump")]));
+    assert_eq!(hints.len(), 4);
+}
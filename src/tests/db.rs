@@ -0,0 +1,62 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::db::Database;
+use crate::history::{FileEntry, Outcome, Record};
+use super::Scratch;
+
+fn record(paths: &[PathBuf]) -> Record {
+    Record {
+        id: 0,
+        finished: 1_700_000_000,
+        outcome: Outcome::Success,
+        files: paths.len(),
+        bytes: 0,
+        duration: Duration::from_millis(5),
+        method: "Random (1 pass)".to_string(),
+        description: format!("{} file(s)", paths.len()),
+        preset: String::new(),
+        tag: String::new(),
+        user: "tester".to_string(),
+        operator: String::new(),
+        verification: "None".to_string(),
+        entries: paths
+            .iter()
+            .map(|path| FileEntry { path: path.clone(), bytes: 0, error: None, strategy: String::new() })
+            .collect(),
+    }
+}
+
+#[test]
+fn a_database_without_a_schema_version_is_refused() {
+    let scratch = Scratch::new("db-schema-0");
+    scratch.file("history.db", b"# file-eraser database\nschema 0\n");
+    let error = Database::open_at(scratch.path()).err().expect("schema 0 opened");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn backslashes_in_paths_survive_a_reopen() {
+    let scratch = Scratch::new("db-backslash");
+    let paths = [PathBuf::from("C:\\x41\\notes.txt"), PathBuf::from("tab\there\\u0041")];
+    Database::open_at(scratch.path()).unwrap().insert(record(&paths)).unwrap();
+
+    let db = Database::open_at(scratch.path()).unwrap();
+    let stored: Vec<&PathBuf> = db.records()[0].entries.iter().map(|entry| &entry.path).collect();
+    assert_eq!(stored, [&paths[0], &paths[1]]);
+}
+
+#[cfg(unix)]
+#[test]
+fn names_that_are_not_utf8_survive_a_reopen() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let scratch = Scratch::new("db-latin1");
+    // "café" in Latin-1, as an old file system may still hold it.
+    let path = scratch.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.txt"));
+    Database::open_at(scratch.path()).unwrap().insert(record(std::slice::from_ref(&path))).unwrap();
+    assert!(fs::read_to_string(scratch.path().join("history.db")).unwrap().contains("caf\\xe9.txt"));
+
+    let db = Database::open_at(scratch.path()).unwrap();
+    assert_eq!(db.records()[0].entries[0].path, path);
+}
@@ -0,0 +1,25 @@
+use std::fs;
+use std::path::PathBuf;
+use crate::desktop;
+use super::Scratch;
+
+#[test]
+fn folders_are_listed_once_and_fall_back_to_what_is_left() {
+    let scratch = Scratch::new("desktop-folders");
+    let kept = scratch.path().join("kept");
+    fs::create_dir(&kept).unwrap();
+    let files = [
+        kept.join("a.txt"),
+        scratch.path().join("b.txt"),
+        kept.join("c.txt"),
+        // The folder went with its files.
+        scratch.path().join("gone").join("d.txt"),
+    ];
+    assert_eq!(desktop::folders(&files), [kept, scratch.path().to_path_buf()]);
+}
+
+#[test]
+fn paths_are_copied_one_per_line() {
+    let files = [PathBuf::from("/home/user/a.txt"), PathBuf::from("/home/user/b c.txt")];
+    assert_eq!(desktop::path_list(&files), "/home/user/a.txt\n/home/user/b c.txt");
+}
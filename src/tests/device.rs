@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::Path;
+use crate::device::{self, Checkpoint, Options, Target};
+use crate::engine::{CancelToken, Progress};
+use crate::method::Pass;
+use crate::progress;
+use super::Scratch;
+
+const SIZE: usize = 8 * 1024 * 1024;
+
+fn options(pattern: u8, resume: bool, checkpoints: &Path) -> Options {
+    Options {
+        method: "test".to_string(),
+        passes: vec![Pass::Pattern(vec![pattern])],
+        resume,
+        crypto_erase: false,
+        unlock_hidden: false,
+        skip_bad_sectors: false,
+        simulate: None,
+        checkpoints: Some(checkpoints.to_path_buf()),
+    }
+}
+
+/// A regular file stands in for the drive; the wipe only needs something it
+/// can open, seek and write.
+#[test]
+fn an_interrupted_wipe_resumes_where_it_stopped() {
+    let scratch = Scratch::new("resume");
+    let disk = scratch.file("disk", &vec![0; SIZE]);
+    let checkpoints = scratch.path().join("checkpoints");
+    let targets = vec![Target { path: disk.clone(), serial: Some("TEST-1".to_string()), psid: None, hidden: None }];
+
+    // Nothing is buffered, so the wipe waits on every update and is
+    // cancelled after its first or second chunk.
+    let cancel = CancelToken::default();
+    let (tx, rx) = flume::bounded(0);
+    let first = options(0xAA, false, &checkpoints);
+    std::thread::scope(|scope| {
+        let (targets, first, token) = (&targets, &first, cancel.clone());
+        scope.spawn(move || device::wipe_all(targets, first, &token, &tx.into()));
+        let mut events = rx.iter();
+        assert!(matches!(events.next(), Some(Progress::DriveUpdated(0, _))));
+        cancel.cancel();
+        assert!(events.any(|event| matches!(event, Progress::Cancelled)));
+    });
+
+    let checkpoint = Checkpoint::load(&checkpoints, &disk).expect("a checkpoint");
+    let stopped = checkpoint.offset as usize;
+    assert_eq!(checkpoint.pass, 0);
+    assert!(stopped > 0 && stopped < SIZE);
+
+    // Resumed with the same method key but another byte, so what each run
+    // wrote can be told apart.
+    let (tx, rx) = progress::channel();
+    device::wipe_all(&targets, &options(0xBB, true, &checkpoints), &CancelToken::default(), &tx);
+    drop(tx);
+    assert!(matches!(rx.iter().last(), Some(Progress::Finished(true))));
+
+    let data = fs::read(&disk).unwrap();
+    assert!(data[..stopped].iter().all(|byte| *byte == 0xAA));
+    assert!(data[stopped..].iter().all(|byte| *byte == 0xBB));
+    assert!(Checkpoint::load(&checkpoints, &disk).is_none());
+}
+
+#[test]
+fn a_checkpoint_for_another_method_is_ignored() {
+    let scratch = Scratch::new("other-method");
+    let disk = scratch.file("disk", &vec![0; SIZE]);
+    let checkpoints = scratch.path().join("checkpoints");
+    let targets = vec![Target { path: disk.clone(), serial: Some("TEST-1".to_string()), psid: None, hidden: None }];
+
+    let cancel = CancelToken::default();
+    let (tx, rx) = flume::bounded(0);
+    let first = options(0xAA, false, &checkpoints);
+    std::thread::scope(|scope| {
+        let (targets, first, token) = (&targets, &first, cancel.clone());
+        scope.spawn(move || device::wipe_all(targets, first, &token, &tx.into()));
+        rx.recv().unwrap();
+        cancel.cancel();
+        rx.iter().for_each(drop);
+    });
+    assert!(Checkpoint::load(&checkpoints, &disk).is_some());
+
+    let mut other = options(0xBB, true, &checkpoints);
+    other.method = "other".to_string();
+    let (tx, rx) = progress::channel();
+    device::wipe_all(&targets, &other, &CancelToken::default(), &tx);
+    drop(tx);
+    assert!(matches!(rx.iter().last(), Some(Progress::Finished(true))));
+    assert!(fs::read(&disk).unwrap().iter().all(|byte| *byte == 0xBB));
+}
+
+#[test]
+fn a_drive_without_a_serial_number_starts_over() {
+    let scratch = Scratch::new("no-serial");
+    let disk = scratch.file("disk", &vec![0; SIZE]);
+    let checkpoints = scratch.path().join("checkpoints");
+    let mut targets = vec![Target { path: disk.clone(), serial: Some("TEST-1".to_string()), psid: None, hidden: None }];
+
+    let cancel = CancelToken::default();
+    let (tx, rx) = flume::bounded(0);
+    let first = options(0xAA, false, &checkpoints);
+    std::thread::scope(|scope| {
+        let (targets, first, token) = (&targets, &first, cancel.clone());
+        scope.spawn(move || device::wipe_all(targets, first, &token, &tx.into()));
+        rx.recv().unwrap();
+        cancel.cancel();
+        rx.iter().for_each(drop);
+    });
+    assert!(Checkpoint::load(&checkpoints, &disk).is_some());
+
+    // Another disk of the same size behind the same node would look just
+    // like this one, so the checkpoint cannot be trusted.
+    targets[0].serial = None;
+    let (tx, rx) = progress::channel();
+    device::wipe_all(&targets, &options(0xBB, true, &checkpoints), &CancelToken::default(), &tx);
+    drop(tx);
+    assert!(matches!(rx.iter().last(), Some(Progress::Finished(true))));
+    assert!(fs::read(&disk).unwrap().iter().all(|byte| *byte == 0xBB));
+    assert!(Checkpoint::load(&checkpoints, &disk).is_none());
+}
@@ -0,0 +1,227 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use crate::chunks;
+use crate::engine::{self, CancelToken, Cause, Mismatch, Options, Progress, Timestamps, Verification};
+use crate::method::Pass;
+use crate::progress;
+use super::Scratch;
+use super::fake::Memory;
+
+fn options(passes: Vec<Pass>, verification: Verification) -> Options {
+    Options {
+        passes,
+        verification,
+        timestamps: Timestamps::Keep,
+        churn_directories: false,
+        simulate: None,
+        checksums: false,
+    }
+}
+
+/// Runs `overwrite` over `storage` with a channel nobody listens to.
+fn overwrite(storage: &mut Memory, options: &Options, cancel: &CancelToken) -> io::Result<u64> {
+    let (tx, _rx) = progress::channel();
+    engine::overwrite(storage, options, cancel, &tx)
+}
+
+#[test]
+fn every_pass_covers_the_whole_file() {
+    // Not a multiple of the chunk size, so the last chunk is a short one.
+    let mut storage = Memory::new(vec![0x5A; 10_000]);
+    let passes = vec![Pass::Pattern(vec![0x00]), Pass::Pattern(vec![0xFF]), Pass::Random];
+    overwrite(&mut storage, &options(passes, Verification::None), &CancelToken::default()).unwrap();
+
+    assert_eq!(storage.synced.len(), 3);
+    assert!(storage.synced[0].iter().all(|byte| *byte == 0x00));
+    assert!(storage.synced[1].iter().all(|byte| *byte == 0xFF));
+    assert_eq!(storage.data.len(), 10_000);
+    assert_ne!(storage.synced[2], vec![0xFF; 10_000]);
+}
+
+#[test]
+fn patterns_stay_aligned_across_chunks() {
+    let pattern = vec![0x92, 0x49, 0x24];
+    let mut storage = Memory::new(vec![0; 10_001]);
+    overwrite(&mut storage, &options(vec![Pass::Pattern(pattern.clone())], Verification::None), &CancelToken::default()).unwrap();
+
+    let expected: Vec<u8> = pattern.iter().cycle().take(10_001).copied().collect();
+    assert_eq!(storage.data, expected);
+}
+
+#[test]
+fn verification_reads_back_every_byte() {
+    let mut storage = Memory::new(vec![1; 9_000]);
+    let passes = vec![Pass::Random, Pass::Random];
+    let verified = overwrite(&mut storage, &options(passes.clone(), Verification::LastPass), &CancelToken::default()).unwrap();
+    assert_eq!(verified, 9_000);
+
+    let verified = overwrite(&mut storage, &options(passes, Verification::EveryPass), &CancelToken::default()).unwrap();
+    assert_eq!(verified, 18_000);
+}
+
+#[test]
+fn empty_files_are_left_alone() {
+    let mut storage = Memory::new(Vec::new());
+    let verified = overwrite(&mut storage, &options(vec![Pass::Random], Verification::LastPass), &CancelToken::default()).unwrap();
+    assert_eq!(verified, 0);
+    assert!(storage.synced.is_empty());
+}
+
+#[test]
+fn mismatches_report_every_bad_block() {
+    let mut storage = Memory::new(vec![0; 20_000]);
+    storage.corrupt = vec![10, 4096 + 7, 12_288 + 100];
+    let error = overwrite(&mut storage, &options(vec![Pass::Random], Verification::LastPass), &CancelToken::default()).unwrap_err();
+
+    let mismatch = error.into_inner().and_then(|inner| inner.downcast::<Mismatch>().ok()).expect("a mismatch");
+    assert_eq!(
+        *mismatch,
+        Mismatch { pass: 1, blocks: 3, offsets: vec![10, 4096 + 7, 12_288 + 100], cause: Cause::Unknown },
+    );
+}
+
+#[test]
+fn write_errors_stop_the_pass() {
+    let fail_at = 2 * chunks::START + 1_000;
+    let mut storage = Memory::new(vec![7; 4 * chunks::START]);
+    storage.fail_at = Some(fail_at as u64);
+    let error = overwrite(&mut storage, &options(vec![Pass::Pattern(vec![0])], Verification::None), &CancelToken::default()).unwrap_err();
+
+    assert!(error.to_string().contains("injected write error"));
+    // The chunks before the failing one were written, the rest was not.
+    assert!(storage.data[..chunks::START].iter().all(|byte| *byte == 0));
+    assert!(storage.data[fail_at..].iter().all(|byte| *byte == 7));
+    assert!(storage.synced.is_empty());
+}
+
+#[test]
+fn cancelling_stops_between_chunks() {
+    let cancel = CancelToken::default();
+    let mut storage = Memory::new(vec![7; 8 * chunks::START]);
+    storage.cancel_after = Some((2, cancel.clone()));
+    let error = overwrite(&mut storage, &options(vec![Pass::Pattern(vec![0])], Verification::None), &cancel).unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    assert_eq!(storage.written as usize, storage.data.iter().position(|byte| *byte == 7).unwrap());
+    // The second chunk is at most a step larger than the first.
+    assert!(storage.written as usize >= chunks::START + chunks::MIN);
+    assert!(storage.written as usize <= 2 * chunks::START + chunks::STEP);
+}
+
+#[test]
+fn erased_files_are_gone_with_their_names() {
+    let scratch = Scratch::new("erase");
+    let paths = vec![scratch.file("first.txt", b"secret one"), scratch.file("second.txt", &[3; 50_000])];
+    let (tx, rx) = progress::channel();
+    engine::erase_all(&paths, &options(vec![Pass::Random], Verification::LastPass), &CancelToken::default(), &tx);
+    drop(tx);
+
+    let events: Vec<Progress> = rx.iter().collect();
+    assert!(matches!(events.last(), Some(Progress::Finished(true))));
+    // Both files are looked at before the first is touched, and the
+    // clean-up comes last.
+    assert!(matches!(events[..2], [Progress::ScanStarted(2), Progress::ScanUpdated(2)]));
+    assert!(matches!(events[2], Progress::Started(0)));
+    assert!(matches!(events[events.len() - 2], Progress::CleanupDone));
+    let verified: u64 = events.iter().filter_map(|event| match event {
+        Progress::Verified(bytes) => Some(*bytes),
+        _ => None,
+    }).sum();
+    // Memory-backed temp folders are unlinked without writing anything.
+    assert!(verified == 50_010 || verified == 0);
+    assert!(scratch.entries().is_empty());
+}
+
+#[test]
+fn churning_leaves_only_the_other_files_behind() {
+    let scratch = Scratch::new("churn");
+    let kept = scratch.file("kept.txt", b"stays");
+    let erased = [scratch.path().join("gone.txt")];
+    engine::churn_directories(&erased, &CancelToken::default()).unwrap();
+    assert_eq!(scratch.entries(), ["kept.txt"]);
+    assert_eq!(fs::read(kept).unwrap(), b"stays");
+}
+
+#[test]
+fn a_missing_file_fails_alone() {
+    let scratch = Scratch::new("missing");
+    let paths = vec![scratch.path().join("not-there"), scratch.file("there", b"data")];
+    let (tx, rx) = progress::channel();
+    engine::erase_all(&paths, &options(vec![Pass::Random], Verification::None), &CancelToken::default(), &tx);
+    drop(tx);
+
+    let events: Vec<Progress> = rx.iter().collect();
+    assert!(events.iter().any(|event| matches!(event, Progress::Failed(0, _))));
+    assert!(matches!(events.last(), Some(Progress::Finished(false))));
+    assert!(scratch.entries().is_empty());
+}
+
+#[test]
+fn a_cancelled_job_leaves_the_files() {
+    let scratch = Scratch::new("cancelled");
+    let paths = vec![scratch.file("kept", &[1; 10_000])];
+    let cancel = CancelToken::default();
+    cancel.cancel();
+    let (tx, rx) = progress::channel();
+    engine::erase_all(&paths, &options(vec![Pass::Random], Verification::None), &cancel, &tx);
+    drop(tx);
+
+    assert!(matches!(rx.iter().last(), Some(Progress::Cancelled)));
+    // Unless the temp folder is memory-backed, where there is nothing to overwrite.
+    if engine::memory_backed(scratch.path()).is_none() {
+        assert_eq!(scratch.entries(), vec!["kept".to_string()]);
+    }
+}
+
+#[test]
+fn sparse_files_are_written_in_full() {
+    let scratch = Scratch::new("sparse");
+    let path: PathBuf = scratch.path().join("sparse");
+    let file = fs::File::create(&path).unwrap();
+    file.set_len(1024 * 1024).unwrap();
+    drop(file);
+    {
+        use std::io::{Seek, SeekFrom, Write};
+        let mut file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(500_000)).unwrap();
+        file.write_all(b"in the middle of nowhere").unwrap();
+    }
+
+    let file = fs::OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    let (tx, _rx) = progress::channel();
+    let options = options(vec![Pass::Pattern(vec![0xAB])], Verification::LastPass);
+    let verified = engine::overwrite_open(file, &options, &CancelToken::default(), &tx).unwrap();
+
+    assert_eq!(verified, 1024 * 1024);
+    let data = fs::read(&path).unwrap();
+    assert_eq!(data.len(), 1024 * 1024);
+    assert!(data.iter().all(|byte| *byte == 0xAB));
+}
+
+#[test]
+fn a_simulated_job_touches_nothing() {
+    let scratch = Scratch::new("simulated");
+    let paths = vec![scratch.file("kept", &[9; 30_000])];
+    let options = Options { simulate: Some(100_000_000), ..options(vec![Pass::Random], Verification::LastPass) };
+    let (tx, rx) = progress::channel();
+    engine::erase_all(&paths, &options, &CancelToken::default(), &tx);
+    drop(tx);
+
+    let events: Vec<Progress> = rx.iter().collect();
+    assert!(events.iter().any(|event| matches!(event, Progress::Updated(percent) if *percent == 100.0)));
+    assert!(matches!(events.last(), Some(Progress::Finished(true))));
+    assert_eq!(fs::read(&paths[0]).unwrap(), vec![9; 30_000]);
+}
+
+#[test]
+fn checksums_put_a_mismatch_on_the_storage() {
+    let mut storage = Memory::new(vec![0; 20_000]);
+    storage.corrupt = vec![5_000];
+    let options = Options { checksums: true, ..options(vec![Pass::Random, Pass::Random], Verification::EveryPass) };
+    let error = overwrite(&mut storage, &options, &CancelToken::default()).unwrap_err();
+
+    let mismatch = error.into_inner().and_then(|inner| inner.downcast::<Mismatch>().ok()).expect("a mismatch");
+    assert_eq!(mismatch.cause, Cause::Storage);
+    assert!(mismatch.to_string().contains("acknowledged writes it did not keep"));
+}
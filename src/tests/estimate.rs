@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::engine::Verification;
+use crate::estimate::{self, Source};
+use crate::history::{FileEntry, Outcome, Record};
+use crate::method::{Method, Registry};
+use super::Scratch;
+
+const MIB: u64 = 1024 * 1024;
+
+fn record(path: PathBuf, method: &Method, bytes: u64, secs: u64, outcome: Outcome) -> Record {
+    Record {
+        id: 0,
+        finished: 1_700_000_000,
+        outcome,
+        files: 1,
+        bytes,
+        duration: Duration::from_secs(secs),
+        method: method.to_string(),
+        description: "1 file(s)".to_string(),
+        preset: String::new(),
+        tag: String::new(),
+        user: "tester".to_string(),
+        operator: String::new(),
+        verification: "None".to_string(),
+        entries: vec![FileEntry { path, bytes, error: None, strategy: String::new() }],
+    }
+}
+
+#[test]
+fn history_gives_the_speed_of_one_pass() {
+    let scratch = Scratch::new("estimate-history");
+    let registry = Registry::builtin();
+    let file = scratch.path().join("gone.bin");
+    let one = Method::random(1);
+    let mut three = record(file.clone(), &Method::random(3), 100 * MIB, 30, Outcome::Success);
+    three.method += "; 1 shadow copies deleted first";
+    let records = [
+        // Ten passes' worth of data in ten seconds.
+        record(file.clone(), &one, 100 * MIB, 10, Outcome::Success),
+        three,
+        // Failed and tiny jobs say nothing about the drive.
+        record(file.clone(), &one, 100 * MIB, 1000, Outcome::Failed),
+        record(file, &one, MIB, 1000, Outcome::Success),
+    ];
+    let methods: Vec<Method> = registry.methods().iter().cloned().chain([one.clone(), Method::random(3)]).collect();
+    let throughput = estimate::from_history(&records, &methods, scratch.path()).unwrap();
+    assert_eq!(throughput.source, Source::History(2));
+    assert_eq!(throughput.bytes_per_second, (10 * MIB) as f64);
+    assert_eq!(throughput.duration(100 * MIB, &Method::random(3), Verification::None), Duration::from_secs(30));
+    assert_eq!(throughput.duration(100 * MIB, &one, Verification::LastPass), Duration::from_secs(20));
+
+    assert_eq!(estimate::from_history(&records[2..], &methods, scratch.path()), None);
+}
+
+#[test]
+fn write_test_leaves_nothing_behind() {
+    let scratch = Scratch::new("estimate-benchmark");
+    let throughput = estimate::benchmark(scratch.path()).unwrap();
+    assert_eq!(throughput.source, Source::Benchmark);
+    assert!(throughput.bytes_per_second > 0.0);
+    assert!(scratch.entries().is_empty());
+}
@@ -0,0 +1,95 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use crate::engine::CancelToken;
+use crate::storage::Storage;
+
+/// A file held in memory, with the faults real storage has.
+#[derive(Default)]
+pub struct Memory {
+    pub data: Vec<u8>,
+    position: u64,
+    /// The contents at every sync; the engine syncs once per pass.
+    pub synced: Vec<Vec<u8>>,
+    /// Bytes written in total, over every pass.
+    pub written: u64,
+    /// A write that covers this offset fails, as on a bad sector.
+    pub fail_at: Option<u64>,
+    /// Offsets whose byte reads back flipped, as on storage that drops
+    /// writes and reports success.
+    pub corrupt: Vec<u64>,
+    /// Cancelled once this many writes got through.
+    pub cancel_after: Option<(usize, CancelToken)>,
+    writes: usize,
+}
+
+impl Memory {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data, ..Self::default() }
+    }
+}
+
+impl Read for Memory {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = (self.position as usize).min(self.data.len());
+        let count = buf.len().min(self.data.len() - start);
+        buf[..count].copy_from_slice(&self.data[start..start + count]);
+        for offset in &self.corrupt {
+            if let Some(index) = (*offset as usize).checked_sub(start).filter(|index| *index < count) {
+                buf[index] ^= 0xFF;
+            }
+        }
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Write for Memory {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let start = self.position as usize;
+        let end = start + buf.len();
+        if let Some(offset) = self.fail_at
+            && (start..end).contains(&(offset as usize))
+        {
+            return Err(io::Error::other(format!("injected write error at {}", offset)));
+        }
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+        self.data[start..end].copy_from_slice(buf);
+        self.position = end as u64;
+        self.written += buf.len() as u64;
+        self.writes += 1;
+        if let Some((after, cancel)) = &self.cancel_after
+            && self.writes >= *after
+        {
+            cancel.cancel();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Memory {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.data.len().checked_add_signed(delta as isize).map(|offset| offset as u64),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before start"))?;
+        Ok(self.position)
+    }
+}
+
+impl Storage for Memory {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.data.len() as u64)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.synced.push(self.data.clone());
+        Ok(())
+    }
+}
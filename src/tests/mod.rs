@@ -0,0 +1,73 @@
+//! Tests that drive the engine end to end: against the in-memory fake in
+//! `fake` where faults have to be injected, against files in a scratch
+//! folder where the real file system matters.
+
+mod agent;
+mod announce;
+mod chunks;
+#[cfg(unix)]
+mod cli;
+mod container;
+mod db;
+mod desktop;
+mod device;
+mod engine;
+mod estimate;
+mod fake;
+mod onboarding;
+mod passes;
+mod picker;
+mod portable;
+mod progress;
+mod region;
+mod reports;
+mod settings;
+mod space;
+mod staging;
+mod strategy;
+mod summary;
+mod units;
+mod vault;
+mod worker;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A folder of its own under the temp dir, removed with everything in it
+/// when dropped.
+pub struct Scratch(PathBuf);
+
+impl Scratch {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("file-eraser-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("scratch folder");
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn file(&self, name: &str, contents: &[u8]) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, contents).expect("scratch file");
+        path
+    }
+
+    /// Names of what is left in the folder.
+    pub fn entries(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.0)
+            .expect("scratch folder")
+            .map(|entry| entry.expect("scratch entry").file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use crate::cli;
+use crate::method::Registry;
+use crate::onboarding::Profile;
+use crate::settings::Settings;
+use super::Scratch;
+
+#[test]
+fn profiles_name_methods_that_exist() {
+    let registry = Registry::builtin();
+    for profile in Profile::ALL {
+        assert!(registry.find(profile.method()).is_some(), "{}", profile);
+    }
+}
+
+#[test]
+fn walkthrough_is_only_shown_once() {
+    let registry = Registry::builtin();
+    assert!(!Settings::default().onboarded);
+    // Settings saved before the walkthrough existed.
+    assert!(Settings::parse("method = \"dod\"\n", &registry).onboarded);
+    let mut settings = Settings::parse("onboarded = false\n", &registry);
+    assert!(!settings.onboarded);
+    settings.onboarded = true;
+    assert!(Settings::parse(&settings.to_toml(), &registry).onboarded);
+}
+
+#[test]
+fn gui_takes_the_files_after_it() {
+    let scratch = Scratch::new("onboarding-queued");
+    let folder = scratch.path().join("folder");
+    fs::create_dir(&folder).unwrap();
+    fs::write(folder.join(".hidden"), b"x").unwrap();
+    let file = scratch.file("file.txt", b"x");
+    let mut args = vec!["--set".to_string(), "announce=true".to_string(), "--gui".to_string()];
+    args.extend([file.display().to_string(), folder.display().to_string()]);
+    assert_eq!(cli::queued(&mut args), Some(vec![file, folder.join(".hidden")]));
+    assert_eq!(args, ["--set", "announce=true"]);
+    assert_eq!(cli::queued(&mut args), None);
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn file_manager_entries_open_the_window() {
+    let scratch = Scratch::new("onboarding-shell");
+    let exe = scratch.path().join("it's here").join("file-eraser");
+    let places = crate::shell::install_at(scratch.path(), &exe).unwrap();
+    assert_eq!(places.len(), 2);
+    let script = fs::read_to_string(&places[0]).unwrap();
+    assert!(script.contains("it'\\''s here/file-eraser' --gui \"$@\""), "{}", script);
+    assert!(fs::read_to_string(&places[1]).unwrap().contains("it's here/file-eraser\" --gui %F"));
+}
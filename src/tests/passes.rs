@@ -0,0 +1,134 @@
+//! Property tests for the pass generator: random file sizes and methods,
+//! each case drawn from its own seed so a failure names the case to rerun.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::engine::{self, CancelToken, Cause, Mismatch, Options, Progress, Timestamps, Verification};
+use crate::method::{Pass, Registry};
+use crate::progress;
+use super::fake::Memory;
+
+const CASES: u64 = 48;
+
+/// What one case overwrites and how.
+#[derive(Debug)]
+struct Case {
+    size: usize,
+    passes: Vec<Pass>,
+}
+
+impl Case {
+    fn draw(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        // Mostly small files around the chunk size, some large enough to
+        // report progress (every 100 chunks).
+        let size = match rng.gen_range(0..4) {
+            0 => rng.gen_range(0..=3),
+            1 | 2 => rng.gen_range(0..20_000),
+            _ => rng.gen_range(210_000..450_000),
+        };
+        let passes = if rng.gen_bool(0.5) {
+            let methods = Registry::builtin();
+            methods.methods()[rng.gen_range(0..methods.methods().len())].passes()
+        } else {
+            (0..rng.gen_range(1..=5))
+                .map(|_| match rng.gen_range(0..3) {
+                    0 => Pass::Random,
+                    _ => Pass::Pattern((0..rng.gen_range(1..=5)).map(|_| rng.r#gen()).collect()),
+                })
+                .collect()
+        };
+        // Gutmann's 35 passes over the largest sizes would make for a slow test.
+        let size = if passes.len() > 8 { size.min(30_000) } else { size };
+        Self { size, passes }
+    }
+
+    fn options(&self, verification: Verification) -> Options {
+        Options {
+            passes: self.passes.clone(),
+            verification,
+            timestamps: Timestamps::Keep,
+            churn_directories: false,
+            simulate: None,
+            checksums: false,
+        }
+    }
+}
+
+/// Runs `check` over every case, naming the seed of the one that fails.
+fn for_each_case(check: impl Fn(&Case, &mut StdRng)) {
+    for seed in 0..CASES {
+        let case = Case::draw(seed);
+        let mut rng = StdRng::seed_from_u64(!seed);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| check(&case, &mut rng)));
+        if let Err(panic) = result {
+            eprintln!("Failing case (seed {}): {} bytes, {:?}", seed, case.size, case.passes);
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
+
+#[test]
+fn every_pass_writes_the_whole_file_once() {
+    for_each_case(|case, rng| {
+        let mut storage = Memory::new((0..case.size).map(|_| rng.r#gen()).collect());
+        let (tx, _rx) = progress::channel();
+        engine::overwrite(&mut storage, &case.options(Verification::None), &CancelToken::default(), &tx).unwrap();
+
+        let passes = if case.size == 0 { 0 } else { case.passes.len() };
+        assert_eq!(storage.written, (passes * case.size) as u64);
+        assert_eq!(storage.synced.len(), passes);
+        assert_eq!(storage.data.len(), case.size);
+    });
+}
+
+#[test]
+fn progress_only_goes_forward() {
+    for_each_case(|case, _| {
+        let mut storage = Memory::new(vec![0; case.size]);
+        let (tx, rx) = progress::channel();
+        engine::overwrite(&mut storage, &case.options(Verification::EveryPass), &CancelToken::default(), &tx).unwrap();
+        drop(tx);
+
+        let updates: Vec<f32> = rx
+            .iter()
+            .filter_map(|event| match event {
+                Progress::Updated(percent) => Some(percent),
+                _ => None,
+            })
+            .collect();
+        assert!(updates.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", updates);
+        assert!(updates.iter().all(|percent| (0.0..=100.0).contains(percent)), "{:?}", updates);
+    });
+}
+
+#[test]
+fn the_file_ends_up_holding_the_last_pass() {
+    for_each_case(|case, _| {
+        let mut storage = Memory::new(vec![0; case.size]);
+        let (tx, _rx) = progress::channel();
+        let verified = engine::overwrite(&mut storage, &case.options(Verification::LastPass), &CancelToken::default(), &tx).unwrap();
+
+        assert_eq!(verified, case.size as u64);
+        if let Some(Pass::Pattern(pattern)) = case.passes.last() {
+            assert!(storage.data.iter().zip(pattern.iter().cycle()).all(|(byte, expected)| byte == expected));
+        }
+    });
+}
+
+#[test]
+fn a_flipped_byte_is_always_caught() {
+    for_each_case(|case, rng| {
+        if case.size == 0 {
+            return;
+        }
+        let offset = rng.gen_range(0..case.size as u64);
+        let mut storage = Memory::new(vec![0; case.size]);
+        storage.corrupt = vec![offset];
+        let (tx, _rx) = progress::channel();
+        let error = engine::overwrite(&mut storage, &case.options(Verification::LastPass), &CancelToken::default(), &tx).unwrap_err();
+
+        let mismatch = error.into_inner().and_then(|inner| inner.downcast::<Mismatch>().ok()).expect("a mismatch");
+        assert_eq!(*mismatch, Mismatch { pass: case.passes.len(), blocks: 1, offsets: vec![offset], cause: Cause::Unknown });
+    });
+}
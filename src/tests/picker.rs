@@ -0,0 +1,34 @@
+use std::fs;
+use crate::picker;
+use super::Scratch;
+
+#[test]
+fn folder_walks_leave_out_hidden_entries_unless_asked() {
+    let scratch = Scratch::new("picker-hidden");
+    let visible = scratch.file("notes.txt", b"notes");
+    let hidden = scratch.file(".env", b"TOKEN=secret");
+    fs::create_dir(scratch.path().join(".cache")).unwrap();
+    fs::write(scratch.path().join(".cache").join("blob"), b"blob").unwrap();
+
+    let mut files = Vec::new();
+    assert!(picker::walk(scratch.path(), false, &mut files).is_empty());
+    assert_eq!(files, std::slice::from_ref(&visible));
+
+    let mut files = Vec::new();
+    assert!(picker::walk(scratch.path(), true, &mut files).is_empty());
+    files.sort();
+    assert_eq!(files, [scratch.path().join(".cache").join("blob"), hidden, visible]);
+}
+
+#[test]
+fn an_unreadable_folder_is_reported_not_fatal() {
+    let scratch = Scratch::new("picker-unreadable");
+    let file = scratch.file("kept.txt", b"kept");
+    let missing = scratch.path().join("gone");
+
+    let mut files = vec![file.clone()];
+    let unreadable = picker::walk(&missing, true, &mut files);
+    assert_eq!(unreadable.len(), 1);
+    assert_eq!(unreadable[0].0, missing);
+    assert_eq!(files, [file]);
+}
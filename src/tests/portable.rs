@@ -0,0 +1,14 @@
+use std::fs;
+use crate::portable;
+use super::Scratch;
+
+#[test]
+fn marker_beside_the_executable_makes_it_portable() {
+    let scratch = Scratch::new("portable-marker");
+    let exe = scratch.path().join("file-eraser.exe");
+    assert_eq!(portable::root(&exe, false), None);
+    assert_eq!(portable::root(&exe, true), Some(scratch.path().join("file-eraser-data")));
+
+    fs::write(scratch.path().join(portable::MARKER), b"").unwrap();
+    assert_eq!(portable::root(&exe, false), Some(scratch.path().join("file-eraser-data")));
+}
@@ -0,0 +1,89 @@
+use crate::engine::Progress;
+use crate::progress::{self, Phase};
+
+#[test]
+fn only_the_latest_percentage_is_kept() {
+    let (tx, rx) = progress::channel();
+    for percent in 1..=1000 {
+        tx.send(Progress::Updated(percent as f32 / 10.0)).unwrap();
+    }
+    tx.send(Progress::DriveUpdated(1, 20.0)).unwrap();
+    tx.send(Progress::DriveUpdated(0, 10.0)).unwrap();
+    tx.send(Progress::DriveUpdated(1, 30.0)).unwrap();
+    let latest = rx.latest();
+    assert!(matches!(
+        latest[..],
+        [Progress::Updated(100.0), Progress::DriveUpdated(0, 10.0), Progress::DriveUpdated(1, 30.0)]
+    ));
+    assert!(rx.latest().is_empty());
+}
+
+#[test]
+fn events_come_after_the_percentage_before_them() {
+    let (tx, rx) = progress::channel();
+    tx.send(Progress::Started(0)).unwrap();
+    tx.send(Progress::Updated(50.0)).unwrap();
+    tx.send(Progress::Updated(100.0)).unwrap();
+    tx.send(Progress::Started(1)).unwrap();
+    tx.send(Progress::Updated(10.0)).unwrap();
+    drop(tx);
+    let events: Vec<Progress> = rx.iter().collect();
+    assert!(matches!(
+        events[..],
+        [Progress::Started(0), Progress::Updated(100.0), Progress::Started(1), Progress::Updated(10.0)]
+    ));
+}
+
+#[test]
+fn sending_fails_once_nobody_listens() {
+    let (tx, rx) = progress::channel();
+    drop(rx);
+    assert!(tx.send(Progress::Updated(1.0)).is_err());
+    assert!(tx.send(Progress::Finished(true)).is_err());
+}
+
+#[test]
+fn phase_percentages_are_coalesced_between_passes() {
+    let (tx, rx) = progress::channel();
+    tx.send(Progress::PassStarted(1, 2)).unwrap();
+    for percent in 1..=100 {
+        tx.send(Progress::WipeUpdated(percent as f32)).unwrap();
+        tx.send(Progress::Updated(percent as f32 / 2.0)).unwrap();
+    }
+    tx.send(Progress::PassStarted(2, 2)).unwrap();
+    tx.send(Progress::VerifyUpdated(10.0)).unwrap();
+    drop(tx);
+    let events: Vec<Progress> = rx.iter().collect();
+    assert!(matches!(
+        events[..],
+        [
+            Progress::PassStarted(1, 2),
+            Progress::WipeUpdated(100.0),
+            Progress::Updated(50.0),
+            Progress::PassStarted(2, 2),
+            Progress::VerifyUpdated(10.0),
+        ]
+    ));
+}
+
+#[test]
+fn the_phase_follows_the_events() {
+    let mut phase = Phase::default();
+    phase.follow(&Progress::ScanStarted(3));
+    phase.follow(&Progress::ScanUpdated(2));
+    assert_eq!(phase.to_string(), "Looked at 2 of 3 file(s)");
+
+    phase.follow(&Progress::PassStarted(2, 3));
+    phase.follow(&Progress::WipeUpdated(40.0));
+    phase.follow(&Progress::Updated(99.0));
+    assert_eq!(phase, Phase::Writing { pass: 2, passes: 3, percent: 40.0 });
+    assert_eq!(phase.to_string(), "Pass 2 of 3: writing 40%");
+
+    phase.follow(&Progress::VerifyUpdated(5.0));
+    assert_eq!(phase.title(), "Pass 2 of 3: reading back");
+    // A stray scan percentage does not pull the job back.
+    phase.follow(&Progress::ScanUpdated(3));
+    assert_eq!(phase.title(), "Pass 2 of 3: reading back");
+    phase.follow(&Progress::CleanupDone);
+    assert_eq!(phase, Phase::CleanedUp);
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use crate::engine::{self, CancelToken, Options, Timestamps, Verification};
+use crate::method::Pass;
+use crate::progress;
+use crate::region::{self, Region};
+use super::Scratch;
+
+#[test]
+fn only_the_region_is_overwritten() {
+    let scratch = Scratch::new("region");
+    let original: Vec<u8> = (0..20_000u32).map(|index| (index % 251) as u8).collect();
+    let path = scratch.file("container.db", &original);
+    let options = Options {
+        passes: vec![Pass::Random, Pass::Pattern(vec![0xEE])],
+        verification: Verification::EveryPass,
+        timestamps: Timestamps::Keep,
+        churn_directories: false,
+        simulate: None,
+        checksums: true,
+    };
+    // Crosses chunk boundaries and ends in a short chunk.
+    let region = Region { offset: 3_000, length: 9_001 };
+    let (tx, _rx) = progress::channel();
+    let verified = engine::overwrite_region(&path, region, &options, &CancelToken::default(), &tx).unwrap();
+    assert_eq!(verified, 2 * 9_001);
+
+    let data = fs::read(&path).unwrap();
+    assert_eq!(data.len(), original.len());
+    assert_eq!(data[..3_000], original[..3_000]);
+    assert!(data[3_000..12_001].iter().all(|byte| *byte == 0xEE));
+    assert_eq!(data[12_001..], original[12_001..]);
+
+    // Past the end is refused instead of growing the file.
+    let beyond = Region { offset: 19_000, length: 2_000 };
+    assert!(engine::overwrite_region(&path, beyond, &options, &CancelToken::default(), &tx).is_err());
+    assert_eq!(fs::metadata(&path).unwrap().len(), 20_000);
+}
+
+#[test]
+fn regions_are_read_as_offset_and_length() {
+    assert_eq!(Region::parse("4096:512"), Ok(Region { offset: 4096, length: 512 }));
+    assert_eq!(Region::parse("0x1000:0x200"), Ok(Region { offset: 4096, length: 512 }));
+    assert_eq!(Region::parse(" 1_000 : 24 ").map(Region::end), Ok(1024));
+    assert!(Region::parse("4096").is_err());
+    assert!(Region::parse("10:0").is_err());
+    assert!(Region::parse("x:5").is_err());
+    assert_eq!(Region { offset: 16, length: 4 }.to_string(), "bytes 16 to 19");
+    assert_eq!(region::ascii(b"key=\x00\x7f s3cret\n"), "key=.. s3cret.");
+}
+
+#[test]
+fn two_clicks_in_the_hex_view_pick_a_region() {
+    let scratch = Scratch::new("region-view");
+    let path = scratch.file("image.bin", &[0x41; 1_000]);
+    let mut panel = region::Panel::open(path).unwrap();
+    assert_eq!(panel.page.len(), region::PAGE as usize);
+
+    panel.click(300);
+    assert_eq!(panel.region(), Ok(Region { offset: 300, length: 1 }));
+    panel.click(260);
+    assert_eq!(panel.region(), Ok(Region { offset: 260, length: 41 }));
+    assert_eq!(panel.anchor, None);
+
+    // The last page is a short one.
+    panel.show(999).unwrap();
+    assert_eq!(panel.view, 768);
+    assert_eq!(panel.page.len(), 232);
+    panel.length = "1000".to_string();
+    assert!(panel.region().is_err());
+}
@@ -0,0 +1,11 @@
+use std::time::{Duration, SystemTime};
+use crate::reports;
+
+#[test]
+fn no_report_is_older_than_the_clock_reaches() {
+    let week = reports::cutoff(7).unwrap();
+    let expected = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+    assert!(expected.duration_since(week).unwrap_or_default() < Duration::from_secs(5));
+    assert_eq!(reports::cutoff(u64::MAX), None);
+    assert_eq!(reports::cutoff(u64::MAX / (24 * 60 * 60)), None);
+}
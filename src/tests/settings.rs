@@ -0,0 +1,44 @@
+use crate::method::Registry;
+use crate::settings::Settings;
+use super::Scratch;
+
+#[test]
+fn overrides_stay_out_of_the_saved_settings() {
+    let methods = Registry::load();
+    let user = Settings::parse("method = \"dod\"\nannounce = false\nonboarded = false\n", &methods);
+    // As if `--config` and `--set` had been given.
+    let mut settings = Settings::parse("method = \"gutmann\"\nadvanced = true\nonboarded = false\n", &methods);
+    settings.set("tag", "CASE-7", &methods).unwrap();
+    settings.set("checksums", "true", &methods).unwrap();
+    settings.layer_over(user.clone());
+
+    assert_eq!(settings.saved().to_toml(), user.to_toml());
+
+    settings.announce = true;
+    settings.onboarded = true;
+    settings.window.width = 1200.0;
+    let saved = settings.saved();
+    assert_eq!(saved.method, user.method);
+    assert!(!saved.advanced && !saved.checksums);
+    assert!(saved.announce && saved.onboarded);
+    assert_eq!(saved.window.width, 1200.0);
+}
+
+
+#[test]
+fn the_settings_file_is_private_to_the_user() {
+    let scratch = Scratch::new("settings-private");
+    let methods = Registry::load();
+    let mut settings = Settings::default();
+    settings.set("webhook_secret", "hunter2", &methods).unwrap();
+    let path = scratch.path().join("config").join("settings.toml");
+    settings.save_to(&path).unwrap();
+
+    let saved = Settings::parse(&std::fs::read_to_string(&path).unwrap(), &methods);
+    assert_eq!(saved.webhook_secret, "hunter2");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
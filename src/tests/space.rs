@@ -0,0 +1,45 @@
+use std::io;
+use crate::space::{self, Room, Volume};
+use super::Scratch;
+
+const PLENTY: Room = Room { bytes: 1 << 30, inodes: Some(1_000_000), read_only: false };
+
+#[test]
+fn read_only_volumes_are_refused() {
+    let volume = Volume { dir: "/mnt".into(), files: vec!["/mnt/a".into()] };
+    let problem = space::judge(&volume, Room { read_only: true, ..PLENTY }, false, false).expect("a problem");
+    assert!(problem.refuse);
+    assert_eq!(problem.kind, io::ErrorKind::ReadOnlyFilesystem);
+    assert_eq!(problem.files, volume.files);
+}
+
+#[test]
+fn copy_on_write_needs_room_for_the_largest_file() {
+    let scratch = Scratch::new("space-cow");
+    let file = scratch.file("big", &[0; 8192]);
+    let volume = Volume { dir: scratch.path().to_path_buf(), files: vec![file] };
+    let room = Room { bytes: 4096 + 1024 * 1024, ..PLENTY };
+    assert!(space::judge(&volume, room, false, false).is_none());
+    let room = Room { bytes: 4096, ..PLENTY };
+    assert!(space::judge(&volume, room, false, true).expect("a problem").refuse);
+}
+
+#[test]
+fn shortages_that_only_leave_names_behind_are_warnings() {
+    let volume = Volume { dir: "/data".into(), files: vec!["/data/a".into(), "/data/b".into()] };
+    let nearly_full = space::judge(&volume, Room { bytes: 1000, ..PLENTY }, false, false).expect("a problem");
+    assert!(!nearly_full.refuse);
+    let few_inodes = Room { inodes: Some(10), ..PLENTY };
+    assert!(space::judge(&volume, few_inodes, false, false).is_none());
+    assert!(!space::judge(&volume, few_inodes, true, false).expect("a problem").refuse);
+    assert!(space::judge(&volume, PLENTY, true, false).is_none());
+}
+
+#[test]
+fn files_are_grouped_by_volume() {
+    let scratch = Scratch::new("space-volumes");
+    let files = vec![scratch.file("a", b"a"), scratch.file("b", b"b")];
+    let volumes = space::volumes(&files);
+    assert_eq!(volumes.len(), 1);
+    assert_eq!(volumes[0].files, files);
+}
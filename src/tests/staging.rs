@@ -0,0 +1,78 @@
+use std::fs;
+use crate::policy::Policy;
+use crate::staging::{self, Area};
+use super::Scratch;
+
+#[test]
+fn staged_files_leave_their_folder_and_can_come_back() {
+    let scratch = Scratch::new("staging");
+    let data = scratch.path().join("data");
+    let file = scratch.file("report.docx", b"second thoughts");
+    let mut area = Area::open_at(&data).unwrap();
+    area.stage(&file, 3600, &Policy::default()).unwrap();
+
+    assert!(!file.exists());
+    let staged = Area::open_at(&data).unwrap().items().to_vec();
+    assert_eq!(staged.len(), 1);
+    assert_eq!(staged[0].original, file);
+    assert!(staged[0].held.starts_with(&data));
+    assert!(area.due(staged[0].due - 1).is_empty());
+    assert_eq!(area.due(staged[0].due), staged);
+
+    assert_eq!(area.restore(staged[0].id).unwrap(), file);
+    assert_eq!(fs::read(&file).unwrap(), b"second thoughts");
+    assert!(Area::open_at(&data).unwrap().items().is_empty());
+}
+
+#[test]
+fn a_restore_never_replaces_a_new_file() {
+    let scratch = Scratch::new("staging-taken");
+    let data = scratch.path().join("data");
+    let file = scratch.file("notes.txt", b"old");
+    let mut area = Area::open_at(&data).unwrap();
+    area.stage(&file, 60, &Policy::default()).unwrap();
+    fs::write(&file, b"new").unwrap();
+
+    let id = area.items()[0].id;
+    assert_eq!(area.restore(id).unwrap_err().kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(fs::read(&file).unwrap(), b"new");
+    assert_eq!(area.items().len(), 1);
+}
+
+#[test]
+fn destroyed_files_are_forgotten() {
+    let scratch = Scratch::new("staging-prune");
+    let data = scratch.path().join("data");
+    let mut area = Area::open_at(&data).unwrap();
+    area.stage(&scratch.file("first", b"1"), 0, &Policy::default()).unwrap();
+    area.stage(&scratch.file("second", b"2"), 0, &Policy::default()).unwrap();
+
+    fs::remove_file(&area.items()[0].held).unwrap();
+    area.prune().unwrap();
+    assert_eq!(area.items().len(), 1);
+    assert_eq!(Area::open_at(&data).unwrap().items()[0].original, scratch.path().join("second"));
+}
+
+#[test]
+fn forbidden_files_are_neither_staged_nor_destroyed_from_there() {
+    let scratch = Scratch::new("staging-policy");
+    let data = scratch.path().join("data");
+    let protected = scratch.path().join("protected");
+    fs::create_dir(&protected).unwrap();
+    let kept = protected.join("ledger.xlsx");
+    fs::write(&kept, b"keep").unwrap();
+    let policy = Policy { forbidden: vec![protected.clone()], ..Policy::default() };
+
+    let mut area = Area::open_at(&data).unwrap();
+    assert_eq!(area.stage(&kept, 0, &policy).unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(kept.exists() && area.items().is_empty());
+
+    // Staged before the administrator protected the folder: held under a
+    // new name, it is still judged by the one it came from.
+    let later = protected.join("payroll.csv");
+    fs::write(&later, b"staged").unwrap();
+    area.stage(&later, 0, &Policy::default()).unwrap();
+    let (allowed, errors) = staging::permitted(area.due(u64::MAX), &policy);
+    assert!(allowed.is_empty());
+    assert_eq!(errors.len(), 1);
+}
@@ -0,0 +1,31 @@
+use crate::strategy::{Confidence, Facts, Score, Step, Strategy};
+
+fn ntfs(compressed: bool, encrypted: bool) -> Facts {
+    Facts {
+        filesystem: Some("NTFS".to_string()),
+        rotational: Some(true),
+        compressed,
+        encrypted,
+        ..Facts::default()
+    }
+}
+
+#[test]
+fn ntfs_compressed_files_are_decompressed_first() {
+    let strategy = Strategy::choose(ntfs(true, false));
+    assert_eq!(strategy.steps[..2], [Step::Decompress, Step::Overwrite]);
+    assert_eq!(Score::of([&strategy]).confidence, Confidence::Reduced);
+
+    let fallback = strategy.without_decompression();
+    assert!(!fallback.steps.contains(&Step::Decompress));
+    assert!(fallback.to_string().contains("could not be switched off"));
+    assert_eq!(Score::of([&fallback]).confidence, Confidence::BestEffort);
+}
+
+#[test]
+fn efs_files_are_overwritten_encrypted() {
+    let strategy = Strategy::choose(ntfs(false, true));
+    assert_eq!(strategy.steps[0], Step::Overwrite);
+    assert!(strategy.to_string().contains("EFS-encrypted"));
+    assert_eq!(Score::of([&strategy]).reasons, vec!["EFS"]);
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::history::Outcome;
+use crate::summary::{self, Batch, Fate, Tally};
+
+#[test]
+fn the_tally_reads_as_one_line() {
+    let tally = Tally { succeeded: 12, skipped: 3, failed: 1 };
+    assert_eq!(tally.to_string(), "12 succeeded, 3 skipped, 1 failed");
+}
+
+#[test]
+fn the_average_speed_needs_bytes_and_time() {
+    assert_eq!(summary::speed(10 * 1024 * 1024, Duration::from_secs(2)), Some(5.0 * 1024.0 * 1024.0));
+    assert_eq!(summary::speed(0, Duration::from_secs(2)), None);
+    assert_eq!(summary::speed(4096, Duration::from_millis(10)), None);
+}
+
+#[test]
+fn a_cancelled_batch_keeps_what_it_destroyed() {
+    let files: Vec<PathBuf> = ["a", "b", "c", "d", "e"].iter().map(PathBuf::from).collect();
+    let failures = vec![(files[1].clone(), "Permission denied".to_string())];
+    // Cancelled while the fourth file was being overwritten.
+    let fates = summary::fates(&files, &failures, Some(3), Outcome::Cancelled);
+    assert_eq!(fates, [
+        Fate::Destroyed,
+        Fate::Failed("Permission denied".to_string()),
+        Fate::Destroyed,
+        Fate::Failed("Cancelled while it was being erased".to_string()),
+        Fate::Skipped("Not attempted: the job was cancelled".to_string()),
+    ]);
+
+    let batch = Batch::sort(&files, &fates);
+    assert_eq!(batch.erased, [files[0].clone(), files[2].clone()]);
+    assert_eq!(batch.failed.iter().map(|(path, _)| path).collect::<Vec<_>>(), [&files[1], &files[3]]);
+    assert_eq!(batch.skipped, [(files[4].clone(), "Not attempted: the job was cancelled".to_string())]);
+}
+
+#[test]
+fn a_batch_cancelled_before_the_first_file_destroyed_nothing() {
+    let files: Vec<PathBuf> = ["a", "b"].iter().map(PathBuf::from).collect();
+    let batch = Batch::sort(&files, &summary::fates(&files, &[], None, Outcome::Cancelled));
+    assert!(batch.erased.is_empty() && batch.failed.is_empty());
+    assert_eq!(batch.skipped.len(), 2);
+}
+
+#[test]
+fn failures_are_failed_even_when_no_file_was_named() {
+    // Drive wipes report failures without ever starting a file.
+    let files: Vec<PathBuf> = ["/dev/sdb", "/dev/sdc"].iter().map(PathBuf::from).collect();
+    let failures = vec![(files[0].clone(), "I/O error".to_string())];
+    let batch = Batch::sort(&files, &summary::fates(&files, &failures, None, Outcome::Failed));
+    assert_eq!(batch.erased, [files[1].clone()]);
+    assert_eq!(batch.failed, failures);
+    assert!(batch.skipped.is_empty());
+}
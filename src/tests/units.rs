@@ -0,0 +1,35 @@
+use std::time::Duration;
+use crate::units::{self, Base, Style};
+
+#[test]
+fn sizes_follow_the_base_and_the_locale() {
+    let binary = Style::for_locale("en_US.UTF-8", Base::Binary);
+    assert_eq!(binary.size(512), "512 B");
+    assert_eq!(binary.size(1536 * 1024), "1.5 MiB");
+    let german = Style::for_locale("de_DE.UTF-8", Base::Decimal);
+    assert_eq!(german.size(1_500_000), "1,5 MB");
+    assert_eq!(german.speed(2_250_000_000.0), "2,2 GB/s");
+    assert_eq!(Style::for_locale("C", Base::Decimal).size(999), "999 B");
+}
+
+#[test]
+fn locales_pick_separator_and_direction() {
+    assert_eq!(Style::for_locale("", Base::Binary), Style::DEFAULT);
+    assert_eq!(Style::for_locale("fr-FR", Base::Binary).decimal, ',');
+    let persian = Style::for_locale("fa_IR.UTF-8", Base::Binary);
+    assert_eq!((persian.decimal, persian.right_to_left), ('\u{066B}', true));
+    assert!(Style::for_locale("he_IL", Base::Binary).right_to_left);
+    assert!(!Style::for_locale("de_DE@euro", Base::Binary).right_to_left);
+}
+
+#[test]
+fn durations_read_naturally() {
+    let style = Style::for_locale("nl_NL", Base::Binary);
+    assert_eq!(style.duration(Duration::from_millis(12_340)), "12,3 s");
+    assert_eq!(style.duration(Duration::from_secs(4 * 60 + 5)), "4 min 05 s");
+    assert_eq!(style.duration(Duration::from_secs(2 * 3600 + 5 * 60)), "2 h 05 min");
+    assert_eq!(units::clock(Duration::from_secs(3 * 3600 + 7)), "3:00:07");
+    assert_eq!(units::about(Duration::from_secs(20)), "under a minute");
+    assert_eq!(units::about(Duration::from_secs(9 * 60 + 1)), "about 10 minutes");
+    assert_eq!(units::about(Duration::from_secs(2 * 3600 + 5 * 60)), "about 2 h 05 min");
+}
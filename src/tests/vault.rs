@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::db::Database;
+use crate::history::{FileEntry, Outcome, Record};
+use argon2::Params;
+use crate::vault::{self, Key};
+use super::Scratch;
+
+/// Cheap enough for tests; real vaults use the Argon2 defaults.
+fn params() -> Params {
+    Params::new(8, 1, 1, None).unwrap()
+}
+
+#[test]
+fn sealed_data_opens_only_with_its_key_and_context() {
+    let key = Key::derive("correct horse", b"saltsalt", params()).unwrap();
+    let sealed = key.seal(b"/home/user/secret.txt", "job 1");
+    assert!(!sealed.windows(6).any(|window| window == b"secret"));
+    assert_eq!(key.open(&sealed, "job 1").as_deref(), Some(&b"/home/user/secret.txt"[..]));
+    // Fresh nonces, so the same text never seals the same way twice.
+    assert_ne!(key.seal(b"/home/user/secret.txt", "job 1"), sealed);
+
+    assert_eq!(key.open(&sealed, "job 2"), None);
+    assert_eq!(Key::derive("battery staple", b"saltsalt", params()).unwrap().open(&sealed, "job 1"), None);
+    let mut tampered = sealed.clone();
+    tampered[14] ^= 1;
+    assert_eq!(key.open(&tampered, "job 1"), None);
+    assert_eq!(key.open(&sealed[..20], "job 1"), None);
+    assert_eq!(key.open(&sealed[..5], "job 1"), None);
+}
+
+#[test]
+fn a_sealed_history_reads_back_after_unlocking() {
+    let scratch = Scratch::new("vault");
+    fs::write(scratch.path().join("history.log"), "1700000000\tsuccess\t1\t10\t5\tRandom (1 pass)\t/home/user/legacy.txt\n").unwrap();
+    let mut db = Database::open_at(scratch.path()).unwrap();
+    db.insert(record("/home/user/plain.txt")).unwrap();
+
+    let vault = scratch.path().join(vault::FILE);
+    db.seal(vault::create_at(&vault, "correct horse", params()).unwrap()).unwrap();
+    db.insert(record("/home/user/sealed.txt")).unwrap();
+
+    let text = fs::read_to_string(scratch.path().join("history.db")).unwrap();
+    assert!(!["plain.txt", "sealed.txt", "legacy.txt"].iter().any(|name| text.contains(name)), "{}", text);
+    // Neither the plain database nor the imported old log is left behind.
+    assert_eq!(scratch.entries(), ["history.db", "vault"]);
+
+    assert_eq!(vault::unlock_at(&vault, "battery staple").err().map(|e| e.kind()), Some(std::io::ErrorKind::PermissionDenied));
+    let mut db = Database::open_at(scratch.path()).unwrap();
+    assert!(db.records().is_empty());
+    db.unlock(vault::unlock_at(&vault, "correct horse").unwrap());
+    let paths: Vec<&PathBuf> = db.records().iter().flat_map(|record| &record.entries).map(|entry| &entry.path).collect();
+    assert_eq!(paths, [&PathBuf::from("/home/user/plain.txt"), &PathBuf::from("/home/user/sealed.txt")]);
+    assert_eq!(db.records().iter().map(|record| record.id).collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(db.records()[0].description, "/home/user/legacy.txt");
+}
+
+#[test]
+fn sealed_rows_cannot_trade_places() {
+    let scratch = Scratch::new("vault-swap");
+    let vault = scratch.path().join(vault::FILE);
+    let key = vault::create_at(&vault, "correct horse", params()).unwrap();
+    let mut db = Database::open_at(scratch.path()).unwrap();
+    db.unlock(key.clone());
+    db.insert(record("/home/user/first.txt")).unwrap();
+    db.insert(record("/home/user/second.txt")).unwrap();
+
+    // The second job's sealed data under the first job's id.
+    let path = scratch.path().join("history.db");
+    let text = fs::read_to_string(&path).unwrap();
+    let sealed: Vec<&str> = text.lines().filter_map(|line| line.strip_prefix("sealed ")).collect();
+    let second = sealed[1].split_once(' ').unwrap().1;
+    fs::write(&path, text.replace(sealed[0], &format!("1 {}", second))).unwrap();
+
+    let mut db = Database::open_at(scratch.path()).unwrap();
+    db.unlock(key);
+    assert_eq!(db.records().iter().map(|record| record.id).collect::<Vec<_>>(), [2]);
+}
+
+fn record(path: &str) -> Record {
+    Record {
+        id: 0,
+        finished: 1_700_000_000,
+        outcome: Outcome::Success,
+        files: 1,
+        bytes: 10,
+        duration: Duration::from_millis(5),
+        method: "Random (1 pass)".to_string(),
+        description: "1 file(s)".to_string(),
+        preset: String::new(),
+        tag: String::new(),
+        user: "tester".to_string(),
+        operator: String::new(),
+        verification: "None".to_string(),
+        entries: vec![FileEntry { path: PathBuf::from(path), bytes: 10, error: None, strategy: String::new() }],
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::worker::Worker;
+
+#[test]
+fn dropping_the_handle_stops_the_job() {
+    let stopped = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let stopped = stopped.clone();
+        Worker::spawn(move |cancel| {
+            while !cancel.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            stopped.store(true, Ordering::Relaxed);
+        })
+    };
+    drop(worker);
+    assert!(stopped.load(Ordering::Relaxed));
+}
+
+#[test]
+fn a_crash_is_reported_by_join() {
+    let worker = Worker::spawn(|_| panic!("disk caught fire"));
+    assert_eq!(worker.join(), Err("disk caught fire".to_string()));
+}
+
+#[test]
+fn shutdown_gives_up_on_a_job_that_does_not_stop() {
+    let worker = Worker::spawn(|_| std::thread::sleep(Duration::from_secs(2)));
+    let started = std::time::Instant::now();
+    worker.shutdown(Duration::from_millis(50));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
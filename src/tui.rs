@@ -0,0 +1,476 @@
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::engine::{self, Progress};
+use crate::method::{Method, Registry};
+use crate::progress::Phase;
+use crate::policy::Policy;
+use crate::settings::Settings;
+use crate::worker::Worker;
+use crate::{db, history, identity, mail, progress, units, webhook};
+
+const HELP: &str = "a add  d remove  m method  e erase  c/Esc cancel  q quit  \u{2191}\u{2193} select";
+
+/// Where a queued file stands.
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+    Queued,
+    Erasing(f32),
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+struct Entry {
+    path: PathBuf,
+    bytes: u64,
+    state: State,
+    strategy: String,
+}
+
+/// What the keyboard is currently doing.
+enum Mode {
+    Normal,
+    /// Typing the path of a file to add.
+    Adding(String),
+    /// Waiting for `y` before the queue is erased.
+    Confirm,
+}
+
+/// How often progress is redrawn while no key is pressed.
+const REDRAW_MS: i32 = 100;
+
+/// Terminal version of the Erase tab for machines without a display. It
+/// drives the same engine and reads the same `Progress` messages as the GUI.
+struct Tui<'a> {
+    methods: &'a Registry,
+    method: usize,
+    settings: &'a Settings,
+    policy: &'a Policy,
+    queue: Vec<Entry>,
+    selected: usize,
+    mode: Mode,
+    receiver: Option<progress::Receiver>,
+    worker: Option<Worker>,
+    current: usize,
+    /// What the running job is doing, shown under the speed.
+    phase: Phase,
+    started: Instant,
+    verification: engine::Verification,
+    message: String,
+}
+
+/// Puts the terminal into raw mode and restores it when dropped, so a
+/// crash does not leave the shell unusable.
+struct RawMode(libc::termios);
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        // SAFETY: tcgetattr fills in the zeroed termios on success.
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+        // SAFETY: `raw` is a valid termios derived from the current one.
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        print!("\x1b[?25l\x1b[?1049h");
+        Ok(Self(original))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?1049l\x1b[?25h");
+        let _ = io::stdout().flush();
+        // SAFETY: restores the termios saved in `enable`.
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0) };
+    }
+}
+
+/// Runs the terminal UI until `q`, starting with `paths` queued.
+pub fn run(
+    paths: Vec<PathBuf>,
+    methods: &Registry,
+    method: &Method,
+    settings: &Settings,
+    policy: &Policy,
+) -> io::Result<()> {
+    let method = methods.methods().iter().position(|known| known == method).unwrap_or(0);
+    let mut tui = Tui {
+        methods,
+        method,
+        settings,
+        policy,
+        queue: Vec::new(),
+        selected: 0,
+        mode: Mode::Normal,
+        receiver: None,
+        worker: None,
+        current: 0,
+        phase: Phase::default(),
+        started: Instant::now(),
+        verification: engine::Verification::None,
+        message: "Ready".to_string(),
+    };
+    for path in paths {
+        tui.add(path);
+    }
+
+    let _raw = RawMode::enable()?;
+    let mut stdin = io::stdin();
+    let mut buffer = [0u8; 64];
+    loop {
+        tui.drain();
+        tui.draw()?;
+        let mut poll = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: one valid pollfd; the timeout keeps progress redrawing.
+        let ready = unsafe { libc::poll(&mut poll, 1, REDRAW_MS) };
+        if ready <= 0 {
+            continue;
+        }
+        let read = stdin.read(&mut buffer)?;
+        if !tui.key(&buffer[..read]) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Tui<'_> {
+    fn running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    fn add(&mut self, path: PathBuf) {
+        if self.queue.iter().any(|entry| entry.path == path) {
+            return;
+        }
+        if let Err(e) = self.policy.check_path(&path) {
+            self.message = e.to_string();
+            return;
+        }
+        match std::fs::metadata(&path) {
+            Ok(meta) if meta.is_file() => self.queue.push(Entry {
+                bytes: meta.len(),
+                path,
+                state: State::Queued,
+                strategy: String::new(),
+            }),
+            Ok(_) => self.message = format!("{} is not a file", path.display()),
+            Err(e) => self.message = format!("{}: {}", path.display(), e),
+        }
+    }
+
+    /// Handles one read from the keyboard. Returns `false` to quit.
+    fn key(&mut self, input: &[u8]) -> bool {
+        match &mut self.mode {
+            Mode::Adding(path) => {
+                for byte in input {
+                    match byte {
+                        b'\r' | b'\n' => {
+                            let path = PathBuf::from(path.trim());
+                            self.mode = Mode::Normal;
+                            self.add(path);
+                            return true;
+                        }
+                        0x1b => {
+                            self.mode = Mode::Normal;
+                            return true;
+                        }
+                        0x7f | 0x08 => {
+                            path.pop();
+                        }
+                        byte if *byte >= 0x20 => path.push(*byte as char),
+                        _ => {}
+                    }
+                }
+                return true;
+            }
+            Mode::Confirm => {
+                self.mode = Mode::Normal;
+                if input == b"y" || input == b"Y" {
+                    self.erase();
+                } else {
+                    self.message = "Not erased".to_string();
+                }
+                return true;
+            }
+            Mode::Normal => {}
+        }
+
+        match input {
+            b"q" if !self.running() => return false,
+            b"q" => self.message = "Cancel the running job first".to_string(),
+            b"a" if !self.running() => self.mode = Mode::Adding(String::new()),
+            b"d" if !self.running() && self.selected < self.queue.len() => {
+                self.queue.remove(self.selected);
+                self.selected = self.selected.min(self.queue.len().saturating_sub(1));
+            }
+            b"m" if !self.running() => {
+                let methods = self.methods.methods();
+                // Skip whatever the policy does not allow; the current method always is.
+                self.method = (1..=methods.len())
+                    .map(|step| (self.method + step) % methods.len())
+                    .find(|index| self.policy.allows_method(&methods[*index]))
+                    .unwrap_or(self.method);
+            }
+            b"e" if !self.running() && !self.queue.is_empty() => self.mode = Mode::Confirm,
+            b"c" | b"\x1b" if self.running() => {
+                if let Some(worker) = &self.worker {
+                    worker.cancel();
+                }
+                self.message = "Cancelling...".to_string();
+            }
+            b"\x1b[A" => self.selected = self.selected.saturating_sub(1),
+            b"\x1b[B" => self.selected = (self.selected + 1).min(self.queue.len().saturating_sub(1)),
+            _ => {}
+        }
+        true
+    }
+
+    fn erase(&mut self) {
+        let method = self.methods.methods()[self.method].clone();
+        if let Err(e) = self.policy.check_method(&method) {
+            self.message = e;
+            return;
+        }
+        let verification = self.settings.verification.at_least(if method.verify() {
+            engine::Verification::LastPass
+        } else {
+            engine::Verification::None
+        });
+        let options = engine::Options {
+            passes: method.passes(),
+            verification,
+            timestamps: self.settings.timestamps,
+            churn_directories: self.settings.churn_directories,
+            simulate: self.settings.simulate,
+            checksums: self.settings.checksums,
+        };
+        for entry in &mut self.queue {
+            entry.state = State::Queued;
+        }
+        let paths: Vec<PathBuf> = self.queue.iter().map(|entry| entry.path.clone()).collect();
+        let (tx, rx) = progress::channel();
+        self.receiver = Some(rx);
+        self.current = 0;
+        self.phase = Phase::default();
+        self.started = Instant::now();
+        self.verification = verification;
+        self.message = format!("Erasing with {}", method);
+        self.worker = Some(Worker::spawn(move |cancel| engine::erase_all(&paths, &options, cancel, &tx)));
+    }
+
+    /// Applies every progress message that arrived since the last redraw.
+    fn drain(&mut self) {
+        let Some(receiver) = self.receiver.clone() else {
+            return;
+        };
+        for progress in receiver.try_iter() {
+            self.phase.follow(&progress);
+            match progress {
+                Progress::Started(index) => {
+                    self.current = index;
+                    if let Some(entry) = self.queue.get_mut(index) {
+                        entry.state = State::Erasing(0.0);
+                    }
+                }
+                Progress::Updated(value) => {
+                    if let Some(entry) = self.queue.get_mut(self.current) {
+                        entry.state = State::Erasing(value);
+                    }
+                }
+                Progress::Failed(index, error) => {
+                    if let Some(entry) = self.queue.get_mut(index) {
+                        entry.state = State::Failed(error);
+                    }
+                }
+                Progress::Unsanitized(index, mismatch) => {
+                    if let Some(entry) = self.queue.get_mut(index) {
+                        entry.state = State::Failed(mismatch.to_string());
+                    }
+                }
+                Progress::Strategy(strategy) => {
+                    if let Some(entry) = self.queue.get_mut(self.current) {
+                        entry.strategy = strategy.to_string();
+                    }
+                }
+                Progress::Verified(_) => {
+                    if let Some(entry) = self.queue.get_mut(self.current) {
+                        entry.state = State::Done;
+                    }
+                }
+                Progress::Finished(success) => {
+                    self.finish(if success { history::Outcome::Success } else { history::Outcome::Failed });
+                }
+                Progress::Cancelled => self.finish(history::Outcome::Cancelled),
+                Progress::DriveUpdated(..) | Progress::DriveFinished(..) | Progress::CryptoErased(_)
+                | Progress::MemoryBacked(_) | Progress::MtpReplaced(_) | Progress::ShadowCopies(_) => {}
+                // Followed by `phase` above.
+                Progress::ScanStarted(_) | Progress::ScanUpdated(_) | Progress::PassStarted(..)
+                | Progress::WipeUpdated(_) | Progress::VerifyUpdated(_) | Progress::CleanupDone => {}
+            }
+        }
+    }
+
+    fn finish(&mut self, outcome: history::Outcome) {
+        self.receiver = None;
+        for entry in &mut self.queue {
+            if matches!(entry.state, State::Queued | State::Erasing(_)) {
+                entry.state = State::Cancelled;
+            }
+        }
+        let entries: Vec<history::FileEntry> = self.queue
+            .iter()
+            .map(|entry| history::FileEntry {
+                path: entry.path.clone(),
+                bytes: entry.bytes,
+                error: match &entry.state {
+                    State::Done => None,
+                    State::Failed(error) => Some(error.clone()),
+                    _ => Some("Cancelled".to_string()),
+                },
+                strategy: entry.strategy.clone(),
+            })
+            .collect();
+        let destroyed = entries.iter().filter(|entry| entry.error.is_none());
+        let record = history::Record {
+            id: 0,
+            finished: history::unix_now(),
+            description: format!("{} file(s) from the terminal", entries.len()),
+            method: self.methods.methods()[self.method].to_string(),
+            preset: String::new(),
+            tag: self.settings.tag.clone(),
+            user: identity::os_user(),
+            operator: self.settings.operator.clone(),
+            verification: self.verification.to_string(),
+            files: destroyed.clone().count(),
+            bytes: destroyed.map(|entry| entry.bytes).sum(),
+            duration: self.started.elapsed(),
+            outcome,
+            entries,
+        };
+        self.message = format!(
+            "{}: {} of {} file(s) erased",
+            match outcome {
+                history::Outcome::Success => "Finished",
+                history::Outcome::Cancelled => "Cancelled",
+                _ => "Finished with errors",
+            },
+            record.files,
+            self.queue.len(),
+        );
+        if let Some(Err(e)) = self.worker.take().map(Worker::join) {
+            self.message += &format!(" (the erase thread crashed: {})", e);
+        }
+        if self.settings.simulate.is_some() {
+            self.message += " (simulated, not recorded)";
+        } else {
+            match db::Database::open().and_then(|mut db| db.insert(record).map(|id| (db, id))) {
+                Ok((db, id)) => {
+                    let record = db.get(id).expect("record was just inserted");
+                    self.policy.file_reports(record, db.key());
+                    webhook::notify(self.settings, record);
+                    mail::notify(self.settings, record);
+                }
+                Err(e) => self.message += &format!(" (history not saved: {})", e),
+            }
+        }
+        self.queue.retain(|entry| entry.state != State::Done);
+        self.selected = self.selected.min(self.queue.len().saturating_sub(1));
+    }
+
+    /// Bytes per second over the whole queue since the job started.
+    fn speed(&self) -> Option<f64> {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let done: f64 = self.queue
+            .iter()
+            .map(|entry| match entry.state {
+                State::Done => entry.bytes as f64,
+                State::Erasing(value) => entry.bytes as f64 * value as f64 / 100.0,
+                _ => 0.0,
+            })
+            .sum();
+        (elapsed >= 1.0).then(|| done / elapsed)
+    }
+
+    fn draw(&self) -> io::Result<()> {
+        let width = terminal_width().clamp(40, 200);
+        let mut out = String::from("\x1b[H\x1b[2J");
+        out += &format!("File Eraser - method: {}\r\n", self.methods.methods()[self.method]);
+        out += &format!("{}\r\n\r\n", HELP);
+
+        if self.queue.is_empty() {
+            out += "  The queue is empty, press a to add a file.\r\n";
+        }
+        let bar_width = 20;
+        for (index, entry) in self.queue.iter().enumerate() {
+            let marker = if index == self.selected { '>' } else { ' ' };
+            let (fill, status) = match &entry.state {
+                State::Queued => (0.0, "queued".to_string()),
+                State::Erasing(value) => (*value, format!("{:5.1}%", value)),
+                State::Done => (100.0, "done".to_string()),
+                State::Failed(error) => (0.0, format!("failed: {}", error)),
+                State::Cancelled => (0.0, "cancelled".to_string()),
+            };
+            let filled = (fill / 100.0 * bar_width as f32).round() as usize;
+            let bar: String = "\u{2588}".repeat(filled) + &"\u{2591}".repeat(bar_width - filled.min(bar_width));
+            let name = entry.path.display().to_string();
+            let room = width.saturating_sub(bar_width + 30);
+            let name = if name.chars().count() > room {
+                let tail: String = name.chars().rev().take(room.saturating_sub(3)).collect::<Vec<_>>().into_iter().rev().collect();
+                format!("...{}", tail)
+            } else {
+                name
+            };
+            out += &format!(
+                "{} {} {:>10}  {}  {}\r\n",
+                marker,
+                bar,
+                units::size(entry.bytes),
+                name,
+                status,
+            );
+        }
+
+        out += "\r\n";
+        if self.running() {
+            match self.speed() {
+                Some(speed) => out += &format!("Speed: {}\r\n", units::speed(speed)),
+                None => out += "Speed: -\r\n",
+            }
+            out += &format!("{}\r\n", self.phase);
+        }
+        match &self.mode {
+            Mode::Adding(path) => out += &format!("Add file: {}_\r\n", path),
+            Mode::Confirm => out += &format!(
+                "Erase {} file(s)? They cannot be recovered. Press y to confirm.\r\n",
+                self.queue.len(),
+            ),
+            Mode::Normal => out += &format!("{}\r\n", self.message),
+        }
+        let mut stdout = io::stdout();
+        stdout.write_all(out.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+fn terminal_width() -> usize {
+    // SAFETY: TIOCGWINSZ fills in the zeroed winsize on success.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0 && size.ws_col > 0 {
+        size.ws_col as usize
+    } else {
+        80
+    }
+}
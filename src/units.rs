@@ -0,0 +1,193 @@
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// How sizes are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base {
+    /// Powers of 1024: KiB, MiB, GiB. What file managers on Linux show.
+    Binary,
+    /// Powers of 1000: kB, MB, GB. What drive labels and macOS show.
+    Decimal,
+}
+
+impl Base {
+    pub const ALL: [Base; 2] = [Base::Binary, Base::Decimal];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Base::Binary => "binary",
+            Base::Decimal => "decimal",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|base| base.key() == key)
+    }
+
+    fn step(self) -> f64 {
+        match self {
+            Base::Binary => 1024.0,
+            Base::Decimal => 1000.0,
+        }
+    }
+
+    fn units(self) -> [&'static str; 5] {
+        match self {
+            Base::Binary => ["B", "KiB", "MiB", "GiB", "TiB"],
+            Base::Decimal => ["B", "kB", "MB", "GB", "TB"],
+        }
+    }
+}
+
+impl std::fmt::Display for Base {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base::Binary => write!(f, "Binary (KiB, MiB, GiB)"),
+            Base::Decimal => write!(f, "Decimal (kB, MB, GB)"),
+        }
+    }
+}
+
+/// Languages written right to left, for the window's layout.
+const RIGHT_TO_LEFT: [&str; 8] = ["ar", "ckb", "dv", "fa", "he", "ps", "ur", "yi"];
+/// Languages that write a decimal comma. Everything else gets a point.
+const DECIMAL_COMMA: [&str; 38] = [
+    "az", "be", "bg", "bs", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fo", "fr", "gl", "hr", "hu", "id",
+    "is", "it", "ka", "kk", "ky", "lt", "lv", "mk", "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sq",
+];
+
+/// How numbers are written for the person at the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub base: Base,
+    pub decimal: char,
+    pub right_to_left: bool,
+}
+
+impl Style {
+    pub const DEFAULT: Style = Style { base: Base::Binary, decimal: '.', right_to_left: false };
+
+    /// The style for a locale name such as `de_DE.UTF-8`, `fa-IR` or `C`.
+    pub fn for_locale(locale: &str, base: Base) -> Self {
+        let language = locale.split(['_', '-', '.', '@']).next().unwrap_or_default().to_ascii_lowercase();
+        let decimal = match language.as_str() {
+            // Persian has a decimal separator of its own.
+            "fa" => '\u{066B}',
+            language if DECIMAL_COMMA.contains(&language) => ',',
+            _ => '.',
+        };
+        Style { base, decimal, right_to_left: RIGHT_TO_LEFT.contains(&language.as_str()) }
+    }
+
+    /// The style for the user's locale.
+    pub fn detect(base: Base) -> Self {
+        Self::for_locale(&locale(), base)
+    }
+
+    /// `value` with `decimals` digits after the locale's separator.
+    pub fn number(self, value: f64, decimals: usize) -> String {
+        let text = format!("{:.*}", decimals, value);
+        match self.decimal {
+            '.' => text,
+            decimal => text.replace('.', &decimal.to_string()),
+        }
+    }
+
+    pub fn size(self, bytes: u64) -> String {
+        let units = self.base.units();
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= self.base.step() && unit < units.len() - 1 {
+            value /= self.base.step();
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{} B", bytes)
+        } else {
+            format!("{} {}", self.number(value, 1), units[unit])
+        }
+    }
+
+    pub fn speed(self, bytes_per_second: f64) -> String {
+        format!("{}/s", self.size(bytes_per_second as u64))
+    }
+
+    /// How long something took: tenths of a second for short jobs, whole
+    /// minutes and hours for long ones.
+    pub fn duration(self, duration: Duration) -> String {
+        let secs = duration.as_secs();
+        match secs {
+            0..60 => format!("{} s", self.number(duration.as_secs_f64(), 1)),
+            60..3600 => format!("{} min {:02} s", secs / 60, secs % 60),
+            _ => format!("{} h {:02} min", secs / 3600, secs / 60 % 60),
+        }
+    }
+}
+
+/// A time left, as a running clock.
+pub fn clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// A rough duration, for estimates that are no better than that.
+pub fn about(duration: Duration) -> String {
+    let minutes = duration.as_secs().div_ceil(60);
+    match minutes {
+        0..=1 => "under a minute".to_string(),
+        2..=59 => format!("about {} minutes", minutes),
+        _ => format!("about {} h {:02} min", minutes / 60, minutes % 60),
+    }
+}
+
+#[cfg(not(windows))]
+fn locale() -> String {
+    ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+#[cfg(windows)]
+fn locale() -> String {
+    unsafe extern "system" {
+        fn GetUserDefaultLocaleName(name: *mut u16, length: i32) -> i32;
+    }
+    // LOCALE_NAME_MAX_LENGTH.
+    let mut name = [0u16; 85];
+    // SAFETY: the buffer is as long as we say it is.
+    let length = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    String::from_utf16_lossy(&name[..(length.max(1) - 1) as usize])
+}
+
+static STYLE: RwLock<Style> = RwLock::new(Style::DEFAULT);
+
+/// The style everything is shown in, set once the settings are known.
+pub fn style() -> Style {
+    *STYLE.read().expect("units lock")
+}
+
+/// Follows the user's locale, with sizes counted in `base`.
+pub fn apply(base: Base) {
+    *STYLE.write().expect("units lock") = Style::detect(base);
+}
+
+/// A size in the user's style, for the interface, the command line and
+/// reports alike.
+pub fn size(bytes: u64) -> String {
+    style().size(bytes)
+}
+
+pub fn speed(bytes_per_second: f64) -> String {
+    style().speed(bytes_per_second)
+}
+
+pub fn duration(duration: Duration) -> String {
+    style().duration(duration)
+}
+
+/// Whether the window should be laid out right to left.
+pub fn right_to_left() -> bool {
+    style().right_to_left
+}
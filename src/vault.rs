@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+use rand::RngCore;
+use crate::hash::{hex, hmac_sha256, unhex};
+use crate::reports;
+
+const HEADER: &str = "# file-eraser vault";
+/// Name of the vault next to the history database.
+pub const FILE: &str = "vault";
+const NONCE: usize = 12;
+
+/// The key that seals the history and certificates, derived from the user's
+/// passphrase with Argon2id. ChaCha20-Poly1305 seals, with what was sealed
+/// (a history row, a certificate) as associated data so nothing can be
+/// swapped into another slot.
+#[derive(Clone)]
+pub struct Key {
+    cipher: [u8; 32],
+    check: [u8; 32],
+}
+
+impl Key {
+    pub fn derive(passphrase: &str, salt: &[u8], params: Params) -> io::Result<Self> {
+        let mut master = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut master)
+            .map_err(|e| io::Error::other(format!("Cannot derive the key: {}", e)))?;
+        Ok(Self {
+            cipher: hmac_sha256(&master, b"file-eraser cipher"),
+            check: hmac_sha256(&master, b"file-eraser check"),
+        })
+    }
+
+    /// Encrypts `plain` under a fresh random nonce: nonce, then ciphertext
+    /// and tag. `context` names what is sealed and has to be the same to
+    /// open it.
+    pub fn seal(&self, plain: &[u8], context: &str) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let sealed = ChaCha20Poly1305::new(&self.cipher.into())
+            .encrypt(&nonce.into(), Payload { msg: plain, aad: context.as_bytes() })
+            .expect("ChaCha20-Poly1305 seals any length that fits in memory");
+        [&nonce[..], &sealed].concat()
+    }
+
+    /// The plaintext of something `seal` made for `context`, or `None` when
+    /// it was sealed under another key or for something else, or changed
+    /// since.
+    pub fn open(&self, sealed: &[u8], context: &str) -> Option<Vec<u8>> {
+        let (nonce, sealed) = sealed.split_at_checked(NONCE)?;
+        ChaCha20Poly1305::new(&self.cipher.into())
+            .decrypt(nonce.into(), Payload { msg: sealed, aad: context.as_bytes() })
+            .ok()
+    }
+}
+
+/// `<data dir>/file-eraser/vault`, which holds the salt and a check value
+/// for the passphrase, never the passphrase or the key.
+fn path() -> io::Result<PathBuf> {
+    Ok(reports::data_dir()?.join(FILE))
+}
+
+/// Whether the history and certificates are encrypted.
+pub fn enabled() -> bool {
+    path().is_ok_and(|path| path.exists())
+}
+
+/// Sets up encryption with `passphrase`, returning the key.
+pub fn create(passphrase: &str) -> io::Result<Key> {
+    create_at(&path()?, passphrase, Params::default())
+}
+
+/// `params` are stored in the vault, so new vaults can be made costlier
+/// later without locking anyone out of an old one.
+pub fn create_at(path: &Path, passphrase: &str, params: Params) -> io::Result<Key> {
+    if passphrase.is_empty() {
+        return Err(io::Error::other("The passphrase is empty"));
+    }
+    let mut salt = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let key = Key::derive(passphrase, &salt, params.clone())?;
+    let text = format!(
+        "{}\nkdf argon2id {} {} {} {}\ncheck {}\n",
+        HEADER,
+        params.m_cost(),
+        params.t_cost(),
+        params.p_cost(),
+        hex(&salt),
+        hex(&key.check),
+    );
+    reports::write(path, text.as_bytes())?;
+    Ok(key)
+}
+
+/// The key for the history and certificates, if `passphrase` is right.
+pub fn unlock(passphrase: &str) -> io::Result<Key> {
+    unlock_at(&path()?, passphrase)
+}
+
+pub fn unlock_at(path: &Path, passphrase: &str) -> io::Result<Key> {
+    let text = fs::read_to_string(path)?;
+    let damaged = || io::Error::new(io::ErrorKind::InvalidData, "The vault file is damaged");
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(damaged());
+    }
+    let kdf: Vec<&str> = lines.next().ok_or_else(damaged)?.split(' ').collect();
+    let (params, salt) = match kdf[..] {
+        ["kdf", "argon2id", memory, passes, lanes, salt] => {
+            let number = |text: &str| text.parse().map_err(|_| damaged());
+            let params = Params::new(number(memory)?, number(passes)?, number(lanes)?, None).map_err(|_| damaged())?;
+            (params, unhex(salt).ok_or_else(damaged)?)
+        }
+        _ => return Err(damaged()),
+    };
+    let check = lines.next().and_then(|line| line.strip_prefix("check ")).ok_or_else(damaged)?;
+    let key = Key::derive(passphrase, &salt, params)?;
+    if hex(&key.check) != check {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Wrong passphrase"));
+    }
+    Ok(key)
+}
+
+/// The key from `FILE_ERASER_PASSPHRASE`, for the command line, the
+/// terminal interface and agents, which have nobody to ask.
+pub fn from_environment() -> Option<Key> {
+    if !enabled() {
+        return None;
+    }
+    let passphrase = std::env::var("FILE_ERASER_PASSPHRASE").ok()?;
+    unlock(&passphrase)
+        .inspect_err(|e| eprintln!("Cannot unlock the history with FILE_ERASER_PASSPHRASE: {}", e))
+        .ok()
+}
+
+/// The error for writing a record while the vault is locked.
+pub fn locked_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "The history is encrypted and locked; unlock it or set FILE_ERASER_PASSPHRASE",
+    )
+}
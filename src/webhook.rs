@@ -0,0 +1,72 @@
+use std::fmt::Write as _;
+use std::io;
+use crate::certificate::quote;
+use crate::hash::{hex, hmac_sha256, sha256};
+use crate::history::{Outcome, Record};
+use crate::http::{self, Url};
+use crate::settings::Settings;
+
+/// The JSON sent when a job ends. Paths are replaced by their SHA-256, so a
+/// SIEM can match them against its own records without the names of what
+/// was destroyed leaving the machine.
+fn payload(record: &Record) -> String {
+    let mut out = String::from("{\n");
+    let event = match record.outcome {
+        Outcome::Success => "job.finished",
+        Outcome::Cancelled => "job.cancelled",
+        Outcome::Failed | Outcome::Unsanitized => "job.failed",
+    };
+    let _ = writeln!(out, "  \"event\": \"{}\",", event);
+    let _ = writeln!(out, "  \"job_id\": {},", record.id);
+    let _ = writeln!(out, "  \"finished_unix\": {},", record.finished);
+    let _ = writeln!(out, "  \"method\": {},", quote(&record.method));
+    let _ = writeln!(out, "  \"verification\": {},", quote(&record.verification));
+    let _ = writeln!(out, "  \"outcome\": \"{}\",", record.outcome.key());
+    let _ = writeln!(out, "  \"files_erased\": {},", record.files);
+    let _ = writeln!(out, "  \"bytes_erased\": {},", record.bytes);
+    let _ = writeln!(out, "  \"duration_ms\": {},", record.duration.as_millis());
+    out += "  \"files\": [";
+    for (index, entry) in record.entries.iter().enumerate() {
+        out += if index == 0 { "\n" } else { ",\n" };
+        let path = entry.path.to_string_lossy();
+        let _ = write!(
+            out,
+            "    {{ \"path_sha256\": \"{}\", \"bytes\": {}, \"erased\": {}, \"error\": {} }}",
+            hex(&sha256(path.as_bytes())),
+            entry.bytes,
+            entry.error.is_none(),
+            entry.error.as_deref().map(quote).unwrap_or_else(|| "null".to_string()),
+        );
+    }
+    out += if record.entries.is_empty() { "]\n}\n" } else { "\n  ]\n}\n" };
+    out
+}
+
+/// Posts the payload for `record` to `url`. With a secret the body is signed
+/// in an `X-File-Eraser-Signature: sha256=<hex HMAC>` header, so the
+/// receiver can tell the notification really came from this machine.
+fn send(url: &str, secret: &str, record: &Record) -> io::Result<()> {
+    let url = Url::parse(url).map_err(io::Error::other)?;
+    let body = payload(record);
+    let signature = format!("sha256={}", hex(&hmac_sha256(secret.as_bytes(), body.as_bytes())));
+    let mut headers = vec![("Content-Type", "application/json")];
+    if !secret.is_empty() {
+        headers.push(("X-File-Eraser-Signature", &signature));
+    }
+    let response = http::request("POST", &url, &headers, body.as_bytes())?;
+    if !response.success() {
+        return Err(io::Error::other(format!("{} answered {}", url, response.status)));
+    }
+    Ok(())
+}
+
+/// Sends the notification when a webhook is configured. Failures are
+/// logged; the job itself is already over.
+pub fn notify(settings: &Settings, record: &Record) {
+    if settings.webhook_url.is_empty() {
+        return;
+    }
+    if let Err(e) = send(&settings.webhook_url, &settings.webhook_secret, record) {
+        eprintln!("Error sending webhook for job {}: {}", record.id, e);
+    }
+}
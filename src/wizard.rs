@@ -0,0 +1,208 @@
+use iced::widget;
+use crate::history::Outcome;
+use crate::strategy::Confidence;
+use crate::{container, estimate, picker, preview, shadow, snapshot, summary, App, Message};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Pick,
+    Level,
+    Confirm,
+    Progress,
+    Report,
+}
+
+impl Step {
+    pub fn next(self) -> Self {
+        match self {
+            Step::Pick => Step::Level,
+            Step::Level => Step::Confirm,
+            Step::Confirm => Step::Progress,
+            Step::Progress | Step::Report => Step::Report,
+        }
+    }
+
+    pub fn back(self) -> Self {
+        match self {
+            Step::Pick | Step::Level => Step::Pick,
+            Step::Confirm => Step::Level,
+            // A running or finished job cannot be stepped back into.
+            Step::Progress | Step::Report => self,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Step::Pick => "Step 1 of 5: Choose what to erase",
+            Step::Level => "Step 2 of 5: Choose how carefully to erase it",
+            Step::Confirm => "Step 3 of 5: Check and confirm",
+            Step::Progress => "Step 4 of 5: Erasing",
+            Step::Report => "Step 5 of 5: Done",
+        }
+    }
+}
+
+pub fn view(app: &App) -> iced::Element<'_, Message> {
+    let body: iced::Element<'_, Message> = match app.step {
+        Step::Pick => pick(app),
+        Step::Level => level(app),
+        Step::Confirm => confirm(app),
+        Step::Progress => progress(app),
+        Step::Report => report(app),
+    };
+
+    let back = widget::button("Back").on_press_maybe(
+        matches!(app.step, Step::Level | Step::Confirm).then_some(Message::WizardBack),
+    );
+    let next: iced::Element<'_, Message> = match app.step {
+        Step::Pick => widget::button("Next")
+            .on_press_maybe((!app.files.is_empty()).then_some(Message::WizardNext))
+            .into(),
+        Step::Level => widget::button("Next").on_press(Message::WizardNext).into(),
+        Step::Confirm => widget::button("Erase permanently")
+            .style(widget::button::danger)
+            .on_press_maybe(
+                (app.confirmed && (app.snapshots_acknowledged || !snapshot::blocking(&app.snapshots)))
+                    .then_some(Message::EraseFile),
+            )
+            .into(),
+        Step::Progress => widget::button("Cancel").on_press(Message::Cancel).into(),
+        Step::Report => widget::button("Erase more files").on_press(Message::WizardRestart).into(),
+    };
+
+    widget::column![
+        widget::text(app.step.title()).size(20),
+        widget::container(body).height(iced::Length::Fill),
+        widget::row![back, widget::horizontal_space(), next].spacing(10),
+    ]
+        .spacing(15)
+        .into()
+}
+
+fn pick(app: &App) -> iced::Element<'_, Message> {
+    widget::column![
+        widget::text("Add the files or folders you want to destroy. Nothing is erased until you confirm."),
+        widget::row![
+            widget::button("Add files").on_press(Message::SelectFile(picker::Selection::Multiple)),
+            widget::button("Add folder").on_press(Message::SelectFile(picker::Selection::Directory)),
+            app.pack_list(),
+            widget::button("Clear").on_press_maybe((!app.files.is_empty()).then_some(Message::ClearFiles)),
+        ]
+            .spacing(10),
+        widget::scrollable(widget::column(
+            app.files.iter().map(|file| widget::text!(" {}", file.display()).into())
+        )),
+    ]
+        .spacing(10)
+        .into()
+}
+
+fn level(app: &App) -> iced::Element<'_, Message> {
+    // Radio buttons need a `Copy` value, so they carry the index into the registry.
+    let selected = app.methods.methods().iter().position(|method| *method == app.settings.method);
+    widget::scrollable(
+        widget::column(app.methods.methods().iter().enumerate().filter(|(_, method)| {
+            // Only what the organization's policy allows is offered.
+            app.policy.allows_method(method) && (!app.policy.locks("method") || **method == app.settings.method)
+        }).map(|(index, method)| {
+            widget::column![
+                widget::radio(method.to_string(), index, selected, Message::MethodPicked),
+                widget::text!(
+                    "{} Takes about {}x as long as a single pass.",
+                    method.explanation(),
+                    method.duration_multiplier(),
+                )
+                    .size(14),
+            ]
+                .spacing(5)
+                .into()
+        }))
+            .spacing(15),
+    )
+        .into()
+}
+
+fn confirm(app: &App) -> iced::Element<'_, Message> {
+    widget::column![
+        widget::text!(
+            "{} file(s) will be overwritten using {} and then deleted. This cannot be undone.",
+            app.files.len(),
+            app.job_method(),
+        ),
+        app.preset_row(),
+        app.tag_row(),
+    ]
+        .push_maybe(estimate::comparison(app))
+        .push(widget::text("Is this the right file? Check the contents below before going on.").size(14))
+        .push(
+            widget::scrollable(
+                widget::column(
+                    app.previews
+                        .iter()
+                        .map(preview::view)
+                        .chain(app.files.iter().skip(app.previews.len()).map(|file| widget::text!(" {}", file.display()).into()))
+                )
+                    .spacing(15)
+            )
+                .height(iced::Length::Fill),
+        )
+        .push_maybe(container::warning(app))
+        .push_maybe(shadow::warning(app))
+        .push_maybe(snapshot::warning(app))
+        .push(
+            widget::checkbox("I checked the previews and understand these files cannot be recovered", app.confirmed)
+                .on_toggle(Message::ConfirmToggled),
+        )
+        .spacing(10)
+        .into()
+}
+
+fn progress(app: &App) -> iced::Element<'_, Message> {
+    widget::column![
+        widget::text!("Erasing file {} of {}...", app.current + 1, app.job.as_ref().map_or(0, |job| job.size)),
+        widget::progress_bar(0.0..=100.0, app.progress),
+        widget::text("You can keep using your computer while this runs."),
+    ]
+        .spacing(10)
+        .into()
+}
+
+fn report(app: &App) -> iced::Element<'_, Message> {
+    let Some(report) = &app.report else {
+        return widget::text("No report available.").into();
+    };
+
+    let summary = match report.outcome {
+        Outcome::Cancelled => "The job was cancelled. Files that were not finished may still be readable.".to_string(),
+        Outcome::Unsanitized => {
+            "NOT RELIABLY SANITIZED. Verification read back different data than was written, \
+             so the job was stopped. Treat the drive as unreliable and the files below as still readable."
+                .to_string()
+        }
+        _ if report.failures.is_empty() => format!("All {} file(s) were destroyed.", report.files),
+        _ => format!("{} of {} file(s) could not be erased.", report.failures.len(), report.files),
+    };
+    let summary = widget::text(summary);
+    let summary = if report.outcome == Outcome::Unsanitized {
+        summary.size(20).color(iced::Color::from_rgb(0.8, 0.1, 0.1))
+    } else {
+        summary
+    };
+
+    let score = report.score.as_ref().map(|score| {
+        let color = match score.confidence {
+            Confidence::High => iced::Color::from_rgb(0.1, 0.6, 0.2),
+            Confidence::Reduced => iced::Color::from_rgb(0.8, 0.6, 0.0),
+            Confidence::BestEffort => iced::Color::from_rgb(0.8, 0.3, 0.0),
+        };
+        widget::text(score.to_string()).size(18).color(color)
+    });
+
+    widget::column![summary]
+        .push_maybe(score)
+        .push(widget::text!("Method: {}", report.method))
+        .push(widget::text!("Verification: {}", report.verification))
+        .push(summary::view(report, app.erasing))
+        .spacing(10)
+        .into()
+}
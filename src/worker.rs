@@ -0,0 +1,70 @@
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::engine::CancelToken;
+use crate::runtime;
+
+/// A job running on the async runtime's blocking pool, owned by whoever
+/// started it. Dropping it cancels the job and waits for it, so nothing is
+/// left writing behind a closed window.
+pub struct Worker {
+    cancel: CancelToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    pub fn spawn(work: impl FnOnce(&CancelToken) + Send + 'static) -> Self {
+        let cancel = CancelToken::default();
+        let token = cancel.clone();
+        let task = runtime::handle().spawn_blocking(move || work(&token));
+        Self { cancel, task: Some(task) }
+    }
+
+    /// Asks the job to stop at the next chunk; it still reports `Cancelled`.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Waits for the job. `Err` holds the panic message if it crashed.
+    pub fn join(mut self) -> Result<(), String> {
+        match self.task.take() {
+            Some(task) => runtime::block_on(task).map_err(runtime::crash),
+            None => Ok(()),
+        }
+    }
+
+    /// Lets the job run on unwatched.
+    pub fn detach(mut self) {
+        self.task.take();
+    }
+
+    /// Cancels the job and gives it `grace` to stop. A write stuck in the
+    /// kernel, on a drive that stopped answering, cannot be interrupted, so
+    /// after that the job is left behind rather than hanging the exit.
+    pub fn shutdown(mut self, grace: Duration) {
+        self.cancel();
+        let Some(task) = &mut self.task else {
+            return;
+        };
+        match runtime::block_on(async { tokio::time::timeout(grace, task).await }) {
+            Ok(result) => {
+                self.task = None;
+                if let Err(e) = result {
+                    eprintln!("The erase crashed: {}", runtime::crash(e));
+                }
+            }
+            Err(_) => {
+                eprintln!("The erase did not stop within {} seconds, leaving it behind", grace.as_secs());
+                self.detach();
+            }
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            self.cancel.cancel();
+            let _ = runtime::block_on(task);
+        }
+    }
+}